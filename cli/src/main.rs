@@ -57,7 +57,7 @@ fn main() -> Result<(), anyhow::Error> {
         Format::Mp4 => {
             let config = Config { cumulative_mdat_box_size: args.cumulative_mdat_box_size, ..Default::default() };
             match mp4san::sanitize_with_config(&mut infile, config).context("Error parsing mp4 file")? {
-                SanitizedMetadata { metadata: Some(metadata), data } => {
+                SanitizedMetadata { metadata: Some(metadata), data, .. } => {
                     if let Some(output_path) = args.output {
                         let mut outfile = File::create(output_path).context("Error opening output file")?;
                         outfile.write(&metadata).context("Error writing output")?;
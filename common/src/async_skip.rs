@@ -9,7 +9,7 @@ use std::task::{ready, Context, Poll};
 use futures_util::io::{BufReader, Cursor};
 use futures_util::{AsyncBufRead, AsyncRead, AsyncSeek};
 
-use crate::{AsyncSkip, SeekSkipAdapter};
+use crate::{AsyncSkip, SeekSkipAdapter, SliceReader};
 
 //
 // public types
@@ -143,6 +143,30 @@ impl<R: AsyncSeek + Unpin + ?Sized> AsyncSkip for SeekSkipAdapter<R> {
     }
 }
 
+//
+// SliceReader impls
+//
+
+impl<T: AsRef<[u8]> + Unpin> AsyncRead for SliceReader<T> {
+    fn poll_read(self: Pin<&mut Self>, _cx: &mut Context<'_>, buf: &mut [u8]) -> Poll<io::Result<usize>> {
+        io::Read::read(self.get_mut(), buf).into()
+    }
+}
+
+impl<T: AsRef<[u8]> + Unpin> AsyncSkip for SliceReader<T> {
+    fn poll_skip(self: Pin<&mut Self>, _cx: &mut Context<'_>, amount: u64) -> Poll<io::Result<()>> {
+        crate::Skip::skip(self.get_mut(), amount).into()
+    }
+
+    fn poll_stream_position(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<u64>> {
+        crate::Skip::stream_position(self.get_mut()).into()
+    }
+
+    fn poll_stream_len(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<u64>> {
+        crate::Skip::stream_len(self.get_mut()).into()
+    }
+}
+
 //
 // AsyncSkip impls
 //
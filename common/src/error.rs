@@ -1,11 +1,14 @@
 //! Error types returned by the public API.
 
-use std::any::type_name;
-use std::fmt;
-use std::fmt::{Debug, Display};
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::any::type_name;
+use core::fmt;
+use core::fmt::{Debug, Display};
+use core::panic::Location;
+use core::result::Result as StdResult;
+#[cfg(feature = "std")]
 use std::io;
-use std::panic::Location;
-use std::result::Result as StdResult;
 
 use derive_more::Display;
 
@@ -14,6 +17,7 @@ use derive_more::Display;
 //
 
 /// Error type returned by `mediasan`.
+#[cfg(feature = "std")]
 #[derive(Debug, thiserror::Error)]
 pub enum Error<E: ReportableError> {
     /// An IO error occurred while reading the given input.
@@ -30,10 +34,7 @@ pub enum Error<E: ReportableError> {
 /// A `Report<E>` can be used to identify exactly where the error `E` occurred in `mediasan`. The [`Debug`]
 /// implementation will print a human-readable parser stack trace. The underlying error of type `E` can also be
 /// retrieved e.g. for matching against with [`get_ref`](Self::get_ref) or [`into_inner`](Self::into_inner).
-#[derive(thiserror::Error)]
-#[error("{error}")]
 pub struct Report<E: ReportableError> {
-    #[source]
     error: E,
     stack: E::Stack,
 }
@@ -48,10 +49,10 @@ pub struct ExtraUnparsedInput;
 #[display(fmt = "while parsing value of type `{}`", _0)]
 pub struct WhileParsingType(&'static str);
 
-/// A convenience type alias for a [`Result`](std::result::Result) containing an error wrapped by a [`Report`].
+/// A convenience type alias for a [`Result`](core::result::Result) containing an error wrapped by a [`Report`].
 pub type Result<T, E> = StdResult<T, Report<E>>;
 
-/// An trait providing [`Report`]-related extensions for [`Result`](std::result::Result).
+/// An trait providing [`Report`]-related extensions for [`Result`](core::result::Result).
 pub trait ResultExt: Sized {
     #[track_caller]
     /// Attach a [`Display`]-able type to the error [`Report`]'s stack trace.
@@ -138,6 +139,19 @@ impl<E: ReportableError> Debug for Report<E> {
     }
 }
 
+impl<E: ReportableError> Display for Report<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Display::fmt(&self.error, f)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<E: ReportableError + std::error::Error + 'static> std::error::Error for Report<E> {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
 //
 // ReportErrorStack impls
 //
@@ -217,6 +231,7 @@ impl<T, E: ReportableError> ResultExt for Result<T, E> {
     }
 }
 
+#[cfg(feature = "std")]
 impl<T, E: ReportableError> ResultExt for StdResult<T, Error<E>> {
     #[track_caller]
     fn attach_printable<P: Display + Send + Sync + 'static>(self, printable: P) -> Self {
@@ -1,28 +1,46 @@
+#![cfg_attr(not(feature = "std"), no_std)]
 #![warn(missing_docs)]
 
 //! `mediasan-common` is a common library shared by the `mediasan` media format "sanitizers".
+//!
+//! # Features
+//!
+//! The `std` feature, enabled by default, provides the I/O traits and adapters used to read inputs, and the
+//! [`std::error::Error`] implementations of the error types. Without it, only the error reporting and parsing
+//! primitives are available, requiring only `alloc`.
+
+extern crate alloc;
 
 #[macro_use]
 pub mod macros;
 
+#[cfg(feature = "std")]
 pub mod async_skip;
 pub mod error;
 pub mod parse;
+#[cfg(feature = "std")]
 mod skip;
+#[cfg(feature = "std")]
 pub mod sync;
 pub mod util;
 
+#[cfg(feature = "std")]
 use std::io;
+#[cfg(feature = "std")]
 use std::pin::Pin;
+#[cfg(feature = "std")]
 use std::task::{Context, Poll};
 
+#[cfg(feature = "std")]
 use derive_more::{Deref, DerefMut};
 
 //
 // public types
 //
 
-pub use error::{Error, Report, Result, ResultExt};
+#[cfg(feature = "std")]
+pub use error::Error;
+pub use error::{Report, Result, ResultExt};
 
 /// A pointer to a span in the given input.
 #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
@@ -35,6 +53,7 @@ pub struct InputSpan {
 }
 
 /// A subset of the [`Seek`] trait, providing a cursor which can skip forward within a stream of bytes.
+#[cfg(feature = "std")]
 pub trait Skip {
     /// Skip an amount of bytes in a stream.
     ///
@@ -45,10 +64,14 @@ pub trait Skip {
     fn stream_position(&mut self) -> io::Result<u64>;
 
     /// Returns the length of this stream, in bytes.
+    ///
+    /// A stream which can only be read forward may not know its length, in which case it should return an error of kind
+    /// [`io::ErrorKind::Unsupported`].
     fn stream_len(&mut self) -> io::Result<u64>;
 }
 
 /// A subset of the [`AsyncSeek`] trait, providing a cursor which can skip forward within a stream of bytes.
+#[cfg(feature = "std")]
 pub trait AsyncSkip {
     /// Skip an amount of bytes in a stream.
     ///
@@ -59,11 +82,44 @@ pub trait AsyncSkip {
     fn poll_stream_position(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<u64>>;
 
     /// Returns the length of this stream, in bytes.
+    ///
+    /// A stream which can only be read forward may not know its length, in which case it should return an error of kind
+    /// [`io::ErrorKind::Unsupported`].
     fn poll_stream_len(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<u64>>;
 }
 
 /// An adapter implementing [`Skip`]/[`AsyncSkip`] for all types implementing [`Seek`]/[`AsyncSeek`].
+#[cfg(feature = "std")]
 #[derive(Clone, Copy, Debug, Default, Deref, DerefMut)]
 pub struct SeekSkipAdapter<T: ?Sized>(pub T);
 
+/// An adapter implementing [`Skip`] for any type implementing [`Read`](io::Read), by reading and discarding skipped
+/// data.
+///
+/// This allows inputs which can't be seeked at all, such as pipes or decompressors, to be used where [`Skip`] is
+/// required, at the cost of reading all skipped data. The length of the stream can't be determined without consuming it,
+/// so [`Skip::stream_len`] always returns an error.
+#[cfg(feature = "std")]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ReadDiscardSkip<T> {
+    inner: T,
+    position: u64,
+}
+
+/// A reader over in-memory bytes, such as a `&[u8]`, [`Vec<u8>`], or [`Bytes`](bytes::Bytes), implementing [`Read`] +
+/// [`Skip`] and [`AsyncRead`] + [`AsyncSkip`] by advancing a cursor.
+///
+/// Unlike [`io::Cursor`], this doesn't require the [`Seek`] trait, and so can be used directly with any byte container.
+///
+/// [`Read`]: io::Read
+/// [`Seek`]: io::Seek
+/// [`AsyncRead`]: futures_util::AsyncRead
+#[cfg(feature = "std")]
+#[derive(Clone, Debug, Default)]
+pub struct SliceReader<T> {
+    inner: T,
+    position: u64,
+}
+
+#[cfg(feature = "std")]
 pub use async_skip::AsyncSkipExt;
@@ -2,4 +2,4 @@
 
 mod fourcc;
 
-pub use fourcc::FourCC;
+pub use fourcc::{FourCC, ParseFourCCError};
@@ -1,8 +1,12 @@
-use std::fmt;
+use core::fmt;
+use core::str::FromStr;
+#[cfg(feature = "std")]
 use std::io;
 
 use bytes::Buf;
 use bytes::BufMut;
+use derive_more::Display;
+#[cfg(feature = "std")]
 use futures_util::{pin_mut, AsyncRead, AsyncReadExt};
 
 /// A four-byte character code.
@@ -12,11 +16,27 @@ pub struct FourCC {
     pub value: [u8; 4],
 }
 
+/// An error parsing a [`FourCC`] from a string.
+#[derive(Clone, Debug, Display, PartialEq, Eq)]
+pub enum ParseFourCCError {
+    /// The string was not exactly four bytes long.
+    #[display(fmt = "four-character code must be 4 bytes long, not {}", _0)]
+    InvalidLength(usize),
+
+    /// The string contained a byte which is not printable ASCII.
+    #[display(fmt = "four-character code contains non-printable byte 0x{:02x}", _0)]
+    NonPrintable(u8),
+}
+
 impl FourCC {
     /// The encoded length of a [`FourCC`], in bytes.
     pub const ENCODED_LEN: u32 = 4;
 
-    /// Construct a [`FourCC`] from a string.
+    /// Construct a [`FourCC`] from a string, padding it with spaces.
+    ///
+    /// No validation is performed; see the [`FromStr`] implementation for a validating alternative. Note that this
+    /// inherent function shadows [`FromStr::from_str`] when called as `FourCC::from_str`, so use [`str::parse`] or
+    /// [`TryFrom<&str>`](TryFrom) to validate the string instead.
     pub const fn from_str(name: &str) -> Self {
         let name = name.as_bytes();
         let mut fourcc = [b' '; 4];
@@ -34,6 +54,7 @@ impl FourCC {
     }
 
     /// Read a [`FourCC`] from an [`AsyncRead`].
+    #[cfg(feature = "std")]
     pub async fn read<R: AsyncRead>(input: R) -> io::Result<Self> {
         let mut value = [0; 4];
         pin_mut!(input);
@@ -60,9 +81,36 @@ impl FourCC {
     }
 }
 
+impl FromStr for FourCC {
+    type Err = ParseFourCCError;
+
+    /// Parse a [`FourCC`] from a string of exactly four printable ASCII characters.
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        let value: [u8; 4] = name
+            .as_bytes()
+            .try_into()
+            .map_err(|_| ParseFourCCError::InvalidLength(name.len()))?;
+        if let Some(byte) = value.iter().find(|byte| !(b' '..=b'~').contains(*byte)) {
+            return Err(ParseFourCCError::NonPrintable(*byte));
+        }
+        Ok(Self { value })
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParseFourCCError {}
+
+impl TryFrom<&str> for FourCC {
+    type Error = ParseFourCCError;
+
+    fn try_from(name: &str) -> Result<Self, Self::Error> {
+        name.parse()
+    }
+}
+
 impl fmt::Display for FourCC {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        if let Ok(string) = std::str::from_utf8(&self.value) {
+        if let Ok(string) = core::str::from_utf8(&self.value) {
             let string = string.trim();
             write!(f, "{string}")
         } else {
@@ -70,3 +118,32 @@ impl fmt::Display for FourCC {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_display_roundtrip() {
+        for name in ["avc1", "hvcC", "mp4a", "a b~"] {
+            let fourcc: FourCC = name.parse().unwrap();
+            assert_eq!(fourcc.value, name.as_bytes());
+            assert_eq!(fourcc.to_string(), name);
+            assert_eq!(FourCC::try_from(name), Ok(fourcc));
+        }
+    }
+
+    #[test]
+    fn parse_invalid_length() {
+        assert_eq!("".parse::<FourCC>(), Err(ParseFourCCError::InvalidLength(0)));
+        assert_eq!("mp4".parse::<FourCC>(), Err(ParseFourCCError::InvalidLength(3)));
+        assert_eq!("mp4a ".parse::<FourCC>(), Err(ParseFourCCError::InvalidLength(5)));
+        assert_eq!("\u{a9}xyz".parse::<FourCC>(), Err(ParseFourCCError::InvalidLength(5)));
+    }
+
+    #[test]
+    fn parse_non_printable() {
+        assert_eq!("mp4\0".parse::<FourCC>(), Err(ParseFourCCError::NonPrintable(0)));
+        assert_eq!("\tab ".parse::<FourCC>(), Err(ParseFourCCError::NonPrintable(b'\t')));
+    }
+}
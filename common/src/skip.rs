@@ -5,7 +5,7 @@ use std::io;
 use std::io::{BufRead, BufReader, Read, Seek};
 use std::io::{Cursor, Empty};
 
-use crate::{SeekSkipAdapter, Skip};
+use crate::{ReadDiscardSkip, SeekSkipAdapter, Skip, SliceReader};
 
 //
 // Skip impls
@@ -135,3 +135,133 @@ impl<T: Read + ?Sized> Read for SeekSkipAdapter<T> {
         self.0.read(buf)
     }
 }
+
+//
+// ReadDiscardSkip impls
+//
+
+impl<T> ReadDiscardSkip<T> {
+    /// Construct a [`ReadDiscardSkip`] reading from `inner`, which is assumed to be positioned at the start of the stream.
+    pub fn new(inner: T) -> Self {
+        Self { inner, position: 0 }
+    }
+
+    /// Unwrap this adapter, returning the underlying reader.
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+impl<T: Read> Read for ReadDiscardSkip<T> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read_len = self.inner.read(buf)?;
+        self.position += read_len as u64;
+        Ok(read_len)
+    }
+}
+
+impl<T: Read> Skip for ReadDiscardSkip<T> {
+    fn skip(&mut self, amount: u64) -> io::Result<()> {
+        io::copy(&mut (&mut self.inner).take(amount), &mut io::sink())?;
+        // Like a seek, a skip beyond the end of the stream advances the position past its end.
+        self.position = self
+            .position
+            .checked_add(amount)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "skip past u64::MAX"))?;
+        Ok(())
+    }
+
+    fn stream_position(&mut self) -> io::Result<u64> {
+        Ok(self.position)
+    }
+
+    fn stream_len(&mut self) -> io::Result<u64> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "stream length of a forward-only input is unknown",
+        ))
+    }
+}
+
+//
+// SliceReader impls
+//
+
+impl<T: AsRef<[u8]>> SliceReader<T> {
+    /// Construct a [`SliceReader`] reading from the start of `inner`.
+    pub fn new(inner: T) -> Self {
+        Self { inner, position: 0 }
+    }
+
+    /// Unwrap this reader, returning the underlying bytes.
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+
+    /// Returns the bytes remaining after the current position, which are empty if it is past the end.
+    pub(crate) fn remaining(&self) -> &[u8] {
+        let bytes = self.inner.as_ref();
+        usize::try_from(self.position)
+            .ok()
+            .and_then(|position| bytes.get(position..))
+            .unwrap_or_default()
+    }
+}
+
+impl<T: AsRef<[u8]>> Read for SliceReader<T> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let remaining = self.remaining();
+        let read_len = remaining.len().min(buf.len());
+        buf[..read_len].copy_from_slice(&remaining[..read_len]);
+        self.position += read_len as u64;
+        Ok(read_len)
+    }
+}
+
+impl<T: AsRef<[u8]>> Skip for SliceReader<T> {
+    fn skip(&mut self, amount: u64) -> io::Result<()> {
+        // Like a seek, a skip beyond the end of the bytes advances the position past their end.
+        self.position = self
+            .position
+            .checked_add(amount)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "skip past u64::MAX"))?;
+        Ok(())
+    }
+
+    fn stream_position(&mut self) -> io::Result<u64> {
+        Ok(self.position)
+    }
+
+    fn stream_len(&mut self) -> io::Result<u64> {
+        Ok(self.inner.as_ref().len() as u64)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn slice_reader() {
+        let mut reader = SliceReader::new(&b"abcdefg"[..]);
+        let mut buf = [0; 2];
+        reader.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"ab");
+        reader.skip(3).unwrap();
+        assert_eq!(reader.stream_position().unwrap(), 5);
+        assert_eq!(reader.stream_len().unwrap(), 7);
+
+        let mut rest = vec![];
+        reader.read_to_end(&mut rest).unwrap();
+        assert_eq!(rest, b"fg");
+    }
+
+    #[test]
+    fn slice_reader_skip_past_end() {
+        let mut reader = SliceReader::new(vec![0; 4]);
+        reader.skip(8).unwrap();
+        assert_eq!(reader.stream_position().unwrap(), 8);
+        assert_eq!(reader.read(&mut [0; 4]).unwrap(), 0);
+        assert_eq!(reader.skip(u64::MAX).unwrap_err().kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -1,5 +1,6 @@
 //! Common utilities used by the `mediasan` crates.
 
+#[cfg(feature = "std")]
 use std::io;
 
 /// Checked addition with a signed integer. Computes `lhs + rhs`, returning `None` if overflow occurred.
@@ -21,6 +22,7 @@ pub trait CheckedAddSigned: Sized {
 }
 
 /// Extensions to [`Result`] when the error type is [`io::Error`].
+#[cfg(feature = "std")]
 pub trait IoResultExt: Sized {
     /// The type returned by the [`Ok`] variant of this [`Result`]
     type Ok;
@@ -53,6 +55,7 @@ impl_checked_add_signed!(u64, i64);
 impl_checked_add_signed!(u128, i128);
 impl_checked_add_signed!(usize, isize);
 
+#[cfg(feature = "std")]
 impl<T> IoResultExt for Result<T, io::Error> {
     type Ok = T;
 
@@ -15,7 +15,7 @@ pub fn derive_parse_box(input: TokenStream) -> TokenStream {
     if matches!(input.data, Data::Enum(_) | Data::Union(_)) {
         // This one _does_ need a semicolon though.
         return TokenStream::from(quote! {
-            std::compile_error!("this trait can only be derived for structs");
+            ::core::compile_error!("this trait can only be derived for structs");
         });
     }
     let box_type = extract_box_type(&input);
@@ -42,7 +42,7 @@ pub fn derive_parsed_box(input: TokenStream) -> TokenStream {
     if matches!(input.data, Data::Enum(_) | Data::Union(_)) {
         // This one _does_ need a semicolon though.
         return TokenStream::from(quote! {
-            std::compile_error!("this trait can only be derived for structs");
+            ::core::compile_error!("this trait can only be derived for structs");
         });
     }
     let size = sum_box_size(&input);
@@ -101,7 +101,7 @@ fn derive_read_fn(input: &DeriveInput) -> TokenStream2 {
                 }
             }
             quote! {
-                fn parse(buf: &mut bytes::BytesMut) -> std::result::Result<Self, mp4san::Report<mp4san::parse::ParseError>> {
+                fn parse(buf: &mut bytes::BytesMut) -> ::core::result::Result<Self, mp4san::Report<mp4san::parse::ParseError>> {
                     #(
                         let #bind_ident: #field_ty =
                             mp4san::parse::error::ParseResultExt::while_parsing_field(
@@ -120,7 +120,7 @@ fn derive_read_fn(input: &DeriveInput) -> TokenStream2 {
                                 #ident::box_type(),
                             );
                     }
-                    std::result::Result::Ok(#ident { #( #field_ident: #bind_ident ),* })
+                    ::core::result::Result::Ok(#ident { #( #field_ident: #bind_ident ),* })
                 }
             }
         }
@@ -134,18 +134,18 @@ fn extract_box_type(input: &DeriveInput) -> TokenStream2 {
         // When emitting compiler errors, no semicolon should be placed after `compile_error!()`:
         // doing so will generate extraneous errors (type mismatch errors, Rust parse errors, or the
         // like) in addition to the error we intend to emit.
-        return quote! { std::compile_error!("missing `#[box_type]` attribute") };
+        return quote! { ::core::compile_error!("missing `#[box_type]` attribute") };
     };
     if let Some(extra_attr) = iter.next() {
         return quote_spanned! { extra_attr.span() =>
-            std::compile_error!("more than one `#[box_type]` attribute is not allowed")
+            ::core::compile_error!("more than one `#[box_type]` attribute is not allowed")
         };
     }
     let lit = match attr.meta.require_name_value().map(|name_value| &name_value.value).ok() {
         Some(Expr::Lit(lit)) => &lit.lit,
         _ => {
             return quote_spanned! { attr.span() =>
-                std::compile_error!("`box_type` attribute must be of the form `#[box_type = ...]`")
+                ::core::compile_error!("`box_type` attribute must be of the form `#[box_type = ...]`")
             }
         }
     };
@@ -169,7 +169,7 @@ fn extract_box_type(input: &DeriveInput) -> TokenStream2 {
             } else if string.len() == 4 {
                 return quote! {
                     let type_string = #string_lit;
-                    let type_ = std::convert::TryInto::try_into(type_string.as_bytes()).unwrap();
+                    let type_ = ::core::convert::TryInto::try_into(type_string.as_bytes()).unwrap();
                     mp4san::parse::BoxType::FourCC(mp4san::parse::FourCC { value: type_ })
                 };
             }
@@ -184,7 +184,7 @@ fn extract_box_type(input: &DeriveInput) -> TokenStream2 {
         }
         _ => {}
     }
-    quote_spanned! { lit.span() => std::compile_error!(concat!(
+    quote_spanned! { lit.span() => ::core::compile_error!(concat!(
         r#"malformed `box_type` attribute input: try `"moov"`, `b"moov"`, or `0x6d6f6f76` for a"#,
         r#" compact type, or `"a7b5465c-7eac-4caa-b744-bdc340127d37"` or"#,
         r#" `0xa7b5465c_7eac_4caa_b744_bdc340127d37` for an extended type"#,
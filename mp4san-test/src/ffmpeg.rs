@@ -11,30 +11,7 @@ use ac_ffmpeg::Error as FFMpegError;
 use crate::VerifyError;
 
 pub fn verify_ffmpeg(data: &[u8], expected_media_data: Option<&[u8]>) -> Result<(), VerifyError<FFMpegError>> {
-    #[no_mangle]
-    unsafe extern "C" fn mp4san_test_ffmpeg_log(level: c_int, message: *const c_char) {
-        let message = CStr::from_ptr(message).to_string_lossy();
-        let message = message.trim();
-
-        let level = match level {
-            0..=23 => log::Level::Error,
-            24..=31 => log::Level::Warn,
-            32..=47 => log::Level::Info,
-            48..=55 => log::Level::Debug,
-            _ => log::Level::Trace,
-        };
-        log::log!(target: "ffmpeg", level, "{message}");
-    }
-
-    #[allow(clippy::useless_transmute)] // false positive
-    unsafe {
-        // va_list is a macro to a struct on some targets, which causes type checking to fail.
-        let log_callback: unsafe extern "C" fn(ptr: *mut c_void, level: c_int, format: *const c_char, va_list: _) =
-            bindings::mp4san_test_ffmpeg_log_callback;
-        let log_callback: unsafe extern "C" fn(ptr: *mut c_void, level: c_int, format: *const c_char, va_list: _) =
-            mem::transmute(log_callback);
-        ffmpeg_sys_next::av_log_set_callback(Some(log_callback));
-    }
+    init_log();
 
     let io = ffmpeg_io::IO::from_seekable_read_stream(io::Cursor::new(data));
     let demuxer = FFMpegDemuxer::builder().set_option("strict", "strict").build(io)?;
@@ -54,7 +31,7 @@ pub fn verify_ffmpeg(data: &[u8], expected_media_data: Option<&[u8]>) -> Result<
                     })?;
             if frame.data() != expected_frame_data {
                 let offset = unverified_media_data.len() as u64 - expected_media_data.unwrap_or_default().len() as u64;
-                return Err(VerifyError::DataMismatch { offset, len: frame.data().len() });
+                return Err(VerifyError::DataMismatch { offset, len: frame.data().len(), track_idx: None });
             }
             *unverified_media_data = &unverified_media_data[expected_frame_data.len()..];
         }
@@ -66,3 +43,49 @@ pub fn verify_ffmpeg(data: &[u8], expected_media_data: Option<&[u8]>) -> Result<
     }
     Ok(())
 }
+
+/// Read `data` using ffmpeg, verifying that the duration of its longest stream is within `tolerance` seconds of
+/// `expected_secs`.
+pub fn verify_ffmpeg_duration(data: &[u8], expected_secs: f64, tolerance: f64) -> Result<(), VerifyError<FFMpegError>> {
+    init_log();
+
+    let io = ffmpeg_io::IO::from_seekable_read_stream(io::Cursor::new(data));
+    let demuxer = FFMpegDemuxer::builder().set_option("strict", "strict").build(io)?;
+    let demuxer = demuxer.find_stream_info(None).map_err(|(_demuxer, error)| error)?;
+    let duration_secs = demuxer
+        .streams()
+        .iter()
+        .flat_map(|stream| stream.duration().as_f64())
+        .max_by(f64::total_cmp);
+    match duration_secs {
+        Some(duration_secs) if (duration_secs - expected_secs).abs() <= tolerance => Ok(()),
+        duration_secs => Err(VerifyError::DurationMismatch { duration_secs, expected_secs }),
+    }
+}
+
+fn init_log() {
+    #[no_mangle]
+    unsafe extern "C" fn mp4san_test_ffmpeg_log(level: c_int, message: *const c_char) {
+        let message = CStr::from_ptr(message).to_string_lossy();
+        let message = message.trim();
+
+        let level = match level {
+            0..=23 => log::Level::Error,
+            24..=31 => log::Level::Warn,
+            32..=47 => log::Level::Info,
+            48..=55 => log::Level::Debug,
+            _ => log::Level::Trace,
+        };
+        log::log!(target: "ffmpeg", level, "{message}");
+    }
+
+    #[allow(clippy::useless_transmute)] // false positive
+    unsafe {
+        // va_list is a macro to a struct on some targets, which causes type checking to fail.
+        let log_callback: unsafe extern "C" fn(ptr: *mut c_void, level: c_int, format: *const c_char, va_list: _) =
+            bindings::mp4san_test_ffmpeg_log_callback;
+        let log_callback: unsafe extern "C" fn(ptr: *mut c_void, level: c_int, format: *const c_char, va_list: _) =
+            mem::transmute(log_callback);
+        ffmpeg_sys_next::av_log_set_callback(Some(log_callback));
+    }
+}
@@ -5,8 +5,10 @@ mod blob;
 pub mod error;
 mod iso_file;
 
-use std::ffi::{c_char, CStr};
+use std::ffi::{c_char, CStr, CString};
 use std::ptr::null_mut;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::{env, fs, process};
 
 use crate::VerifyError;
 
@@ -18,28 +20,10 @@ use self::error::Error;
 use self::iso_file::IsoFile;
 
 pub fn verify_gpac(data: &[u8], expected_media_data: Option<&[u8]>) -> Result<(), VerifyError<Error>> {
-    #[no_mangle]
-    unsafe extern "C" fn mp4san_test_gpac_log(level: GF_LOG_Level, tool: GF_LOG_Tool, message: *const c_char) {
-        let message = CStr::from_ptr(message).to_string_lossy();
-        let message = message.trim();
-
-        let level = match level {
-            GF_LOG_Level::GF_LOG_QUIET | GF_LOG_Level::GF_LOG_ERROR => log::Level::Error,
-            GF_LOG_Level::GF_LOG_WARNING => log::Level::Warn,
-            GF_LOG_Level::GF_LOG_INFO => log::Level::Info,
-            GF_LOG_Level::GF_LOG_DEBUG => log::Level::Debug,
-        };
-
-        log::log!(target: "gpac", level, "[{tool:?}] {message}");
-    }
-
-    unsafe {
-        gf_log_set_callback(null_mut(), Some(mp4san_test_gpac_log_callback));
-        gf_log_set_tool_level(GF_LOG_Tool::GF_LOG_ALL, GF_LOG_Level::GF_LOG_DEBUG);
-    }
+    init_log();
 
     let blob = Blob::new(data);
-    let mut file = IsoFile::new(blob.url())?;
+    let file = IsoFile::new(blob.url())?;
     let mut tracks = (1..=file.track_count())
         .map(|track_number| file.samples(track_number).peekable())
         .collect::<Vec<_>>();
@@ -57,20 +41,10 @@ pub fn verify_gpac(data: &[u8], expected_media_data: Option<&[u8]>) -> Result<()
         };
         let sample = sample?;
 
-        if let Some(unverified_media_data) = &mut unverified_media_data {
-            if sample.len() > unverified_media_data.len() {
-                return Err(VerifyError::DataLongerThanExpected {
-                    frame_len: sample.len(),
-                    remaining: unverified_media_data.len(),
-                });
-            }
-
-            let expected_sample_data = &unverified_media_data[..sample.len()];
-            if &sample[..] != expected_sample_data {
-                let offset = unverified_media_data.len() as u64 - expected_media_data.unwrap_or_default().len() as u64;
-                return Err(VerifyError::DataMismatch { offset, len: sample.len() });
-            }
-            *unverified_media_data = &unverified_media_data[expected_sample_data.len()..];
+        if let (Some(expected_media_data), Some(unverified_media_data)) =
+            (expected_media_data, &mut unverified_media_data)
+        {
+            verify_sample(&sample, expected_media_data, unverified_media_data, None)?;
         }
     }
     if let Some(unverified_media_data) = unverified_media_data {
@@ -81,6 +55,146 @@ pub fn verify_gpac(data: &[u8], expected_media_data: Option<&[u8]>) -> Result<()
     Ok(())
 }
 
+/// Read `data` using GPAC, verifying that the demuxed samples of each track match the expected media data for that
+/// track, indexed by 0-based track index.
+///
+/// Unlike [`verify_gpac`], which expects the samples of all tracks interleaved in the order they're stored, each
+/// track's samples are verified independently.
+pub fn verify_gpac_tracks(data: &[u8], expected_track_media_data: &[&[u8]]) -> Result<(), VerifyError<Error>> {
+    init_log();
+
+    let blob = Blob::new(data);
+    let file = IsoFile::new(blob.url())?;
+    let track_count = file.track_count();
+    if track_count as usize != expected_track_media_data.len() {
+        return Err(VerifyError::TrackCountMismatch {
+            track_count,
+            expected_track_count: expected_track_media_data.len(),
+        });
+    }
+    for (track_idx, expected_media_data) in expected_track_media_data.iter().enumerate() {
+        let mut unverified_media_data = *expected_media_data;
+        for sample in file.samples(track_idx as u32 + 1) {
+            verify_sample(
+                &sample?,
+                expected_media_data,
+                &mut unverified_media_data,
+                Some(track_idx),
+            )?;
+        }
+        if !unverified_media_data.is_empty() {
+            return Err(VerifyError::DataShorterThanExpected { remaining: unverified_media_data.len() });
+        }
+    }
+    Ok(())
+}
+
+/// Verify `data` using [`verify_gpac`], then remux it using GPAC and verify that the remuxed file demuxes to the same
+/// samples.
+///
+/// The remuxed file stores each track's samples contiguously, so its samples are compared track by track rather than
+/// against `expected_media_data`, which may interleave the samples of multiple tracks.
+pub fn verify_remux_stable(data: &[u8], expected_media_data: Option<&[u8]>) -> Result<(), VerifyError<Error>> {
+    verify_gpac(data, expected_media_data)?;
+
+    let remuxed = remux(data)?;
+
+    let blob = Blob::new(data);
+    let file = IsoFile::new(blob.url())?;
+    let remuxed_blob = Blob::new(&remuxed);
+    let remuxed_file = IsoFile::new(remuxed_blob.url())?;
+    let track_count = file.track_count();
+    let remuxed_track_count = remuxed_file.track_count();
+    if remuxed_track_count != track_count {
+        return Err(VerifyError::RemuxTrackCountMismatch { track_count, remuxed_track_count });
+    }
+    for track_number in 1..=track_count {
+        let samples = file.samples(track_number).collect::<Result<Vec<_>, _>>()?;
+        let remuxed_samples = remuxed_file.samples(track_number).collect::<Result<Vec<_>, _>>()?;
+        let sample_count = samples.len().max(remuxed_samples.len());
+        let mismatched_sample_idx = (0..sample_count).find(|&sample_idx| {
+            samples.get(sample_idx).map(|sample| &sample[..])
+                != remuxed_samples.get(sample_idx).map(|sample| &sample[..])
+        });
+        if let Some(sample_idx) = mismatched_sample_idx {
+            let sample_number = sample_idx as u32 + 1;
+            return Err(VerifyError::RemuxMismatch { track_number, sample_number });
+        }
+    }
+    Ok(())
+}
+
+/// Remux `data` into a new file using GPAC, copying each track and its samples.
+fn remux(data: &[u8]) -> Result<Vec<u8>, Error> {
+    static REMUX_COUNT: AtomicU32 = AtomicU32::new(0);
+
+    let remux_count = REMUX_COUNT.fetch_add(1, Ordering::Relaxed);
+    let path = env::temp_dir().join(format!("mp4san-test-remux-{}-{remux_count}.mp4", process::id()));
+    let path_cstr = CString::new(path.to_string_lossy().into_owned()).expect("temp path contains no nul bytes");
+
+    let blob = Blob::new(data);
+    let file = IsoFile::new(blob.url())?;
+    let remuxed_file = IsoFile::create(&path_cstr)?;
+    let result = (|| -> Result<Vec<u8>, Error> {
+        for track_number in 1..=file.track_count() {
+            let remuxed_track_number = file.clone_track(track_number, &remuxed_file)?;
+            for sample in file.samples(track_number) {
+                remuxed_file.add_sample(remuxed_track_number, &sample?)?;
+            }
+        }
+        remuxed_file.close()?;
+        Ok(fs::read(&path).expect("error reading remuxed file"))
+    })();
+    let _ = fs::remove_file(&path);
+    result
+}
+
+/// Verify that `sample` matches the start of `unverified_media_data`, the unverified remainder of
+/// `expected_media_data`, and advance past it.
+fn verify_sample(
+    sample: &[u8],
+    expected_media_data: &[u8],
+    unverified_media_data: &mut &[u8],
+    track_idx: Option<usize>,
+) -> Result<(), VerifyError<Error>> {
+    if sample.len() > unverified_media_data.len() {
+        return Err(VerifyError::DataLongerThanExpected {
+            frame_len: sample.len(),
+            remaining: unverified_media_data.len(),
+        });
+    }
+
+    let expected_sample_data = &unverified_media_data[..sample.len()];
+    if sample != expected_sample_data {
+        let offset = (expected_media_data.len() - unverified_media_data.len()) as u64;
+        return Err(VerifyError::DataMismatch { offset, len: sample.len(), track_idx });
+    }
+    *unverified_media_data = &unverified_media_data[sample.len()..];
+    Ok(())
+}
+
+fn init_log() {
+    #[no_mangle]
+    unsafe extern "C" fn mp4san_test_gpac_log(level: GF_LOG_Level, tool: GF_LOG_Tool, message: *const c_char) {
+        let message = CStr::from_ptr(message).to_string_lossy();
+        let message = message.trim();
+
+        let level = match level {
+            GF_LOG_Level::GF_LOG_QUIET | GF_LOG_Level::GF_LOG_ERROR => log::Level::Error,
+            GF_LOG_Level::GF_LOG_WARNING => log::Level::Warn,
+            GF_LOG_Level::GF_LOG_INFO => log::Level::Info,
+            GF_LOG_Level::GF_LOG_DEBUG => log::Level::Debug,
+        };
+
+        log::log!(target: "gpac", level, "[{tool:?}] {message}");
+    }
+
+    unsafe {
+        gf_log_set_callback(null_mut(), Some(mp4san_test_gpac_log_callback));
+        gf_log_set_tool_level(GF_LOG_Tool::GF_LOG_ALL, GF_LOG_Level::GF_LOG_DEBUG);
+    }
+}
+
 impl From<bindings::Bool> for bool {
     fn from(from: bindings::Bool) -> Self {
         match from {
@@ -11,6 +11,13 @@ pub struct Error(GF_Err);
 pub type Result<T> = std::result::Result<T, Error>;
 
 impl Error {
+    pub fn check(err: GF_Err) -> Result<()> {
+        match err {
+            GF_Err::GF_OK => Ok(()),
+            err => Err(Self(err)),
+        }
+    }
+
     pub fn last() -> Self {
         Self(unsafe { gf_isom_last_error(null_mut()) })
     }
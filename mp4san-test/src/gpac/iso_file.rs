@@ -1,10 +1,12 @@
 use std::ffi::CStr;
+use std::mem;
 use std::ptr::{null, NonNull};
 use std::slice;
 
 use super::bindings::{
-    gf_isom_close, gf_isom_get_sample_count, gf_isom_get_sample_ex, gf_isom_open, gf_isom_sample_del,
-    gf_isom_sample_new, GF_ISOFile, GF_ISOOpenMode, GF_ISOSample,
+    gf_isom_add_sample, gf_isom_clone_track, gf_isom_close, gf_isom_get_sample_count, gf_isom_get_sample_ex,
+    gf_isom_open, gf_isom_sample_del, gf_isom_sample_new, GF_ISOFile, GF_ISOOpenMode, GF_ISOSample,
+    GF_ISOTrackCloneFlags,
 };
 use super::error::{Error, Result};
 
@@ -15,6 +17,7 @@ pub struct IsoFile {
 pub struct IsoSample {
     gf_isosample: NonNull<GF_ISOSample>,
     data_offset: u64,
+    sample_description_index: u32,
 }
 
 impl IsoFile {
@@ -25,6 +28,50 @@ impl IsoFile {
         Ok(Self { gf_isofile })
     }
 
+    /// Create a new file at `path`, which is written when the file is [closed](Self::close).
+    pub fn create(path: &CStr) -> Result<Self> {
+        let gf_isofile = unsafe { gf_isom_open(path.as_ptr(), GF_ISOOpenMode::GF_ISOM_OPEN_WRITE, null()) };
+        let gf_isofile = NonNull::new(gf_isofile).ok_or_else(Error::last)?;
+
+        Ok(Self { gf_isofile })
+    }
+
+    /// Copy the track `track_number` and its sample descriptions, but not its samples, to `dest`, returning the new
+    /// track's number in `dest`.
+    pub fn clone_track(&self, track_number: u32, dest: &IsoFile) -> Result<u32> {
+        let mut dest_track_number = 0;
+        let err = unsafe {
+            gf_isom_clone_track(
+                self.gf_isofile.as_ptr(),
+                track_number,
+                dest.gf_isofile.as_ptr(),
+                GF_ISOTrackCloneFlags::GF_ISOM_CLONE_TRACK_NO_QT,
+                &mut dest_track_number,
+            )
+        };
+        Error::check(err)?;
+        Ok(dest_track_number)
+    }
+
+    pub fn add_sample(&self, track_number: u32, sample: &IsoSample) -> Result<()> {
+        let err = unsafe {
+            gf_isom_add_sample(
+                self.gf_isofile.as_ptr(),
+                track_number,
+                sample.sample_description_index,
+                sample.gf_isosample.as_ptr(),
+            )
+        };
+        Error::check(err)
+    }
+
+    /// Close the file, writing it if it was [created](Self::create).
+    pub fn close(self) -> Result<()> {
+        let gf_isofile = self.gf_isofile;
+        mem::forget(self);
+        Error::check(unsafe { gf_isom_close(gf_isofile.as_ptr()) })
+    }
+
     pub fn samples(&self, track_number: u32) -> impl Iterator<Item = Result<IsoSample>> + '_ {
         let sample_count = unsafe { gf_isom_get_sample_count(self.gf_isofile.as_ptr(), track_number) };
         (1..=sample_count).map(move |sample_number| self.sample(track_number, sample_number))
@@ -45,7 +92,7 @@ impl IsoFile {
             )
         };
         match NonNull::new(gf_isosample_res) {
-            Some(gf_isosample) => Ok(IsoSample { gf_isosample, data_offset }),
+            Some(gf_isosample) => Ok(IsoSample { gf_isosample, data_offset, sample_description_index }),
             None => {
                 unsafe { gf_isom_sample_del(&mut gf_isosample) };
                 Err(unsafe { Error::last_for_file(self.gf_isofile) })
@@ -84,7 +131,7 @@ impl Drop for IsoSample {
 macro_rules! isofile_method {
     ($($name:ident => $gf_name:ident() -> $return:ty),* $(,)?) => {
         impl IsoFile {
-            $(pub fn $name(&mut self) -> $return {
+            $(pub fn $name(&self) -> $return {
                 unsafe {
                     super::bindings::$gf_name(self.gf_isofile.as_ptr()).into()
                 }
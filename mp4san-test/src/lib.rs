@@ -22,14 +22,36 @@ pub enum VerifyError<T> {
     #[error("data longer than expected: frame len {frame_len} > {remaining} remaining")]
     DataLongerThanExpected { frame_len: usize, remaining: usize },
 
-    #[error("data at offset {offset} did not match")]
-    DataMismatch { offset: u64, len: usize },
+    #[error("data at offset {offset}{} did not match", fmt_track_idx(*track_idx))]
+    DataMismatch {
+        offset: u64,
+        len: usize,
+        track_idx: Option<usize>,
+    },
+
+    #[error("duration {duration_secs:?} seconds did not match expected {expected_secs} seconds")]
+    DurationMismatch {
+        duration_secs: Option<f64>,
+        expected_secs: f64,
+    },
 
     #[error("data shorter than expected: {remaining} remaining")]
     DataShorterThanExpected { remaining: usize },
 
     #[error(transparent)]
     Parse(#[from] T),
+
+    #[error("remuxed file has {remuxed_track_count} tracks, but {track_count} were expected")]
+    RemuxTrackCountMismatch { track_count: u32, remuxed_track_count: u32 },
+
+    #[error("remuxed file sample {sample_number} of track {track_number} did not match")]
+    RemuxMismatch { track_number: u32, sample_number: u32 },
+
+    #[error("file has {track_count} tracks, but {expected_track_count} were expected")]
+    TrackCountMismatch {
+        track_count: u32,
+        expected_track_count: usize,
+    },
 }
 
 //
@@ -46,6 +68,17 @@ pub fn ffmpeg_assert_eq(data: &[u8], expected_media_data: &[u8]) {
         .unwrap_or_else(|error| panic!("ffmpeg returned an error: {error}\n{error:?}"));
 }
 
+/// Read `data` using ffmpeg, verifying that the duration of its longest stream is within `tolerance` seconds of
+/// `expected_secs`.
+#[cfg_attr(not(feature = "ffmpeg"), allow(unused_variables))]
+pub fn ffmpeg_assert_duration(data: &[u8], expected_secs: f64, tolerance: f64) {
+    #[cfg(not(feature = "ffmpeg"))]
+    log::info!("not verifying sanitizer output using ffmpeg; ffmpeg feature disabled");
+    #[cfg(feature = "ffmpeg")]
+    ffmpeg::verify_ffmpeg_duration(data, expected_secs, tolerance)
+        .unwrap_or_else(|error| panic!("ffmpeg returned an error: {error}\n{error:?}"));
+}
+
 /// Read `data` using ffmpeg, verifying that it cannot be demuxed.
 #[cfg_attr(not(feature = "ffmpeg"), allow(unused_variables))]
 pub fn ffmpeg_assert_invalid(data: &[u8]) {
@@ -76,6 +109,17 @@ pub fn gpac_assert_eq(data: &[u8], expected_media_data: &[u8]) {
         .unwrap_or_else(|error| panic!("gpac returned an error: {error}\n{error:?}"));
 }
 
+/// Read `data` using GPAC, verifying that the demuxed frames of each track match the corresponding entry of
+/// `expected_track_media_data`, indexed by 0-based track index.
+#[cfg_attr(not(feature = "gpac"), allow(unused_variables))]
+pub fn gpac_assert_tracks_eq(data: &[u8], expected_track_media_data: &[&[u8]]) {
+    #[cfg(not(feature = "gpac"))]
+    log::info!("not verifying sanitizer output using gpac; gpac feature disabled");
+    #[cfg(feature = "gpac")]
+    gpac::verify_gpac_tracks(data, expected_track_media_data)
+        .unwrap_or_else(|error| panic!("gpac returned an error: {error}\n{error:?}"));
+}
+
 /// Read `data` using GPAC, verifying that it cannot be demuxed.
 #[cfg_attr(not(feature = "gpac"), allow(unused_variables))]
 pub fn gpac_assert_invalid(data: &[u8]) {
@@ -96,6 +140,29 @@ pub fn gpac_assert_valid(data: &[u8]) {
     gpac::verify_gpac(data, None).unwrap_or_else(|error| panic!("gpac returned an error: {error}\n{error:?}"));
 }
 
+/// Read `data` using GPAC and remux it using GPAC, verifying that the demuxed frames of both match the
+/// `expected_media_data`.
+#[cfg_attr(not(feature = "gpac"), allow(unused_variables))]
+pub fn gpac_assert_remux_stable(data: &[u8], expected_media_data: &[u8]) {
+    #[cfg(not(feature = "gpac"))]
+    log::info!("not verifying sanitizer output using gpac; gpac feature disabled");
+    #[cfg(feature = "gpac")]
+    gpac::verify_remux_stable(data, Some(expected_media_data))
+        .unwrap_or_else(|error| panic!("gpac returned an error: {error}\n{error:?}"));
+}
+
+/// Read `data` using GPAC and remux it using GPAC, verifying that the demuxed frames of either don't match the
+/// `expected_media_data`.
+#[cfg_attr(not(feature = "gpac"), allow(unused_variables))]
+pub fn gpac_assert_remux_unstable(data: &[u8], expected_media_data: &[u8]) {
+    #[cfg(not(feature = "gpac"))]
+    log::info!("not verifying sanitizer output using gpac; gpac feature disabled");
+    #[cfg(feature = "gpac")]
+    gpac::verify_remux_stable(data, Some(expected_media_data))
+        .err()
+        .unwrap_or_else(|| panic!("gpac didn't return an error"));
+}
+
 pub fn example_ftyp() -> Vec<u8> {
     const EXAMPLE_FTYP: &[&[u8]] = &[
         &[0, 0, 0, 20], // box size
@@ -117,29 +184,103 @@ pub fn example_mdat() -> Vec<u8> {
 
 pub fn example_moov() -> Vec<u8> {
     const EXAMPLE_MOOV: &[&[u8]] = &[
-        &[0, 0, 0, 56], // box size
+        &[0, 0, 1, 25], // box size
         b"moov",        // box type
         //
         // trak box (inside moov box)
         //
-        &[0, 0, 0, 48], // box size
+        &[0, 0, 1, 17], // box size
         b"trak",        // box type
         //
+        // tkhd box (inside trak box)
+        //
+        &[0, 0, 0, 92], // box size
+        b"tkhd",        // box type
+        &[0, 0, 0, 3],  // box version & flags (track enabled, track in movie)
+        &[0, 0, 0, 0],  // creation time
+        &[0, 0, 0, 0],  // modification time
+        &[0, 0, 0, 1],  // track id
+        &[0, 0, 0, 0],  // reserved
+        &[0, 0, 0, 0],  // duration
+        &[0; 8],        // reserved
+        &[0, 0],        // layer
+        &[0, 0],        // alternate group
+        &[0, 0],        // volume
+        &[0, 0],        // reserved
+        &[0, 1, 0, 0],  // matrix
+        &[0; 12],
+        &[0, 1, 0, 0],
+        &[0; 12],
+        &[0x40, 0, 0, 0],
+        &[0, 0, 0, 0], // width
+        &[0, 0, 0, 0], // height
+        //
         // mdia box (inside trak box)
         //
-        &[0, 0, 0, 40], // box size
-        b"mdia",        // box type
+        &[0, 0, 0, 173], // box size
+        b"mdia",         // box type
         //
-        // minf box (inside mdia box)
+        // mdhd box (inside mdia box)
         //
         &[0, 0, 0, 32], // box size
-        b"minf",        // box type
+        b"mdhd",        // box type
+        &[0, 0, 0, 0],  // box version & flags
+        &[0, 0, 0, 0],  // creation time
+        &[0, 0, 0, 0],  // modification time
+        &[0, 0, 0, 1],  // timescale
+        &[0, 0, 0, 0],  // duration
+        b"\x55\xc4",    // language ("und")
+        &[0, 0],        // pre-defined
+        //
+        // hdlr box (inside mdia box)
+        //
+        &[0, 0, 0, 33], // box size
+        b"hdlr",        // box type
+        &[0, 0, 0, 0],  // box version & flags
+        &[0, 0, 0, 0],  // pre-defined
+        b"vide",        // handler type
+        &[0; 12],       // reserved
+        &[0],           // name
+        //
+        // minf box (inside mdia box)
+        //
+        &[0, 0, 0, 100], // box size
+        b"minf",         // box type
         //
         // stbl box (inside minf box)
         //
-        &[0, 0, 0, 24], // box size
+        &[0, 0, 0, 92], // box size
         b"stbl",        // box type
         //
+        // stsd box (inside stbl box)
+        //
+        &[0, 0, 0, 16], // box size
+        b"stsd",        // box type
+        &[0, 0, 0, 0],  // box version & flags
+        &[0, 0, 0, 0],  // entry count
+        //
+        // stts box (inside stbl box)
+        //
+        &[0, 0, 0, 16], // box size
+        b"stts",        // box type
+        &[0, 0, 0, 0],  // box version & flags
+        &[0, 0, 0, 0],  // entry count
+        //
+        // stsc box (inside stbl box)
+        //
+        &[0, 0, 0, 16], // box size
+        b"stsc",        // box type
+        &[0, 0, 0, 0],  // box version & flags
+        &[0, 0, 0, 0],  // entry count
+        //
+        // stsz box (inside stbl box)
+        //
+        &[0, 0, 0, 20], // box size
+        b"stsz",        // box type
+        &[0, 0, 0, 0],  // box version & flags
+        &[0, 0, 0, 0],  // sample size
+        &[0, 0, 0, 0],  // sample count
+        //
         // stco box (inside stbl box)
         //
         &[0, 0, 0, 16], // box size
@@ -149,3 +290,13 @@ pub fn example_moov() -> Vec<u8> {
     ];
     EXAMPLE_MOOV.concat()
 }
+
+//
+// private functions
+//
+
+fn fmt_track_idx(track_idx: Option<usize>) -> String {
+    track_idx
+        .map(|track_idx| format!(" of track {track_idx}"))
+        .unwrap_or_default()
+}
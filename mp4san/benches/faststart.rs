@@ -0,0 +1,31 @@
+use std::io::Cursor;
+
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+use mp4san::sanitize;
+use mp4san_test::{example_ftyp, example_moov};
+
+criterion_group!(benches, faststart_single_track);
+criterion_main!(benches);
+
+const MDAT_PAYLOAD_LEN: u32 = 1 << 20;
+
+/// Sanitize a single-track input with its `moov` after its `mdat`, which must be moved to the front, displacing the
+/// `mdat`.
+pub fn faststart_single_track(c: &mut Criterion) {
+    let mut group = c.benchmark_group("faststart single track");
+
+    let mut input = example_ftyp();
+    input.extend_from_slice(&(MDAT_PAYLOAD_LEN + 8).to_be_bytes());
+    input.extend_from_slice(b"mdat");
+    input.resize(input.len() + MDAT_PAYLOAD_LEN as usize, 0);
+    input.extend_from_slice(&example_moov());
+
+    group.bench_function("sanitize", |bencher| {
+        bencher.iter_batched(
+            || Cursor::new(&input[..]),
+            |input| black_box(sanitize(input).unwrap()),
+            BatchSize::SmallInput,
+        )
+    });
+    group.finish();
+}
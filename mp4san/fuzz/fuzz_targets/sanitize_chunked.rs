@@ -0,0 +1,99 @@
+#![no_main]
+
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures_util::task::noop_waker_ref;
+use futures_util::AsyncRead;
+use libfuzzer_sys::fuzz_target;
+use mp4san::{AsyncSkip, SliceReader};
+
+/// The maximum number of leading bytes of the fuzz input used as chunk sizes.
+const MAX_CHUNK_SIZES_LEN: usize = 16;
+
+/// An input which delivers its data in chunks of at most the given sizes, in turn, and which isn't ready before each
+/// chunk.
+struct ChunkedInput<'a> {
+    inner: SliceReader<&'a [u8]>,
+    chunk_sizes: &'a [u8],
+    chunk_idx: usize,
+    ready: bool,
+}
+
+impl AsyncRead for ChunkedInput<'_> {
+    fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut [u8]) -> Poll<io::Result<usize>> {
+        if !std::mem::replace(&mut self.ready, false) {
+            self.ready = true;
+            cx.waker().wake_by_ref();
+            return Poll::Pending;
+        }
+        let chunk_size = match self.chunk_sizes {
+            [] => usize::MAX,
+            chunk_sizes => usize::from(chunk_sizes[self.chunk_idx % chunk_sizes.len()]) + 1,
+        };
+        self.chunk_idx += 1;
+        let read_len = buf.len().min(chunk_size);
+        Pin::new(&mut self.inner).poll_read(cx, &mut buf[..read_len])
+    }
+}
+
+impl AsyncSkip for ChunkedInput<'_> {
+    fn poll_skip(mut self: Pin<&mut Self>, cx: &mut Context<'_>, amount: u64) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_skip(cx, amount)
+    }
+
+    fn poll_stream_position(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<u64>> {
+        Pin::new(&mut self.inner).poll_stream_position(cx)
+    }
+
+    fn poll_stream_len(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<u64>> {
+        Pin::new(&mut self.inner).poll_stream_len(cx)
+    }
+}
+
+fuzz_target!(|data: &[u8]| {
+    // The first byte gives the number of following bytes which are chunk sizes, and the rest is the mp4 input.
+    let (chunk_sizes, data) = match data.split_first() {
+        Some((&chunk_sizes_len, data)) => {
+            data.split_at(data.len().min(usize::from(chunk_sizes_len) % (MAX_CHUNK_SIZES_LEN + 1)))
+        }
+        None => (&[][..], data),
+    };
+    let input = ChunkedInput { inner: SliceReader::new(data), chunk_sizes, chunk_idx: 0, ready: false };
+
+    let mut future = Box::pin(mp4san::sanitize_async(input));
+    let mut cx = Context::from_waker(noop_waker_ref());
+    let result = loop {
+        if let Poll::Ready(result) = future.as_mut().poll(&mut cx) {
+            break result;
+        }
+    };
+
+    #[cfg_attr(not(fuzzing_repro), allow(unused))]
+    match result {
+        Ok(sanitized) => {
+            #[cfg(fuzzing_repro)]
+            eprintln!(
+                "mp4san returned ok: metadata len {metadata_len:?} data offset {data_offset} len {data_len}",
+                metadata_len = sanitized.metadata.as_ref().map(|metadata| metadata.len()),
+                data_offset = sanitized.data.offset,
+                data_len = sanitized.data.len,
+            );
+        }
+        Err(error) => match error {
+            mp4san::Error::Io(error) => match error.kind() {
+                io::ErrorKind::InvalidData => {
+                    #[cfg(fuzzing_repro)]
+                    eprintln!("mp4san returned an io error: {error}\n{error:?}");
+                }
+                _ => panic!(),
+            },
+            mp4san::Error::Parse(error) => {
+                #[cfg(fuzzing_repro)]
+                eprintln!("mp4san returned a parse error: {error}\n{error:?}");
+            }
+        },
+    }
+});
@@ -1,5 +1,6 @@
 //! Error types returned by the public API.
 
+#[cfg(feature = "std")]
 use crate::parse::ParseError;
 
 //
@@ -7,6 +8,7 @@ use crate::parse::ParseError;
 //
 
 /// Error type returned by `mp4san`.
+#[cfg(feature = "std")]
 pub type Error = mediasan_common::error::Error<ParseError>;
 
 pub use mediasan_common::Report;
@@ -15,7 +17,7 @@ pub use mediasan_common::Report;
 // private types
 //
 
-pub(crate) type Result<T, E> = std::result::Result<T, Report<E>>;
+pub(crate) type Result<T, E> = core::result::Result<T, Report<E>>;
 
 #[doc(hidden)]
 pub use mediasan_common::ResultExt as __ResultExt;
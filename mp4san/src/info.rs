@@ -0,0 +1,123 @@
+//! Information about the contents of an MP4 input, as returned by [`probe`](crate::probe).
+
+use mediasan_common::InputSpan;
+
+use crate::error::Result;
+use crate::parse::{BoxType, MoovBox, ParseError, TrakBox};
+use crate::sample_table::SampleTable;
+
+/// Information about the presentation in an MP4 input, as returned by [`probe`](crate::probe).
+#[derive(Clone, Debug)]
+pub struct MediaInfo {
+    tracks: Vec<TrackInfo>,
+}
+
+/// Information about a single track (`trak`) in an MP4 input.
+#[derive(Clone, Debug)]
+pub struct TrackInfo {
+    sample_table: SampleTable,
+    codec_config: Option<CodecConfig>,
+}
+
+/// The decoder configuration for a track, as found in its first sample entry.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CodecConfig {
+    /// The kind of decoder configuration record contained in [`data`](Self::data).
+    pub kind: CodecConfigKind,
+
+    /// The payload of the decoder configuration box, excluding its box header.
+    pub data: Vec<u8>,
+}
+
+/// The kind of a track's [`CodecConfig`], identified by the box it was found in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum CodecConfigKind {
+    /// An H.264 `AVCDecoderConfigurationRecord`, from an `avcC` box.
+    Avc,
+
+    /// An H.265 `HEVCDecoderConfigurationRecord`, from an `hvcC` box.
+    Hevc,
+
+    /// A VP8 or VP9 `VPCodecConfigurationRecord`, from a `vpcC` box.
+    Vp,
+
+    /// An `AV1CodecConfigurationRecord`, from an `av1C` box.
+    Av1,
+
+    /// An MPEG-4 `ES_Descriptor`, from an `esds` box.
+    Esds,
+}
+
+//
+// MediaInfo impls
+//
+
+impl MediaInfo {
+    pub(crate) fn new(moov: &mut MoovBox) -> Result<Self, ParseError> {
+        let tracks = moov
+            .traks()
+            .map(|trak| TrackInfo::new(trak?))
+            .collect::<Result<_, _>>()?;
+        Ok(Self { tracks })
+    }
+
+    /// The tracks in the input, in the order they appear in the movie header (`moov`).
+    pub fn tracks(&self) -> &[TrackInfo] {
+        &self.tracks
+    }
+
+    /// Returns the span within the input of the first keyframe of the track at index `track_index`.
+    ///
+    /// Returns [`None`] if there is no such track. See [`TrackInfo::first_keyframe`].
+    pub fn first_keyframe(&self, track_index: usize) -> Option<InputSpan> {
+        self.tracks.get(track_index)?.first_keyframe()
+    }
+}
+
+//
+// TrackInfo impls
+//
+
+impl TrackInfo {
+    fn new(trak: &mut TrakBox) -> Result<Self, ParseError> {
+        let stbl = trak.stbl_mut()?;
+        let sample_table = SampleTable::new(stbl)?;
+        let codec_config = stbl.stsd_mut()?.codec_config()?.and_then(|(box_type, data)| {
+            let kind = match box_type {
+                BoxType::AVCC => CodecConfigKind::Avc,
+                BoxType::HVCC => CodecConfigKind::Hevc,
+                BoxType::VPCC => CodecConfigKind::Vp,
+                BoxType::AV1C => CodecConfigKind::Av1,
+                BoxType::ESDS => CodecConfigKind::Esds,
+                _ => return None,
+            };
+            Some(CodecConfig { kind, data: data.to_vec() })
+        });
+        Ok(Self { sample_table, codec_config })
+    }
+
+    /// The number of samples in the track.
+    pub fn sample_count(&self) -> u32 {
+        self.sample_table.sample_count()
+    }
+
+    /// Returns the span within the input of the track's first keyframe, i.e. its first sync sample.
+    ///
+    /// The span's offset is relative to the start of the original input, and so points into the input's media data
+    /// (`mdat`) regardless of where the sanitizer would relocate it.
+    ///
+    /// Returns [`None`] if the track has no sync samples, or if the track's sample tables don't locate the sample.
+    pub fn first_keyframe(&self) -> Option<InputSpan> {
+        self.sample_table.sample_span(self.sample_table.first_sync_sample()?)
+    }
+
+    /// Returns the raw decoder configuration (e.g. H.264 SPS/PPS or an MPEG-4 `esds`) from the track's first sample
+    /// entry.
+    ///
+    /// Returns [`None`] if the sample entry isn't a known video or audio sample entry type, or doesn't contain a known
+    /// decoder configuration box.
+    pub fn codec_config(&self) -> Option<&CodecConfig> {
+        self.codec_config.as_ref()
+    }
+}
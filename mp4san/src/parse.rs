@@ -1,36 +1,106 @@
 //! Unstable API for parsing individual MP4 box types.
 
 mod array;
+mod auxc;
+mod av1c;
+mod ccst;
 mod co64;
+mod colr;
+mod cprt;
+mod edts;
+mod elst;
 pub mod error;
 mod ftyp;
+mod hdlr;
 mod header;
+mod hvcc;
+mod iloc;
 mod integers;
+mod kind;
+mod mdhd;
 mod mdia;
+mod meta;
+mod mfhd;
 mod minf;
+mod moof;
 mod moov;
 mod mp4box;
+mod mvhd;
+mod pdin;
+mod pssh;
+mod saio;
+mod sbgp;
+mod sgpd;
+mod sidx;
 mod stbl;
 mod stco;
+mod string;
+mod stsc;
+mod stsd;
+mod stss;
+mod stsz;
+mod taic;
+mod tfhd;
+mod tkhd;
+mod traf;
 mod trak;
+mod trun;
+mod udta;
 mod value;
+mod vexu;
+mod vpcc;
 
 pub use array::{ArrayEntry, ArrayEntryMut, BoundedArray, UnboundedArray};
+pub use auxc::{AuxCBox, AuxiBox};
+pub use av1c::Av1CBox;
+pub use ccst::CcstBox;
 pub use co64::Co64Box;
+pub use colr::{ColrBox, NclxColour};
+pub use cprt::CprtBox;
+pub use edts::EdtsBox;
+pub use elst::{ElstBox, ElstEntry};
 pub use error::ParseError;
 pub use ftyp::FtypBox;
+pub use hdlr::HdlrBox;
 pub use header::{box_type, fourcc, BoxHeader, BoxSize, BoxType, BoxUuid, ConstFullBoxHeader, FullBoxHeader};
+pub use hvcc::HvcCBox;
+pub use iloc::IlocBox;
 pub use integers::Mp4Prim;
-pub use mdia::MdiaBox;
+pub use kind::KindBox;
+pub use mdhd::MdhdBox;
+pub use mdia::{MdiaBox, MdiaChildrenValidator};
+pub use meta::MetaBox;
+pub use mfhd::MfhdBox;
 pub use minf::MinfBox;
+pub use moof::MoofBox;
 pub use moov::MoovBox;
 pub use mp4box::{AnyMp4Box, BoxData, Boxes, BoxesValidator, Mp4Box, ParseBox, ParsedBox};
+pub use mvhd::MvhdBox;
+pub use pdin::PdinBox;
+pub use pssh::PsshBox;
+pub use saio::SaioBox;
+pub use sbgp::SbgpBox;
+pub use sgpd::SgpdBox;
+pub use sidx::{SidxBox, SidxReference};
 pub use stbl::{StblBox, StblCoMut};
 pub use stco::StcoBox;
+pub use string::NullTerminatedString;
+pub use stsc::{StscBox, StscEntry};
+pub use stsd::StsdBox;
+pub use stss::StssBox;
+pub use stsz::StszBox;
+pub use taic::TaicBox;
+pub use tfhd::TfhdBox;
+pub use tkhd::TkhdBox;
+pub use traf::TrafBox;
 pub use trak::TrakBox;
+pub use trun::{TrunBox, TrunSample};
+pub use udta::UdtaBox;
 pub use value::{Mp4Value, Mp4ValueReaderExt, Mp4ValueWriterExt};
+pub use vexu::{EyesBox, HeroBox, PrjiBox, ProjBox, StriBox, VexuBox};
+pub use vpcc::VpcCBox;
 
-pub use mediasan_common::parse::FourCC;
+pub use mediasan_common::parse::{FourCC, ParseFourCCError};
 pub use mp4san_derive::{ParseBox, ParsedBox};
 
 #[cfg(test)]
@@ -58,6 +128,7 @@ mod test {
     #[box_type = "xa04"]
     pub struct Fifth;
 
+    #[allow(dead_code)]
     #[derive(Clone, Debug, ParseBox, ParsedBox)]
     #[box_type = "test"]
     pub struct ArrayBox {
@@ -71,7 +142,7 @@ mod test {
         let not_a_real = NotARealBox { bar_ax: u64::MAX, foo_by: u32::MAX };
         assert_eq!(
             not_a_real.encoded_len(),
-            (<u64 as Mp4Prim>::encoded_len() + <u32 as Mp4Prim>::encoded_len()) as u64
+            <u64 as Mp4Prim>::encoded_len() + <u32 as Mp4Prim>::encoded_len()
         );
     }
 
@@ -1,7 +1,7 @@
 #![allow(missing_docs)]
 
-use std::fmt::Debug;
-use std::marker::PhantomData;
+use core::fmt::Debug;
+use core::marker::PhantomData;
 
 use bytes::{Buf, BufMut, BytesMut};
 use derive_where::derive_where;
@@ -43,14 +43,19 @@ pub struct ArrayEntryMut<'a, T> {
 //
 
 impl<C: Clone, T: Mp4Prim> BoundedArray<C, T> {
-    pub fn entries(&self) -> impl Iterator<Item = ArrayEntry<'_, T>> + ExactSizeIterator + '_ {
+    pub fn entries(&self) -> impl ExactSizeIterator<Item = ArrayEntry<'_, T>> + '_ {
         self.array.entries()
     }
 
-    pub fn entries_mut(&mut self) -> impl Iterator<Item = ArrayEntryMut<'_, T>> + ExactSizeIterator + '_ {
+    pub fn entries_mut(&mut self) -> impl ExactSizeIterator<Item = ArrayEntryMut<'_, T>> + '_ {
         self.array.entries_mut()
     }
 
+    /// The values of the entries, each parsed as a `T`.
+    pub fn values(&self) -> impl ExactSizeIterator<Item = Result<T, ParseError>> + '_ {
+        self.array.values()
+    }
+
     pub fn entry_count(&self) -> C {
         self.entry_count.clone()
     }
@@ -59,11 +64,12 @@ impl<C: Clone, T: Mp4Prim> BoundedArray<C, T> {
 impl<C: Mp4Prim + Into<u32> + Clone, T: Mp4Prim> Mp4Value for BoundedArray<C, T> {
     fn parse(buf: &mut BytesMut) -> Result<Self, ParseError> {
         let entry_count = C::parse(&mut *buf).while_parsing_type()?;
-        let entries_len = (T::encoded_len() as u32)
-            .checked_mul(entry_count.clone().into())
+        // NB: The length is computed in 64 bits, so that a huge entry count in a short buffer is reported as truncated.
+        let entries_len = T::encoded_len()
+            .checked_mul(entry_count.clone().into().into())
             .ok_or_else(|| report_attach!(ParseError::InvalidInput, "overflow", WhileParsingType::new::<Self>()))?;
         ensure_attach!(
-            buf.remaining() as u32 >= entries_len,
+            buf.remaining() as u64 >= entries_len,
             ParseError::TruncatedBox,
             WhileParsingType::new::<Self>(),
         );
@@ -93,19 +99,30 @@ impl<C: From<u32>, T: Mp4Prim> FromIterator<T> for BoundedArray<C, T> {
 // UnboundedArray impls
 //
 
+impl UnboundedArray<u8> {
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.entries[..]
+    }
+}
+
 impl<T: Mp4Prim> UnboundedArray<T> {
-    pub fn entries(&self) -> impl Iterator<Item = ArrayEntry<'_, T>> + ExactSizeIterator + '_ {
+    pub fn entries(&self) -> impl ExactSizeIterator<Item = ArrayEntry<'_, T>> + '_ {
         self.entries
             .chunks_exact(T::encoded_len() as usize)
             .map(|data| ArrayEntry { data, _t: PhantomData })
     }
 
-    pub fn entries_mut(&mut self) -> impl Iterator<Item = ArrayEntryMut<'_, T>> + ExactSizeIterator + '_ {
+    pub fn entries_mut(&mut self) -> impl ExactSizeIterator<Item = ArrayEntryMut<'_, T>> + '_ {
         self.entries
             .chunks_exact_mut(T::encoded_len() as usize)
             .map(|data| ArrayEntryMut { data, _t: PhantomData })
     }
 
+    /// The values of the entries, each parsed as a `T`.
+    pub fn values(&self) -> impl ExactSizeIterator<Item = Result<T, ParseError>> + '_ {
+        self.entries().map(|entry| entry.get())
+    }
+
     pub fn entry_count(&self) -> usize {
         self.entries.len() / T::encoded_len() as usize
     }
@@ -159,3 +176,20 @@ impl<T: Mp4Prim> ArrayEntryMut<'_, T> {
         self.data.put_mp4_value(&value)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn values() {
+        let array: BoundedArray<u32, u64> = [1, 2, u64::MAX].into_iter().collect();
+        let mut buf = BytesMut::new();
+        array.put_buf(&mut buf);
+        let array = BoundedArray::<u32, u64>::parse(&mut buf).unwrap();
+
+        assert_eq!(array.values().len(), 3);
+        let values = array.values().collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(values, [1, 2, u64::MAX]);
+    }
+}
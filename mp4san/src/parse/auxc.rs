@@ -0,0 +1,109 @@
+#![allow(missing_docs)]
+
+use super::{ConstFullBoxHeader, NullTerminatedString, ParseBox, ParsedBox, UnboundedArray};
+
+/// The auxiliary type property, identifying the type of a HEIF/AVIF auxiliary image such as an alpha plane or depth
+/// map.
+#[derive(Clone, Debug, ParseBox, ParsedBox)]
+#[box_type = "auxC"]
+pub struct AuxCBox {
+    header: ConstFullBoxHeader,
+    aux_type: NullTerminatedString,
+    aux_subtype: UnboundedArray<u8>,
+}
+
+/// The auxiliary track type info box, identifying the type of an auxiliary image sequence track.
+#[derive(Clone, Debug, ParseBox, ParsedBox)]
+#[box_type = "auxi"]
+pub struct AuxiBox {
+    header: ConstFullBoxHeader,
+    aux_track_type: NullTerminatedString,
+}
+
+impl AuxCBox {
+    /// The URN identifying an alpha plane auxiliary image.
+    pub const ALPHA_URN: &'static [u8] = b"urn:mpeg:mpegB:cicp:systems:auxiliary:alpha";
+
+    pub fn new(aux_type: &[u8], aux_subtype: &[u8]) -> Self {
+        Self {
+            header: Default::default(),
+            aux_type: aux_type.into(),
+            aux_subtype: aux_subtype.iter().copied().collect(),
+        }
+    }
+
+    /// The URN identifying the type of the auxiliary image, not including its null terminator.
+    pub fn aux_type(&self) -> &[u8] {
+        self.aux_type.as_bytes()
+    }
+
+    /// Additional type-specific information about the auxiliary image.
+    pub fn aux_subtype(&self) -> &[u8] {
+        self.aux_subtype.as_bytes()
+    }
+}
+
+impl AuxiBox {
+    pub fn new(aux_track_type: &[u8]) -> Self {
+        Self { header: Default::default(), aux_track_type: aux_track_type.into() }
+    }
+
+    /// The URN identifying the type of the auxiliary track, not including its null terminator.
+    pub fn aux_track_type(&self) -> &[u8] {
+        self.aux_track_type.as_bytes()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use assert_matches::assert_matches;
+    use bytes::{BufMut, BytesMut};
+
+    use crate::parse::{FullBoxHeader, Mp4Value, ParseError};
+
+    use super::*;
+
+    #[test]
+    fn alpha() {
+        let mut data = BytesMut::new();
+        AuxCBox::new(AuxCBox::ALPHA_URN, &[]).put_buf(&mut data);
+        let auxc = AuxCBox::parse(&mut data).unwrap();
+        assert_eq!(auxc.aux_type(), AuxCBox::ALPHA_URN);
+        assert_eq!(auxc.aux_subtype(), b"");
+    }
+
+    #[test]
+    fn subtype() {
+        let mut data = BytesMut::new();
+        AuxCBox::new(b"urn:example", &[1, 0, 2]).put_buf(&mut data);
+        let auxc = AuxCBox::parse(&mut data).unwrap();
+        assert_eq!(auxc.aux_type(), b"urn:example");
+        assert_eq!(auxc.aux_subtype(), [1, 0, 2]);
+    }
+
+    #[test]
+    fn missing_terminator() {
+        let mut data = BytesMut::new();
+        FullBoxHeader::default().put_buf(&mut data);
+        data.put_slice(AuxCBox::ALPHA_URN);
+        let err = AuxCBox::parse(&mut data).unwrap_err();
+        assert_matches!(err.get_ref(), ParseError::InvalidInput, "{err:?}");
+    }
+
+    #[test]
+    fn auxi_roundtrip() {
+        let mut data = BytesMut::new();
+        AuxiBox::new(AuxCBox::ALPHA_URN).put_buf(&mut data);
+        let auxi = AuxiBox::parse(&mut data).unwrap();
+        assert_eq!(auxi.aux_track_type(), AuxCBox::ALPHA_URN);
+    }
+
+    #[test]
+    fn auxi_missing_terminator() {
+        let mut data = BytesMut::new();
+        FullBoxHeader::default().put_buf(&mut data);
+        data.put_slice(AuxCBox::ALPHA_URN);
+        let err = AuxiBox::parse(&mut data).unwrap_err();
+        assert_matches!(err.get_ref(), ParseError::InvalidInput, "{err:?}");
+    }
+}
@@ -0,0 +1,173 @@
+#![allow(missing_docs)]
+
+use alloc::format;
+
+use bytes::{Buf, BytesMut};
+
+use crate::error::Result;
+
+use super::error::{ParseResultExt, WhileParsingField};
+use super::{BoxType, Mp4ValueReaderExt, ParseBox, ParseError, ParsedBox, UnboundedArray};
+
+/// The AV1 codec configuration box, containing an `AV1CodecConfigurationRecord`.
+#[derive(Clone, Debug, ParsedBox)]
+pub struct Av1CBox {
+    /// The `marker` and `version` fields.
+    marker_version: u8,
+    /// The `seq_profile` and `seq_level_idx_0` fields.
+    seq_profile_level: u8,
+    /// The `seq_tier_0` and color configuration fields.
+    seq_tier_color_config: u8,
+    /// The `initial_presentation_delay` fields.
+    initial_presentation_delay: u8,
+    /// Configuration OBUs, validated to lie within the box.
+    config_obus: UnboundedArray<u8>,
+}
+
+const NAME: BoxType = BoxType::AV1C;
+
+const MARKER_VERSION: u8 = 0x81;
+
+const OBU_EXTENSION_FLAG: u8 = 0x04;
+const OBU_HAS_SIZE_FIELD: u8 = 0x02;
+
+/// The maximum encoded length of a `leb128()` value, per the AV1 specification.
+const MAX_LEB128_LEN: usize = 8;
+
+impl Av1CBox {
+    pub fn seq_profile(&self) -> u8 {
+        self.seq_profile_level >> 5
+    }
+
+    pub fn seq_level_idx_0(&self) -> u8 {
+        self.seq_profile_level & 0x1f
+    }
+
+    pub fn seq_tier_0(&self) -> bool {
+        self.seq_tier_color_config & 0x80 != 0
+    }
+}
+
+impl ParseBox for Av1CBox {
+    fn parse(buf: &mut BytesMut) -> Result<Self, ParseError> {
+        let marker_version = buf.get_mp4_value().while_parsing_field(NAME, "marker_version")?;
+        ensure_attach!(
+            marker_version == MARKER_VERSION,
+            ParseError::InvalidInput,
+            format!("invalid marker and version {marker_version:#04x}"),
+            WhileParsingField(NAME, "marker_version"),
+        );
+        let seq_profile_level = buf.get_mp4_value().while_parsing_field(NAME, "seq_profile_level")?;
+        let seq_tier_color_config = buf.get_mp4_value().while_parsing_field(NAME, "seq_tier_color_config")?;
+        let initial_presentation_delay = buf
+            .get_mp4_value()
+            .while_parsing_field(NAME, "initial_presentation_delay")?;
+        validate_config_obus(&buf[..])?;
+        let config_obus = buf.get_mp4_value().while_parsing_field(NAME, "config_obus")?;
+        Ok(Self { marker_version, seq_profile_level, seq_tier_color_config, initial_presentation_delay, config_obus })
+    }
+
+    fn box_type() -> BoxType {
+        NAME
+    }
+}
+
+/// Validate that the sizes of any OBUs in `config_obus` lie within it.
+fn validate_config_obus(mut config_obus: &[u8]) -> Result<(), ParseError> {
+    while config_obus.has_remaining() {
+        let obu_header = config_obus.get_u8();
+        if obu_header & OBU_EXTENSION_FLAG != 0 {
+            ensure_attach!(
+                config_obus.has_remaining(),
+                ParseError::TruncatedBox,
+                WhileParsingField(NAME, "config_obus")
+            );
+            config_obus.advance(1);
+        }
+        if obu_header & OBU_HAS_SIZE_FIELD == 0 {
+            // The OBU extends to the end of the box.
+            break;
+        }
+        let obu_size = get_leb128(&mut config_obus)?;
+        ensure_attach!(
+            config_obus.remaining() as u64 >= obu_size,
+            ParseError::TruncatedBox,
+            "OBU size exceeds box size",
+            WhileParsingField(NAME, "config_obus"),
+        );
+        config_obus.advance(obu_size as usize);
+    }
+    Ok(())
+}
+
+fn get_leb128(buf: &mut &[u8]) -> Result<u64, ParseError> {
+    let mut value = 0;
+    for byte_idx in 0..MAX_LEB128_LEN {
+        ensure_attach!(
+            buf.has_remaining(),
+            ParseError::TruncatedBox,
+            WhileParsingField(NAME, "config_obus")
+        );
+        let byte = buf.get_u8();
+        value |= u64::from(byte & 0x7f) << (byte_idx * 7);
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    bail_attach!(
+        ParseError::InvalidInput,
+        "OBU size too long",
+        WhileParsingField(NAME, "config_obus")
+    );
+}
+
+#[cfg(test)]
+mod test {
+    use assert_matches::assert_matches;
+    use bytes::BufMut;
+
+    use super::*;
+
+    /// Write an `av1C` box's data, for Main profile level 4.0 with a sequence header OBU.
+    fn write_test_av1c_data(out: &mut BytesMut, obu_size: u8) {
+        out.put_u8(MARKER_VERSION);
+        out.put_u8(0x08); // Main profile, level 4.0
+        out.put_u8(0x0c); // main tier, 4:2:0 subsampling
+        out.put_u8(0); // no initial presentation delay
+        out.put_u8(0x0a); // sequence header OBU with size field
+        out.put_u8(obu_size);
+        out.put_bytes(0, 10);
+    }
+
+    #[test]
+    fn roundtrip() {
+        let mut data = BytesMut::new();
+        write_test_av1c_data(&mut data, 10);
+        let av1c = Av1CBox::parse(&mut data.clone()).unwrap();
+        assert_eq!(av1c.seq_profile(), 0);
+        assert_eq!(av1c.seq_level_idx_0(), 8);
+        assert!(!av1c.seq_tier_0());
+
+        let mut encoded = BytesMut::new();
+        av1c.put_buf(&mut encoded);
+        assert_eq!(av1c.encoded_len(), encoded.len() as u64);
+        assert_eq!(encoded, data);
+    }
+
+    #[test]
+    fn obu_size_too_large() {
+        let mut data = BytesMut::new();
+        write_test_av1c_data(&mut data, 11);
+        let err = Av1CBox::parse(&mut data).unwrap_err();
+        assert_matches!(err.get_ref(), ParseError::TruncatedBox, "{err:?}");
+    }
+
+    #[test]
+    fn invalid_marker() {
+        let mut data = BytesMut::new();
+        write_test_av1c_data(&mut data, 10);
+        data[0] = 0x01;
+        let err = Av1CBox::parse(&mut data).unwrap_err();
+        assert_matches!(err.get_ref(), ParseError::InvalidInput, "{err:?}");
+    }
+}
@@ -0,0 +1,88 @@
+#![allow(missing_docs)]
+
+use super::{ConstFullBoxHeader, ParseBox, ParsedBox};
+
+/// The coding constraints box, used in HEIF/AVIF image sequence sample entries.
+#[derive(Clone, Debug, Default, ParseBox, ParsedBox)]
+#[box_type = "ccst"]
+pub struct CcstBox {
+    header: ConstFullBoxHeader,
+    constraints: u32,
+}
+
+impl CcstBox {
+    const ALL_REF_PICS_INTRA: u32 = 0x8000_0000;
+    const INTRA_PRED_USED: u32 = 0x4000_0000;
+    const MAX_REF_PER_PIC_SHIFT: u32 = 26;
+
+    const MAX_REF_PER_PIC_MASK: u8 = 0xf;
+
+    /// Construct a new `ccst` box.
+    ///
+    /// Only the low 4 bits of `max_ref_per_pic` are encoded; a value of 15 means any number of reference images.
+    pub fn new(all_ref_pics_intra: bool, intra_pred_used: bool, max_ref_per_pic: u8) -> Self {
+        let max_ref_per_pic = max_ref_per_pic & Self::MAX_REF_PER_PIC_MASK;
+        let mut constraints = u32::from(max_ref_per_pic) << Self::MAX_REF_PER_PIC_SHIFT;
+        if all_ref_pics_intra {
+            constraints |= Self::ALL_REF_PICS_INTRA;
+        }
+        if intra_pred_used {
+            constraints |= Self::INTRA_PRED_USED;
+        }
+        Self { header: Default::default(), constraints }
+    }
+
+    pub fn all_ref_pics_intra(&self) -> bool {
+        self.constraints & Self::ALL_REF_PICS_INTRA != 0
+    }
+
+    pub fn intra_pred_used(&self) -> bool {
+        self.constraints & Self::INTRA_PRED_USED != 0
+    }
+
+    /// The maximum number of reference images used to predict any image, where 15 means any number.
+    pub fn max_ref_per_pic(&self) -> u8 {
+        (self.constraints >> Self::MAX_REF_PER_PIC_SHIFT) as u8 & Self::MAX_REF_PER_PIC_MASK
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use assert_matches::assert_matches;
+    use bytes::{BufMut, BytesMut};
+
+    use crate::parse::{BoxHeader, Mp4Box, Mp4Value, ParseError};
+
+    use super::*;
+
+    #[test]
+    fn roundtrip() {
+        let mut buf = BytesMut::new();
+        CcstBox::new(true, false, 15).put_buf(&mut buf);
+        let ccst = CcstBox::parse(&mut buf).unwrap();
+        assert!(ccst.all_ref_pics_intra());
+        assert!(!ccst.intra_pred_used());
+        assert_eq!(ccst.max_ref_per_pic(), 15);
+    }
+
+    #[test]
+    fn max_ref_per_pic_masked() {
+        let ccst = CcstBox::new(false, false, 0x1f);
+        assert!(!ccst.all_ref_pics_intra());
+        assert!(!ccst.intra_pred_used());
+        assert_eq!(ccst.max_ref_per_pic(), 15);
+    }
+
+    #[test]
+    fn extra_data() {
+        let mut buf = BytesMut::new();
+        let ccst = CcstBox::new(false, true, 1);
+        BoxHeader::with_u32_data_size(CcstBox::box_type(), ccst.encoded_len() as u32 + 1).put_buf(&mut buf);
+        ccst.put_buf(&mut buf);
+        buf.put_u8(0);
+
+        let mut ccst = Mp4Box::<CcstBox>::parse(&mut buf).unwrap();
+        let err = ccst.data.parse().unwrap_err();
+        assert_matches!(err.get_ref(), ParseError::InvalidInput, "{err:?}");
+    }
+}
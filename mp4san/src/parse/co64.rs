@@ -1,6 +1,8 @@
 #![allow(missing_docs)]
 
-use super::{ArrayEntryMut, BoundedArray, ConstFullBoxHeader, ParseBox, ParsedBox};
+use crate::error::Result;
+
+use super::{ArrayEntry, ArrayEntryMut, BoundedArray, ConstFullBoxHeader, ParseBox, ParseError, ParsedBox};
 
 #[derive(Clone, Debug, Default, ParseBox, ParsedBox)]
 #[box_type = "co64"]
@@ -10,10 +12,19 @@ pub struct Co64Box {
 }
 
 impl Co64Box {
-    pub fn entries_mut(&mut self) -> impl Iterator<Item = ArrayEntryMut<'_, u64>> + ExactSizeIterator + '_ {
+    pub fn entries(&self) -> impl ExactSizeIterator<Item = ArrayEntry<'_, u64>> + '_ {
+        self.entries.entries()
+    }
+
+    pub fn entries_mut(&mut self) -> impl ExactSizeIterator<Item = ArrayEntryMut<'_, u64>> + '_ {
         self.entries.entries_mut()
     }
 
+    /// The chunk offsets.
+    pub fn values(&self) -> impl ExactSizeIterator<Item = Result<u64, ParseError>> + '_ {
+        self.entries.values()
+    }
+
     pub fn entry_count(&self) -> u32 {
         self.entries.entry_count()
     }
@@ -27,9 +38,10 @@ impl FromIterator<u64> for Co64Box {
 
 #[cfg(test)]
 mod test {
-    use bytes::BytesMut;
+    use assert_matches::assert_matches;
+    use bytes::{BufMut, BytesMut};
 
-    use crate::parse::{ParseBox, ParsedBox};
+    use crate::parse::{FullBoxHeader, Mp4Value, ParseBox, ParseError, ParsedBox};
 
     use super::Co64Box;
 
@@ -39,4 +51,14 @@ mod test {
         Co64Box::default().put_buf(&mut buf);
         Co64Box::parse(&mut buf).unwrap();
     }
+
+    #[test]
+    fn entry_count_overflow() {
+        let mut buf = BytesMut::new();
+        FullBoxHeader::default().put_buf(&mut buf);
+        buf.put_u32(u32::MAX); // entry count
+        buf.put_u64(0); // chunk offset
+        let err = Co64Box::parse(&mut buf).unwrap_err();
+        assert_matches!(err.get_ref(), ParseError::TruncatedBox, "{err:?}");
+    }
 }
@@ -0,0 +1,155 @@
+#![allow(missing_docs)]
+
+use alloc::format;
+use alloc::vec::Vec;
+
+use bytes::BytesMut;
+
+use crate::error::Result;
+
+use super::error::{ParseResultExt, WhileParsingField};
+use super::{BoxType, FourCC, Mp4ValueReaderExt, ParseBox, ParseError, ParsedBox, UnboundedArray};
+
+/// The colour information box, describing the colour representation of a visual sample entry.
+///
+/// Only the `nclx` colour type, containing ISO/IEC 23091-2 coding-independent code points, is interpreted. Other colour
+/// types, such as ICC profiles (`rICC` or `prof`), are kept as-is.
+#[derive(Clone, Debug, ParsedBox)]
+pub struct ColrBox {
+    colour_type: FourCC,
+    colour_info: UnboundedArray<u8>,
+}
+
+/// The coding-independent code points of an `nclx` [`ColrBox`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NclxColour {
+    pub colour_primaries: u16,
+    pub transfer_characteristics: u16,
+    pub matrix_coefficients: u16,
+    pub full_range_flag: bool,
+}
+
+const NAME: BoxType = BoxType::COLR;
+
+const NCLX: FourCC = FourCC::from_str("nclx");
+
+/// The length of the `nclx` colour information, following the colour type.
+const NCLX_LEN: usize = 7;
+
+/// The bit of the last byte of the `nclx` colour information containing the full range flag; the rest are reserved.
+const FULL_RANGE_FLAG: u8 = 0x80;
+
+impl ColrBox {
+    pub fn new_nclx(nclx: NclxColour) -> Self {
+        let mut colour_info = Vec::with_capacity(NCLX_LEN);
+        colour_info.extend_from_slice(&nclx.colour_primaries.to_be_bytes());
+        colour_info.extend_from_slice(&nclx.transfer_characteristics.to_be_bytes());
+        colour_info.extend_from_slice(&nclx.matrix_coefficients.to_be_bytes());
+        colour_info.push(if nclx.full_range_flag { FULL_RANGE_FLAG } else { 0 });
+        Self { colour_type: NCLX, colour_info: colour_info.into_iter().collect() }
+    }
+
+    pub fn colour_type(&self) -> FourCC {
+        self.colour_type
+    }
+
+    /// The coding-independent code points, if the [colour type](Self::colour_type) is `nclx`.
+    pub fn nclx(&self) -> Option<NclxColour> {
+        if self.colour_type != NCLX {
+            return None;
+        }
+        let &[p0, p1, t0, t1, m0, m1, full_range] = self.colour_info.as_bytes() else {
+            return None;
+        };
+        Some(NclxColour {
+            colour_primaries: u16::from_be_bytes([p0, p1]),
+            transfer_characteristics: u16::from_be_bytes([t0, t1]),
+            matrix_coefficients: u16::from_be_bytes([m0, m1]),
+            full_range_flag: full_range & FULL_RANGE_FLAG != 0,
+        })
+    }
+}
+
+impl ParseBox for ColrBox {
+    fn parse(buf: &mut BytesMut) -> Result<Self, ParseError> {
+        let colour_type = buf.get_mp4_value().while_parsing_field(NAME, "colour_type")?;
+        if colour_type == NCLX {
+            ensure_attach!(
+                buf.len() == NCLX_LEN,
+                ParseError::InvalidInput,
+                format!("nclx colour information length {} is not {NCLX_LEN}", buf.len()),
+                WhileParsingField(NAME, "colour_info"),
+            );
+            let full_range = buf[NCLX_LEN - 1];
+            ensure_attach!(
+                full_range & !FULL_RANGE_FLAG == 0,
+                ParseError::InvalidInput,
+                format!("nonzero reserved bits 0x{:02x}", full_range & !FULL_RANGE_FLAG),
+                WhileParsingField(NAME, "full_range_flag"),
+            );
+        }
+        let colour_info = buf.get_mp4_value().while_parsing_field(NAME, "colour_info")?;
+        Ok(Self { colour_type, colour_info })
+    }
+
+    fn box_type() -> BoxType {
+        NAME
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use assert_matches::assert_matches;
+    use bytes::BufMut;
+
+    use super::*;
+
+    const BT709_FULL_RANGE: NclxColour =
+        NclxColour { colour_primaries: 1, transfer_characteristics: 1, matrix_coefficients: 1, full_range_flag: true };
+
+    #[test]
+    fn nclx() {
+        let mut data = BytesMut::new();
+        ColrBox::new_nclx(BT709_FULL_RANGE).put_buf(&mut data);
+        assert_eq!(&data[..], b"nclx\x00\x01\x00\x01\x00\x01\x80");
+        let colr = ColrBox::parse(&mut data).unwrap();
+        assert_eq!(colr.colour_type(), NCLX);
+        assert_eq!(colr.nclx(), Some(BT709_FULL_RANGE));
+
+        let mut data = BytesMut::new();
+        ColrBox::new_nclx(NclxColour { full_range_flag: false, ..BT709_FULL_RANGE }).put_buf(&mut data);
+        assert!(!ColrBox::parse(&mut data).unwrap().nclx().unwrap().full_range_flag);
+    }
+
+    #[test]
+    fn nclx_reserved_bits() {
+        let mut data = BytesMut::new();
+        ColrBox::new_nclx(BT709_FULL_RANGE).put_buf(&mut data);
+        *data.last_mut().unwrap() |= 0x01;
+        let err = ColrBox::parse(&mut data).unwrap_err();
+        assert_matches!(err.get_ref(), ParseError::InvalidInput, "{err:?}");
+    }
+
+    #[test]
+    fn nclx_truncated() {
+        let mut data = BytesMut::new();
+        ColrBox::new_nclx(BT709_FULL_RANGE).put_buf(&mut data);
+        data.truncate(data.len() - 1);
+        let err = ColrBox::parse(&mut data).unwrap_err();
+        assert_matches!(err.get_ref(), ParseError::InvalidInput, "{err:?}");
+    }
+
+    #[test]
+    fn icc_profile() {
+        let mut data = BytesMut::new();
+        data.put_slice(b"prof");
+        data.put_bytes(0xff, 16);
+        let colr = ColrBox::parse(&mut data.clone()).unwrap();
+        assert_eq!(colr.colour_type(), FourCC::from_str("prof"));
+        assert_eq!(colr.nclx(), None);
+
+        let mut encoded = BytesMut::new();
+        colr.put_buf(&mut encoded);
+        assert_eq!(encoded, data);
+    }
+}
@@ -0,0 +1,96 @@
+#![allow(missing_docs)]
+
+use alloc::format;
+
+use bytes::BytesMut;
+
+use crate::error::Result;
+
+use super::error::{ParseResultExt, WhileParsingField};
+use super::mdhd::decode_language;
+use super::{BoxType, ConstFullBoxHeader, Mp4ValueReaderExt, NullTerminatedString, ParseBox, ParseError, ParsedBox};
+
+/// The copyright box, containing a copyright notice for its enclosing movie or track in a given language.
+#[derive(Clone, Debug, ParsedBox)]
+pub struct CprtBox {
+    header: ConstFullBoxHeader,
+    language: u16,
+    notice: NullTerminatedString,
+}
+
+const NAME: BoxType = BoxType::CPRT;
+
+impl CprtBox {
+    /// Construct a [`CprtBox`] from a packed ISO 639-2/T language code and a notice, not including its null terminator.
+    pub fn new(language: u16, notice: &[u8]) -> Self {
+        Self { header: Default::default(), language, notice: notice.into() }
+    }
+
+    /// The ISO 639-2/T language code of the [`notice`](Self::notice).
+    pub fn language(&self) -> [u8; 3] {
+        decode_language(self.language).unwrap_or_else(|| unreachable!())
+    }
+
+    /// The copyright notice, not including its null terminator.
+    pub fn notice(&self) -> &[u8] {
+        self.notice.as_bytes()
+    }
+}
+
+impl ParseBox for CprtBox {
+    fn parse(buf: &mut BytesMut) -> Result<Self, ParseError> {
+        let header = buf.get_mp4_value().while_parsing_field(NAME, "header")?;
+        let language = buf.get_mp4_value().while_parsing_field(NAME, "language")?;
+        ensure_attach!(
+            decode_language(language).is_some(),
+            ParseError::InvalidInput,
+            format!("invalid packed language code 0x{language:04x}"),
+            WhileParsingField(NAME, "language"),
+        );
+        let notice = buf.get_mp4_value().while_parsing_field(NAME, "notice")?;
+        Ok(Self { header, language, notice })
+    }
+
+    fn box_type() -> BoxType {
+        NAME
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use assert_matches::assert_matches;
+    use bytes::BufMut;
+
+    use crate::parse::{FullBoxHeader, Mp4Value};
+
+    use super::*;
+
+    const ENG: u16 = 0x15c7;
+
+    #[test]
+    fn notice() {
+        let mut data = BytesMut::new();
+        CprtBox::new(ENG, b"(c) 2024 Example").put_buf(&mut data);
+        let cprt = CprtBox::parse(&mut data).unwrap();
+        assert_eq!(cprt.language(), *b"eng");
+        assert_eq!(cprt.notice(), b"(c) 2024 Example");
+    }
+
+    #[test]
+    fn invalid_language() {
+        let mut data = BytesMut::new();
+        CprtBox::new(0x8000 | ENG, b"(c) 2024 Example").put_buf(&mut data);
+        let err = CprtBox::parse(&mut data).unwrap_err();
+        assert_matches!(err.get_ref(), ParseError::InvalidInput, "{err:?}");
+    }
+
+    #[test]
+    fn missing_terminator() {
+        let mut data = BytesMut::new();
+        FullBoxHeader::default().put_buf(&mut data);
+        data.put_u16(ENG);
+        data.put_slice(b"(c) 2024 Example");
+        let err = CprtBox::parse(&mut data).unwrap_err();
+        assert_matches!(err.get_ref(), ParseError::InvalidInput, "{err:?}");
+    }
+}
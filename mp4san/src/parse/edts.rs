@@ -0,0 +1,88 @@
+#![allow(missing_docs)]
+
+use crate::error::Result;
+
+use super::error::{MultipleBoxes, ParseResultExt, WhileParsingField};
+use super::{BoxType, Boxes, BoxesValidator, ElstBox, ParseBox, ParseError, ParsedBox};
+
+/// The edit box, containing a track's edit list.
+#[derive(Clone, Debug, ParseBox, ParsedBox)]
+#[box_type = "edts"]
+pub struct EdtsBox {
+    children: Boxes<EdtsChildrenValidator>,
+}
+
+pub(crate) struct EdtsChildrenValidator;
+
+const NAME: BoxType = BoxType::EDTS;
+
+impl EdtsBox {
+    #[cfg(test)]
+    pub(crate) fn with_children<C: Into<Boxes<EdtsChildrenValidator>>>(children: C) -> Self {
+        Self { children: children.into() }
+    }
+
+    /// The edit list, if any.
+    ///
+    /// An edit box without an edit list is treated as having no edit list.
+    pub fn elst_mut(&mut self) -> Result<Option<&mut ElstBox>, ParseError> {
+        self.children
+            .get_optional_mut()
+            .while_parsing_child(NAME, BoxType::ELST)
+    }
+}
+
+impl BoxesValidator for EdtsChildrenValidator {
+    fn validate<V>(children: &Boxes<V>) -> Result<(), ParseError> {
+        let elst_count = children
+            .box_types()
+            .filter(|box_type| *box_type == BoxType::ELST)
+            .count();
+        ensure_attach!(
+            elst_count <= 1,
+            ParseError::InvalidBoxLayout,
+            MultipleBoxes(BoxType::ELST),
+            WhileParsingField(NAME, "children"),
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use assert_matches::assert_matches;
+    use bytes::BytesMut;
+
+    use crate::parse::{ElstEntry, Mp4Box};
+
+    use super::*;
+
+    fn test_elst() -> Mp4Box<ElstBox> {
+        let entry = ElstEntry { segment_duration: 1000, media_time: 0, media_rate: 0x10000 };
+        Mp4Box::with_data(ElstBox::new([entry]).into()).unwrap()
+    }
+
+    #[test]
+    fn one_elst() {
+        let mut data = BytesMut::new();
+        EdtsBox::with_children(vec![test_elst().into()]).put_buf(&mut data);
+        let mut edts = EdtsBox::parse(&mut data).unwrap();
+        assert_eq!(edts.elst_mut().unwrap().unwrap().entry_count(), 1);
+    }
+
+    #[test]
+    fn no_elst() {
+        let mut data = BytesMut::new();
+        EdtsBox::with_children(vec![]).put_buf(&mut data);
+        let mut edts = EdtsBox::parse(&mut data).unwrap();
+        assert!(edts.elst_mut().unwrap().is_none());
+    }
+
+    #[test]
+    fn multiple_elst() {
+        let mut data = BytesMut::new();
+        EdtsBox::with_children(vec![test_elst().into(), test_elst().into()]).put_buf(&mut data);
+        let err = EdtsBox::parse(&mut data).unwrap_err();
+        assert_matches!(err.get_ref(), ParseError::InvalidBoxLayout, "{err:?}");
+    }
+}
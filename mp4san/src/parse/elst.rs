@@ -0,0 +1,194 @@
+#![allow(missing_docs)]
+
+use alloc::format;
+use alloc::vec::Vec;
+
+use bytes::{BufMut, BytesMut};
+
+use crate::error::Result;
+
+use super::error::{ParseResultExt, WhileParsingField};
+use super::{BoundedArray, BoxType, FullBoxHeader, Mp4Value, Mp4ValueReaderExt, ParseBox, ParseError, ParsedBox};
+
+/// The edit list box, mapping the presentation timeline to the media timeline of a track.
+#[derive(Clone, Debug)]
+pub struct ElstBox {
+    header: FullBoxHeader,
+    entries: ElstEntries,
+}
+
+/// A single edit within an [`ElstBox`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ElstEntry {
+    /// The duration of the edit, in the movie timescale.
+    pub segment_duration: u64,
+    /// The starting time of the edit within the media, in the media timescale, or `-1` for an empty edit.
+    pub media_time: i64,
+    /// The playback rate of the edit, as a 16.16 fixed-point number.
+    pub media_rate: i32,
+}
+
+#[derive(Clone, Debug)]
+enum ElstEntries {
+    /// Entries of `[segment_duration, media_time, media_rate]` with 32-bit times.
+    V0(BoundedArray<u32, [u32; 3]>),
+    /// Entries of `[segment_duration, media_time, media_rate]` with 64-bit times, split into 32-bit words.
+    V1(BoundedArray<u32, [u32; 5]>),
+}
+
+const NAME: BoxType = BoxType::ELST;
+
+impl ElstBox {
+    /// Construct an [`ElstBox`], using version 1 if any entry's times do not fit in 32 bits.
+    pub fn new<I: IntoIterator<Item = ElstEntry>>(entries: I) -> Self {
+        let entries = entries.into_iter().collect::<Vec<_>>();
+        let fits_v0 = entries
+            .iter()
+            .all(|entry| u32::try_from(entry.segment_duration).is_ok() && i32::try_from(entry.media_time).is_ok());
+        if fits_v0 {
+            let entries = entries.iter().map(|entry| {
+                [
+                    entry.segment_duration as u32,
+                    entry.media_time as i32 as u32,
+                    entry.media_rate as u32,
+                ]
+            });
+            Self { header: FullBoxHeader { version: 0, flags: 0 }, entries: ElstEntries::V0(entries.collect()) }
+        } else {
+            let entries = entries.iter().map(|entry| {
+                let media_time = entry.media_time as u64;
+                [
+                    (entry.segment_duration >> 32) as u32,
+                    entry.segment_duration as u32,
+                    (media_time >> 32) as u32,
+                    media_time as u32,
+                    entry.media_rate as u32,
+                ]
+            });
+            Self { header: FullBoxHeader { version: 1, flags: 0 }, entries: ElstEntries::V1(entries.collect()) }
+        }
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = Result<ElstEntry, ParseError>> + '_ {
+        let (v0, v1) = match &self.entries {
+            ElstEntries::V0(entries) => (Some(entries.entries()), None),
+            ElstEntries::V1(entries) => (None, Some(entries.entries())),
+        };
+        let v0 = v0.into_iter().flatten().map(|entry| {
+            let [segment_duration, media_time, media_rate] = entry.get()?;
+            Ok(ElstEntry {
+                segment_duration: segment_duration.into(),
+                media_time: (media_time as i32).into(),
+                media_rate: media_rate as i32,
+            })
+        });
+        let v1 = v1.into_iter().flatten().map(|entry| {
+            let [duration_hi, duration_lo, media_time_hi, media_time_lo, media_rate] = entry.get()?;
+            Ok(ElstEntry {
+                segment_duration: u64::from(duration_hi) << 32 | u64::from(duration_lo),
+                media_time: (u64::from(media_time_hi) << 32 | u64::from(media_time_lo)) as i64,
+                media_rate: media_rate as i32,
+            })
+        });
+        v0.chain(v1)
+    }
+
+    pub fn entry_count(&self) -> u32 {
+        match &self.entries {
+            ElstEntries::V0(entries) => entries.entry_count(),
+            ElstEntries::V1(entries) => entries.entry_count(),
+        }
+    }
+}
+
+impl ParseBox for ElstBox {
+    fn parse(buf: &mut BytesMut) -> Result<Self, ParseError> {
+        let header: FullBoxHeader = buf.get_mp4_value().while_parsing_field(NAME, "header")?;
+        let entries = match header.version {
+            0 => ElstEntries::V0(buf.get_mp4_value().while_parsing_field(NAME, "entries")?),
+            1 => ElstEntries::V1(buf.get_mp4_value().while_parsing_field(NAME, "entries")?),
+            version => bail_attach!(
+                ParseError::InvalidInput,
+                format!("unsupported version {version}"),
+                WhileParsingField(NAME, "header"),
+            ),
+        };
+        Ok(Self { header, entries })
+    }
+
+    fn box_type() -> BoxType {
+        NAME
+    }
+}
+
+impl ParsedBox for ElstBox {
+    fn encoded_len(&self) -> u64 {
+        let entries_len = match &self.entries {
+            ElstEntries::V0(entries) => entries.encoded_len(),
+            ElstEntries::V1(entries) => entries.encoded_len(),
+        };
+        self.header.encoded_len() + entries_len
+    }
+
+    fn put_buf(&self, mut out: &mut dyn BufMut) {
+        self.header.put_buf(&mut out);
+        match &self.entries {
+            ElstEntries::V0(entries) => entries.put_buf(&mut out),
+            ElstEntries::V1(entries) => entries.put_buf(&mut out),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const ENTRIES: [ElstEntry; 2] = [
+        ElstEntry { segment_duration: 1000, media_time: -1, media_rate: 0x10000 },
+        ElstEntry { segment_duration: 9000, media_time: 512, media_rate: 0x10000 },
+    ];
+
+    fn roundtrip(elst: ElstBox) -> Vec<ElstEntry> {
+        let mut data = BytesMut::new();
+        elst.put_buf(&mut data);
+        let elst = ElstBox::parse(&mut data.clone()).unwrap();
+
+        let mut encoded = BytesMut::new();
+        elst.put_buf(&mut encoded);
+        assert_eq!(elst.encoded_len(), encoded.len() as u64);
+        assert_eq!(encoded, data);
+        elst.entries().collect::<Result<_, _>>().unwrap()
+    }
+
+    #[test]
+    fn version_0() {
+        let elst = ElstBox::new(ENTRIES);
+        assert_eq!(elst.header.version, 0);
+        assert_eq!(roundtrip(elst), ENTRIES);
+    }
+
+    #[test]
+    fn empty_edit_byte_for_byte() {
+        let mut data = BytesMut::new();
+        FullBoxHeader { version: 0, flags: 0 }.put_buf(&mut data);
+        [1, 1000, u32::MAX, 0x10000].put_buf(&mut data);
+        let elst = ElstBox::parse(&mut data.clone()).unwrap();
+        let entries = elst.entries().collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(
+            entries,
+            [ElstEntry { segment_duration: 1000, media_time: -1, media_rate: 0x10000 }]
+        );
+
+        let mut encoded = BytesMut::new();
+        elst.put_buf(&mut encoded);
+        assert_eq!(encoded, data);
+    }
+
+    #[test]
+    fn version_1() {
+        let entries = [ElstEntry { segment_duration: u64::MAX, ..ENTRIES[0] }, ENTRIES[1]];
+        let elst = ElstBox::new(entries);
+        assert_eq!(elst.header.version, 1);
+        assert_eq!(roundtrip(elst), entries);
+    }
+}
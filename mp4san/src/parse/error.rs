@@ -1,6 +1,6 @@
 //! Error types returned by the unstable parsing API.
 
-use std::fmt::{Debug, Display};
+use core::fmt::{Debug, Display};
 
 use derive_more::Display;
 use mediasan_common::error::{ReportStack, ReportableError};
@@ -14,37 +14,37 @@ use super::{BoxType, FourCC};
 /// While the API of this error type is currently considered unstable, it is more stably guaranteed to implement
 /// [`Display`] + [`Debug`].
 #[allow(missing_docs)]
-#[derive(Clone, Debug, thiserror::Error)]
+#[derive(Clone, Debug, Display)]
 pub enum ParseError {
     /// The input is invalid because its boxes are in a ordering or configuration disallowed by the ISO specification.
-    #[error("Invalid box layout")]
+    #[display(fmt = "Invalid box layout")]
     InvalidBoxLayout,
 
     /// The input is invalid.
-    #[error("Invalid input")]
+    #[display(fmt = "Invalid input")]
     InvalidInput,
 
     /// The input is invalid because it is missing a box required by the ISO specification.
-    #[error("Missing required `{_0}` box")]
+    #[display(fmt = "Missing required `{}` box", _0)]
     MissingRequiredBox(BoxType),
 
     /// The input is invalid because the input ended before the end of a box.
     ///
     /// This can occur either when the entire input is truncated or when a box size is incorrect.
-    #[error("Truncated box")]
+    #[display(fmt = "Truncated box")]
     TruncatedBox,
 
     /// The input is unsupported because it contains an unknown box.
-    #[error("Unsupported box `{_0}`")]
+    #[display(fmt = "Unsupported box `{}`", _0)]
     UnsupportedBox(BoxType),
 
     /// The input is unsupported because its boxes are in an unsupported ordering.
-    #[error("Unsupported box layout")]
+    #[display(fmt = "Unsupported box layout")]
     UnsupportedBoxLayout,
 
     /// The input is unsupported because it doesn't contain [`COMPATIBLE_BRAND`](crate::COMPATIBLE_BRAND) in its file
     /// type header (`ftyp`).
-    #[error("Unsupported format `{_0}`")]
+    #[display(fmt = "Unsupported format `{}`", _0)]
     UnsupportedFormat(FourCC),
 }
 
@@ -76,6 +76,11 @@ pub trait __ParseResultExt: ResultExt + Sized {
 }
 pub(crate) use self::__ParseResultExt as ParseResultExt;
 
+#[cfg(feature = "std")]
+#[derive(Clone, Copy, Debug, Display)]
+#[display(fmt = "at offset 0x{:08x}", _0)]
+pub(crate) struct AtOffset(pub(crate) u64);
+
 #[derive(Clone, Copy, Debug, Display)]
 #[display(fmt = "multiple `{}` boxes", _0)]
 pub(crate) struct MultipleBoxes(pub(crate) BoxType);
@@ -96,6 +101,9 @@ pub(crate) struct WhileParsingChild(pub(crate) BoxType, pub(crate) BoxType);
 #[display(fmt = "where `{} = {}`", _0, _1)]
 pub(crate) struct WhereEq<T, U>(pub(crate) T, pub(crate) U);
 
+#[cfg(feature = "std")]
+impl std::error::Error for ParseError {}
+
 impl ReportableError for ParseError {
     type Stack = ReportStack;
 }
@@ -1,21 +1,89 @@
 #![allow(missing_docs)]
 
-use super::{FourCC, ParseBox, ParsedBox, UnboundedArray};
+use bytes::BytesMut;
 
-#[derive(Clone, Debug, ParseBox, ParsedBox)]
-#[box_type = "ftyp"]
+use crate::error::Result;
+
+use super::error::{ParseResultExt, WhileParsingField};
+use super::{BoxType, FourCC, Mp4Prim, Mp4ValueReaderExt, ParseBox, ParseError, ParsedBox, UnboundedArray};
+
+#[derive(Clone, Debug, ParsedBox)]
 pub struct FtypBox {
     pub major_brand: FourCC,
     pub minor_version: u32,
     pub compatible_brands: UnboundedArray<FourCC>,
 }
 
+const NAME: BoxType = BoxType::FTYP;
+
+/// The encoded length of the `major_brand` and `minor_version` fields, which every `ftyp` must contain.
+const MIN_DATA_LEN: usize = 8;
+
 impl FtypBox {
     pub fn new(major_brand: FourCC, minor_version: u32, compatible_brands: impl IntoIterator<Item = FourCC>) -> Self {
         Self { major_brand, minor_version, compatible_brands: compatible_brands.into_iter().collect() }
     }
 
-    pub fn compatible_brands(&self) -> impl Iterator<Item = FourCC> + ExactSizeIterator + '_ {
+    pub fn compatible_brands(&self) -> impl ExactSizeIterator<Item = FourCC> + '_ {
         self.compatible_brands.entries().map(|entry| entry.get().unwrap())
     }
 }
+
+impl ParseBox for FtypBox {
+    fn parse(buf: &mut BytesMut) -> Result<Self, ParseError> {
+        ensure_attach!(
+            buf.len() >= MIN_DATA_LEN,
+            ParseError::TruncatedBox,
+            WhileParsingField(NAME, "minor_version")
+        );
+        ensure_attach!(
+            (buf.len() - MIN_DATA_LEN) % <FourCC as Mp4Prim>::encoded_len() as usize == 0,
+            ParseError::InvalidInput,
+            "compatible brands length is not a multiple of 4",
+            WhileParsingField(NAME, "compatible_brands"),
+        );
+        let major_brand = buf.get_mp4_value().while_parsing_field(NAME, "major_brand")?;
+        let minor_version = buf.get_mp4_value().while_parsing_field(NAME, "minor_version")?;
+        let compatible_brands = buf.get_mp4_value().while_parsing_field(NAME, "compatible_brands")?;
+        Ok(Self { major_brand, minor_version, compatible_brands })
+    }
+
+    fn box_type() -> BoxType {
+        NAME
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use assert_matches::assert_matches;
+
+    use super::*;
+
+    const ISOM: FourCC = FourCC { value: *b"isom" };
+    const MP42: FourCC = FourCC { value: *b"mp42" };
+
+    #[test]
+    fn roundtrip() {
+        let mut data = BytesMut::new();
+        FtypBox::new(MP42, 0, [ISOM, MP42]).put_buf(&mut data);
+        let ftyp = FtypBox::parse(&mut data).unwrap();
+        assert_eq!(ftyp.major_brand, MP42);
+        assert_eq!(ftyp.compatible_brands().collect::<Vec<_>>(), [ISOM, MP42]);
+    }
+
+    #[test]
+    fn truncated() {
+        let mut data = BytesMut::from(&b"mp42"[..]);
+        let err = FtypBox::parse(&mut data).unwrap_err();
+        assert_matches!(err.get_ref(), ParseError::TruncatedBox, "{err:?}");
+    }
+
+    #[test]
+    fn partial_compatible_brand() {
+        let mut data = BytesMut::new();
+        FtypBox::new(MP42, 0, [ISOM]).put_buf(&mut data);
+        data.truncate(data.len() - 1);
+        let err = FtypBox::parse(&mut data).unwrap_err();
+        assert_matches!(err.get_ref(), ParseError::InvalidInput, "{err:?}");
+    }
+}
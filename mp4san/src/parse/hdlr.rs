@@ -0,0 +1,74 @@
+#![allow(missing_docs)]
+
+use alloc::format;
+use core::str;
+
+use crate::error::Result;
+
+use super::error::WhileParsingField;
+use super::{BoxType, ConstFullBoxHeader, FourCC, ParseBox, ParseError, ParsedBox, UnboundedArray};
+
+#[derive(Clone, Debug, ParseBox, ParsedBox)]
+#[box_type = "hdlr"]
+pub struct HdlrBox {
+    header: ConstFullBoxHeader,
+    pre_defined: u32,
+    pub handler_type: FourCC,
+    reserved: [u32; 3],
+    name: UnboundedArray<u8>,
+}
+
+const NAME: BoxType = BoxType::HDLR;
+
+impl HdlrBox {
+    /// The human-readable name of the track type, up to but not including its null terminator.
+    pub fn name(&self) -> &[u8] {
+        let name = self.name.as_bytes();
+        match name.iter().position(|&byte| byte == 0) {
+            Some(terminator_idx) => &name[..terminator_idx],
+            None => name,
+        }
+    }
+
+    /// Validate that [`name`](Self::name) is valid UTF-8.
+    pub fn validate_name(&self) -> Result<(), ParseError> {
+        if let Err(err) = str::from_utf8(self.name()) {
+            bail_attach!(
+                ParseError::InvalidInput,
+                format!("invalid UTF-8 at byte {} of string", err.valid_up_to()),
+                WhileParsingField(NAME, "name"),
+            );
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use assert_matches::assert_matches;
+    use bytes::BytesMut;
+
+    use crate::parse::fourcc;
+    use crate::util::test::write_hdlr_data;
+
+    use super::*;
+
+    #[test]
+    fn name() {
+        let mut data = BytesMut::new();
+        write_hdlr_data(&mut data, fourcc::META, b"Metadata\0");
+        let hdlr = HdlrBox::parse(&mut data).unwrap();
+        assert_eq!(hdlr.handler_type, fourcc::META);
+        assert_eq!(hdlr.name(), b"Metadata");
+        hdlr.validate_name().unwrap();
+    }
+
+    #[test]
+    fn name_invalid_utf8() {
+        let mut data = BytesMut::new();
+        write_hdlr_data(&mut data, fourcc::META, b"Meta\xffdata\0");
+        let hdlr = HdlrBox::parse(&mut data).unwrap();
+        let err = hdlr.validate_name().unwrap_err();
+        assert_matches!(err.get_ref(), ParseError::InvalidInput, "{err:?}");
+    }
+}
@@ -1,13 +1,15 @@
-use std::fmt;
+use alloc::format;
+use core::fmt;
+use core::mem::size_of;
+#[cfg(feature = "std")]
 use std::io;
-use std::mem::size_of;
 
 use bytes::{Buf, BufMut};
 use derive_more::{Display, From};
-use futures_util::{pin_mut, AsyncRead, AsyncReadExt, FutureExt};
+#[cfg(feature = "std")]
+use futures_util::{pin_mut, AsyncBufRead, AsyncBufReadExt, AsyncRead, AsyncReadExt, FutureExt};
 
 use crate::error::Result;
-use crate::sync::buf_async_reader;
 
 use super::error::WhileParsingBox;
 use super::{FourCC, Mp4Prim, ParseError};
@@ -91,16 +93,53 @@ impl BoxHeader {
         Self { box_type, box_size: BoxSize::UntilEof }
     }
 
-    pub fn parse<B: Buf + Unpin>(input: B) -> Result<Self, ParseError> {
-        Self::read(buf_async_reader(input))
-            .now_or_never()
-            .unwrap()
-            .map_err(|err| {
-                assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
-                report_attach!(ParseError::TruncatedBox, "while parsing box header")
-            })
+    pub fn parse<B: Buf>(mut input: B) -> Result<Self, ParseError> {
+        fn get_bytes<const N: usize, B: Buf>(input: &mut B) -> Result<[u8; N], ParseError> {
+            ensure_attach!(
+                input.remaining() >= N,
+                ParseError::TruncatedBox,
+                "while parsing box header"
+            );
+            let mut bytes = [0; N];
+            input.copy_to_slice(&mut bytes);
+            Ok(bytes)
+        }
+
+        let size = u32::from_be_bytes(get_bytes(&mut input)?);
+        let name = FourCC { value: get_bytes(&mut input)? };
+
+        let size = match size {
+            0 => BoxSize::UntilEof,
+            1 => BoxSize::Ext(u64::from_be_bytes(get_bytes(&mut input)?)),
+            size => BoxSize::Size(size),
+        };
+
+        let name = match name {
+            fourcc::UUID => BoxType::Uuid(BoxUuid { value: get_bytes(&mut input)? }),
+            fourcc => fourcc.into(),
+        };
+
+        Ok(Self { box_type: name, box_size: size })
+    }
+
+    /// Parse the header of the next box from the data buffered in `input`, without consuming it.
+    ///
+    /// Only the data returned by a single call to [`AsyncBufReadExt::fill_buf`] is inspected, so `input` should have a
+    /// buffer capacity of at least [`MAX_SIZE`](Self::MAX_SIZE) bytes, and should have no partially-consumed data
+    /// buffered. Returns `None` if the buffered data does not contain a complete header, including at end of file.
+    #[cfg(feature = "std")]
+    pub async fn peek<R: AsyncBufRead>(input: R) -> io::Result<Option<Self>> {
+        pin_mut!(input);
+
+        let buf = input.fill_buf().await?;
+        match Self::read(buf).now_or_never().unwrap() {
+            Ok(header) => Ok(Some(header)),
+            Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => Ok(None),
+            Err(err) => Err(err),
+        }
     }
 
+    #[cfg(feature = "std")]
     pub(crate) async fn read<R: AsyncRead>(input: R) -> io::Result<Self> {
         pin_mut!(input);
 
@@ -236,32 +275,133 @@ macro_rules! box_type {
 }
 
 box_type! {
+    AV01,
+    AVC1,
+    AVC3,
+    BTRT,
+    CCST,
     CO64,
+    COLR,
+    CPRT,
+    CTTS,
     DINF,
     DREF,
+    EDTS,
+    ELST,
+    ENCA,
+    ENCV,
+    ESDS,
+    EYES,
     FREE,
     FTYP,
     HDLR,
+    HERO,
+    HEV1,
+    HMHD,
+    HVC1,
+    ILOC,
+    KIND,
     MDAT,
     MDHD,
     MDIA,
     MECO,
     META,
     METT,
+    MFHD,
     MINF,
+    MOOF,
     MOOV,
+    MP4A,
+    MVEX,
     MVHD,
+    NMHD,
+    PASP,
+    PDIN,
+    PRJI,
+    PROJ,
+    PSSH,
+    SAIO,
+    SAIZ,
+    SBGP,
+    SDTP,
+    SGPD,
+    SIDX,
     SKIP,
+    SMHD,
     STBL,
     STCO,
+    STHD,
+    STRI,
     STSC,
     STSD,
+    STSS,
     STSZ,
     STTS,
+    STYP,
+    STZ2,
+    TAIC,
+    TFHD,
     TKHD,
+    TRAF,
     TRAK,
+    TREF,
+    TRUN,
+    UDTA,
     URL,
     UUID,
+    VEXU,
+    VMHD,
+    VP08,
+    VP09,
+    WIDE,
+}
+
+/// Box types whose four-character codes are not all lowercase, and so are not generated by `box_type!`.
+#[allow(missing_docs)]
+impl BoxType {
+    /// The `avcC` box type.
+    pub const AVCC: Self = Self::FourCC(FourCC::from_str("avcC"));
+    /// The `av1C` box type.
+    pub const AV1C: Self = Self::FourCC(FourCC::from_str("av1C"));
+    /// The `hvcC` box type.
+    pub const HVCC: Self = Self::FourCC(FourCC::from_str("hvcC"));
+    /// The `vpcC` box type.
+    pub const VPCC: Self = Self::FourCC(FourCC::from_str("vpcC"));
+}
+
+impl BoxType {
+    /// The box types known to contain only other boxes, possibly following a full box header.
+    const CONTAINERS: &'static [Self] = &[
+        Self::DINF,
+        Self::EDTS,
+        Self::EYES,
+        Self::MDIA,
+        Self::MECO,
+        Self::META,
+        Self::MINF,
+        Self::MOOF,
+        Self::MOOV,
+        Self::MVEX,
+        Self::PROJ,
+        Self::STBL,
+        Self::TRAF,
+        Self::TRAK,
+        Self::UDTA,
+        Self::VEXU,
+    ];
+
+    /// Construct a box type from a four-character code.
+    pub const fn parse_fourcc(value: &[u8; 4]) -> Self {
+        Self::FourCC(FourCC { value: *value })
+    }
+
+    /// Whether this box type is known to be a container, whose data consists of child boxes, such as `moov`, `trak`,
+    /// or `stbl`.
+    ///
+    /// The children of a `meta` box follow a full box header, which QuickTime files may omit.
+    pub fn is_container(&self) -> bool {
+        Self::CONTAINERS.contains(self)
+    }
 }
 
 impl fmt::Display for BoxUuid {
@@ -334,3 +474,159 @@ impl<const VERSION: u8, const FLAGS: u32> Mp4Prim for ConstFullBoxHeader<VERSION
         out.put_uint(FLAGS.into(), 3);
     }
 }
+
+#[cfg(test)]
+mod test {
+    use assert_matches::assert_matches;
+
+    use super::*;
+
+    #[test]
+    fn parse_fourcc() {
+        assert_eq!(BoxType::parse_fourcc(b"moov"), BoxType::MOOV);
+        assert_eq!(BoxType::parse_fourcc(b"avcC"), BoxType::AVCC);
+    }
+
+    #[test]
+    fn is_container() {
+        for box_type in [
+            BoxType::DINF,
+            BoxType::EDTS,
+            BoxType::EYES,
+            BoxType::MDIA,
+            BoxType::MECO,
+            BoxType::META,
+            BoxType::MINF,
+            BoxType::MOOF,
+            BoxType::MOOV,
+            BoxType::MVEX,
+            BoxType::PROJ,
+            BoxType::STBL,
+            BoxType::TRAF,
+            BoxType::TRAK,
+            BoxType::UDTA,
+            BoxType::VEXU,
+        ] {
+            assert!(box_type.is_container(), "{box_type}");
+        }
+        for box_type in [
+            BoxType::FTYP,
+            BoxType::MDAT,
+            BoxType::FREE,
+            BoxType::MVHD,
+            BoxType::TKHD,
+            BoxType::HDLR,
+            BoxType::STSD,
+            BoxType::STCO,
+            BoxType::UUID,
+            BoxType::parse_fourcc(b"xyzw"),
+        ] {
+            assert!(!box_type.is_container(), "{box_type}");
+        }
+    }
+
+    #[test]
+    fn largesize() {
+        let mut data = vec![];
+        data.put_u32(1);
+        data.put_slice(b"mdat");
+        data.put_u64(u64::from(u32::MAX) + 16);
+
+        let header = BoxHeader::parse(&data[..]).unwrap();
+        assert_eq!(header.box_type(), BoxType::MDAT);
+        assert_eq!(header.encoded_len(), 16);
+        assert_eq!(header.box_size(), Some(u64::from(u32::MAX) + 16));
+        assert_eq!(header.box_data_size().unwrap(), Some(u64::from(u32::MAX)));
+
+        let mut encoded = vec![];
+        header.put_buf(&mut encoded);
+        assert_eq!(encoded, data);
+    }
+
+    #[test]
+    fn peek() {
+        let mut data = vec![];
+        data.put_u32(1);
+        data.put_slice(b"mdat");
+        data.put_u64(24);
+        data.put_u64(0);
+
+        let mut reader = futures_util::io::BufReader::with_capacity(BoxHeader::MAX_SIZE as usize, &data[..]);
+        let peeked = BoxHeader::peek(&mut reader).now_or_never().unwrap().unwrap();
+        assert_eq!(peeked.map(|header| header.box_type()), Some(BoxType::MDAT));
+        let header = BoxHeader::read(&mut reader).now_or_never().unwrap().unwrap();
+        assert_eq!(peeked, Some(header));
+    }
+
+    #[test]
+    fn peek_truncated() {
+        let mut data = vec![];
+        data.put_u32(1);
+        data.put_slice(b"mdat");
+
+        let mut reader = futures_util::io::BufReader::new(&data[..]);
+        let peeked = BoxHeader::peek(&mut reader).now_or_never().unwrap().unwrap();
+        assert_eq!(peeked, None);
+        assert_eq!(reader.buffer(), &data[..]);
+    }
+
+    #[test]
+    fn largesize_small_data() {
+        let mut data = vec![];
+        data.put_u32(1);
+        data.put_slice(b"free");
+        data.put_u64(24);
+
+        let header = BoxHeader::parse(&data[..]).unwrap();
+        assert_eq!(header.encoded_len(), 16);
+        assert_eq!(header.box_data_size().unwrap(), Some(8));
+
+        let mut encoded = vec![];
+        header.put_buf(&mut encoded);
+        assert_eq!(encoded, data);
+    }
+
+    #[test]
+    fn largesize_too_small() {
+        let mut data = vec![];
+        data.put_u32(1);
+        data.put_slice(b"mdat");
+        data.put_u64(15);
+
+        let header = BoxHeader::parse(&data[..]).unwrap();
+        let err = header.box_data_size().unwrap_err();
+        assert_matches!(err.get_ref(), ParseError::InvalidInput, "{err:?}");
+    }
+
+    #[test]
+    fn largesize_truncated() {
+        let mut data = vec![];
+        data.put_u32(1);
+        data.put_slice(b"mdat");
+        data.put_u32(0);
+
+        let err = BoxHeader::parse(&data[..]).unwrap_err();
+        assert_matches!(err.get_ref(), ParseError::TruncatedBox, "{err:?}");
+    }
+
+    #[test]
+    fn with_data_size() {
+        let header = BoxHeader::with_data_size(BoxType::MDAT, u64::from(u32::MAX) - 8).unwrap();
+        assert_eq!(header.encoded_len(), 8);
+        assert_eq!(header.box_size(), Some(u64::from(u32::MAX)));
+
+        for data_size in [u64::from(u32::MAX) - 7, u64::from(u32::MAX), u64::from(u32::MAX) + 1] {
+            let header = BoxHeader::with_data_size(BoxType::MDAT, data_size).unwrap();
+            assert_eq!(header.encoded_len(), 16);
+            assert_eq!(header.box_data_size().unwrap(), Some(data_size));
+
+            let mut encoded = vec![];
+            header.put_buf(&mut encoded);
+            assert_eq!(&encoded[..4], 1u32.to_be_bytes());
+            assert_eq!(BoxHeader::parse(&encoded[..]).unwrap(), header);
+        }
+
+        let err = BoxHeader::with_data_size(BoxType::MDAT, u64::MAX - 15).unwrap_err();
+        assert_matches!(err.get_ref(), ParseError::InvalidInput, "{err:?}");
+    }
+}
@@ -0,0 +1,182 @@
+#![allow(missing_docs)]
+
+use bytes::{Buf, BytesMut};
+
+use crate::error::Result;
+
+use super::error::{ParseResultExt, WhileParsingField};
+use super::{BoxType, Mp4ValueReaderExt, ParseBox, ParseError, ParsedBox, UnboundedArray};
+
+/// The HEVC configuration box, containing an H.265 `HEVCDecoderConfigurationRecord`.
+#[derive(Clone, Debug, ParsedBox)]
+pub struct HvcCBox {
+    configuration_version: u8,
+    /// The `general_profile_space`, `general_tier_flag`, and `general_profile_idc` fields.
+    general_profile: u8,
+    general_profile_compatibility_flags: u32,
+    general_constraint_indicator_flags: [u8; 6],
+    general_level_idc: u8,
+    /// The remaining fixed-size fields (chroma format, bit depths, frame rate, NAL unit length size, etc.), which are not
+    /// interpreted by the sanitizer.
+    rest: [u8; 9],
+    num_of_arrays: u8,
+    /// Arrays of parameter set and SEI NAL units, validated to lie within the box.
+    arrays: UnboundedArray<u8>,
+}
+
+const NAME: BoxType = BoxType::HVCC;
+
+impl HvcCBox {
+    pub fn configuration_version(&self) -> u8 {
+        self.configuration_version
+    }
+
+    pub fn general_profile_space(&self) -> u8 {
+        self.general_profile >> 6
+    }
+
+    pub fn general_tier_flag(&self) -> bool {
+        self.general_profile & 0x20 != 0
+    }
+
+    pub fn general_profile_idc(&self) -> u8 {
+        self.general_profile & 0x1f
+    }
+
+    pub fn general_level_idc(&self) -> u8 {
+        self.general_level_idc
+    }
+
+    pub fn num_of_arrays(&self) -> u8 {
+        self.num_of_arrays
+    }
+}
+
+impl ParseBox for HvcCBox {
+    fn parse(buf: &mut BytesMut) -> Result<Self, ParseError> {
+        let configuration_version = buf.get_mp4_value().while_parsing_field(NAME, "configuration_version")?;
+        let general_profile = buf.get_mp4_value().while_parsing_field(NAME, "general_profile")?;
+        let general_profile_compatibility_flags = buf
+            .get_mp4_value()
+            .while_parsing_field(NAME, "general_profile_compatibility_flags")?;
+        let general_constraint_indicator_flags = buf
+            .get_mp4_value()
+            .while_parsing_field(NAME, "general_constraint_indicator_flags")?;
+        let general_level_idc = buf.get_mp4_value().while_parsing_field(NAME, "general_level_idc")?;
+        let rest = buf.get_mp4_value().while_parsing_field(NAME, "rest")?;
+        let num_of_arrays = buf.get_mp4_value().while_parsing_field(NAME, "num_of_arrays")?;
+        validate_arrays(&buf[..], num_of_arrays)?;
+        let arrays = buf.get_mp4_value().while_parsing_field(NAME, "arrays")?;
+        Ok(Self {
+            configuration_version,
+            general_profile,
+            general_profile_compatibility_flags,
+            general_constraint_indicator_flags,
+            general_level_idc,
+            rest,
+            num_of_arrays,
+            arrays,
+        })
+    }
+
+    fn box_type() -> BoxType {
+        NAME
+    }
+}
+
+/// Validate that `num_of_arrays` NAL unit arrays exactly fill `arrays`.
+fn validate_arrays(mut arrays: &[u8], num_of_arrays: u8) -> Result<(), ParseError> {
+    for _ in 0..num_of_arrays {
+        // Skip the array completeness flag and NAL unit type.
+        ensure_attach!(
+            arrays.remaining() >= 3,
+            ParseError::TruncatedBox,
+            WhileParsingField(NAME, "arrays")
+        );
+        arrays.advance(1);
+        let num_nalus = arrays.get_u16();
+        for _ in 0..num_nalus {
+            ensure_attach!(
+                arrays.remaining() >= 2,
+                ParseError::TruncatedBox,
+                WhileParsingField(NAME, "arrays")
+            );
+            let nal_unit_length = arrays.get_u16().into();
+            ensure_attach!(
+                arrays.remaining() >= nal_unit_length,
+                ParseError::TruncatedBox,
+                "NAL unit length exceeds box size",
+                WhileParsingField(NAME, "arrays"),
+            );
+            arrays.advance(nal_unit_length);
+        }
+    }
+    ensure_attach!(
+        !arrays.has_remaining(),
+        ParseError::InvalidInput,
+        "extra data after NAL unit arrays",
+        WhileParsingField(NAME, "arrays"),
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use assert_matches::assert_matches;
+    use bytes::BufMut;
+
+    use super::*;
+
+    /// Write an `hvcC` box's data, for Main profile level 4.1 with a single VPS array.
+    fn write_test_hvcc_data(out: &mut BytesMut, nal_units: &[&[u8]]) {
+        out.put_u8(1); // configuration version
+        out.put_u8(0x01); // general profile space 0, main tier, Main profile
+        out.put_u32(0x6000_0000); // general profile compatibility flags
+        out.put_bytes(0, 6); // general constraint indicator flags
+        out.put_u8(123); // general level idc
+        out.put_bytes(0, 9); // rest
+        out.put_u8(1); // num of arrays
+        out.put_u8(0x80 | 32); // array completeness, VPS NAL unit type
+        out.put_u16(nal_units.len() as u16);
+        for nal_unit in nal_units {
+            out.put_u16(nal_unit.len() as u16);
+            out.put_slice(nal_unit);
+        }
+    }
+
+    #[test]
+    fn roundtrip() {
+        let mut data = BytesMut::new();
+        write_test_hvcc_data(&mut data, &[b"\x40\x01\x0c", b"\x40\x01"]);
+        let hvcc = HvcCBox::parse(&mut data.clone()).unwrap();
+        assert_eq!(hvcc.configuration_version(), 1);
+        assert_eq!(hvcc.general_profile_space(), 0);
+        assert!(!hvcc.general_tier_flag());
+        assert_eq!(hvcc.general_profile_idc(), 1);
+        assert_eq!(hvcc.general_level_idc(), 123);
+        assert_eq!(hvcc.num_of_arrays(), 1);
+
+        let mut encoded = BytesMut::new();
+        hvcc.put_buf(&mut encoded);
+        assert_eq!(hvcc.encoded_len(), encoded.len() as u64);
+        assert_eq!(encoded, data);
+    }
+
+    #[test]
+    fn nal_unit_length_too_large() {
+        let mut data = BytesMut::new();
+        write_test_hvcc_data(&mut data, &[b"\x40\x01\x0c"]);
+        data.truncate(data.len() - 1);
+        let err = HvcCBox::parse(&mut data).unwrap_err();
+        assert_matches!(err.get_ref(), ParseError::TruncatedBox, "{err:?}");
+    }
+
+    #[test]
+    fn num_of_arrays_too_large() {
+        let mut data = BytesMut::new();
+        write_test_hvcc_data(&mut data, &[b"\x40\x01\x0c"]);
+        data[22] = 2;
+        let err = HvcCBox::parse(&mut data).unwrap_err();
+        assert_matches!(err.get_ref(), ParseError::TruncatedBox, "{err:?}");
+    }
+}
@@ -0,0 +1,256 @@
+#![allow(missing_docs)]
+
+use alloc::format;
+
+use bytes::{Buf, BufMut, BytesMut};
+
+use crate::error::Result;
+
+use super::error::{ParseResultExt, WhileParsingField};
+use super::{BoxType, FullBoxHeader, Mp4Value, Mp4ValueReaderExt, ParseBox, ParseError, ParsedBox, UnboundedArray};
+
+/// The item location box, locating the extents of the items of a metadata box (`meta`), such as the images of a HEIF
+/// file.
+///
+/// The field sizes and the layout of the items are validated, but the items themselves are kept as-is.
+#[derive(Clone, Debug)]
+pub struct IlocBox {
+    header: FullBoxHeader,
+    /// The offset size in the high nibble, and the length size in the low nibble.
+    offset_length_size: u8,
+    /// The base offset size in the high nibble, and the index size (reserved in version 0) in the low nibble.
+    base_offset_index_size: u8,
+    item_count: u32,
+    items: UnboundedArray<u8>,
+}
+
+const NAME: BoxType = BoxType::ILOC;
+
+/// The valid values of each of the field size nibbles.
+const VALID_FIELD_SIZES: &[u8] = &[0, 4, 8];
+
+impl IlocBox {
+    pub fn version(&self) -> u8 {
+        self.header.version
+    }
+
+    /// The size in bytes of each extent offset.
+    pub fn offset_size(&self) -> u8 {
+        self.offset_length_size >> 4
+    }
+
+    /// The size in bytes of each extent length.
+    pub fn length_size(&self) -> u8 {
+        self.offset_length_size & 0xf
+    }
+
+    /// The size in bytes of the base offset of each item.
+    pub fn base_offset_size(&self) -> u8 {
+        self.base_offset_index_size >> 4
+    }
+
+    /// The size in bytes of each extent index, which is always `0` for version 0.
+    pub fn index_size(&self) -> u8 {
+        match self.header.version {
+            0 => 0,
+            _ => self.base_offset_index_size & 0xf,
+        }
+    }
+
+    pub fn item_count(&self) -> u32 {
+        self.item_count
+    }
+
+    /// Whether any item is located by extents at offsets within the file, such as within the media data, rather than
+    /// within the item data box (`idat`), another item, or another file.
+    pub fn has_file_offsets(&self) -> bool {
+        let mut has_file_offsets = false;
+        let _ = self.walk_items(|construction_method, data_reference_index, extent_count| {
+            has_file_offsets |= construction_method == 0 && data_reference_index == 0 && extent_count != 0;
+        });
+        has_file_offsets
+    }
+
+    /// Check that the items, with the declared item count and field sizes, fit within the box.
+    fn validate_items(&self) -> Result<(), ParseError> {
+        self.walk_items(|_, _, _| {})
+    }
+
+    /// Call `f` with the construction method, data reference index, and extent count of each item, checking that the
+    /// items fit within the box.
+    fn walk_items<F: FnMut(u16, u16, u16)>(&self, mut f: F) -> Result<(), ParseError> {
+        let overflow = || {
+            report_attach!(
+                ParseError::InvalidInput,
+                format!("{} items overflow the box", self.item_count),
+                WhileParsingField(NAME, "items"),
+            )
+        };
+        let mut items = self.items.as_bytes();
+        let item_id_len = if self.header.version < 2 { 2 } else { 4 };
+        let extent_len =
+            usize::from(self.index_size()) + usize::from(self.offset_size()) + usize::from(self.length_size());
+        for _ in 0..self.item_count {
+            items = items.get(item_id_len..).ok_or_else(overflow)?;
+            // NB: The construction method is a 4-bit field following 12 reserved bits, absent in version 0.
+            let construction_method = match self.header.version {
+                0 => 0,
+                _ => get_u16(&mut items).ok_or_else(overflow)? & 0xf,
+            };
+            let data_reference_index = get_u16(&mut items).ok_or_else(overflow)?;
+            items = items.get(usize::from(self.base_offset_size())..).ok_or_else(overflow)?;
+            let extent_count = get_u16(&mut items).ok_or_else(overflow)?;
+            let extents_len = usize::from(extent_count).checked_mul(extent_len).ok_or_else(overflow)?;
+            items = items.get(extents_len..).ok_or_else(overflow)?;
+            f(construction_method, data_reference_index, extent_count);
+        }
+        Ok(())
+    }
+}
+
+/// Read a big-endian `u16` from the start of `buf`, advancing past it, or return `None` if it's too short.
+fn get_u16(buf: &mut &[u8]) -> Option<u16> {
+    let value = buf.get(..2)?.get_u16();
+    *buf = &buf[2..];
+    Some(value)
+}
+
+impl ParseBox for IlocBox {
+    fn parse(buf: &mut BytesMut) -> Result<Self, ParseError> {
+        let header: FullBoxHeader = buf.get_mp4_value().while_parsing_field(NAME, "header")?;
+        ensure_attach!(
+            header.version <= 2,
+            ParseError::InvalidInput,
+            format!("unsupported version {}", header.version),
+            WhileParsingField(NAME, "header"),
+        );
+        let offset_length_size: u8 = buf.get_mp4_value().while_parsing_field(NAME, "offset_size")?;
+        let base_offset_index_size: u8 = buf.get_mp4_value().while_parsing_field(NAME, "base_offset_size")?;
+        let item_count = match header.version {
+            0 | 1 => buf
+                .get_mp4_value::<u16>()
+                .while_parsing_field(NAME, "item_count")?
+                .into(),
+            _ => buf.get_mp4_value().while_parsing_field(NAME, "item_count")?,
+        };
+        let items = buf.get_mp4_value().while_parsing_field(NAME, "items")?;
+        let iloc = Self { header, offset_length_size, base_offset_index_size, item_count, items };
+
+        for (field_name, size) in [
+            ("offset_size", iloc.offset_size()),
+            ("length_size", iloc.length_size()),
+            ("base_offset_size", iloc.base_offset_size()),
+            ("index_size", iloc.index_size()),
+        ] {
+            ensure_attach!(
+                VALID_FIELD_SIZES.contains(&size),
+                ParseError::InvalidInput,
+                format!("invalid field size {size}"),
+                WhileParsingField(NAME, field_name),
+            );
+        }
+        iloc.validate_items()?;
+        Ok(iloc)
+    }
+
+    fn box_type() -> BoxType {
+        NAME
+    }
+}
+
+impl ParsedBox for IlocBox {
+    fn encoded_len(&self) -> u64 {
+        let item_count_len = if self.header.version < 2 { 2 } else { 4 };
+        self.header.encoded_len() + 2 + item_count_len + self.items.encoded_len()
+    }
+
+    fn put_buf(&self, mut out: &mut dyn BufMut) {
+        self.header.put_buf(&mut out);
+        self.offset_length_size.put_buf(&mut out);
+        self.base_offset_index_size.put_buf(&mut out);
+        match self.header.version {
+            0 | 1 => (self.item_count as u16).put_buf(&mut out),
+            _ => self.item_count.put_buf(&mut out),
+        }
+        self.items.put_buf(&mut out);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use assert_matches::assert_matches;
+
+    use super::*;
+
+    /// Write an iloc box with a single item with a single extent, using the given field sizes.
+    fn write_test_iloc_data(version: u8, offset_size: u8, length_size: u8, item_count: u16) -> BytesMut {
+        let mut data = BytesMut::new();
+        FullBoxHeader { version, flags: 0 }.put_buf(&mut data);
+        data.put_u8(offset_size << 4 | length_size);
+        data.put_u8(0); // base offset size and index size
+        data.put_u16(item_count);
+        data.put_u16(1); // item ID
+        if version != 0 {
+            data.put_u16(0); // construction method
+        }
+        data.put_u16(0); // data reference index
+        data.put_u16(1); // extent count
+        data.put_bytes(0, usize::from(offset_size) + usize::from(length_size));
+        data
+    }
+
+    #[test]
+    fn roundtrip() {
+        for version in [0, 1] {
+            let data = write_test_iloc_data(version, 4, 8, 1);
+            let iloc = IlocBox::parse(&mut data.clone()).unwrap();
+            assert_eq!(iloc.version(), version);
+            assert_eq!(iloc.offset_size(), 4);
+            assert_eq!(iloc.length_size(), 8);
+            assert_eq!(iloc.base_offset_size(), 0);
+            assert_eq!(iloc.index_size(), 0);
+            assert_eq!(iloc.item_count(), 1);
+
+            let mut encoded = BytesMut::new();
+            iloc.put_buf(&mut encoded);
+            assert_eq!(iloc.encoded_len(), encoded.len() as u64);
+            assert_eq!(encoded, data);
+        }
+    }
+
+    #[test]
+    fn file_offsets() {
+        let iloc = IlocBox::parse(&mut write_test_iloc_data(0, 4, 4, 1)).unwrap();
+        assert!(iloc.has_file_offsets());
+
+        // Set the construction method of the item to 1, locating it within the idat box.
+        let mut data = write_test_iloc_data(1, 4, 4, 1);
+        data[11] = 1;
+        let iloc = IlocBox::parse(&mut data).unwrap();
+        assert!(!iloc.has_file_offsets());
+
+        let iloc = IlocBox::parse(&mut write_test_iloc_data(0, 4, 4, 0).split_to(10)).unwrap();
+        assert!(!iloc.has_file_offsets());
+    }
+
+    #[test]
+    fn invalid_offset_size() {
+        let mut data = write_test_iloc_data(0, 6, 4, 1);
+        let err = IlocBox::parse(&mut data).unwrap_err();
+        assert_matches!(err.get_ref(), ParseError::InvalidInput, "{err:?}");
+    }
+
+    #[test]
+    fn item_count_overflow() {
+        let mut data = write_test_iloc_data(0, 4, 4, 2);
+        let err = IlocBox::parse(&mut data).unwrap_err();
+        assert_matches!(err.get_ref(), ParseError::InvalidInput, "{err:?}");
+    }
+
+    #[test]
+    fn unsupported_version() {
+        let mut data = write_test_iloc_data(3, 4, 4, 1);
+        let err = IlocBox::parse(&mut data).unwrap_err();
+        assert_matches!(err.get_ref(), ParseError::InvalidInput, "{err:?}");
+    }
+}
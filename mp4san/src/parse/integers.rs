@@ -1,6 +1,6 @@
 #![allow(missing_docs)]
 
-use std::mem::size_of;
+use core::mem::size_of;
 
 use bytes::Buf;
 use bytes::BufMut;
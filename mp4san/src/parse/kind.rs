@@ -0,0 +1,61 @@
+#![allow(missing_docs)]
+
+use super::{ConstFullBoxHeader, NullTerminatedString, ParseBox, ParsedBox};
+
+/// The track kind box, declaring the role of a track, such as captions or audio description, within a scheme.
+#[derive(Clone, Debug, ParseBox, ParsedBox)]
+#[box_type = "kind"]
+pub struct KindBox {
+    header: ConstFullBoxHeader,
+    scheme_uri: NullTerminatedString,
+    value: NullTerminatedString,
+}
+
+impl KindBox {
+    /// The DASH role scheme, whose values include `caption`, `subtitle`, and `description`.
+    pub const DASH_ROLE_SCHEME_URI: &'static [u8] = b"urn:mpeg:dash:role:2011";
+
+    pub fn new(scheme_uri: &[u8], value: &[u8]) -> Self {
+        Self { header: Default::default(), scheme_uri: scheme_uri.into(), value: value.into() }
+    }
+
+    /// The URI identifying the scheme of the [`value`](Self::value), not including its null terminator.
+    pub fn scheme_uri(&self) -> &[u8] {
+        self.scheme_uri.as_bytes()
+    }
+
+    /// The track's role within the scheme, not including its null terminator.
+    pub fn value(&self) -> &[u8] {
+        self.value.as_bytes()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use assert_matches::assert_matches;
+    use bytes::{BufMut, BytesMut};
+
+    use crate::parse::{FullBoxHeader, Mp4Value, ParseError};
+
+    use super::*;
+
+    #[test]
+    fn captions() {
+        let mut data = BytesMut::new();
+        KindBox::new(KindBox::DASH_ROLE_SCHEME_URI, b"caption").put_buf(&mut data);
+        let kind = KindBox::parse(&mut data).unwrap();
+        assert_eq!(kind.scheme_uri(), KindBox::DASH_ROLE_SCHEME_URI);
+        assert_eq!(kind.value(), b"caption");
+    }
+
+    #[test]
+    fn missing_terminator() {
+        let mut data = BytesMut::new();
+        FullBoxHeader::default().put_buf(&mut data);
+        data.put_slice(KindBox::DASH_ROLE_SCHEME_URI);
+        data.put_u8(0);
+        data.put_slice(b"caption");
+        let err = KindBox::parse(&mut data).unwrap_err();
+        assert_matches!(err.get_ref(), ParseError::InvalidInput, "{err:?}");
+    }
+}
@@ -0,0 +1,184 @@
+#![allow(missing_docs)]
+
+use alloc::format;
+
+use bytes::{BufMut, BytesMut};
+
+use crate::error::Result;
+
+use super::error::{ParseResultExt, WhileParsingField};
+use super::{BoxType, FullBoxHeader, Mp4Value, Mp4ValueReaderExt, ParseBox, ParseError, ParsedBox};
+
+#[derive(Clone, Debug)]
+pub struct MdhdBox {
+    header: FullBoxHeader,
+    creation_time: u64,
+    modification_time: u64,
+    timescale: u32,
+    duration: u64,
+    language: u16,
+    pre_defined: u16,
+}
+
+const NAME: BoxType = BoxType::MDHD;
+
+impl MdhdBox {
+    /// The number of time units that pass in one second, for this track's media.
+    pub fn timescale(&self) -> u32 {
+        self.timescale
+    }
+
+    /// The duration of this track's media, in the media [timescale](Self::timescale).
+    ///
+    /// A value of all ones indicates the duration is unknown.
+    pub fn duration(&self) -> u64 {
+        self.duration
+    }
+
+    /// The ISO 639-2/T language code of this track's media, or [`None`] if its packed encoding is invalid.
+    pub fn language(&self) -> Option<[u8; 3]> {
+        decode_language(self.language)
+    }
+
+    /// Returns `true` if the [duration](Self::duration) is all ones, indicating it is unknown.
+    pub fn is_duration_unknown(&self) -> bool {
+        match self.header.version {
+            0 => self.duration == u32::MAX.into(),
+            _ => self.duration == u64::MAX,
+        }
+    }
+}
+
+/// Decode an ISO 639-2/T language code packed as three 5-bit letters, each offset from `0x60`, following a zero pad bit.
+pub(crate) fn decode_language(packed: u16) -> Option<[u8; 3]> {
+    if packed & 0x8000 != 0 {
+        return None;
+    }
+    let mut language = [0; 3];
+    for (idx, letter) in language.iter_mut().enumerate() {
+        let offset = (packed >> (10 - 5 * idx)) & 0x1f;
+        if !(1..=26).contains(&offset) {
+            return None;
+        }
+        *letter = 0x60 + offset as u8;
+    }
+    Some(language)
+}
+
+impl ParseBox for MdhdBox {
+    fn parse(buf: &mut BytesMut) -> Result<Self, ParseError> {
+        let header: FullBoxHeader = buf.get_mp4_value().while_parsing_field(NAME, "header")?;
+        let (creation_time, modification_time, timescale, duration) = match header.version {
+            0 => (
+                buf.get_mp4_value::<u32>()
+                    .while_parsing_field(NAME, "creation_time")?
+                    .into(),
+                buf.get_mp4_value::<u32>()
+                    .while_parsing_field(NAME, "modification_time")?
+                    .into(),
+                buf.get_mp4_value().while_parsing_field(NAME, "timescale")?,
+                buf.get_mp4_value::<u32>().while_parsing_field(NAME, "duration")?.into(),
+            ),
+            1 => (
+                buf.get_mp4_value().while_parsing_field(NAME, "creation_time")?,
+                buf.get_mp4_value().while_parsing_field(NAME, "modification_time")?,
+                buf.get_mp4_value().while_parsing_field(NAME, "timescale")?,
+                buf.get_mp4_value().while_parsing_field(NAME, "duration")?,
+            ),
+            version => bail_attach!(
+                ParseError::InvalidInput,
+                format!("unsupported version {version}"),
+                WhileParsingField(NAME, "header"),
+            ),
+        };
+        let language = buf.get_mp4_value().while_parsing_field(NAME, "language")?;
+        let pre_defined = buf.get_mp4_value().while_parsing_field(NAME, "pre_defined")?;
+        Ok(Self { header, creation_time, modification_time, timescale, duration, language, pre_defined })
+    }
+
+    fn box_type() -> BoxType {
+        NAME
+    }
+}
+
+impl ParsedBox for MdhdBox {
+    fn encoded_len(&self) -> u64 {
+        let times_len = match self.header.version {
+            0 => 4 + 4 + 4,
+            _ => 8 + 8 + 8,
+        };
+        self.header.encoded_len()
+            + times_len
+            + self.timescale.encoded_len()
+            + self.language.encoded_len()
+            + self.pre_defined.encoded_len()
+    }
+
+    fn put_buf(&self, mut out: &mut dyn BufMut) {
+        self.header.put_buf(&mut out);
+        match self.header.version {
+            0 => {
+                (self.creation_time as u32).put_buf(&mut out);
+                (self.modification_time as u32).put_buf(&mut out);
+                self.timescale.put_buf(&mut out);
+                (self.duration as u32).put_buf(&mut out);
+            }
+            _ => {
+                self.creation_time.put_buf(&mut out);
+                self.modification_time.put_buf(&mut out);
+                self.timescale.put_buf(&mut out);
+                self.duration.put_buf(&mut out);
+            }
+        }
+        self.language.put_buf(&mut out);
+        self.pre_defined.put_buf(&mut out);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use bytes::BytesMut;
+
+    use crate::util::test::write_mdhd_data;
+
+    use super::*;
+
+    #[test]
+    fn roundtrip_version_0() {
+        let mut data = BytesMut::new();
+        write_mdhd_data(&mut data, 48000, 96000);
+        let mdhd = MdhdBox::parse(&mut data.clone()).unwrap();
+        assert_eq!(mdhd.timescale(), 48000);
+        assert_eq!(mdhd.language(), Some(*b"ujs"));
+        assert_eq!(mdhd.duration(), 96000);
+        assert!(!mdhd.is_duration_unknown());
+
+        let mut encoded = BytesMut::new();
+        mdhd.put_buf(&mut encoded);
+        assert_eq!(mdhd.encoded_len(), encoded.len() as u64);
+        assert_eq!(encoded, data);
+    }
+
+    #[test]
+    fn language() {
+        assert_eq!(decode_language(0x55c4), Some(*b"und"));
+        assert_eq!(decode_language(0x15c7), Some(*b"eng"));
+        assert_eq!(decode_language(0), None);
+        assert_eq!(decode_language(0x8000 | 0x15c7), None);
+        assert_eq!(decode_language(0x1fff), None);
+    }
+
+    #[test]
+    fn roundtrip_version_1() {
+        let mut data = BytesMut::new();
+        write_mdhd_data(&mut data, 48000, u64::MAX);
+        let mdhd = MdhdBox::parse(&mut data.clone()).unwrap();
+        assert_eq!(mdhd.duration(), u64::MAX);
+        assert!(mdhd.is_duration_unknown());
+
+        let mut encoded = BytesMut::new();
+        mdhd.put_buf(&mut encoded);
+        assert_eq!(mdhd.encoded_len(), encoded.len() as u64);
+        assert_eq!(encoded, data);
+    }
+}
@@ -1,26 +1,107 @@
 #![allow(missing_docs)]
 
+use alloc::vec::Vec;
+
+use mediasan_common::ResultExt;
+
 use crate::error::Result;
 
-use super::error::ParseResultExt;
+use super::error::{ParseResultExt, WhileParsingField};
 use super::mp4box::Boxes;
-use super::{BoxType, MinfBox, ParseBox, ParseError, ParsedBox};
+use super::{BoxType, BoxesValidator, HdlrBox, MdhdBox, MinfBox, ParseBox, ParseError, ParsedBox};
 
 #[derive(Clone, Debug, ParseBox, ParsedBox)]
 #[box_type = "mdia"]
 pub struct MdiaBox {
-    pub children: Boxes,
+    pub children: Boxes<MdiaChildrenValidator>,
 }
 
+pub struct MdiaChildrenValidator;
+
 const NAME: BoxType = BoxType::MDIA;
 
+/// The child box types retained by [`MdiaBox::minimize`].
+const MINIMAL_CHILDREN: &[BoxType] = &[BoxType::MDHD, BoxType::HDLR, BoxType::MINF];
+
 impl MdiaBox {
     #[cfg(test)]
-    pub(crate) fn with_children<C: Into<Boxes>>(children: C) -> Self {
+    pub(crate) fn with_children<C: Into<Boxes<MdiaChildrenValidator>>>(children: C) -> Self {
         Self { children: children.into() }
     }
 
+    /// Remove any boxes not required for playback from within this box and its descendants, returning the types of
+    /// the boxes removed, in order.
+    pub fn minimize(&mut self) -> Result<Vec<BoxType>, ParseError> {
+        let mut removed = self.children.retain_box_types(MINIMAL_CHILDREN);
+        removed.extend(self.minf_mut()?.minimize()?);
+        Ok(removed)
+    }
+
+    /// Remove any free space boxes (`free` or `skip`) from within this box and its descendants, returning the total
+    /// encoded length of the boxes removed.
+    pub fn strip_free_boxes(&mut self) -> Result<u64, ParseError> {
+        let stripped_len = self.children.strip_free_boxes();
+        Ok(stripped_len + self.minf_mut()?.strip_free_boxes()?)
+    }
+
+    pub fn mdhd_mut(&mut self) -> Result<&mut MdhdBox, ParseError> {
+        self.children.get_one_mut().while_parsing_child(NAME, BoxType::MDHD)
+    }
+
+    pub fn hdlr_mut(&mut self) -> Result<&mut HdlrBox, ParseError> {
+        self.children.get_one_mut().while_parsing_child(NAME, BoxType::HDLR)
+    }
+
     pub fn minf_mut(&mut self) -> Result<&mut MinfBox, ParseError> {
         self.children.get_one_mut().while_parsing_child(NAME, BoxType::MINF)
     }
 }
+
+impl BoxesValidator for MdiaChildrenValidator {
+    fn validate<V>(children: &Boxes<V>) -> Result<(), ParseError> {
+        for box_type in [BoxType::MDHD, BoxType::HDLR, BoxType::MINF] {
+            children
+                .ensure_one(box_type)
+                .attach_printable(WhileParsingField(NAME, "children"))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use assert_matches::assert_matches;
+    use bytes::BytesMut;
+
+    use crate::parse::{fourcc, AnyMp4Box, Mp4Box};
+    use crate::util::test::{test_hdlr, test_mdhd};
+
+    use super::*;
+
+    fn test_minf() -> AnyMp4Box {
+        Mp4Box::with_data(MinfBox::with_children(vec![]).into()).unwrap().into()
+    }
+
+    #[test]
+    fn roundtrip() {
+        let mut data = BytesMut::new();
+        MdiaBox::with_children(vec![test_mdhd(), test_hdlr(fourcc::META), test_minf()]).put_buf(&mut data);
+        MdiaBox::parse(&mut data).unwrap();
+    }
+
+    #[test]
+    fn no_hdlr() {
+        let mut data = BytesMut::new();
+        MdiaBox::with_children(vec![test_mdhd(), test_minf()]).put_buf(&mut data);
+        let err = MdiaBox::parse(&mut data).unwrap_err();
+        assert_matches!(err.get_ref(), ParseError::MissingRequiredBox(BoxType::HDLR), "{err:?}");
+    }
+
+    #[test]
+    fn multiple_mdhd() {
+        let mut data = BytesMut::new();
+        MdiaBox::with_children(vec![test_mdhd(), test_mdhd(), test_hdlr(fourcc::META), test_minf()]).put_buf(&mut data);
+        let err = MdiaBox::parse(&mut data).unwrap_err();
+        assert_matches!(err.get_ref(), ParseError::InvalidBoxLayout, "{err:?}");
+    }
+}
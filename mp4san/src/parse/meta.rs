@@ -0,0 +1,108 @@
+#![allow(missing_docs)]
+
+use bytes::{BufMut, BytesMut};
+
+use crate::error::Result;
+
+use super::error::ParseResultExt;
+use super::{BoxType, Boxes, FullBoxHeader, IlocBox, Mp4Value, Mp4ValueReaderExt, ParseBox, ParseError, ParsedBox};
+
+/// The metadata box, containing untimed metadata such as items or iTunes-style tags.
+///
+/// Both the ISO layout, which begins with a full box header, and the QuickTime layout, which doesn't, are supported.
+#[derive(Clone, Debug)]
+pub struct MetaBox {
+    header: Option<FullBoxHeader>,
+    children: Boxes,
+}
+
+const NAME: BoxType = BoxType::META;
+
+impl MetaBox {
+    #[cfg(test)]
+    pub(crate) fn with_children<C: Into<Boxes>>(children: C) -> Self {
+        Self { header: Some(Default::default()), children: children.into() }
+    }
+
+    /// Whether this box contains an item location box (`iloc`), whose items may be located within the media data.
+    pub fn has_item_locations(&self) -> bool {
+        self.children.box_types().any(|box_type| box_type == BoxType::ILOC)
+    }
+
+    pub fn iloc_mut(&mut self) -> Result<Option<&mut IlocBox>, ParseError> {
+        self.children
+            .get_optional_mut()
+            .while_parsing_child(NAME, BoxType::ILOC)
+    }
+}
+
+impl ParseBox for MetaBox {
+    fn parse(buf: &mut BytesMut) -> Result<Self, ParseError> {
+        // A QuickTime metadata box begins directly with its handler box.
+        let header = match buf.get(4..8) {
+            Some(b"hdlr") => None,
+            _ => Some(buf.get_mp4_value().while_parsing_field(NAME, "header")?),
+        };
+        let children = buf.get_mp4_value().while_parsing_field(NAME, "children")?;
+        Ok(Self { header, children })
+    }
+
+    fn box_type() -> BoxType {
+        NAME
+    }
+}
+
+impl ParsedBox for MetaBox {
+    fn encoded_len(&self) -> u64 {
+        self.header.as_ref().map_or(0, Mp4Value::encoded_len) + self.children.encoded_len()
+    }
+
+    fn put_buf(&self, mut out: &mut dyn BufMut) {
+        if let Some(header) = &self.header {
+            header.put_buf(&mut out);
+        }
+        self.children.put_buf(&mut out);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::parse::fourcc;
+    use crate::util::test::{test_free, test_hdlr, write_test_meta_data};
+
+    use super::*;
+
+    #[test]
+    fn iso() {
+        let mut data = BytesMut::new();
+        write_test_meta_data(&mut data);
+        let meta = MetaBox::parse(&mut data.clone()).unwrap();
+        assert!(meta.header.is_some());
+        assert!(!meta.has_item_locations());
+
+        let mut encoded = BytesMut::new();
+        meta.put_buf(&mut encoded);
+        assert_eq!(encoded, data);
+    }
+
+    #[test]
+    fn quicktime() {
+        let mut data = BytesMut::new();
+        test_hdlr(fourcc::META).put_buf(&mut data);
+        let meta = MetaBox::parse(&mut data.clone()).unwrap();
+        assert!(meta.header.is_none());
+
+        let mut encoded = BytesMut::new();
+        meta.put_buf(&mut encoded);
+        assert_eq!(encoded, data);
+    }
+
+    #[test]
+    fn item_locations() {
+        let iloc = test_free(BoxType::ILOC, 16);
+        let mut data = BytesMut::new();
+        MetaBox::with_children(vec![test_hdlr(fourcc::META), iloc]).put_buf(&mut data);
+        let meta = MetaBox::parse(&mut data).unwrap();
+        assert!(meta.has_item_locations());
+    }
+}
@@ -0,0 +1,21 @@
+#![allow(missing_docs)]
+
+use super::{ConstFullBoxHeader, ParseBox, ParsedBox};
+
+/// The movie fragment header box, containing the sequence number of its movie fragment (`moof`).
+#[derive(Clone, Debug, Default, ParseBox, ParsedBox)]
+#[box_type = "mfhd"]
+pub struct MfhdBox {
+    header: ConstFullBoxHeader,
+    sequence_number: u32,
+}
+
+impl MfhdBox {
+    pub fn new(sequence_number: u32) -> Self {
+        Self { header: Default::default(), sequence_number }
+    }
+
+    pub fn sequence_number(&self) -> u32 {
+        self.sequence_number
+    }
+}
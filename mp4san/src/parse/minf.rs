@@ -1,5 +1,7 @@
 #![allow(missing_docs)]
 
+use alloc::vec::Vec;
+
 use crate::error::Result;
 
 use super::error::ParseResultExt;
@@ -14,12 +16,38 @@ pub struct MinfBox {
 
 const NAME: BoxType = BoxType::MINF;
 
+/// The child box types retained by [`MinfBox::minimize`].
+const MINIMAL_CHILDREN: &[BoxType] = &[
+    BoxType::VMHD,
+    BoxType::SMHD,
+    BoxType::HMHD,
+    BoxType::STHD,
+    BoxType::NMHD,
+    BoxType::DINF,
+    BoxType::STBL,
+];
+
 impl MinfBox {
     #[cfg(test)]
     pub(crate) fn with_children<C: Into<Boxes>>(children: C) -> Self {
         Self { children: children.into() }
     }
 
+    /// Remove any boxes not required for playback from within this box and its descendants, returning the types of
+    /// the boxes removed, in order.
+    pub fn minimize(&mut self) -> Result<Vec<BoxType>, ParseError> {
+        let mut removed = self.children.retain_box_types(MINIMAL_CHILDREN);
+        removed.extend(self.stbl_mut()?.minimize());
+        Ok(removed)
+    }
+
+    /// Remove any free space boxes (`free` or `skip`) from within this box and its descendants, returning the total
+    /// encoded length of the boxes removed.
+    pub fn strip_free_boxes(&mut self) -> Result<u64, ParseError> {
+        let stripped_len = self.children.strip_free_boxes();
+        Ok(stripped_len + self.stbl_mut()?.strip_free_boxes())
+    }
+
     pub fn stbl_mut(&mut self) -> Result<&mut StblBox, ParseError> {
         self.children.get_one_mut().while_parsing_child(NAME, BoxType::STBL)
     }
@@ -0,0 +1,83 @@
+#![allow(missing_docs)]
+
+use mediasan_common::ResultExt;
+
+use crate::error::Result;
+
+use super::error::{ParseResultExt, WhileParsingField};
+use super::{BoxType, Boxes, BoxesValidator, MfhdBox, ParseBox, ParseError, ParsedBox, TrafBox};
+
+/// The movie fragment box, containing the metadata of a fragment of a fragmented MP4, whose samples are in the
+/// following `mdat`.
+#[derive(Clone, Debug, ParseBox, ParsedBox)]
+#[box_type = "moof"]
+pub struct MoofBox {
+    children: Boxes<MoofChildrenValidator>,
+}
+
+pub(crate) struct MoofChildrenValidator;
+
+const NAME: BoxType = BoxType::MOOF;
+
+impl MoofBox {
+    #[cfg(test)]
+    pub(crate) fn with_children<C: Into<Boxes<MoofChildrenValidator>>>(children: C) -> Self {
+        Self { children: children.into() }
+    }
+
+    pub fn mfhd_mut(&mut self) -> Result<&mut MfhdBox, ParseError> {
+        self.children.get_one_mut().while_parsing_child(NAME, BoxType::MFHD)
+    }
+
+    pub fn trafs(&mut self) -> impl Iterator<Item = Result<&mut TrafBox, ParseError>> + '_ {
+        self.children
+            .get_mut()
+            .map(|result| result.while_parsing_child(NAME, BoxType::TRAF))
+    }
+}
+
+impl BoxesValidator for MoofChildrenValidator {
+    fn validate<V>(children: &Boxes<V>) -> Result<(), ParseError> {
+        children
+            .ensure_one(BoxType::MFHD)
+            .attach_printable(WhileParsingField(NAME, "children"))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use assert_matches::assert_matches;
+    use bytes::BytesMut;
+
+    use crate::parse::{Mp4Box, TfhdBox, TrunBox};
+
+    use super::*;
+
+    #[test]
+    fn roundtrip() {
+        let mfhd = Mp4Box::with_data(MfhdBox::new(7).into()).unwrap();
+        let tfhd = Mp4Box::with_data(TfhdBox::new(1, None).into()).unwrap();
+        let trun = Mp4Box::with_data(TrunBox::new(Some(8), [1, 2]).into()).unwrap();
+        let traf = Mp4Box::with_data(TrafBox::with_children(vec![tfhd.into(), trun.into()]).into()).unwrap();
+        let mut data = BytesMut::new();
+        MoofBox::with_children(vec![mfhd.into(), traf.into()]).put_buf(&mut data);
+
+        let mut moof = MoofBox::parse(&mut data).unwrap();
+        assert_eq!(moof.mfhd_mut().unwrap().sequence_number(), 7);
+        let mut trafs = moof.trafs();
+        let traf = trafs.next().unwrap().unwrap();
+        assert!(trafs.next().is_none());
+        assert_eq!(traf.tfhd_mut().unwrap().track_id(), 1);
+        let mut truns = traf.truns();
+        assert_eq!(truns.next().unwrap().unwrap().sample_count(), 2);
+        assert!(truns.next().is_none());
+    }
+
+    #[test]
+    fn no_mfhd() {
+        let mut data = BytesMut::new();
+        MoofBox::with_children(vec![]).put_buf(&mut data);
+        let err = MoofBox::parse(&mut data).unwrap_err();
+        assert_matches!(err.get_ref(), ParseError::MissingRequiredBox(BoxType::MFHD), "{err:?}");
+    }
+}
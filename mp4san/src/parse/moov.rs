@@ -1,9 +1,12 @@
 #![allow(missing_docs)]
 
+use alloc::vec::Vec;
+
 use crate::error::Result;
+use crate::log;
 
-use super::error::{ParseResultExt, WhileParsingField};
-use super::{BoxType, Boxes, BoxesValidator, ParseBox, ParseError, ParsedBox, TrakBox};
+use super::error::{ParseResultExt, WhileParsingChild, WhileParsingField};
+use super::{BoxType, Boxes, BoxesValidator, FourCC, MvhdBox, ParseBox, ParseError, ParsedBox, PsshBox, TrakBox};
 
 #[derive(Clone, Debug, ParseBox, ParsedBox)]
 #[box_type = "moov"]
@@ -15,6 +18,12 @@ pub(crate) struct MoovChildrenValidator;
 
 const NAME: BoxType = BoxType::MOOV;
 
+/// Timescales above this many units per second are logged as suspicious.
+const MAX_EXPECTED_TIMESCALE: u32 = 10_000_000;
+
+/// The child box types retained by [`MoovBox::minimize`].
+const MINIMAL_CHILDREN: &[BoxType] = &[BoxType::MVHD, BoxType::MVEX, BoxType::PSSH, BoxType::TRAK];
+
 impl MoovBox {
     #[cfg(test)]
     pub(crate) fn with_children<C: Into<Boxes<MoovChildrenValidator>>>(children: C) -> Self {
@@ -26,6 +35,179 @@ impl MoovBox {
             .get_mut()
             .map(|result| result.while_parsing_child(NAME, BoxType::TRAK))
     }
+
+    pub fn mvhd_mut(&mut self) -> Result<&mut MvhdBox, ParseError> {
+        self.children.get_one_mut().while_parsing_child(NAME, BoxType::MVHD)
+    }
+
+    pub fn psshs(&mut self) -> impl Iterator<Item = Result<&mut PsshBox, ParseError>> + '_ {
+        self.children
+            .get_mut()
+            .map(|result| result.while_parsing_child(NAME, BoxType::PSSH))
+    }
+
+    /// Validate that the string fields within this box are valid UTF-8.
+    ///
+    /// Currently, this validates the handler name (`hdlr`) of each track.
+    pub fn validate_strings(&mut self) -> Result<(), ParseError> {
+        for trak in self.traks() {
+            trak?.mdia_mut()?.hdlr_mut()?.validate_name()?;
+        }
+        Ok(())
+    }
+
+    /// Validate that the duration of each track can be scaled from its media timescale (`mdhd`) to the movie timescale
+    /// (`mvhd`) without overflowing.
+    ///
+    /// Nothing is checked if there is no movie header, and tracks with an unknown duration are not checked. Timescales
+    /// which are zero or unexpectedly large, and track durations (`tkhd`) longer than the movie's, are logged.
+    pub fn validate_durations(&mut self) -> Result<(), ParseError> {
+        let mvhd: Option<&mut MvhdBox> = self
+            .children
+            .get_optional_mut()
+            .while_parsing_child(NAME, BoxType::MVHD)?;
+        let Some(mvhd) = mvhd else {
+            return Ok(());
+        };
+        let movie_timescale = mvhd.timescale();
+        if movie_timescale == 0 || movie_timescale > MAX_EXPECTED_TIMESCALE {
+            log::warn!("mvhd: suspicious timescale {movie_timescale}");
+        }
+        let movie_duration = (!mvhd.is_duration_unknown()).then_some(mvhd.duration());
+        for trak in self.traks() {
+            let trak = trak?;
+            let tkhd = trak.tkhd_mut()?;
+            if let (Some(movie_duration), false) = (movie_duration, tkhd.is_duration_unknown()) {
+                if tkhd.duration() > movie_duration {
+                    log::warn!(
+                        "tkhd: track duration {} exceeds movie duration {movie_duration}",
+                        tkhd.duration()
+                    );
+                }
+            }
+            let mdhd = trak.mdia_mut()?.mdhd_mut()?;
+            let media_timescale = mdhd.timescale();
+            if media_timescale == 0 || media_timescale > MAX_EXPECTED_TIMESCALE {
+                log::warn!("mdhd: suspicious timescale {media_timescale}");
+            }
+            if mdhd.is_duration_unknown() {
+                continue;
+            }
+            ensure_attach!(
+                mdhd.duration().checked_mul(movie_timescale.into()).is_some(),
+                ParseError::InvalidInput,
+                "track duration overflows when scaled to movie timescale",
+                WhileParsingChild(BoxType::MDIA, BoxType::MDHD),
+            );
+        }
+        Ok(())
+    }
+
+    /// Returns the types of the movie, track, and media header boxes (`mvhd`, `tkhd`, and `mdhd`) whose duration is all
+    /// ones, indicating it is unknown, as in live or streaming content.
+    pub fn unknown_duration_boxes(&mut self) -> Result<Vec<BoxType>, ParseError> {
+        let mut box_types = Vec::new();
+        let mvhd: Option<&mut MvhdBox> = self
+            .children
+            .get_optional_mut()
+            .while_parsing_child(NAME, BoxType::MVHD)?;
+        if mvhd.is_some_and(|mvhd| mvhd.is_duration_unknown()) {
+            box_types.push(BoxType::MVHD);
+        }
+        for trak in self.traks() {
+            let trak = trak?;
+            if trak.tkhd_mut()?.is_duration_unknown() {
+                box_types.push(BoxType::TKHD);
+            }
+            if trak.mdia_mut()?.mdhd_mut()?.is_duration_unknown() {
+                box_types.push(BoxType::MDHD);
+            }
+        }
+        Ok(box_types)
+    }
+
+    /// Remove any tracks which are not enabled, returning the number of tracks removed.
+    ///
+    /// It is an error for all tracks to be removed.
+    pub fn drop_disabled_traks(&mut self) -> Result<usize, ParseError> {
+        let mut dropped_count = 0;
+        self.children
+            .retain_mut(|trak: &mut TrakBox| {
+                let enabled = trak.tkhd_mut()?.is_enabled();
+                dropped_count += usize::from(!enabled);
+                Ok(enabled)
+            })
+            .while_parsing_child(NAME, BoxType::TRAK)?;
+        ensure_attach!(
+            self.children.box_types().any(|box_type| box_type == BoxType::TRAK),
+            ParseError::MissingRequiredBox(BoxType::TRAK),
+            "all tracks are disabled",
+            WhileParsingField(NAME, "children"),
+        );
+        Ok(dropped_count)
+    }
+
+    /// Remove any tracks whose media handler type (`hdlr`) is among `handler_types`, returning the number of tracks
+    /// removed.
+    ///
+    /// It is an error for all tracks to be removed.
+    pub fn drop_traks_by_handler_type(&mut self, handler_types: &[FourCC]) -> Result<usize, ParseError> {
+        let mut dropped_count = 0;
+        self.children
+            .retain_mut(|trak: &mut TrakBox| {
+                let dropped = handler_types.contains(&trak.handler_type()?);
+                dropped_count += usize::from(dropped);
+                Ok(!dropped)
+            })
+            .while_parsing_child(NAME, BoxType::TRAK)?;
+        ensure_attach!(
+            self.children.box_types().any(|box_type| box_type == BoxType::TRAK),
+            ParseError::InvalidInput,
+            "all tracks were dropped by handler type",
+            WhileParsingField(NAME, "children"),
+        );
+        Ok(dropped_count)
+    }
+
+    /// Remove any boxes not required for playback from within this box and its tracks, returning the types of the boxes
+    /// removed, in order.
+    ///
+    /// User data (`udta`), metadata (`meta`), and free space boxes, among others, are removed. Sample descriptions
+    /// (`stsd`), including codec configuration, are retained as-is, as are track references (`tref`), sample groups
+    /// (`sgpd` and `sbgp`), sample auxiliary information (`saiz` and `saio`), and protection system headers (`pssh`).
+    pub fn minimize(&mut self) -> Result<Vec<BoxType>, ParseError> {
+        let mut removed = self.children.retain_box_types(MINIMAL_CHILDREN);
+        for trak in self.traks() {
+            removed.extend(trak?.minimize()?);
+        }
+        Ok(removed)
+    }
+
+    /// Remove any user data (`udta`) and metadata (`meta`) boxes from within this box and its tracks, returning the types
+    /// of the boxes removed.
+    ///
+    /// It is an error for a removed box to contain an item location box (`iloc`), as its items may be located within the
+    /// media data.
+    pub fn strip_metadata_boxes(&mut self) -> Result<Vec<BoxType>, ParseError> {
+        let mut stripped = self
+            .children
+            .strip_metadata_boxes()
+            .while_parsing_field(NAME, "children")?;
+        for trak in self.traks() {
+            stripped.extend(trak?.strip_metadata_boxes()?);
+        }
+        Ok(stripped)
+    }
+
+    /// Remove any free space boxes (`free` or `skip`) from within this box and its tracks, returning the total encoded
+    /// length of the boxes removed.
+    pub fn strip_free_boxes(&mut self) -> Result<u64, ParseError> {
+        let mut stripped_len = self.children.strip_free_boxes();
+        for trak in self.traks() {
+            stripped_len += trak?.strip_free_boxes()?;
+        }
+        Ok(stripped_len)
+    }
 }
 
 impl BoxesValidator for MoovChildrenValidator {
@@ -41,9 +223,11 @@ impl BoxesValidator for MoovChildrenValidator {
 
 #[cfg(test)]
 mod test {
+    use assert_matches::assert_matches;
     use bytes::BytesMut;
 
-    use crate::parse::Mp4Box;
+    use crate::parse::{fourcc, MetaBox, Mp4Box};
+    use crate::util::test::{test_hdlr, test_iloc, test_udta};
 
     use super::*;
 
@@ -58,6 +242,29 @@ mod test {
         MoovBox::parse(&mut data).unwrap();
     }
 
+    #[test]
+    fn strip_metadata_boxes() {
+        let mut moov = MoovBox::with_children(vec![test_trak().into(), test_udta()]);
+        assert_eq!(moov.strip_metadata_boxes().unwrap(), [BoxType::UDTA]);
+        assert_eq!(moov.children.box_types().collect::<Vec<_>>(), [BoxType::TRAK]);
+    }
+
+    #[test]
+    fn strip_metadata_boxes_item_locations() {
+        let iloc = test_iloc(0);
+        let meta = Mp4Box::with_data(MetaBox::with_children(vec![test_hdlr(fourcc::META), iloc]).into()).unwrap();
+        let mut moov = MoovBox::with_children(vec![test_trak().into(), meta.into()]);
+        let err = moov.strip_metadata_boxes().unwrap_err();
+        assert_matches!(err.get_ref(), ParseError::UnsupportedBoxLayout, "{err:?}");
+
+        // Items located within the idat box are stripped along with it.
+        let iloc = test_iloc(1);
+        let meta = Mp4Box::with_data(MetaBox::with_children(vec![test_hdlr(fourcc::META), iloc]).into()).unwrap();
+        let mut moov = MoovBox::with_children(vec![test_trak().into(), meta.into()]);
+        assert_eq!(moov.strip_metadata_boxes().unwrap(), [BoxType::META]);
+        assert_eq!(moov.children.box_types().collect::<Vec<_>>(), [BoxType::TRAK]);
+    }
+
     #[test]
     fn no_traks() {
         let mut data = BytesMut::new();
@@ -1,27 +1,41 @@
 #![allow(missing_docs)]
 
-use std::fmt::Debug;
-use std::marker::PhantomData;
-use std::mem::take;
-use std::pin::Pin;
-use std::result::Result as StdResult;
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::fmt::Debug;
+use core::marker::PhantomData;
+use core::mem::take;
+#[cfg(feature = "std")]
+use core::pin::Pin;
+#[cfg(feature = "std")]
+use core::result::Result as StdResult;
+#[cfg(feature = "std")]
+use std::io;
 
 use bytes::{Buf, BufMut, BytesMut};
 use derive_more::From;
 use derive_where::derive_where;
 use downcast_rs::{impl_downcast, Downcast};
-use dyn_clonable::clonable;
+use dyn_clone::{clone_trait_object, DynClone};
+#[cfg(feature = "std")]
 use futures_util::io::BufReader;
+#[cfg(feature = "std")]
 use futures_util::{AsyncRead, AsyncReadExt};
 use mediasan_common::error::WhileParsingType;
-use mediasan_common::{AsyncSkipExt, ResultExt};
+#[cfg(feature = "std")]
+use mediasan_common::AsyncSkipExt;
+use mediasan_common::ResultExt;
 
 use crate::error::Result;
+#[cfg(feature = "std")]
+use crate::sanitizer::BoxDataTooLarge;
+#[cfg(feature = "std")]
 use crate::util::IoResultExt;
-use crate::{AsyncSkip, BoxDataTooLarge, Error};
+#[cfg(feature = "std")]
+use crate::{AsyncSkip, Error};
 
 use super::error::{MultipleBoxes, WhileParsingBox};
-use super::{BoxHeader, BoxType, Mp4Value, ParseError};
+use super::{BoxHeader, BoxType, MetaBox, Mp4Value, ParseError, UdtaBox};
 
 #[derive(Debug)]
 #[derive_where(Clone; BoxData<T>)]
@@ -39,14 +53,24 @@ pub enum BoxData<T: ?Sized> {
     Parsed(Box<T>),
 }
 
+/// A box whose header has been read from an input, but whose data has not yet been read.
+///
+/// The box's data can later be read with [`read_data`](Self::read_data) or skipped with [`skip`](Self::skip).
+#[cfg(feature = "std")]
+pub(crate) struct LazyBox<'a, R> {
+    reader: Pin<&'a mut BufReader<R>>,
+    header: BoxHeader,
+    offset: u64,
+    data_size: u64,
+}
+
 pub trait ParseBox: Sized {
     fn parse(buf: &mut BytesMut) -> Result<Self, ParseError>;
 
     fn box_type() -> BoxType;
 }
 
-#[clonable]
-pub trait ParsedBox: Clone + Debug + Downcast {
+pub trait ParsedBox: DynClone + Debug + Downcast {
     fn encoded_len(&self) -> u64;
 
     fn put_buf(&self, out: &mut dyn BufMut);
@@ -75,6 +99,7 @@ impl<T: ParsedBox + ?Sized> Mp4Box<T> {
     }
 
     /// Read and parse a box's data assuming its header has already been read.
+    #[cfg(feature = "std")]
     pub(crate) async fn read_data<R>(
         mut reader: Pin<&mut BufReader<R>>,
         header: BoxHeader,
@@ -86,7 +111,10 @@ impl<T: ParsedBox + ?Sized> Mp4Box<T> {
     {
         let box_data_size = match header.box_data_size()? {
             Some(box_data_size) => box_data_size,
-            None => reader.as_mut().stream_len().await? - reader.as_mut().stream_position().await?,
+            None => {
+                let data_offset = reader.as_mut().stream_position().await?;
+                until_eof_data_size(reader.as_mut(), header.box_type(), data_offset).await?
+            }
         };
 
         ensure_attach!(
@@ -125,6 +153,26 @@ impl<T: ParsedBox + ?Sized> Mp4Box<T> {
     }
 }
 
+#[cfg(feature = "std")]
+impl AnyMp4Box {
+    /// Record a box's header without reading its data, assuming its header has already been read.
+    pub(crate) async fn read_header_only<R>(
+        mut reader: Pin<&mut BufReader<R>>,
+        header: BoxHeader,
+    ) -> StdResult<LazyBox<'_, R>, Error>
+    where
+        R: AsyncRead + AsyncSkip,
+    {
+        let data_offset = reader.as_mut().stream_position().await?;
+        let data_size = match header.box_data_size()? {
+            Some(data_size) => data_size,
+            None => until_eof_data_size(reader.as_mut(), header.box_type(), data_offset).await?,
+        };
+        let offset = data_offset.saturating_sub(header.encoded_len());
+        Ok(LazyBox { reader, header, offset, data_size })
+    }
+}
+
 impl<T: ParsedBox + ?Sized> Mp4Value for Mp4Box<T> {
     fn parse(mut buf: &mut BytesMut) -> Result<Self, ParseError> {
         let parsed_header = BoxHeader::parse(&mut buf).attach_printable(WhileParsingType::new::<Self>())?;
@@ -201,6 +249,18 @@ impl<T: ParsedBox + ?Sized> BoxData<T> {
         }
     }
 
+    /// Parse the data, if it isn't already, returning the parsed data.
+    pub fn into_parsed(mut self) -> Result<Box<T>, ParseError>
+    where
+        T: ParseBox + Sized,
+    {
+        self.parse()?;
+        match self {
+            BoxData::Parsed(parsed) => Ok(parsed),
+            BoxData::Bytes(_) => unreachable!(),
+        }
+    }
+
     fn parse_as<U: ParseBox + ParsedBox + Into<Box<T>>>(&mut self) -> Result<Option<&mut U>, ParseError> {
         if let BoxData::Bytes(data) = self {
             let parsed = U::parse(data).while_parsing_type()?;
@@ -256,6 +316,7 @@ impl<T: ParsedBox + Sized> From<T> for BoxData<T> {
 // ParsedBox impls
 //
 
+clone_trait_object!(ParsedBox);
 impl_downcast!(ParsedBox);
 
 impl<'a, T: ParsedBox + 'a> From<T> for Box<dyn ParsedBox + 'a> {
@@ -269,16 +330,113 @@ impl<'a, T: ParsedBox + 'a> From<T> for Box<dyn ParsedBox + 'a> {
 //
 
 impl<V> Boxes<V> {
-    pub fn box_types(&self) -> impl Iterator<Item = BoxType> + ExactSizeIterator + '_ {
+    pub fn box_types(&self) -> impl ExactSizeIterator<Item = BoxType> + '_ {
         self.boxes.iter().map(|mp4box| mp4box.parsed_header.box_type())
     }
 
+    pub fn iter(&self) -> impl ExactSizeIterator<Item = &AnyMp4Box> + '_ {
+        self.boxes.iter()
+    }
+
+    pub(crate) fn iter_mut(&mut self) -> impl ExactSizeIterator<Item = &mut AnyMp4Box> + '_ {
+        self.boxes.iter_mut()
+    }
+
     pub fn get_mut<T: ParseBox + ParsedBox>(&mut self) -> impl Iterator<Item = Result<&mut T, ParseError>> {
         self.boxes
             .iter_mut()
             .flat_map(|mp4box| mp4box.parse_data_as().transpose())
     }
 
+    pub fn get_optional_mut<T: ParseBox + ParsedBox>(&mut self) -> Result<Option<&mut T>, ParseError> {
+        ensure_attach!(
+            self.box_types().filter(|box_type| *box_type == T::box_type()).count() <= 1,
+            ParseError::InvalidBoxLayout,
+            MultipleBoxes(T::box_type()),
+        );
+        self.get_mut().next().transpose()
+    }
+
+    /// Ensure exactly one box of type `box_type` is present.
+    pub(crate) fn ensure_one(&self, box_type: BoxType) -> Result<(), ParseError> {
+        match self.box_types().filter(|child_type| *child_type == box_type).count() {
+            0 => bail_attach!(ParseError::MissingRequiredBox(box_type)),
+            1 => Ok(()),
+            _ => bail_attach!(ParseError::InvalidBoxLayout, MultipleBoxes(box_type)),
+        }
+    }
+
+    /// Retain only the boxes of type `T` for which `f` returns `true`, parsing them as necessary.
+    ///
+    /// Boxes of other types are always retained.
+    pub fn retain_mut<T, F>(&mut self, mut f: F) -> Result<(), ParseError>
+    where
+        T: ParseBox + ParsedBox,
+        F: FnMut(&mut T) -> Result<bool, ParseError>,
+    {
+        let mut retain = Vec::with_capacity(self.boxes.len());
+        for mp4box in &mut self.boxes {
+            retain.push(match mp4box.parse_data_as()? {
+                Some(parsed) => f(parsed)?,
+                None => true,
+            });
+        }
+        let mut retain = retain.into_iter();
+        self.boxes.retain(|_| retain.next().unwrap_or(true));
+        Ok(())
+    }
+
+    /// Remove any free space boxes (`free` or `skip`), returning the total encoded length of the boxes removed.
+    pub fn strip_free_boxes(&mut self) -> u64 {
+        let mut stripped_len = 0;
+        self.boxes.retain(|mp4box| match mp4box.parsed_header.box_type() {
+            BoxType::FREE | BoxType::SKIP => {
+                stripped_len += mp4box.encoded_len();
+                false
+            }
+            _ => true,
+        });
+        stripped_len
+    }
+
+    /// Remove any user data (`udta`) and metadata (`meta`) boxes, returning the types of the boxes removed, in order.
+    ///
+    /// It is an error for a removed box to contain an item location box (`iloc`) locating items by offset within the
+    /// file, as its items may be located within the media data, which would then no longer be described.
+    pub fn strip_metadata_boxes(&mut self) -> Result<Vec<BoxType>, ParseError> {
+        for mp4box in &mut self.boxes {
+            ensure_attach!(
+                !has_item_file_offsets(mp4box)?,
+                ParseError::UnsupportedBoxLayout,
+                "stripped metadata box has items located by file offset",
+                WhileParsingBox(mp4box.parsed_header.box_type()),
+            );
+        }
+        let mut stripped = Vec::new();
+        self.boxes.retain(|mp4box| match mp4box.parsed_header.box_type() {
+            box_type @ (BoxType::UDTA | BoxType::META) => {
+                stripped.push(box_type);
+                false
+            }
+            _ => true,
+        });
+        Ok(stripped)
+    }
+
+    /// Remove any boxes whose type is not in `box_types`, returning the types of the boxes removed, in order.
+    pub fn retain_box_types(&mut self, box_types: &[BoxType]) -> Vec<BoxType> {
+        let mut removed = Vec::new();
+        self.boxes.retain(|mp4box| {
+            let box_type = mp4box.parsed_header.box_type();
+            let retain = box_types.contains(&box_type);
+            if !retain {
+                removed.push(box_type);
+            }
+            retain
+        });
+        removed
+    }
+
     pub fn get_one_mut<T: ParseBox + ParsedBox>(&mut self) -> Result<&mut T, ParseError> {
         ensure_attach!(
             self.box_types().filter(|box_type| *box_type == T::box_type()).count() <= 1,
@@ -319,8 +477,91 @@ impl<V> From<Vec<AnyMp4Box>> for Boxes<V> {
     }
 }
 
+//
+// LazyBox impls
+//
+
+#[cfg(feature = "std")]
+impl<'a, R: AsyncRead + AsyncSkip> LazyBox<'a, R> {
+    /// The offset of the start of the box's header within the input.
+    pub(crate) fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// The encoded length of the box, including its header.
+    pub(crate) fn encoded_len(&self) -> u64 {
+        self.header.encoded_len() + self.data_size
+    }
+
+    /// Read the box's data, returning the unparsed box.
+    pub(crate) async fn read_data<T: ParseBox + ParsedBox>(self, max_size: u64) -> StdResult<Mp4Box<T>, Error> {
+        Mp4Box::read_data(self.reader, self.header, max_size).await
+    }
+
+    /// Skip the box's data, returning the amount of data that was skipped.
+    pub(crate) async fn skip(mut self) -> StdResult<u64, Error> {
+        let box_type = self.header.box_type();
+        self.reader
+            .skip(self.data_size)
+            .await
+            .map_eof(|_| Error::Parse(report_attach!(ParseError::TruncatedBox, WhileParsingBox(box_type))))?;
+        Ok(self.data_size)
+    }
+}
+
 //
 // BoxesValidator impls
 //
 
 impl BoxesValidator for () {}
+
+//
+// private functions
+//
+
+/// Returns the size of the data of a box which extends to the end of the input, starting at `data_offset`.
+///
+/// Inputs which can only be skipped forward may not know their length, signalled by [`AsyncSkip::poll_stream_len`]
+/// returning an error of kind [`io::ErrorKind::Unsupported`], in which case such a box is unsupported.
+#[cfg(feature = "std")]
+async fn until_eof_data_size<R>(
+    mut reader: Pin<&mut BufReader<R>>,
+    box_type: BoxType,
+    data_offset: u64,
+) -> StdResult<u64, Error>
+where
+    R: AsyncRead + AsyncSkip,
+{
+    match reader.stream_len().await {
+        Ok(stream_len) => Ok(stream_len.checked_sub(data_offset).ok_or_else(|| {
+            report_attach!(
+                ParseError::InvalidInput,
+                format!("stream length 0x{stream_len:08x} before box data at 0x{data_offset:08x}"),
+                WhileParsingBox(box_type),
+            )
+        })?),
+        Err(err) if err.kind() == io::ErrorKind::Unsupported => Err(report_attach!(
+            ParseError::UnsupportedBoxLayout,
+            "box extends to end of input of unknown length",
+            WhileParsingBox(box_type),
+        )
+        .into()),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Whether a user data (`udta`) or metadata (`meta`) box contains an item location box (`iloc`) locating items by offset
+/// within the file.
+fn has_item_file_offsets(mp4box: &mut AnyMp4Box) -> Result<bool, ParseError> {
+    if let Some(meta) = mp4box.parse_data_as::<MetaBox>()? {
+        return Ok(meta.iloc_mut()?.is_some_and(|iloc| iloc.has_file_offsets()));
+    }
+    if let Some(udta) = mp4box.parse_data_as::<UdtaBox>()? {
+        for meta in udta.metas() {
+            if meta?.iloc_mut()?.is_some_and(|iloc| iloc.has_file_offsets()) {
+                return Ok(true);
+            }
+        }
+    }
+    Ok(false)
+}
@@ -0,0 +1,165 @@
+#![allow(missing_docs)]
+
+use alloc::format;
+
+use bytes::{BufMut, BytesMut};
+
+use crate::error::Result;
+
+use super::error::{ParseResultExt, WhileParsingField};
+use super::{BoxType, FullBoxHeader, Mp4Value, Mp4ValueReaderExt, ParseBox, ParseError, ParsedBox};
+
+#[derive(Clone, Debug)]
+pub struct MvhdBox {
+    header: FullBoxHeader,
+    creation_time: u64,
+    modification_time: u64,
+    timescale: u32,
+    duration: u64,
+    /// The remaining fields (rate, volume, matrix, next track id, etc.), which are not interpreted by the sanitizer.
+    rest: [u8; 80],
+}
+
+const NAME: BoxType = BoxType::MVHD;
+
+impl MvhdBox {
+    /// The number of time units that pass in one second, for the movie as a whole.
+    pub fn timescale(&self) -> u32 {
+        self.timescale
+    }
+
+    /// The duration of the longest track, in the movie [timescale](Self::timescale).
+    ///
+    /// A value of all ones indicates the duration is unknown.
+    pub fn duration(&self) -> u64 {
+        self.duration
+    }
+
+    /// The ID to be used for the next track added to the movie, which is greater than the largest track ID in use.
+    pub fn next_track_id(&self) -> u32 {
+        let [.., a, b, c, d] = self.rest;
+        u32::from_be_bytes([a, b, c, d])
+    }
+
+    /// Returns `true` if the [duration](Self::duration) is all ones, indicating it is unknown.
+    pub fn is_duration_unknown(&self) -> bool {
+        match self.header.version {
+            0 => self.duration == u32::MAX.into(),
+            _ => self.duration == u64::MAX,
+        }
+    }
+}
+
+impl ParseBox for MvhdBox {
+    fn parse(buf: &mut BytesMut) -> Result<Self, ParseError> {
+        let header: FullBoxHeader = buf.get_mp4_value().while_parsing_field(NAME, "header")?;
+        let (creation_time, modification_time, timescale, duration) = match header.version {
+            0 => (
+                buf.get_mp4_value::<u32>()
+                    .while_parsing_field(NAME, "creation_time")?
+                    .into(),
+                buf.get_mp4_value::<u32>()
+                    .while_parsing_field(NAME, "modification_time")?
+                    .into(),
+                buf.get_mp4_value().while_parsing_field(NAME, "timescale")?,
+                buf.get_mp4_value::<u32>().while_parsing_field(NAME, "duration")?.into(),
+            ),
+            1 => (
+                buf.get_mp4_value().while_parsing_field(NAME, "creation_time")?,
+                buf.get_mp4_value().while_parsing_field(NAME, "modification_time")?,
+                buf.get_mp4_value().while_parsing_field(NAME, "timescale")?,
+                buf.get_mp4_value().while_parsing_field(NAME, "duration")?,
+            ),
+            version => bail_attach!(
+                ParseError::InvalidInput,
+                format!("unsupported version {version}"),
+                WhileParsingField(NAME, "header"),
+            ),
+        };
+        let rest = buf.get_mp4_value().while_parsing_field(NAME, "rest")?;
+        Ok(Self { header, creation_time, modification_time, timescale, duration, rest })
+    }
+
+    fn box_type() -> BoxType {
+        NAME
+    }
+}
+
+impl ParsedBox for MvhdBox {
+    fn encoded_len(&self) -> u64 {
+        let times_len = match self.header.version {
+            0 => 4 + 4 + 4,
+            _ => 8 + 8 + 8,
+        };
+        self.header.encoded_len() + times_len + self.timescale.encoded_len() + 80
+    }
+
+    fn put_buf(&self, mut out: &mut dyn BufMut) {
+        self.header.put_buf(&mut out);
+        match self.header.version {
+            0 => {
+                (self.creation_time as u32).put_buf(&mut out);
+                (self.modification_time as u32).put_buf(&mut out);
+                self.timescale.put_buf(&mut out);
+                (self.duration as u32).put_buf(&mut out);
+            }
+            _ => {
+                self.creation_time.put_buf(&mut out);
+                self.modification_time.put_buf(&mut out);
+                self.timescale.put_buf(&mut out);
+                self.duration.put_buf(&mut out);
+            }
+        }
+        self.rest.put_buf(&mut out);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use bytes::BytesMut;
+
+    use crate::util::test::write_mvhd_data;
+
+    use super::*;
+
+    #[test]
+    fn roundtrip() {
+        let mut data = BytesMut::new();
+        write_mvhd_data(&mut data, 1000);
+        let mvhd = MvhdBox::parse(&mut data.clone()).unwrap();
+        assert_eq!(mvhd.timescale(), 1000);
+        assert_eq!(mvhd.duration(), 0);
+        assert_eq!(mvhd.next_track_id(), u32::MAX);
+
+        let mut encoded = BytesMut::new();
+        mvhd.put_buf(&mut encoded);
+        assert_eq!(mvhd.encoded_len(), encoded.len() as u64);
+        assert_eq!(encoded, data);
+    }
+
+    #[test]
+    fn roundtrip_version_1() {
+        let mut v0_data = BytesMut::new();
+        write_mvhd_data(&mut v0_data, 1000);
+        let rest = &v0_data[v0_data.len() - 80..];
+
+        let mut data = BytesMut::new();
+        FullBoxHeader { version: 1, flags: 0 }.put_buf(&mut data);
+        data.put_u64(1 << 32); // creation time
+        data.put_u64(1 << 33); // modification time
+        data.put_u32(90000); // timescale
+        data.put_u64(u64::from(u32::MAX) + 1); // duration
+        data.put_slice(rest);
+
+        let mvhd = MvhdBox::parse(&mut data.clone()).unwrap();
+        assert_eq!(mvhd.timescale(), 90000);
+        assert_eq!(mvhd.duration(), u64::from(u32::MAX) + 1);
+        assert!(!mvhd.is_duration_unknown());
+        assert_eq!(mvhd.next_track_id(), u32::MAX);
+
+        let mut encoded = BytesMut::new();
+        mvhd.put_buf(&mut encoded);
+        assert_eq!(mvhd.encoded_len(), encoded.len() as u64);
+        assert_eq!(encoded, data);
+    }
+}
@@ -0,0 +1,12 @@
+#![allow(missing_docs)]
+
+use super::{ParseBox, ParsedBox, UnboundedArray};
+
+/// The progressive download information box, containing pairs of download rates and suggested initial playback delays.
+///
+/// The box contains no offsets, so its data is kept as-is.
+#[derive(Clone, Debug, ParseBox, ParsedBox)]
+#[box_type = "pdin"]
+pub struct PdinBox {
+    data: UnboundedArray<u8>,
+}
@@ -0,0 +1,159 @@
+#![allow(missing_docs)]
+
+use alloc::format;
+
+use bytes::{BufMut, BytesMut};
+
+use crate::error::Result;
+
+use super::error::{ParseResultExt, WhileParsingField};
+use super::{
+    ArrayEntry, BoundedArray, BoxType, FullBoxHeader, Mp4Value, Mp4ValueReaderExt, ParseBox, ParseError, ParsedBox,
+};
+
+/// The protection system specific header box, identifying a DRM system and the key IDs it applies to.
+#[derive(Clone, Debug)]
+pub struct PsshBox {
+    header: FullBoxHeader,
+    system_id: [u8; 16],
+    /// The key IDs, present only in version 1 and above.
+    key_ids: Option<BoundedArray<u32, [u8; 16]>>,
+    data: BoundedArray<u32, u8>,
+}
+
+const NAME: BoxType = BoxType::PSSH;
+
+impl PsshBox {
+    pub fn system_id(&self) -> [u8; 16] {
+        self.system_id
+    }
+
+    pub fn key_id_count(&self) -> u32 {
+        self.key_ids.as_ref().map_or(0, BoundedArray::entry_count)
+    }
+
+    pub fn key_ids(&self) -> impl Iterator<Item = ArrayEntry<'_, [u8; 16]>> + '_ {
+        self.key_ids.iter().flat_map(BoundedArray::entries)
+    }
+
+    pub fn data_size(&self) -> u32 {
+        self.data.entry_count()
+    }
+}
+
+/// Ensure the count-prefixed array at the start of `buf` fits within `buf`.
+fn ensure_array_fits(buf: &BytesMut, entry_len: u64, field_name: &'static str) -> Result<(), ParseError> {
+    if let Some(count) = buf.get(..4) {
+        let count = u32::from_be_bytes(count.try_into().unwrap_or_else(|_| unreachable!()));
+        ensure_attach!(
+            u64::from(count) * entry_len <= buf.len() as u64 - 4,
+            ParseError::InvalidInput,
+            format!("{count} entries do not fit in box"),
+            WhileParsingField(NAME, field_name),
+        );
+    }
+    Ok(())
+}
+
+impl ParseBox for PsshBox {
+    fn parse(buf: &mut BytesMut) -> Result<Self, ParseError> {
+        let header: FullBoxHeader = buf.get_mp4_value().while_parsing_field(NAME, "header")?;
+        ensure_attach!(
+            header.version <= 1,
+            ParseError::InvalidInput,
+            format!("unsupported version {}", header.version),
+            WhileParsingField(NAME, "header"),
+        );
+        let system_id = buf.get_mp4_value().while_parsing_field(NAME, "system_id")?;
+        let key_ids = match header.version {
+            0 => None,
+            _ => {
+                ensure_array_fits(buf, 16, "key_ids")?;
+                Some(buf.get_mp4_value().while_parsing_field(NAME, "key_ids")?)
+            }
+        };
+        ensure_array_fits(buf, 1, "data")?;
+        let data = buf.get_mp4_value().while_parsing_field(NAME, "data")?;
+        Ok(Self { header, system_id, key_ids, data })
+    }
+
+    fn box_type() -> BoxType {
+        NAME
+    }
+}
+
+impl ParsedBox for PsshBox {
+    fn encoded_len(&self) -> u64 {
+        let key_ids_len = self.key_ids.as_ref().map_or(0, Mp4Value::encoded_len);
+        self.header.encoded_len() + self.system_id.encoded_len() + key_ids_len + self.data.encoded_len()
+    }
+
+    fn put_buf(&self, mut out: &mut dyn BufMut) {
+        self.header.put_buf(&mut out);
+        self.system_id.put_buf(&mut out);
+        if let Some(key_ids) = &self.key_ids {
+            key_ids.put_buf(&mut out);
+        }
+        self.data.put_buf(&mut out);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use assert_matches::assert_matches;
+    use bytes::BytesMut;
+
+    use crate::util::test::write_test_pssh_data;
+
+    use super::*;
+
+    const TEST_SYSTEM_ID: [u8; 16] = *b"\xed\xef\x8b\xa9\x79\xd6\x4a\xce\xa3\xc8\x27\xdc\xd5\x1d\x21\xed";
+
+    #[test]
+    fn version_0() {
+        let mut data = BytesMut::new();
+        write_test_pssh_data(&mut data, 0, TEST_SYSTEM_ID, &[], b"data");
+        let pssh = PsshBox::parse(&mut data.clone()).unwrap();
+        assert_eq!(pssh.system_id(), TEST_SYSTEM_ID);
+        assert_eq!(pssh.key_id_count(), 0);
+        assert_eq!(pssh.data_size(), 4);
+
+        let mut encoded = BytesMut::new();
+        pssh.put_buf(&mut encoded);
+        assert_eq!(encoded, data);
+    }
+
+    #[test]
+    fn version_1_key_ids() {
+        let mut data = BytesMut::new();
+        write_test_pssh_data(&mut data, 1, TEST_SYSTEM_ID, &[[1; 16], [2; 16]], b"");
+        let pssh = PsshBox::parse(&mut data.clone()).unwrap();
+        assert_eq!(pssh.system_id(), TEST_SYSTEM_ID);
+        assert_eq!(pssh.key_id_count(), 2);
+        let key_ids = pssh.key_ids().map(|key_id| key_id.get().unwrap()).collect::<Vec<_>>();
+        assert_eq!(key_ids, [[1; 16], [2; 16]]);
+
+        let mut encoded = BytesMut::new();
+        pssh.put_buf(&mut encoded);
+        assert_eq!(pssh.encoded_len(), encoded.len() as u64);
+        assert_eq!(encoded, data);
+    }
+
+    #[test]
+    fn key_id_count_overflow() {
+        let mut data = BytesMut::new();
+        write_test_pssh_data(&mut data, 1, TEST_SYSTEM_ID, &[[1; 16]], b"");
+        data[20..24].copy_from_slice(&u32::MAX.to_be_bytes());
+        let err = PsshBox::parse(&mut data).unwrap_err();
+        assert_matches!(err.get_ref(), ParseError::InvalidInput, "{err:?}");
+    }
+
+    #[test]
+    fn data_size_overflow() {
+        let mut data = BytesMut::new();
+        write_test_pssh_data(&mut data, 0, TEST_SYSTEM_ID, &[], b"data");
+        data[20..24].copy_from_slice(&5u32.to_be_bytes());
+        let err = PsshBox::parse(&mut data).unwrap_err();
+        assert_matches!(err.get_ref(), ParseError::InvalidInput, "{err:?}");
+    }
+}
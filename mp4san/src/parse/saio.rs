@@ -0,0 +1,211 @@
+#![allow(missing_docs)]
+
+use alloc::format;
+use alloc::vec::Vec;
+
+use bytes::{BufMut, BytesMut};
+use mediasan_common::util::checked_add_signed;
+
+use crate::error::Result;
+
+use super::error::{ParseResultExt, WhileParsingField};
+use super::{
+    BoundedArray, BoxType, FourCC, FullBoxHeader, Mp4Value, Mp4ValueReaderExt, ParseBox, ParseError, ParsedBox,
+};
+
+/// The sample auxiliary information offsets box, locating per-sample auxiliary information, such as capture timestamps,
+/// stored alongside the media data.
+#[derive(Clone, Debug)]
+pub struct SaioBox {
+    header: FullBoxHeader,
+    /// The auxiliary information type and its parameter, present only if flagged.
+    aux_info_type: Option<(FourCC, u32)>,
+    offsets: SaioOffsets,
+}
+
+#[derive(Clone, Debug)]
+enum SaioOffsets {
+    V0(BoundedArray<u32, u32>),
+    V1(BoundedArray<u32, u64>),
+}
+
+const NAME: BoxType = BoxType::SAIO;
+
+impl SaioBox {
+    pub const AUX_INFO_TYPE_PRESENT: u32 = 0x000001;
+
+    /// Construct a [`SaioBox`], using version 1 if any offset does not fit in 32 bits.
+    pub fn new<I: IntoIterator<Item = u64>>(aux_info_type: Option<(FourCC, u32)>, offsets: I) -> Self {
+        let offsets = offsets.into_iter().collect::<Vec<_>>();
+        let flags = if aux_info_type.is_some() {
+            Self::AUX_INFO_TYPE_PRESENT
+        } else {
+            0
+        };
+        let (version, offsets) = match offsets.iter().map(|offset| u32::try_from(*offset)).collect() {
+            Ok(offsets) => (0, SaioOffsets::V0(offsets)),
+            Err(_) => (1, SaioOffsets::V1(offsets.into_iter().collect())),
+        };
+        Self { header: FullBoxHeader { version, flags }, aux_info_type, offsets }
+    }
+
+    /// The type of the auxiliary information, if given, e.g. `stai` for TAI timestamps.
+    pub fn aux_info_type(&self) -> Option<FourCC> {
+        self.aux_info_type.map(|(aux_info_type, _)| aux_info_type)
+    }
+
+    pub fn entry_count(&self) -> u32 {
+        match &self.offsets {
+            SaioOffsets::V0(offsets) => offsets.entry_count(),
+            SaioOffsets::V1(offsets) => offsets.entry_count(),
+        }
+    }
+
+    /// The offsets of the auxiliary information, which are absolute offsets within the file when within a movie box
+    /// (`moov`).
+    pub fn offsets(&self) -> impl Iterator<Item = Result<u64, ParseError>> + '_ {
+        let (v0, v1) = match &self.offsets {
+            SaioOffsets::V0(offsets) => (Some(offsets.entries()), None),
+            SaioOffsets::V1(offsets) => (None, Some(offsets.entries())),
+        };
+        let v0 = v0.into_iter().flatten().map(|entry| entry.get().map(u64::from));
+        let v1 = v1.into_iter().flatten().map(|entry| entry.get());
+        v0.chain(v1)
+    }
+
+    /// Displace each offset by `displacement`, as when moving the media data containing the auxiliary information.
+    ///
+    /// Version 0 offsets are 32 bits, so it is an error for any to be displaced beyond them.
+    pub fn displace_offsets(&mut self, displacement: i64) -> Result<(), ParseError> {
+        let displaced = || report_attach!(ParseError::InvalidInput, "sample auxiliary information offset overflow");
+        match &mut self.offsets {
+            SaioOffsets::V0(offsets) => {
+                for mut entry in &mut offsets.entries_mut() {
+                    let value = entry.get()?;
+                    let displaced_value = checked_add_signed(u64::from(value), displacement)
+                        .and_then(|displaced_value| u32::try_from(displaced_value).ok());
+                    entry.set(displaced_value.ok_or_else(displaced)?);
+                }
+            }
+            SaioOffsets::V1(offsets) => {
+                for mut entry in &mut offsets.entries_mut() {
+                    let value = entry.get()?;
+                    entry.set(checked_add_signed(value, displacement).ok_or_else(displaced)?);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl ParseBox for SaioBox {
+    fn parse(buf: &mut BytesMut) -> Result<Self, ParseError> {
+        let header: FullBoxHeader = buf.get_mp4_value().while_parsing_field(NAME, "header")?;
+        let aux_info_type = match header.flags & Self::AUX_INFO_TYPE_PRESENT {
+            0 => None,
+            _ => Some((
+                buf.get_mp4_value().while_parsing_field(NAME, "aux_info_type")?,
+                buf.get_mp4_value()
+                    .while_parsing_field(NAME, "aux_info_type_parameter")?,
+            )),
+        };
+        let offsets = match header.version {
+            0 => SaioOffsets::V0(buf.get_mp4_value().while_parsing_field(NAME, "offsets")?),
+            1 => SaioOffsets::V1(buf.get_mp4_value().while_parsing_field(NAME, "offsets")?),
+            version => bail_attach!(
+                ParseError::InvalidInput,
+                format!("unsupported version {version}"),
+                WhileParsingField(NAME, "header"),
+            ),
+        };
+        Ok(Self { header, aux_info_type, offsets })
+    }
+
+    fn box_type() -> BoxType {
+        NAME
+    }
+}
+
+impl ParsedBox for SaioBox {
+    fn encoded_len(&self) -> u64 {
+        let aux_info_type_len = match self.aux_info_type {
+            Some((aux_info_type, parameter)) => aux_info_type.encoded_len() + parameter.encoded_len(),
+            None => 0,
+        };
+        let offsets_len = match &self.offsets {
+            SaioOffsets::V0(offsets) => offsets.encoded_len(),
+            SaioOffsets::V1(offsets) => offsets.encoded_len(),
+        };
+        self.header.encoded_len() + aux_info_type_len + offsets_len
+    }
+
+    fn put_buf(&self, mut out: &mut dyn BufMut) {
+        self.header.put_buf(&mut out);
+        if let Some((aux_info_type, parameter)) = self.aux_info_type {
+            aux_info_type.put_buf(&mut out);
+            parameter.put_buf(&mut out);
+        }
+        match &self.offsets {
+            SaioOffsets::V0(offsets) => offsets.put_buf(&mut out),
+            SaioOffsets::V1(offsets) => offsets.put_buf(&mut out),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use assert_matches::assert_matches;
+
+    use super::*;
+
+    const STAI: FourCC = FourCC { value: *b"stai" };
+
+    fn roundtrip(saio: SaioBox) -> SaioBox {
+        let mut data = BytesMut::new();
+        saio.put_buf(&mut data);
+        let saio = SaioBox::parse(&mut data.clone()).unwrap();
+
+        let mut encoded = BytesMut::new();
+        saio.put_buf(&mut encoded);
+        assert_eq!(saio.encoded_len(), encoded.len() as u64);
+        assert_eq!(encoded, data);
+        saio
+    }
+
+    #[test]
+    fn version_0() {
+        let saio = roundtrip(SaioBox::new(Some((STAI, 0)), [16, 32]));
+        assert_eq!(saio.header.version, 0);
+        assert_eq!(saio.aux_info_type(), Some(STAI));
+        assert_eq!(saio.offsets().collect::<Result<Vec<_>, _>>().unwrap(), [16, 32]);
+    }
+
+    #[test]
+    fn version_1() {
+        let saio = roundtrip(SaioBox::new(None, [u64::from(u32::MAX) + 1]));
+        assert_eq!(saio.header.version, 1);
+        assert_eq!(saio.aux_info_type(), None);
+        assert_eq!(
+            saio.offsets().collect::<Result<Vec<_>, _>>().unwrap(),
+            [u64::from(u32::MAX) + 1]
+        );
+    }
+
+    #[test]
+    fn displace_offsets() {
+        let mut saio = SaioBox::new(Some((STAI, 0)), [16, 32]);
+        saio.displace_offsets(-16).unwrap();
+        assert_eq!(saio.offsets().collect::<Result<Vec<_>, _>>().unwrap(), [0, 16]);
+        let err = saio.displace_offsets(-1).unwrap_err();
+        assert_matches!(err.get_ref(), ParseError::InvalidInput, "{err:?}");
+    }
+
+    #[test]
+    fn truncated() {
+        let mut data = BytesMut::new();
+        SaioBox::new(Some((STAI, 0)), [16, 32]).put_buf(&mut data);
+        data.truncate(data.len() - 1);
+        let err = SaioBox::parse(&mut data).unwrap_err();
+        assert_matches!(err.get_ref(), ParseError::TruncatedBox, "{err:?}");
+    }
+}
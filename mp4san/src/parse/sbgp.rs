@@ -0,0 +1,176 @@
+#![allow(missing_docs)]
+
+use alloc::format;
+
+use bytes::{BufMut, BytesMut};
+
+use crate::error::Result;
+
+use super::error::{ParseResultExt, WhileParsingField};
+use super::{
+    ArrayEntry, BoundedArray, BoxType, FourCC, FullBoxHeader, Mp4Value, Mp4ValueReaderExt, ParseBox, ParseError,
+    ParsedBox,
+};
+
+/// The sample to group box, assigning runs of samples to entries of the sample group description box (`sgpd`) with the
+/// same grouping type.
+#[derive(Clone, Debug)]
+pub struct SbgpBox {
+    header: FullBoxHeader,
+    grouping_type: FourCC,
+    /// The grouping type parameter, present only in version 1.
+    grouping_type_parameter: Option<u32>,
+    /// Entries of `[sample_count, group_description_index]`.
+    entries: BoundedArray<u32, [u32; 2]>,
+}
+
+const NAME: BoxType = BoxType::SBGP;
+
+impl SbgpBox {
+    /// Construct a [`SbgpBox`], using version 1 if a `grouping_type_parameter` is given.
+    pub fn new<I>(grouping_type: FourCC, grouping_type_parameter: Option<u32>, entries: I) -> Self
+    where
+        I: IntoIterator<Item = [u32; 2]>,
+    {
+        let version = u8::from(grouping_type_parameter.is_some());
+        Self {
+            header: FullBoxHeader { version, flags: 0 },
+            grouping_type,
+            grouping_type_parameter,
+            entries: entries.into_iter().collect(),
+        }
+    }
+
+    pub fn grouping_type(&self) -> FourCC {
+        self.grouping_type
+    }
+
+    pub fn grouping_type_parameter(&self) -> Option<u32> {
+        self.grouping_type_parameter
+    }
+
+    pub fn entries(&self) -> impl ExactSizeIterator<Item = ArrayEntry<'_, [u32; 2]>> + '_ {
+        self.entries.entries()
+    }
+
+    pub fn entry_count(&self) -> u32 {
+        self.entries.entry_count()
+    }
+}
+
+impl ParseBox for SbgpBox {
+    fn parse(buf: &mut BytesMut) -> Result<Self, ParseError> {
+        let header: FullBoxHeader = buf.get_mp4_value().while_parsing_field(NAME, "header")?;
+        ensure_attach!(
+            header.version <= 1,
+            ParseError::InvalidInput,
+            format!("unsupported version {}", header.version),
+            WhileParsingField(NAME, "header"),
+        );
+        let grouping_type = buf.get_mp4_value().while_parsing_field(NAME, "grouping_type")?;
+        let grouping_type_parameter = match header.version {
+            0 => None,
+            _ => Some(
+                buf.get_mp4_value()
+                    .while_parsing_field(NAME, "grouping_type_parameter")?,
+            ),
+        };
+        if let Some(entry_count) = buf.get(..4) {
+            let entry_count = u32::from_be_bytes(entry_count.try_into().unwrap_or_else(|_| unreachable!()));
+            let entries_len = buf.len() as u64 - 4;
+            ensure_attach!(
+                u64::from(entry_count) * 8 == entries_len,
+                ParseError::InvalidInput,
+                format!("{entry_count} entries do not match box size"),
+                WhileParsingField(NAME, "entries"),
+            );
+        }
+        let entries = buf.get_mp4_value().while_parsing_field(NAME, "entries")?;
+        Ok(Self { header, grouping_type, grouping_type_parameter, entries })
+    }
+
+    fn box_type() -> BoxType {
+        NAME
+    }
+}
+
+impl ParsedBox for SbgpBox {
+    fn encoded_len(&self) -> u64 {
+        let grouping_type_parameter_len = self.grouping_type_parameter.as_ref().map_or(0, Mp4Value::encoded_len);
+        self.header.encoded_len()
+            + self.grouping_type.encoded_len()
+            + grouping_type_parameter_len
+            + self.entries.encoded_len()
+    }
+
+    fn put_buf(&self, mut out: &mut dyn BufMut) {
+        self.header.put_buf(&mut out);
+        self.grouping_type.put_buf(&mut out);
+        if let Some(grouping_type_parameter) = self.grouping_type_parameter {
+            grouping_type_parameter.put_buf(&mut out);
+        }
+        self.entries.put_buf(&mut out);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use assert_matches::assert_matches;
+    use bytes::BytesMut;
+
+    use super::*;
+
+    const ROLL: FourCC = FourCC { value: *b"roll" };
+
+    fn entries(sbgp: &SbgpBox) -> Vec<[u32; 2]> {
+        sbgp.entries().map(|entry| entry.get().unwrap()).collect()
+    }
+
+    #[test]
+    fn version_0() {
+        let mut data = BytesMut::new();
+        SbgpBox::new(ROLL, None, [[3, 1], [2, 0]]).put_buf(&mut data);
+        let sbgp = SbgpBox::parse(&mut data.clone()).unwrap();
+        assert_eq!(sbgp.grouping_type(), ROLL);
+        assert_eq!(sbgp.grouping_type_parameter(), None);
+        assert_eq!(entries(&sbgp), [[3, 1], [2, 0]]);
+
+        let mut encoded = BytesMut::new();
+        sbgp.put_buf(&mut encoded);
+        assert_eq!(sbgp.encoded_len(), encoded.len() as u64);
+        assert_eq!(encoded, data);
+    }
+
+    #[test]
+    fn version_1_grouping_type_parameter() {
+        let mut data = BytesMut::new();
+        SbgpBox::new(ROLL, Some(0x12345678), [[3, 1], [2, 0]]).put_buf(&mut data);
+        let sbgp = SbgpBox::parse(&mut data.clone()).unwrap();
+        assert_eq!(sbgp.grouping_type(), ROLL);
+        assert_eq!(sbgp.grouping_type_parameter(), Some(0x12345678));
+        assert_eq!(entries(&sbgp), [[3, 1], [2, 0]]);
+
+        let mut encoded = BytesMut::new();
+        sbgp.put_buf(&mut encoded);
+        assert_eq!(sbgp.encoded_len(), encoded.len() as u64);
+        assert_eq!(encoded, data);
+    }
+
+    #[test]
+    fn version_mismatch() {
+        let mut data = BytesMut::new();
+        SbgpBox::new(ROLL, Some(1), [[3, 1]]).put_buf(&mut data);
+        data[0] = 0;
+        let err = SbgpBox::parse(&mut data).unwrap_err();
+        assert_matches!(err.get_ref(), ParseError::InvalidInput, "{err:?}");
+    }
+
+    #[test]
+    fn entry_count_too_large() {
+        let mut data = BytesMut::new();
+        SbgpBox::new(ROLL, None, [[3, 1]]).put_buf(&mut data);
+        data[11] = 2;
+        let err = SbgpBox::parse(&mut data).unwrap_err();
+        assert_matches!(err.get_ref(), ParseError::InvalidInput, "{err:?}");
+    }
+}
@@ -0,0 +1,234 @@
+#![allow(missing_docs)]
+
+use alloc::format;
+use alloc::vec::Vec;
+
+use bytes::{BufMut, BytesMut};
+
+use crate::error::Result;
+
+use super::error::{ParseResultExt, WhileParsingField};
+use super::{BoxType, FourCC, FullBoxHeader, Mp4Value, Mp4ValueReaderExt, ParseBox, ParseError, ParsedBox};
+
+/// The sample group description box, describing the entries referred to by the sample to group box (`sbgp`) with the
+/// same grouping type.
+///
+/// Only versions 1 and 2 are supported, as the lengths of the entries of a version 0 box depend on the grouping type.
+#[derive(Clone, Debug)]
+pub struct SgpdBox {
+    header: FullBoxHeader,
+    grouping_type: FourCC,
+    /// The length of every entry, or `0` if each entry is preceded by its own length.
+    default_length: u32,
+    /// The default sample description index, present only in version 2.
+    default_sample_description_index: Option<u32>,
+    entries: Vec<BytesMut>,
+}
+
+const NAME: BoxType = BoxType::SGPD;
+
+impl SgpdBox {
+    /// Construct a [`SgpdBox`], using version 2 if a `default_sample_description_index` is given.
+    ///
+    /// If all of the `entries` have the same non-zero length, it is used as the default length; otherwise each entry is
+    /// encoded with its own length.
+    pub fn new<I>(grouping_type: FourCC, default_sample_description_index: Option<u32>, entries: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<BytesMut>,
+    {
+        let entries: Vec<BytesMut> = entries.into_iter().map(Into::into).collect();
+        let mut entry_lens = entries.iter().map(|entry| entry.len());
+        let default_length = match entry_lens.next() {
+            Some(len) if entry_lens.all(|other_len| other_len == len) => u32::try_from(len).unwrap_or(0),
+            _ => 0,
+        };
+        let version = 1 + u8::from(default_sample_description_index.is_some());
+        Self {
+            header: FullBoxHeader { version, flags: 0 },
+            grouping_type,
+            default_length,
+            default_sample_description_index,
+            entries,
+        }
+    }
+
+    pub fn grouping_type(&self) -> FourCC {
+        self.grouping_type
+    }
+
+    /// The length of every entry, or `0` if each entry is preceded by its own length.
+    pub fn default_length(&self) -> u32 {
+        self.default_length
+    }
+
+    pub fn default_sample_description_index(&self) -> Option<u32> {
+        self.default_sample_description_index
+    }
+
+    pub fn entries(&self) -> impl ExactSizeIterator<Item = &[u8]> + '_ {
+        self.entries.iter().map(|entry| &entry[..])
+    }
+
+    pub fn entry_count(&self) -> u32 {
+        self.entries.len() as u32
+    }
+}
+
+impl ParseBox for SgpdBox {
+    fn parse(buf: &mut BytesMut) -> Result<Self, ParseError> {
+        let header: FullBoxHeader = buf.get_mp4_value().while_parsing_field(NAME, "header")?;
+        ensure_attach!(
+            matches!(header.version, 1 | 2),
+            ParseError::UnsupportedBoxLayout,
+            format!("unsupported version {}", header.version),
+            WhileParsingField(NAME, "header"),
+        );
+        let grouping_type = buf.get_mp4_value().while_parsing_field(NAME, "grouping_type")?;
+        let default_length: u32 = buf.get_mp4_value().while_parsing_field(NAME, "default_length")?;
+        let default_sample_description_index = match header.version {
+            1 => None,
+            _ => Some(
+                buf.get_mp4_value()
+                    .while_parsing_field(NAME, "default_sample_description_index")?,
+            ),
+        };
+        let entry_count: u32 = buf.get_mp4_value().while_parsing_field(NAME, "entry_count")?;
+
+        let mut entries = Vec::new();
+        for _ in 0..entry_count {
+            let entry_len = match default_length {
+                0 => buf.get_mp4_value().while_parsing_field(NAME, "description_length")?,
+                _ => default_length,
+            };
+            ensure_attach!(
+                entry_len as usize <= buf.len(),
+                ParseError::InvalidInput,
+                format!("{entry_count} entries do not match box size"),
+                WhileParsingField(NAME, "entries"),
+            );
+            entries.push(buf.split_to(entry_len as usize));
+        }
+        ensure_attach!(
+            buf.is_empty(),
+            ParseError::InvalidInput,
+            format!("{entry_count} entries do not match box size"),
+            WhileParsingField(NAME, "entries"),
+        );
+
+        Ok(Self { header, grouping_type, default_length, default_sample_description_index, entries })
+    }
+
+    fn box_type() -> BoxType {
+        NAME
+    }
+}
+
+impl ParsedBox for SgpdBox {
+    fn encoded_len(&self) -> u64 {
+        let default_sample_description_index_len =
+            (self.default_sample_description_index.as_ref()).map_or(0, Mp4Value::encoded_len);
+        let entry_len_len = match self.default_length {
+            0 => 0u32.encoded_len(),
+            _ => 0,
+        };
+        let entries_len: u64 = (self.entries.iter())
+            .map(|entry| entry_len_len + entry.len() as u64)
+            .sum();
+        self.header.encoded_len()
+            + self.grouping_type.encoded_len()
+            + self.default_length.encoded_len()
+            + default_sample_description_index_len
+            + self.entry_count().encoded_len()
+            + entries_len
+    }
+
+    fn put_buf(&self, mut out: &mut dyn BufMut) {
+        self.header.put_buf(&mut out);
+        self.grouping_type.put_buf(&mut out);
+        self.default_length.put_buf(&mut out);
+        if let Some(default_sample_description_index) = self.default_sample_description_index {
+            default_sample_description_index.put_buf(&mut out);
+        }
+        self.entry_count().put_buf(&mut out);
+        for entry in &self.entries {
+            if self.default_length == 0 {
+                (entry.len() as u32).put_buf(&mut out);
+            }
+            out.put_slice(entry);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use assert_matches::assert_matches;
+
+    use super::*;
+
+    const ROLL: FourCC = FourCC { value: *b"roll" };
+
+    fn roundtrip(sgpd: &SgpdBox) -> SgpdBox {
+        let mut data = BytesMut::new();
+        sgpd.put_buf(&mut data);
+        assert_eq!(sgpd.encoded_len(), data.len() as u64);
+        let parsed = SgpdBox::parse(&mut data.clone()).unwrap();
+
+        let mut encoded = BytesMut::new();
+        parsed.put_buf(&mut encoded);
+        assert_eq!(encoded, data);
+        parsed
+    }
+
+    #[test]
+    fn version_1() {
+        let sgpd = roundtrip(&SgpdBox::new(ROLL, None, [&b"\xff\xff"[..], b"\x00\x01"]));
+        assert_eq!(sgpd.grouping_type(), ROLL);
+        assert_eq!(sgpd.default_length(), 2);
+        assert_eq!(sgpd.default_sample_description_index(), None);
+        assert_eq!(sgpd.entries().collect::<Vec<_>>(), [b"\xff\xff", b"\x00\x01"]);
+    }
+
+    #[test]
+    fn version_2_default_sample_description_index() {
+        let sgpd = roundtrip(&SgpdBox::new(ROLL, Some(2), [&b"\xff\xff"[..], b"\x00\x01"]));
+        assert_eq!(sgpd.default_length(), 2);
+        assert_eq!(sgpd.default_sample_description_index(), Some(2));
+        assert_eq!(sgpd.entry_count(), 2);
+        assert_eq!(sgpd.entries().collect::<Vec<_>>(), [b"\xff\xff", b"\x00\x01"]);
+    }
+
+    #[test]
+    fn variable_length_entries() {
+        let sgpd = roundtrip(&SgpdBox::new(ROLL, Some(1), [&b"\xff"[..], b"\x00\x01\x02"]));
+        assert_eq!(sgpd.default_length(), 0);
+        assert_eq!(sgpd.entries().collect::<Vec<_>>(), [&b"\xff"[..], b"\x00\x01\x02"]);
+    }
+
+    #[test]
+    fn entry_count_too_large() {
+        let mut data = BytesMut::new();
+        SgpdBox::new(ROLL, Some(1), [&b"\xff\xff"[..]]).put_buf(&mut data);
+        data[19] = 2;
+        let err = SgpdBox::parse(&mut data).unwrap_err();
+        assert_matches!(err.get_ref(), ParseError::InvalidInput, "{err:?}");
+    }
+
+    #[test]
+    fn trailing_bytes() {
+        let mut data = BytesMut::new();
+        SgpdBox::new(ROLL, None, [&b"\xff\xff"[..]]).put_buf(&mut data);
+        data.put_u8(0);
+        let err = SgpdBox::parse(&mut data).unwrap_err();
+        assert_matches!(err.get_ref(), ParseError::InvalidInput, "{err:?}");
+    }
+
+    #[test]
+    fn version_0() {
+        let mut data = BytesMut::new();
+        SgpdBox::new(ROLL, None, [&b"\xff\xff"[..]]).put_buf(&mut data);
+        data[0] = 0;
+        let err = SgpdBox::parse(&mut data).unwrap_err();
+        assert_matches!(err.get_ref(), ParseError::UnsupportedBoxLayout, "{err:?}");
+    }
+}
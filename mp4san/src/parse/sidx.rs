@@ -0,0 +1,259 @@
+#![allow(missing_docs)]
+
+use alloc::format;
+
+use bytes::{Buf, BufMut, BytesMut};
+
+use crate::error::Result;
+
+use super::error::{ParseResultExt, WhileParsingField};
+use super::{
+    ArrayEntry, BoundedArray, BoxType, FullBoxHeader, Mp4Prim, Mp4Value, Mp4ValueReaderExt, Mp4ValueWriterExt,
+    ParseBox, ParseError, ParsedBox,
+};
+
+/// The segment index box, indexing the subsegments of a fragmented file, such as for DASH.
+///
+/// The referenced subsegments are located relative to the anchor point at the end of the box, offset by the [first
+/// offset](Self::first_offset).
+#[derive(Clone, Debug)]
+pub struct SidxBox {
+    header: FullBoxHeader,
+    reference_id: u32,
+    timescale: u32,
+    earliest_presentation_time: u64,
+    first_offset: u64,
+    reserved: u16,
+    references: BoundedArray<u16, SidxReference>,
+}
+
+/// A single reference within a [`SidxBox`], to either a subsegment or another segment index box.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SidxReference {
+    /// Whether the reference is to another segment index box, rather than to a subsegment of media.
+    pub reference_type: bool,
+    /// The size of the referenced material, in bytes. Only the low 31 bits are used.
+    pub referenced_size: u32,
+    /// The duration of the referenced subsegment, in the [timescale](SidxBox::timescale).
+    pub subsegment_duration: u32,
+    /// Whether the referenced subsegment starts with a stream access point.
+    pub starts_with_sap: bool,
+    /// The type of stream access point within the referenced subsegment. Only the low 3 bits are used.
+    pub sap_type: u8,
+    /// The presentation time offset of the first stream access point within the referenced subsegment. Only the low 28
+    /// bits are used.
+    pub sap_delta_time: u32,
+}
+
+const NAME: BoxType = BoxType::SIDX;
+
+const REFERENCE_TYPE_FLAG: u32 = 0x8000_0000;
+const STARTS_WITH_SAP_FLAG: u32 = 0x8000_0000;
+const SAP_TYPE_SHIFT: u32 = 28;
+const SAP_TYPE_MASK: u32 = 0x7;
+const SAP_DELTA_TIME_MASK: u32 = 0x0fff_ffff;
+
+impl SidxBox {
+    /// The ID of the track whose stream is indexed.
+    pub fn reference_id(&self) -> u32 {
+        self.reference_id
+    }
+
+    /// The number of time units that pass in one second, for the indexed stream.
+    pub fn timescale(&self) -> u32 {
+        self.timescale
+    }
+
+    pub fn earliest_presentation_time(&self) -> u64 {
+        self.earliest_presentation_time
+    }
+
+    /// The distance in bytes from the anchor point at the end of this box to the first referenced material.
+    pub fn first_offset(&self) -> u64 {
+        self.first_offset
+    }
+
+    /// Set the [first offset](Self::first_offset).
+    ///
+    /// Returns an error if the offset does not fit in a version 0 box.
+    pub fn set_first_offset(&mut self, first_offset: u64) -> Result<(), ParseError> {
+        if self.header.version == 0 {
+            ensure_attach!(
+                u32::try_from(first_offset).is_ok(),
+                ParseError::InvalidInput,
+                format!("first offset 0x{first_offset:016x} overflows"),
+                WhileParsingField(NAME, "first_offset"),
+            );
+        }
+        self.first_offset = first_offset;
+        Ok(())
+    }
+
+    pub fn references(&self) -> impl ExactSizeIterator<Item = ArrayEntry<'_, SidxReference>> + '_ {
+        self.references.entries()
+    }
+
+    pub fn reference_count(&self) -> u16 {
+        self.references.entry_count()
+    }
+}
+
+impl ParseBox for SidxBox {
+    fn parse(buf: &mut BytesMut) -> Result<Self, ParseError> {
+        let header: FullBoxHeader = buf.get_mp4_value().while_parsing_field(NAME, "header")?;
+        let reference_id = buf.get_mp4_value().while_parsing_field(NAME, "reference_id")?;
+        let timescale = buf.get_mp4_value().while_parsing_field(NAME, "timescale")?;
+        let (earliest_presentation_time, first_offset) = match header.version {
+            0 => (
+                buf.get_mp4_value::<u32>()
+                    .while_parsing_field(NAME, "earliest_presentation_time")?
+                    .into(),
+                buf.get_mp4_value::<u32>()
+                    .while_parsing_field(NAME, "first_offset")?
+                    .into(),
+            ),
+            1 => (
+                buf.get_mp4_value()
+                    .while_parsing_field(NAME, "earliest_presentation_time")?,
+                buf.get_mp4_value().while_parsing_field(NAME, "first_offset")?,
+            ),
+            version => bail_attach!(
+                ParseError::InvalidInput,
+                format!("unsupported version {version}"),
+                WhileParsingField(NAME, "header"),
+            ),
+        };
+        let reserved = buf.get_mp4_value().while_parsing_field(NAME, "reserved")?;
+        let references = buf.get_mp4_value().while_parsing_field(NAME, "references")?;
+        Ok(Self { header, reference_id, timescale, earliest_presentation_time, first_offset, reserved, references })
+    }
+
+    fn box_type() -> BoxType {
+        NAME
+    }
+}
+
+impl ParsedBox for SidxBox {
+    fn encoded_len(&self) -> u64 {
+        let times_len = match self.header.version {
+            0 => 4 + 4,
+            _ => 8 + 8,
+        };
+        Mp4Value::encoded_len(&self.header)
+            + Mp4Value::encoded_len(&self.reference_id)
+            + Mp4Value::encoded_len(&self.timescale)
+            + times_len
+            + Mp4Value::encoded_len(&self.reserved)
+            + self.references.encoded_len()
+    }
+
+    fn put_buf(&self, mut out: &mut dyn BufMut) {
+        out.put_mp4_value(&self.header);
+        out.put_mp4_value(&self.reference_id);
+        out.put_mp4_value(&self.timescale);
+        match self.header.version {
+            0 => {
+                out.put_mp4_value(&(self.earliest_presentation_time as u32));
+                out.put_mp4_value(&(self.first_offset as u32));
+            }
+            _ => {
+                out.put_mp4_value(&self.earliest_presentation_time);
+                out.put_mp4_value(&self.first_offset);
+            }
+        }
+        out.put_mp4_value(&self.reserved);
+        out.put_mp4_value(&self.references);
+    }
+}
+
+impl Mp4Prim for SidxReference {
+    fn parse<B: Buf>(buf: B) -> Result<Self, ParseError> {
+        let [reference, subsegment_duration, sap]: [u32; 3] = Mp4Prim::parse(buf)?;
+        Ok(Self {
+            reference_type: reference & REFERENCE_TYPE_FLAG != 0,
+            referenced_size: reference & !REFERENCE_TYPE_FLAG,
+            subsegment_duration,
+            starts_with_sap: sap & STARTS_WITH_SAP_FLAG != 0,
+            sap_type: ((sap >> SAP_TYPE_SHIFT) & SAP_TYPE_MASK) as u8,
+            sap_delta_time: sap & SAP_DELTA_TIME_MASK,
+        })
+    }
+
+    fn encoded_len() -> u64 {
+        <[u32; 3] as Mp4Prim>::encoded_len()
+    }
+
+    fn put_buf<B: BufMut>(&self, buf: B) {
+        let mut reference = self.referenced_size & !REFERENCE_TYPE_FLAG;
+        if self.reference_type {
+            reference |= REFERENCE_TYPE_FLAG;
+        }
+        let mut sap =
+            (u32::from(self.sap_type) & SAP_TYPE_MASK) << SAP_TYPE_SHIFT | self.sap_delta_time & SAP_DELTA_TIME_MASK;
+        if self.starts_with_sap {
+            sap |= STARTS_WITH_SAP_FLAG;
+        }
+        Mp4Prim::put_buf(&[reference, self.subsegment_duration, sap], buf)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use assert_matches::assert_matches;
+
+    use crate::util::test::write_test_sidx_data;
+
+    use super::*;
+
+    const TEST_REFERENCE: SidxReference = SidxReference {
+        reference_type: false,
+        referenced_size: 1234,
+        subsegment_duration: 90000,
+        starts_with_sap: true,
+        sap_type: 1,
+        sap_delta_time: 0,
+    };
+
+    #[test]
+    fn roundtrip() {
+        for version in [0, 1] {
+            let mut data = BytesMut::new();
+            write_test_sidx_data(&mut data, version, 16, &[TEST_REFERENCE]);
+            let sidx = SidxBox::parse(&mut data.clone()).unwrap();
+            assert_eq!(sidx.first_offset(), 16);
+            assert_eq!(sidx.reference_count(), 1);
+            let references = sidx.references().map(|entry| entry.get().unwrap()).collect::<Vec<_>>();
+            assert_eq!(references, [TEST_REFERENCE]);
+            assert_eq!(sidx.encoded_len(), data.len() as u64);
+
+            let mut encoded = BytesMut::new();
+            sidx.put_buf(&mut encoded);
+            assert_eq!(encoded, data);
+        }
+    }
+
+    #[test]
+    fn set_first_offset() {
+        let mut data = BytesMut::new();
+        write_test_sidx_data(&mut data, 0, 0, &[TEST_REFERENCE]);
+        let mut sidx = SidxBox::parse(&mut data).unwrap();
+        sidx.set_first_offset(100).unwrap();
+        assert_eq!(sidx.first_offset(), 100);
+        let err = sidx.set_first_offset(u64::from(u32::MAX) + 1).unwrap_err();
+        assert_matches!(err.get_ref(), ParseError::InvalidInput, "{err:?}");
+
+        let mut data = BytesMut::new();
+        write_test_sidx_data(&mut data, 1, 0, &[TEST_REFERENCE]);
+        let mut sidx = SidxBox::parse(&mut data).unwrap();
+        sidx.set_first_offset(u64::from(u32::MAX) + 1).unwrap();
+    }
+
+    #[test]
+    fn truncated_references() {
+        let mut data = BytesMut::new();
+        write_test_sidx_data(&mut data, 0, 0, &[TEST_REFERENCE]);
+        data.truncate(data.len() - 1);
+        let err = SidxBox::parse(&mut data).unwrap_err();
+        assert_matches!(err.get_ref(), ParseError::TruncatedBox, "{err:?}");
+    }
+}
@@ -1,14 +1,21 @@
 #![allow(missing_docs)]
 
+use alloc::vec::Vec;
+
 use crate::error::Result;
 
-use super::error::{ParseResultExt, WhileParsingChild};
-use super::{BoxType, Boxes, Co64Box, ParseBox, ParseError, ParsedBox, StcoBox};
+use mediasan_common::ResultExt;
+
+use super::error::{ParseResultExt, WhileParsingChild, WhileParsingField};
+use super::{
+    BoxType, Boxes, BoxesValidator, Co64Box, Mp4Box, ParseBox, ParseError, ParsedBox, SaioBox, StcoBox, StscBox,
+    StsdBox, StssBox, StszBox,
+};
 
 #[derive(Clone, Debug, ParseBox, ParsedBox)]
 #[box_type = "stbl"]
 pub struct StblBox {
-    children: Boxes,
+    children: Boxes<StblChildrenValidator>,
 }
 
 #[derive(Debug)]
@@ -17,16 +24,47 @@ pub enum StblCoMut<'a> {
     Co64(&'a mut Co64Box),
 }
 
+pub(crate) struct StblChildrenValidator;
+
 const NAME: BoxType = BoxType::STBL;
 const STCO: BoxType = BoxType::STCO;
 const CO64: BoxType = BoxType::CO64;
 
+/// The child box types retained by [`StblBox::minimize`].
+const MINIMAL_CHILDREN: &[BoxType] = &[
+    BoxType::STSD,
+    BoxType::STTS,
+    BoxType::CTTS,
+    BoxType::STSC,
+    BoxType::STSZ,
+    BoxType::STZ2,
+    BoxType::STCO,
+    BoxType::CO64,
+    BoxType::STSS,
+    BoxType::SGPD,
+    BoxType::SBGP,
+    BoxType::SAIZ,
+    BoxType::SAIO,
+];
+
 impl StblBox {
     #[cfg(test)]
-    pub(crate) fn with_children<C: Into<Boxes>>(children: C) -> Self {
+    pub(crate) fn with_children<C: Into<Boxes<StblChildrenValidator>>>(children: C) -> Self {
         Self { children: children.into() }
     }
 
+    /// Remove any boxes not required for playback from within this box, returning the types of the boxes removed, in
+    /// order.
+    pub fn minimize(&mut self) -> Vec<BoxType> {
+        self.children.retain_box_types(MINIMAL_CHILDREN)
+    }
+
+    /// Remove any free space boxes (`free` or `skip`) from within this box, returning the total encoded length of the
+    /// boxes removed.
+    pub fn strip_free_boxes(&mut self) -> u64 {
+        self.children.strip_free_boxes()
+    }
+
     pub fn co_mut(&mut self) -> Result<StblCoMut<'_>, ParseError> {
         let have_stco = self.children.box_types().any(|box_type| box_type == STCO);
         let have_co64 = self.children.box_types().any(|box_type| box_type == CO64);
@@ -48,6 +86,76 @@ impl StblBox {
                 .map(StblCoMut::Co64)
         }
     }
+
+    /// Replace the chunk offset box (`stco`), if any, with an equivalent `co64` box with 64-bit entries, returning whether
+    /// it was replaced.
+    pub fn promote_stco_to_co64(&mut self) -> Result<bool, ParseError> {
+        let StblCoMut::Stco(stco) = self.co_mut()? else {
+            return Ok(false);
+        };
+        let co64: Co64Box = stco
+            .values()
+            .map(|value| value.map(u64::from))
+            .collect::<Result<_, _>>()
+            .while_parsing_child(NAME, STCO)?;
+        let co64 = Mp4Box::with_data(co64.into())?.into();
+        if let Some(child) = self
+            .children
+            .iter_mut()
+            .find(|child| child.calculated_header().box_type() == STCO)
+        {
+            *child = co64;
+        }
+        Ok(true)
+    }
+
+    pub fn saios(&mut self) -> impl Iterator<Item = Result<&mut SaioBox, ParseError>> + '_ {
+        self.children
+            .get_mut()
+            .map(|result| result.while_parsing_child(NAME, BoxType::SAIO))
+    }
+
+    pub fn stsc_mut(&mut self) -> Result<&mut StscBox, ParseError> {
+        self.children.get_one_mut().while_parsing_child(NAME, BoxType::STSC)
+    }
+
+    pub fn stsd_mut(&mut self) -> Result<&mut StsdBox, ParseError> {
+        self.children.get_one_mut().while_parsing_child(NAME, BoxType::STSD)
+    }
+
+    pub fn stss_mut(&mut self) -> Result<Option<&mut StssBox>, ParseError> {
+        self.children
+            .get_optional_mut()
+            .while_parsing_child(NAME, BoxType::STSS)
+    }
+
+    pub fn stsz_mut(&mut self) -> Result<&mut StszBox, ParseError> {
+        self.children.get_one_mut().while_parsing_child(NAME, BoxType::STSZ)
+    }
+}
+
+//
+// StblChildrenValidator impls
+//
+
+impl BoxesValidator for StblChildrenValidator {
+    fn validate<V>(children: &Boxes<V>) -> Result<(), ParseError> {
+        for box_type in [BoxType::STSD, BoxType::STTS, BoxType::STSC] {
+            children
+                .ensure_one(box_type)
+                .attach_printable(WhileParsingField(NAME, "children"))?;
+        }
+        for (box_type, alternate_box_type) in [(BoxType::STSZ, BoxType::STZ2), (STCO, CO64)] {
+            ensure_attach!(
+                children
+                    .box_types()
+                    .any(|child_type| child_type == box_type || child_type == alternate_box_type),
+                ParseError::MissingRequiredBox(box_type),
+                WhileParsingField(NAME, "children"),
+            );
+        }
+        Ok(())
+    }
 }
 
 //
@@ -62,3 +170,93 @@ impl StblCoMut<'_> {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use assert_matches::assert_matches;
+    use bytes::BytesMut;
+
+    use crate::parse::{AnyMp4Box, Mp4Box, StcoBox};
+    use crate::util::test::{test_stsc, test_stsd, test_stsz, test_stts};
+
+    use super::*;
+
+    fn test_stco() -> AnyMp4Box {
+        Mp4Box::with_data(StcoBox::default().into()).unwrap().into()
+    }
+
+    #[test]
+    fn roundtrip() {
+        let mut data = BytesMut::new();
+        StblBox::with_children(vec![test_stsd(), test_stts(0), test_stsc(), test_stsz(0), test_stco()])
+            .put_buf(&mut data);
+        StblBox::parse(&mut data).unwrap();
+    }
+
+    #[test]
+    fn promote_stco_to_co64() {
+        let stco = Mp4Box::with_data(StcoBox::from_iter([16, u32::MAX]).into()).unwrap();
+        let mut stbl = StblBox::with_children(vec![test_stsd(), test_stts(2), test_stsc(), test_stsz(2), stco.into()]);
+        let encoded_len = stbl.encoded_len();
+
+        assert!(stbl.promote_stco_to_co64().unwrap());
+        assert_eq!(stbl.encoded_len(), encoded_len + 8);
+        assert_eq!(stbl.children.box_types().last(), Some(CO64));
+        assert_matches!(stbl.co_mut().unwrap(), StblCoMut::Co64(co64) => {
+            let entries = co64.entries().map(|entry| entry.get().unwrap()).collect::<Vec<_>>();
+            assert_eq!(entries, [16, u64::from(u32::MAX)]);
+        });
+        assert!(!stbl.promote_stco_to_co64().unwrap());
+
+        let mut data = BytesMut::new();
+        stbl.put_buf(&mut data);
+        StblBox::parse(&mut data).unwrap();
+    }
+
+    #[test]
+    fn minimize() {
+        let sgpd = Mp4Box::with_bytes(BoxType::SGPD, BytesMut::new());
+        let sbgp = Mp4Box::with_bytes(BoxType::SBGP, BytesMut::new());
+        let sdtp = Mp4Box::with_bytes(BoxType::SDTP, BytesMut::new());
+        let children = vec![
+            test_stsd(),
+            test_stts(0),
+            test_stsc(),
+            test_stsz(0),
+            test_stco(),
+            sgpd,
+            sdtp,
+            sbgp,
+        ];
+        let mut stbl = StblBox::with_children(children);
+
+        assert_eq!(stbl.minimize(), [BoxType::SDTP]);
+        let box_types = stbl.children.box_types().collect::<Vec<_>>();
+        let expected_box_types = [
+            BoxType::STSD,
+            BoxType::STTS,
+            BoxType::STSC,
+            BoxType::STSZ,
+            STCO,
+            BoxType::SGPD,
+            BoxType::SBGP,
+        ];
+        assert_eq!(box_types, expected_box_types);
+    }
+
+    #[test]
+    fn no_stts() {
+        let mut data = BytesMut::new();
+        StblBox::with_children(vec![test_stsd(), test_stsc(), test_stsz(0), test_stco()]).put_buf(&mut data);
+        let err = StblBox::parse(&mut data).unwrap_err();
+        assert_matches!(err.get_ref(), ParseError::MissingRequiredBox(BoxType::STTS), "{err:?}");
+    }
+
+    #[test]
+    fn no_stsz() {
+        let mut data = BytesMut::new();
+        StblBox::with_children(vec![test_stsd(), test_stts(0), test_stsc(), test_stco()]).put_buf(&mut data);
+        let err = StblBox::parse(&mut data).unwrap_err();
+        assert_matches!(err.get_ref(), ParseError::MissingRequiredBox(BoxType::STSZ), "{err:?}");
+    }
+}
@@ -1,6 +1,8 @@
 #![allow(missing_docs)]
 
-use super::{ArrayEntryMut, BoundedArray, ConstFullBoxHeader, ParseBox, ParsedBox};
+use crate::error::Result;
+
+use super::{ArrayEntry, ArrayEntryMut, BoundedArray, ConstFullBoxHeader, ParseBox, ParseError, ParsedBox};
 
 #[derive(Clone, Debug, Default, ParseBox, ParsedBox)]
 #[box_type = "stco"]
@@ -10,10 +12,19 @@ pub struct StcoBox {
 }
 
 impl StcoBox {
-    pub fn entries_mut(&mut self) -> impl Iterator<Item = ArrayEntryMut<'_, u32>> + ExactSizeIterator + '_ {
+    pub fn entries(&self) -> impl ExactSizeIterator<Item = ArrayEntry<'_, u32>> + '_ {
+        self.entries.entries()
+    }
+
+    pub fn entries_mut(&mut self) -> impl ExactSizeIterator<Item = ArrayEntryMut<'_, u32>> + '_ {
         self.entries.entries_mut()
     }
 
+    /// The chunk offsets.
+    pub fn values(&self) -> impl ExactSizeIterator<Item = Result<u32, ParseError>> + '_ {
+        self.entries.values()
+    }
+
     pub fn entry_count(&self) -> u32 {
         self.entries.entry_count()
     }
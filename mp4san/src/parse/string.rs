@@ -0,0 +1,53 @@
+#![allow(missing_docs)]
+
+use bytes::{BufMut, BytesMut};
+
+use crate::error::Result;
+
+use super::{Mp4Value, ParseError};
+
+/// A null-terminated string.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct NullTerminatedString {
+    /// The string's bytes, including its null terminator.
+    bytes: BytesMut,
+}
+
+impl NullTerminatedString {
+    /// The string's bytes, not including its null terminator.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes[..self.bytes.len() - 1]
+    }
+}
+
+impl Mp4Value for NullTerminatedString {
+    fn parse(buf: &mut BytesMut) -> Result<Self, ParseError> {
+        let Some(terminator_idx) = buf.iter().position(|&byte| byte == 0) else {
+            bail_attach!(ParseError::InvalidInput, "missing null terminator");
+        };
+        Ok(Self { bytes: buf.split_to(terminator_idx + 1) })
+    }
+
+    fn encoded_len(&self) -> u64 {
+        self.bytes.len() as u64
+    }
+
+    fn put_buf<B: BufMut>(&self, mut buf: B) {
+        buf.put_slice(&self.bytes[..])
+    }
+}
+
+impl From<&[u8]> for NullTerminatedString {
+    /// Construct a [`NullTerminatedString`] from bytes, not including a null terminator.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the bytes contain a null byte.
+    fn from(from: &[u8]) -> Self {
+        assert!(!from.contains(&0), "string contains a null byte");
+        let mut bytes = BytesMut::with_capacity(from.len() + 1);
+        bytes.extend_from_slice(from);
+        bytes.put_u8(0);
+        Self { bytes }
+    }
+}
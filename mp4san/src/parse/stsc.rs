@@ -0,0 +1,143 @@
+#![allow(missing_docs)]
+
+use bytes::{Buf, BufMut, BytesMut};
+
+use crate::error::Result;
+
+use super::error::{ParseResultExt, WhileParsingField};
+use super::{
+    ArrayEntry, ArrayEntryMut, BoundedArray, BoxType, ConstFullBoxHeader, Mp4Prim, Mp4ValueReaderExt, ParseBox,
+    ParseError, ParsedBox,
+};
+
+#[derive(Clone, Debug, Default, ParsedBox)]
+pub struct StscBox {
+    header: ConstFullBoxHeader,
+    entries: BoundedArray<u32, StscEntry>,
+}
+
+/// A single entry within a [`StscBox`], describing a run of chunks with the same number of samples.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct StscEntry {
+    /// The 1-based number of the first chunk of the run.
+    pub first_chunk: u32,
+    /// The number of samples in each chunk of the run.
+    pub samples_per_chunk: u32,
+    /// The 1-based index of the sample description (`stsd`) entry describing the samples of the run.
+    pub sample_description_index: u32,
+}
+
+const NAME: BoxType = BoxType::STSC;
+
+impl StscBox {
+    pub fn entries(&self) -> impl ExactSizeIterator<Item = ArrayEntry<'_, StscEntry>> + '_ {
+        self.entries.entries()
+    }
+
+    pub fn entries_mut(&mut self) -> impl ExactSizeIterator<Item = ArrayEntryMut<'_, StscEntry>> + '_ {
+        self.entries.entries_mut()
+    }
+
+    pub fn entry_count(&self) -> u32 {
+        self.entries.entry_count()
+    }
+}
+
+impl ParseBox for StscBox {
+    fn parse(buf: &mut BytesMut) -> Result<Self, ParseError> {
+        let header = buf.get_mp4_value().while_parsing_field(NAME, "header")?;
+        let entries: BoundedArray<u32, StscEntry> = buf.get_mp4_value().while_parsing_field(NAME, "entries")?;
+        for entry in entries.entries() {
+            // Chunk numbers are 1-based.
+            let StscEntry { first_chunk, .. } = entry.get().while_parsing_field(NAME, "entries")?;
+            ensure_attach!(
+                first_chunk != 0,
+                ParseError::InvalidInput,
+                "first chunk number is zero",
+                WhileParsingField(NAME, "entries"),
+            );
+        }
+        Ok(Self { header, entries })
+    }
+
+    fn box_type() -> BoxType {
+        NAME
+    }
+}
+
+impl FromIterator<StscEntry> for StscBox {
+    fn from_iter<I: IntoIterator<Item = StscEntry>>(entries: I) -> Self {
+        Self { header: Default::default(), entries: entries.into_iter().collect() }
+    }
+}
+
+impl StscEntry {
+    pub const fn new(first_chunk: u32, samples_per_chunk: u32, sample_description_index: u32) -> Self {
+        Self { first_chunk, samples_per_chunk, sample_description_index }
+    }
+}
+
+impl Mp4Prim for StscEntry {
+    fn parse<B: Buf>(buf: B) -> Result<Self, ParseError> {
+        let [first_chunk, samples_per_chunk, sample_description_index] = Mp4Prim::parse(buf)?;
+        Ok(Self { first_chunk, samples_per_chunk, sample_description_index })
+    }
+
+    fn encoded_len() -> u64 {
+        <[u32; 3]>::encoded_len()
+    }
+
+    fn put_buf<B: BufMut>(&self, buf: B) {
+        [self.first_chunk, self.samples_per_chunk, self.sample_description_index].put_buf(buf)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use assert_matches::assert_matches;
+    use bytes::BytesMut;
+
+    use super::*;
+
+    const TEST_ENTRIES: [StscEntry; 3] = [
+        StscEntry::new(1, 2, 1),
+        StscEntry::new(3, 1, 1),
+        StscEntry::new(7, 4, 2),
+    ];
+
+    #[test]
+    fn roundtrip() {
+        let mut buf = BytesMut::new();
+        StscBox::from_iter(TEST_ENTRIES).put_buf(&mut buf);
+        assert_eq!(buf.len(), 4 + 4 + 12 * TEST_ENTRIES.len());
+        let stsc = StscBox::parse(&mut buf.clone()).unwrap();
+        let entries = stsc.entries().map(|entry| entry.get().unwrap()).collect::<Vec<_>>();
+        assert_eq!(entries, TEST_ENTRIES);
+
+        let mut encoded = BytesMut::new();
+        stsc.put_buf(&mut encoded);
+        assert_eq!(encoded, buf);
+    }
+
+    #[test]
+    fn set_entry() {
+        let mut stsc = StscBox::from_iter(TEST_ENTRIES);
+        let mut entry = stsc.entries_mut().nth(1).unwrap();
+        assert_eq!(entry.get().unwrap(), TEST_ENTRIES[1]);
+        entry.set(StscEntry { samples_per_chunk: 5, ..TEST_ENTRIES[1] });
+
+        let mut buf = BytesMut::new();
+        stsc.put_buf(&mut buf);
+        let stsc = StscBox::parse(&mut buf).unwrap();
+        let entries = stsc.entries().map(|entry| entry.get().unwrap()).collect::<Vec<_>>();
+        assert_eq!(entries, [TEST_ENTRIES[0], StscEntry::new(3, 5, 1), TEST_ENTRIES[2]]);
+    }
+
+    #[test]
+    fn first_chunk_zero() {
+        let mut buf = BytesMut::new();
+        StscBox::from_iter([StscEntry::new(0, 2, 1), StscEntry::new(3, 1, 1)]).put_buf(&mut buf);
+        let err = StscBox::parse(&mut buf).unwrap_err();
+        assert_matches!(err.get_ref(), ParseError::InvalidInput, "{err:?}");
+    }
+}
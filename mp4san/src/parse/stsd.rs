@@ -0,0 +1,377 @@
+#![allow(missing_docs)]
+
+use alloc::format;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use bytes::{Buf, BytesMut};
+
+use crate::error::Result;
+
+use super::error::{ParseResultExt, WhileParsingChild, WhileParsingField};
+use super::{
+    fourcc, AnyMp4Box, Av1CBox, BoxData, BoxType, Boxes, CcstBox, ConstFullBoxHeader, FourCC, HvcCBox, Mp4Value,
+    Mp4ValueReaderExt, ParseBox, ParseError, ParsedBox, VexuBox, VpcCBox,
+};
+
+/// The sample description box, containing the sample entries describing the coding of a track's samples.
+#[derive(Clone, Debug, ParsedBox)]
+pub struct StsdBox {
+    header: ConstFullBoxHeader,
+    entry_count: u32,
+    entries: Boxes,
+}
+
+const NAME: BoxType = BoxType::STSD;
+
+/// Box types of decoder configuration boxes which may be found within a sample entry.
+const CODEC_CONFIG_BOX_TYPES: &[BoxType] = &[
+    BoxType::AVCC,
+    BoxType::HVCC,
+    BoxType::VPCC,
+    BoxType::AV1C,
+    BoxType::ESDS,
+];
+
+/// Sample entry types which extend `VisualSampleEntry`.
+const VISUAL_SAMPLE_ENTRY_TYPES: &[FourCC] = &[
+    fourcc::AVC1,
+    fourcc::AVC3,
+    fourcc::HVC1,
+    fourcc::HEV1,
+    fourcc::VP08,
+    fourcc::VP09,
+    fourcc::AV01,
+    fourcc::ENCV,
+];
+
+/// Sample entry types which extend `AudioSampleEntry`.
+const AUDIO_SAMPLE_ENTRY_TYPES: &[FourCC] = &[fourcc::MP4A, fourcc::ENCA];
+
+/// The length of the fields of a `VisualSampleEntry`, preceding its child boxes.
+const VISUAL_SAMPLE_ENTRY_LEN: usize = 78;
+
+/// The length of the fields of a version 0 `AudioSampleEntry`, preceding its child boxes.
+const AUDIO_SAMPLE_ENTRY_LEN: usize = 28;
+
+impl StsdBox {
+    #[cfg(test)]
+    pub(crate) fn with_entries<C: Into<Boxes>>(entries: C) -> Self {
+        let entries: Boxes = entries.into();
+        let entry_count = entries.iter().len() as u32;
+        Self { header: Default::default(), entry_count, entries }
+    }
+
+    pub fn entry_count(&self) -> u32 {
+        self.entry_count
+    }
+
+    /// The types of the sample entries, i.e. the four-character codes of the codecs used by the track's samples.
+    pub fn entry_types(&self) -> impl ExactSizeIterator<Item = BoxType> + '_ {
+        self.entries.box_types()
+    }
+
+    /// The format of the first sample entry, e.g. `avc1`, `hev1`, or `mp4a`, if any.
+    ///
+    /// Sample entries are otherwise kept opaque, so this is available for any sample entry type. `None` is returned if
+    /// there are no sample entries, or if the first sample entry has an extended (`uuid`) type.
+    pub fn sample_format(&self) -> Option<FourCC> {
+        match self.entry_types().next()? {
+            BoxType::FourCC(format) => Some(format),
+            BoxType::Uuid(_) => None,
+        }
+    }
+
+    /// Returns the type and payload of the decoder configuration box (`avcC`, `hvcC`, `vpcC`, `av1C`, or `esds`) within
+    /// the first sample entry, if any.
+    ///
+    /// Only video and audio sample entries for which the sanitizer knows the layout are searched. The payload is
+    /// validated as by [`validate_sample_entries`](Self::validate_sample_entries).
+    pub fn codec_config(&self) -> Result<Option<(BoxType, BytesMut)>, ParseError> {
+        let Some(entry) = self.entries.iter().next() else {
+            return Ok(None);
+        };
+        let entry_type = entry.calculated_header().box_type();
+        for child in sample_entry_children(entry)? {
+            let child_type = child.calculated_header().box_type();
+            if let (true, BoxData::Bytes(child_data)) = (CODEC_CONFIG_BOX_TYPES.contains(&child_type), child.data) {
+                validate_sample_entry_child(child_type, &child_data).while_parsing_child(NAME, entry_type)?;
+                return Ok(Some((child_type, child_data)));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Validate the child boxes of each sample entry for which the sanitizer knows the layout.
+    ///
+    /// The decoder configuration boxes `hvcC`, `av1C`, and `vpcC`, and the coding constraints box `ccst`, and the video extended usage box `vexu` are parsed, validating that the lengths within them
+    /// lie within their bounds. Other child boxes are only validated to lie within their sample entry.
+    pub fn validate_sample_entries(&self) -> Result<(), ParseError> {
+        for entry in self.entries.iter() {
+            let entry_type = entry.calculated_header().box_type();
+            for child in sample_entry_children(entry)? {
+                if let BoxData::Bytes(child_data) = &child.data {
+                    let child_type = child.calculated_header().box_type();
+                    validate_sample_entry_child(child_type, child_data).while_parsing_child(NAME, entry_type)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl ParseBox for StsdBox {
+    fn parse(buf: &mut BytesMut) -> Result<Self, ParseError> {
+        let header = buf.get_mp4_value().while_parsing_field(NAME, "header")?;
+        let entry_count = buf.get_mp4_value().while_parsing_field(NAME, "entry_count")?;
+        let entries: Boxes = buf.get_mp4_value().while_parsing_field(NAME, "entries")?;
+        let actual_entry_count = entries.iter().len();
+        ensure_attach!(
+            u32::try_from(actual_entry_count) == Ok(entry_count),
+            ParseError::InvalidInput,
+            format!("entry count {entry_count} does not match {actual_entry_count} sample entries"),
+            WhileParsingField(NAME, "entries"),
+        );
+        Ok(Self { header, entry_count, entries })
+    }
+
+    fn box_type() -> BoxType {
+        NAME
+    }
+}
+
+/// Returns the child boxes of a sample entry, or none if its layout is not known.
+fn sample_entry_children(entry: &AnyMp4Box) -> Result<Vec<AnyMp4Box>, ParseError> {
+    let entry_type = entry.calculated_header().box_type();
+    let BoxData::Bytes(entry_data) = &entry.data else {
+        return Ok(vec![]);
+    };
+    let Some(fields_len) = sample_entry_fields_len(entry_type, entry_data) else {
+        return Ok(vec![]);
+    };
+    ensure_attach!(
+        fields_len <= entry_data.len(),
+        ParseError::TruncatedBox,
+        WhileParsingChild(NAME, entry_type),
+    );
+
+    let mut children_data = BytesMut::from(&entry_data[fields_len..]);
+    let mut children = vec![];
+    while children_data.has_remaining() {
+        children.push(Mp4Value::parse(&mut children_data).while_parsing_child(NAME, entry_type)?);
+    }
+    Ok(children)
+}
+
+/// Validate the payload of a child box of a sample entry, if it is of a type the sanitizer parses.
+fn validate_sample_entry_child(child_type: BoxType, child_data: &BytesMut) -> Result<(), ParseError> {
+    match child_type {
+        BoxType::HVCC => drop(BoxData::<HvcCBox>::Bytes(child_data.clone()).into_parsed()?),
+        BoxType::AV1C => drop(BoxData::<Av1CBox>::Bytes(child_data.clone()).into_parsed()?),
+        BoxType::VPCC => drop(BoxData::<VpcCBox>::Bytes(child_data.clone()).into_parsed()?),
+        BoxType::CCST => drop(BoxData::<CcstBox>::Bytes(child_data.clone()).into_parsed()?),
+        BoxType::VEXU => {
+            let mut vexu = BoxData::<VexuBox>::Bytes(child_data.clone()).into_parsed()?;
+            if let Some(eyes) = vexu.eyes_mut()? {
+                eyes.stri_mut()?;
+                eyes.hero_mut()?;
+            }
+            if let Some(proj) = vexu.proj_mut()? {
+                proj.prji_mut()?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Returns the length of the fields preceding the child boxes of a sample entry, if its layout is known.
+fn sample_entry_fields_len(entry_type: BoxType, entry_data: &[u8]) -> Option<usize> {
+    let BoxType::FourCC(entry_type) = entry_type else {
+        return None;
+    };
+    if VISUAL_SAMPLE_ENTRY_TYPES.contains(&entry_type) {
+        return Some(VISUAL_SAMPLE_ENTRY_LEN);
+    }
+    if AUDIO_SAMPLE_ENTRY_TYPES.contains(&entry_type) {
+        // QuickTime sound sample description versions 1 and 2 extend the audio sample entry fields.
+        return match entry_data.get(8..10) {
+            Some([0, 1]) => Some(AUDIO_SAMPLE_ENTRY_LEN + 16),
+            Some([0, 2]) => Some(AUDIO_SAMPLE_ENTRY_LEN + 36),
+            _ => Some(AUDIO_SAMPLE_ENTRY_LEN),
+        };
+    }
+    None
+}
+
+#[cfg(test)]
+mod test {
+    use assert_matches::assert_matches;
+    use bytes::BufMut;
+
+    use crate::parse::{ColrBox, Mp4Box, NclxColour};
+    use crate::util::test::TEST_HVCC_DATA;
+
+    use super::*;
+
+    fn test_sample_entry(entry_type: BoxType, fields_len: usize, children: &[AnyMp4Box]) -> AnyMp4Box {
+        let mut data = BytesMut::new();
+        data.put_bytes(0, fields_len);
+        for child in children {
+            child.put_buf(&mut data);
+        }
+        Mp4Box::with_bytes(entry_type, data)
+    }
+
+    #[test]
+    fn avc_config() {
+        let avcc = Mp4Box::with_bytes(BoxType::AVCC, BytesMut::from(&b"\x01\x64\x00\x1f"[..]));
+        let btrt = Mp4Box::with_bytes(BoxType::BTRT, BytesMut::from(&[0; 12][..]));
+        let avc1 = test_sample_entry(BoxType::AVC1, VISUAL_SAMPLE_ENTRY_LEN, &[btrt, avcc]);
+        let mut data = BytesMut::new();
+        StsdBox::with_entries(vec![avc1]).put_buf(&mut data);
+
+        let stsd = StsdBox::parse(&mut data).unwrap();
+        assert_eq!(stsd.entry_count(), 1);
+        assert_eq!(stsd.entry_types().collect::<Vec<_>>(), [BoxType::AVC1]);
+        assert_eq!(stsd.sample_format(), Some(fourcc::AVC1));
+        let (config_type, config) = stsd.codec_config().unwrap().unwrap();
+        assert_eq!(config_type, BoxType::AVCC);
+        assert_eq!(config, b"\x01\x64\x00\x1f"[..]);
+    }
+
+    #[test]
+    fn extension_boxes_round_trip() {
+        let hvcc = Mp4Box::with_bytes(BoxType::HVCC, BytesMut::from(TEST_HVCC_DATA));
+        let bt2100_pq = NclxColour {
+            colour_primaries: 9,
+            transfer_characteristics: 16,
+            matrix_coefficients: 9,
+            full_range_flag: false,
+        };
+        let mut colr_data = BytesMut::new();
+        ColrBox::new_nclx(bt2100_pq).put_buf(&mut colr_data);
+        let colr = Mp4Box::with_bytes(BoxType::COLR, colr_data);
+        let pasp = Mp4Box::with_bytes(BoxType::PASP, BytesMut::from(&[0, 0, 0, 1, 0, 0, 0, 1][..]));
+        let hvc1 = test_sample_entry(BoxType::HVC1, VISUAL_SAMPLE_ENTRY_LEN, &[hvcc, colr, pasp]);
+        let mut data = BytesMut::new();
+        StsdBox::with_entries(vec![hvc1]).put_buf(&mut data);
+
+        let stsd = StsdBox::parse(&mut data.clone()).unwrap();
+        let (config_type, config) = stsd.codec_config().unwrap().unwrap();
+        assert_eq!(config_type, BoxType::HVCC);
+        assert_eq!(config, TEST_HVCC_DATA);
+
+        let mut reencoded = BytesMut::new();
+        stsd.put_buf(&mut reencoded);
+        assert_eq!(reencoded, data);
+    }
+
+    #[test]
+    fn audio_config() {
+        let esds = Mp4Box::with_bytes(BoxType::ESDS, BytesMut::from(&b"\0\0\0\0\x03"[..]));
+        let mp4a = test_sample_entry(BoxType::MP4A, AUDIO_SAMPLE_ENTRY_LEN, &[esds]);
+        let mut data = BytesMut::new();
+        StsdBox::with_entries(vec![mp4a]).put_buf(&mut data);
+
+        let stsd = StsdBox::parse(&mut data).unwrap();
+        let (config_type, config) = stsd.codec_config().unwrap().unwrap();
+        assert_eq!(config_type, BoxType::ESDS);
+        assert_eq!(config, b"\0\0\0\0\x03"[..]);
+    }
+
+    #[test]
+    fn no_config() {
+        let avc1 = test_sample_entry(BoxType::AVC1, VISUAL_SAMPLE_ENTRY_LEN, &[]);
+        let mut data = BytesMut::new();
+        StsdBox::with_entries(vec![avc1]).put_buf(&mut data);
+        let stsd = StsdBox::parse(&mut data).unwrap();
+        assert_matches!(stsd.codec_config(), Ok(None));
+    }
+
+    #[test]
+    fn unknown_sample_format() {
+        let unknown_type = BoxType::FourCC(FourCC { value: *b"xyz0" });
+        let entry = test_sample_entry(unknown_type, 16, &[]);
+        let mut data = BytesMut::new();
+        StsdBox::with_entries(vec![entry]).put_buf(&mut data);
+
+        let stsd = StsdBox::parse(&mut data.clone()).unwrap();
+        assert_eq!(stsd.sample_format(), Some(FourCC { value: *b"xyz0" }));
+        assert_matches!(stsd.codec_config(), Ok(None));
+
+        let mut reencoded = BytesMut::new();
+        stsd.put_buf(&mut reencoded);
+        assert_eq!(reencoded, data);
+    }
+
+    #[test]
+    fn no_sample_format() {
+        let mut data = BytesMut::new();
+        StsdBox::with_entries(vec![]).put_buf(&mut data);
+        let stsd = StsdBox::parse(&mut data).unwrap();
+        assert_eq!(stsd.sample_format(), None);
+    }
+
+    #[test]
+    fn entry_count_mismatch() {
+        let avc1 = test_sample_entry(BoxType::AVC1, VISUAL_SAMPLE_ENTRY_LEN, &[]);
+        let mut data = BytesMut::new();
+        StsdBox::with_entries(vec![avc1]).put_buf(&mut data);
+        data[7] = 2;
+        let err = StsdBox::parse(&mut data).unwrap_err();
+        assert_matches!(err.get_ref(), ParseError::InvalidInput, "{err:?}");
+    }
+
+    #[test]
+    fn invalid_codec_config() {
+        let mut truncated_hvcc = BytesMut::from(TEST_HVCC_DATA);
+        *truncated_hvcc.last_mut().unwrap() = 1;
+        for (entry_type, config_type, config_data) in [
+            (BoxType::HVC1, BoxType::HVCC, truncated_hvcc),
+            (BoxType::AV01, BoxType::AV1C, BytesMut::from(&b"\x01\x08\x0c\0"[..])),
+            (BoxType::VP09, BoxType::VPCC, BytesMut::from(&b"\x01\0\0\0\0"[..])),
+        ] {
+            let config = Mp4Box::with_bytes(config_type, config_data);
+            let entry = test_sample_entry(entry_type, VISUAL_SAMPLE_ENTRY_LEN, &[config]);
+            let mut data = BytesMut::new();
+            StsdBox::with_entries(vec![entry]).put_buf(&mut data);
+
+            let stsd = StsdBox::parse(&mut data).unwrap();
+            let err = stsd.validate_sample_entries().unwrap_err();
+            assert_matches!(
+                err.get_ref(),
+                ParseError::InvalidInput | ParseError::TruncatedBox,
+                "{err:?}"
+            );
+            let err = stsd.codec_config().unwrap_err();
+            assert_matches!(
+                err.get_ref(),
+                ParseError::InvalidInput | ParseError::TruncatedBox,
+                "{err:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn invalid_coding_constraints() {
+        let ccst = Mp4Box::with_bytes(BoxType::CCST, BytesMut::from(&b"\0\0\0\0\0\0"[..]));
+        let entry = test_sample_entry(BoxType::AV01, VISUAL_SAMPLE_ENTRY_LEN, &[ccst]);
+        let mut data = BytesMut::new();
+        StsdBox::with_entries(vec![entry]).put_buf(&mut data);
+
+        let stsd = StsdBox::parse(&mut data).unwrap();
+        let err = stsd.validate_sample_entries().unwrap_err();
+        assert_matches!(err.get_ref(), ParseError::TruncatedBox, "{err:?}");
+    }
+
+    #[test]
+    fn truncated_sample_entry() {
+        let avc1 = test_sample_entry(BoxType::AVC1, VISUAL_SAMPLE_ENTRY_LEN - 1, &[]);
+        let mut data = BytesMut::new();
+        StsdBox::with_entries(vec![avc1]).put_buf(&mut data);
+        let stsd = StsdBox::parse(&mut data).unwrap();
+        let err = stsd.codec_config().unwrap_err();
+        assert_matches!(err.get_ref(), ParseError::TruncatedBox, "{err:?}");
+    }
+}
@@ -0,0 +1,45 @@
+#![allow(missing_docs)]
+
+use super::{ArrayEntry, BoundedArray, ConstFullBoxHeader, ParseBox, ParsedBox};
+
+#[derive(Clone, Debug, Default, ParseBox, ParsedBox)]
+#[box_type = "stss"]
+pub struct StssBox {
+    header: ConstFullBoxHeader,
+    entries: BoundedArray<u32, u32>,
+}
+
+impl StssBox {
+    /// The sync sample numbers, which are 1-based and in strictly increasing order.
+    pub fn entries(&self) -> impl ExactSizeIterator<Item = ArrayEntry<'_, u32>> + '_ {
+        self.entries.entries()
+    }
+
+    pub fn entry_count(&self) -> u32 {
+        self.entries.entry_count()
+    }
+}
+
+impl FromIterator<u32> for StssBox {
+    fn from_iter<I: IntoIterator<Item = u32>>(entries: I) -> Self {
+        Self { header: Default::default(), entries: entries.into_iter().collect() }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use bytes::BytesMut;
+
+    use crate::parse::{ParseBox, ParsedBox};
+
+    use super::StssBox;
+
+    #[test]
+    fn roundtrip() {
+        let mut buf = BytesMut::new();
+        StssBox::from_iter([1, 31, 61]).put_buf(&mut buf);
+        let stss = StssBox::parse(&mut buf).unwrap();
+        let entries = stss.entries().map(|entry| entry.get().unwrap()).collect::<Vec<_>>();
+        assert_eq!(entries, [1, 31, 61]);
+    }
+}
@@ -0,0 +1,168 @@
+#![allow(missing_docs)]
+
+use alloc::format;
+use core::mem::size_of;
+
+use bytes::{BufMut, BytesMut};
+
+use crate::error::Result;
+
+use super::error::{ParseResultExt, WhileParsingField};
+use super::{
+    ArrayEntry, ArrayEntryMut, BoundedArray, BoxType, ConstFullBoxHeader, Mp4Value, Mp4ValueReaderExt, ParseBox,
+    ParseError, ParsedBox,
+};
+
+#[derive(Clone, Debug, Default)]
+pub struct StszBox {
+    header: ConstFullBoxHeader,
+    sample_size: u32,
+    sample_count: u32,
+    /// Per-sample sizes, present only if `sample_size` is zero.
+    entry_sizes: Option<BoundedArray<u32, u32>>,
+}
+
+const NAME: BoxType = BoxType::STSZ;
+
+impl StszBox {
+    /// Construct a [`StszBox`] where every sample has the same nonzero size.
+    pub fn with_sample_size(sample_size: u32, sample_count: u32) -> Self {
+        assert_ne!(sample_size, 0);
+        Self { header: Default::default(), sample_size, sample_count, entry_sizes: None }
+    }
+
+    /// The size of every sample, if all samples are the same size.
+    pub fn sample_size(&self) -> Option<u32> {
+        match self.entry_sizes {
+            None => Some(self.sample_size),
+            Some(_) => None,
+        }
+    }
+
+    /// The number of samples.
+    ///
+    /// If samples are not all the same size, this is bounded by the length of the box, which holds a size for each.
+    /// Otherwise, it is only bounded by the length of the media data the samples must lie within, which is validated
+    /// separately.
+    pub fn sample_count(&self) -> u32 {
+        self.sample_count
+    }
+
+    /// The per-sample sizes, if samples are not all the same size.
+    pub fn entry_sizes(&self) -> Option<impl ExactSizeIterator<Item = ArrayEntry<'_, u32>> + '_> {
+        self.entry_sizes.as_ref().map(BoundedArray::entries)
+    }
+
+    /// The per-sample sizes, if samples are not all the same size, for modification in place.
+    pub fn entry_sizes_mut(&mut self) -> Option<impl ExactSizeIterator<Item = ArrayEntryMut<'_, u32>> + '_> {
+        self.entry_sizes.as_mut().map(BoundedArray::entries_mut)
+    }
+}
+
+impl ParseBox for StszBox {
+    fn parse(buf: &mut BytesMut) -> Result<Self, ParseError> {
+        let header = buf.get_mp4_value().while_parsing_field(NAME, "header")?;
+        let sample_size = buf.get_mp4_value().while_parsing_field(NAME, "sample_size")?;
+        if sample_size != 0 {
+            let sample_count = buf.get_mp4_value().while_parsing_field(NAME, "sample_count")?;
+            return Ok(Self { header, sample_size, sample_count, entry_sizes: None });
+        }
+        // Reject a declared sample count too large for the remaining data up front, so that nothing sized by it, such
+        // as per-sample validation, can be made to allocate more than the box itself occupies.
+        let declared_sample_count: u32 = super::Mp4Prim::parse(&buf[..]).while_parsing_field(NAME, "sample_count")?;
+        let max_sample_count = (buf.len() - size_of::<u32>()) / size_of::<u32>();
+        ensure_attach!(
+            declared_sample_count as usize <= max_sample_count,
+            ParseError::InvalidInput,
+            format!("sample count {declared_sample_count} exceeds the {max_sample_count} entry sizes present"),
+            WhileParsingField(NAME, "entry_sizes"),
+        );
+        let entry_sizes: BoundedArray<u32, u32> = buf.get_mp4_value().while_parsing_field(NAME, "entry_sizes")?;
+        let sample_count = entry_sizes.entry_count();
+        Ok(Self { header, sample_size, sample_count, entry_sizes: Some(entry_sizes) })
+    }
+
+    fn box_type() -> BoxType {
+        NAME
+    }
+}
+
+impl ParsedBox for StszBox {
+    fn encoded_len(&self) -> u64 {
+        let mut len = self.header.encoded_len() + self.sample_size.encoded_len();
+        match &self.entry_sizes {
+            Some(entry_sizes) => len += entry_sizes.encoded_len(),
+            None => len += self.sample_count.encoded_len(),
+        }
+        len
+    }
+
+    fn put_buf(&self, mut out: &mut dyn BufMut) {
+        self.header.put_buf(&mut out);
+        self.sample_size.put_buf(&mut out);
+        match &self.entry_sizes {
+            Some(entry_sizes) => entry_sizes.put_buf(&mut out),
+            None => self.sample_count.put_buf(&mut out),
+        }
+    }
+}
+
+impl FromIterator<u32> for StszBox {
+    fn from_iter<I: IntoIterator<Item = u32>>(entry_sizes: I) -> Self {
+        let entry_sizes: BoundedArray<u32, u32> = entry_sizes.into_iter().collect();
+        let sample_count = entry_sizes.entry_count();
+        Self { header: Default::default(), sample_size: 0, sample_count, entry_sizes: Some(entry_sizes) }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use assert_matches::assert_matches;
+    use bytes::BytesMut;
+
+    use crate::parse::{ParseBox, ParseError, ParsedBox};
+
+    use super::StszBox;
+
+    #[test]
+    fn roundtrip_sample_size() {
+        let mut buf = BytesMut::new();
+        StszBox::with_sample_size(16, 3).put_buf(&mut buf);
+        let stsz = StszBox::parse(&mut buf).unwrap();
+        assert_eq!(stsz.sample_size(), Some(16));
+        assert_eq!(stsz.sample_count(), 3);
+        assert!(stsz.entry_sizes().is_none());
+    }
+
+    #[test]
+    fn roundtrip_entry_sizes() {
+        let mut buf = BytesMut::new();
+        StszBox::from_iter([3, 1, 2]).put_buf(&mut buf);
+        let stsz = StszBox::parse(&mut buf).unwrap();
+        assert_eq!(stsz.sample_size(), None);
+        assert_eq!(stsz.sample_count(), 3);
+        let entry_sizes = stsz.entry_sizes().unwrap().map(|entry| entry.get().unwrap());
+        assert_eq!(entry_sizes.collect::<Vec<_>>(), [3, 1, 2]);
+    }
+
+    #[test]
+    fn sample_count_exceeds_entry_sizes() {
+        let mut buf = BytesMut::new();
+        StszBox::from_iter([3, 1, 2]).put_buf(&mut buf);
+        let sample_count_offset = buf.len() - 4 * 4;
+        buf[sample_count_offset..][..4].copy_from_slice(&u32::MAX.to_be_bytes());
+        let err = StszBox::parse(&mut buf).unwrap_err();
+        assert_matches!(err.get_ref(), ParseError::InvalidInput, "{err:?}");
+    }
+
+    #[test]
+    fn set_entry_size() {
+        let mut stsz = StszBox::from_iter([3, 1, 2]);
+        stsz.entry_sizes_mut().unwrap().last().unwrap().set(7);
+        let entry_sizes = stsz.entry_sizes().unwrap().map(|entry| entry.get().unwrap());
+        assert_eq!(entry_sizes.collect::<Vec<_>>(), [3, 1, 7]);
+
+        let mut stsz = StszBox::with_sample_size(16, 3);
+        assert!(stsz.entry_sizes_mut().is_none());
+    }
+}
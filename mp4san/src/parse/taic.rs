@@ -0,0 +1,83 @@
+#![allow(missing_docs)]
+
+use super::{ConstFullBoxHeader, ParseBox, ParsedBox};
+
+/// The TAI clock information box, describing the clock used for the TAI capture timestamps (`stai` sample auxiliary
+/// information) of a track.
+#[derive(Clone, Debug, Default, ParseBox, ParsedBox)]
+#[box_type = "taic"]
+pub struct TaicBox {
+    header: ConstFullBoxHeader,
+    time_uncertainty: u64,
+    clock_resolution: u32,
+    clock_drift_rate: i32,
+    /// The clock type in the upper two bits, followed by reserved bits.
+    clock_type: u8,
+}
+
+impl TaicBox {
+    pub fn new(time_uncertainty: u64, clock_resolution: u32, clock_drift_rate: i32, clock_type: u8) -> Self {
+        Self {
+            header: Default::default(),
+            time_uncertainty,
+            clock_resolution,
+            clock_drift_rate,
+            clock_type: clock_type << 6,
+        }
+    }
+
+    /// The uncertainty of the timestamps, in nanoseconds.
+    pub fn time_uncertainty(&self) -> u64 {
+        self.time_uncertainty
+    }
+
+    /// The resolution of the clock, in nanoseconds.
+    pub fn clock_resolution(&self) -> u32 {
+        self.clock_resolution
+    }
+
+    /// The drift rate of the clock, in parts per billion.
+    pub fn clock_drift_rate(&self) -> i32 {
+        self.clock_drift_rate
+    }
+
+    /// Whether the clock is synchronized to TAI: `0` if unknown, `1` if not synchronized, or `2` if synchronized.
+    pub fn clock_type(&self) -> u8 {
+        self.clock_type >> 6
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use assert_matches::assert_matches;
+    use bytes::BytesMut;
+
+    use crate::parse::ParseError;
+
+    use super::*;
+
+    #[test]
+    fn roundtrip() {
+        let mut data = BytesMut::new();
+        TaicBox::new(1_000_000, 1_000, -50, 2).put_buf(&mut data);
+        let taic = TaicBox::parse(&mut data.clone()).unwrap();
+        assert_eq!(taic.time_uncertainty(), 1_000_000);
+        assert_eq!(taic.clock_resolution(), 1_000);
+        assert_eq!(taic.clock_drift_rate(), -50);
+        assert_eq!(taic.clock_type(), 2);
+
+        let mut encoded = BytesMut::new();
+        taic.put_buf(&mut encoded);
+        assert_eq!(taic.encoded_len(), encoded.len() as u64);
+        assert_eq!(encoded, data);
+    }
+
+    #[test]
+    fn truncated() {
+        let mut data = BytesMut::new();
+        TaicBox::default().put_buf(&mut data);
+        data.truncate(data.len() - 1);
+        let err = TaicBox::parse(&mut data).unwrap_err();
+        assert_matches!(err.get_ref(), ParseError::TruncatedBox, "{err:?}");
+    }
+}
@@ -0,0 +1,217 @@
+#![allow(missing_docs)]
+
+use alloc::format;
+
+use bytes::{BufMut, BytesMut};
+
+use crate::error::Result;
+
+use super::error::{ParseResultExt, WhileParsingField};
+use super::{BoxType, FullBoxHeader, Mp4Value, Mp4ValueReaderExt, ParseBox, ParseError, ParsedBox};
+
+/// The track fragment header box, containing the defaults for the samples of a track fragment (`traf`).
+#[derive(Clone, Debug)]
+pub struct TfhdBox {
+    header: FullBoxHeader,
+    track_id: u32,
+    base_data_offset: Option<u64>,
+    sample_description_index: Option<u32>,
+    default_sample_duration: Option<u32>,
+    default_sample_size: Option<u32>,
+    default_sample_flags: Option<u32>,
+}
+
+const NAME: BoxType = BoxType::TFHD;
+
+impl TfhdBox {
+    pub const BASE_DATA_OFFSET_PRESENT: u32 = 0x000001;
+    pub const SAMPLE_DESCRIPTION_INDEX_PRESENT: u32 = 0x000002;
+    pub const DEFAULT_SAMPLE_DURATION_PRESENT: u32 = 0x000008;
+    pub const DEFAULT_SAMPLE_SIZE_PRESENT: u32 = 0x000010;
+    pub const DEFAULT_SAMPLE_FLAGS_PRESENT: u32 = 0x000020;
+    pub const DURATION_IS_EMPTY: u32 = 0x010000;
+    pub const DEFAULT_BASE_IS_MOOF: u32 = 0x020000;
+
+    /// Construct a [`TfhdBox`] with an explicit `base_data_offset`, if given, or else with offsets relative to the
+    /// enclosing movie fragment (`moof`).
+    pub fn new(track_id: u32, base_data_offset: Option<u64>) -> Self {
+        let flags = match base_data_offset {
+            Some(_) => Self::BASE_DATA_OFFSET_PRESENT,
+            None => Self::DEFAULT_BASE_IS_MOOF,
+        };
+        Self {
+            header: FullBoxHeader { version: 0, flags },
+            track_id,
+            base_data_offset,
+            sample_description_index: None,
+            default_sample_duration: None,
+            default_sample_size: None,
+            default_sample_flags: None,
+        }
+    }
+
+    pub fn flags(&self) -> u32 {
+        self.header.flags
+    }
+
+    pub fn track_id(&self) -> u32 {
+        self.track_id
+    }
+
+    /// The absolute offset within the file from which the data offsets of the track fragment's runs (`trun`) are
+    /// measured, if given explicitly.
+    pub fn base_data_offset(&self) -> Option<u64> {
+        self.base_data_offset
+    }
+
+    pub fn sample_description_index(&self) -> Option<u32> {
+        self.sample_description_index
+    }
+
+    pub fn default_sample_duration(&self) -> Option<u32> {
+        self.default_sample_duration
+    }
+
+    pub fn default_sample_size(&self) -> Option<u32> {
+        self.default_sample_size
+    }
+
+    pub fn default_sample_flags(&self) -> Option<u32> {
+        self.default_sample_flags
+    }
+
+    pub fn is_default_base_moof(&self) -> bool {
+        self.header.flags & Self::DEFAULT_BASE_IS_MOOF != 0
+    }
+
+    fn optional_fields(&self) -> [Option<u32>; 4] {
+        [
+            self.sample_description_index,
+            self.default_sample_duration,
+            self.default_sample_size,
+            self.default_sample_flags,
+        ]
+    }
+}
+
+impl ParseBox for TfhdBox {
+    fn parse(buf: &mut BytesMut) -> Result<Self, ParseError> {
+        let header: FullBoxHeader = buf.get_mp4_value().while_parsing_field(NAME, "header")?;
+        ensure_attach!(
+            header.version == 0,
+            ParseError::InvalidInput,
+            format!("unsupported version {}", header.version),
+            WhileParsingField(NAME, "header"),
+        );
+        let track_id = buf.get_mp4_value().while_parsing_field(NAME, "track_id")?;
+        let base_data_offset = get_optional(buf, header.flags & Self::BASE_DATA_OFFSET_PRESENT, "base_data_offset")?;
+        let sample_description_index = get_optional(
+            buf,
+            header.flags & Self::SAMPLE_DESCRIPTION_INDEX_PRESENT,
+            "sample_description_index",
+        )?;
+        let default_sample_duration = get_optional(
+            buf,
+            header.flags & Self::DEFAULT_SAMPLE_DURATION_PRESENT,
+            "default_sample_duration",
+        )?;
+        let default_sample_size = get_optional(
+            buf,
+            header.flags & Self::DEFAULT_SAMPLE_SIZE_PRESENT,
+            "default_sample_size",
+        )?;
+        let default_sample_flags = get_optional(
+            buf,
+            header.flags & Self::DEFAULT_SAMPLE_FLAGS_PRESENT,
+            "default_sample_flags",
+        )?;
+        Ok(Self {
+            header,
+            track_id,
+            base_data_offset,
+            sample_description_index,
+            default_sample_duration,
+            default_sample_size,
+            default_sample_flags,
+        })
+    }
+
+    fn box_type() -> BoxType {
+        NAME
+    }
+}
+
+/// Parse an optional field of a [`TfhdBox`], which is present if its `flag` is set.
+fn get_optional<T: Mp4Value>(buf: &mut BytesMut, flag: u32, field_name: &'static str) -> Result<Option<T>, ParseError> {
+    (flag != 0)
+        .then(|| buf.get_mp4_value())
+        .transpose()
+        .while_parsing_field(NAME, field_name)
+}
+
+impl ParsedBox for TfhdBox {
+    fn encoded_len(&self) -> u64 {
+        let base_data_offset_len = self.base_data_offset.as_ref().map_or(0, Mp4Value::encoded_len);
+        let optional_fields_len: u64 = self.optional_fields().iter().flatten().map(Mp4Value::encoded_len).sum();
+        self.header.encoded_len() + self.track_id.encoded_len() + base_data_offset_len + optional_fields_len
+    }
+
+    fn put_buf(&self, mut out: &mut dyn BufMut) {
+        self.header.put_buf(&mut out);
+        self.track_id.put_buf(&mut out);
+        if let Some(base_data_offset) = self.base_data_offset {
+            base_data_offset.put_buf(&mut out);
+        }
+        for field in self.optional_fields().into_iter().flatten() {
+            field.put_buf(&mut out);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use assert_matches::assert_matches;
+
+    use super::*;
+
+    #[test]
+    fn roundtrip() {
+        for base_data_offset in [None, Some(0x1_0000_0000)] {
+            let mut data = BytesMut::new();
+            TfhdBox::new(1, base_data_offset).put_buf(&mut data);
+            let tfhd = TfhdBox::parse(&mut data.clone()).unwrap();
+            assert_eq!(tfhd.track_id(), 1);
+            assert_eq!(tfhd.base_data_offset(), base_data_offset);
+            assert_eq!(tfhd.is_default_base_moof(), base_data_offset.is_none());
+
+            let mut encoded = BytesMut::new();
+            tfhd.put_buf(&mut encoded);
+            assert_eq!(tfhd.encoded_len(), encoded.len() as u64);
+            assert_eq!(encoded, data);
+        }
+    }
+
+    #[test]
+    fn optional_fields() {
+        let mut data = BytesMut::new();
+        FullBoxHeader {
+            version: 0,
+            flags: TfhdBox::DEFAULT_SAMPLE_SIZE_PRESENT | TfhdBox::DEFAULT_SAMPLE_FLAGS_PRESENT,
+        }
+        .put_buf(&mut data);
+        [1u32, 100, 0x01010000].put_buf(&mut data);
+        let tfhd = TfhdBox::parse(&mut data).unwrap();
+        assert_eq!(tfhd.default_sample_duration(), None);
+        assert_eq!(tfhd.default_sample_size(), Some(100));
+        assert_eq!(tfhd.default_sample_flags(), Some(0x01010000));
+    }
+
+    #[test]
+    fn truncated_base_data_offset() {
+        let mut data = BytesMut::new();
+        TfhdBox::new(1, Some(0)).put_buf(&mut data);
+        data.truncate(data.len() - 1);
+        let err = TfhdBox::parse(&mut data).unwrap_err();
+        assert_matches!(err.get_ref(), ParseError::TruncatedBox, "{err:?}");
+    }
+}
@@ -0,0 +1,198 @@
+#![allow(missing_docs)]
+
+use alloc::format;
+
+use bytes::{BufMut, BytesMut};
+
+use crate::error::Result;
+
+use super::error::{ParseResultExt, WhileParsingField};
+use super::{BoxType, FullBoxHeader, Mp4Value, Mp4ValueReaderExt, ParseBox, ParseError, ParsedBox};
+
+#[derive(Clone, Debug)]
+pub struct TkhdBox {
+    header: FullBoxHeader,
+    creation_time: u64,
+    modification_time: u64,
+    track_id: u32,
+    reserved: u32,
+    duration: u64,
+    /// The remaining fields (layer, volume, matrix, dimensions, etc.), which are not interpreted by the sanitizer.
+    rest: [u8; 60],
+}
+
+const NAME: BoxType = BoxType::TKHD;
+
+impl TkhdBox {
+    pub const TRACK_ENABLED: u32 = 0x000001;
+    pub const TRACK_IN_MOVIE: u32 = 0x000002;
+    pub const TRACK_IN_PREVIEW: u32 = 0x000004;
+
+    pub fn flags(&self) -> u32 {
+        self.header.flags
+    }
+
+    pub fn track_id(&self) -> u32 {
+        self.track_id
+    }
+
+    /// The duration of this track, in the movie timescale.
+    ///
+    /// A value of all ones indicates the duration is unknown.
+    pub fn duration(&self) -> u64 {
+        self.duration
+    }
+
+    /// Returns `true` if the [duration](Self::duration) is all ones, indicating it is unknown.
+    pub fn is_duration_unknown(&self) -> bool {
+        match self.header.version {
+            0 => self.duration == u32::MAX.into(),
+            _ => self.duration == u64::MAX,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.header.flags & Self::TRACK_ENABLED != 0
+    }
+
+    pub fn is_in_movie(&self) -> bool {
+        self.header.flags & Self::TRACK_IN_MOVIE != 0
+    }
+
+    pub fn is_in_preview(&self) -> bool {
+        self.header.flags & Self::TRACK_IN_PREVIEW != 0
+    }
+}
+
+impl ParseBox for TkhdBox {
+    fn parse(buf: &mut BytesMut) -> Result<Self, ParseError> {
+        let header: FullBoxHeader = buf.get_mp4_value().while_parsing_field(NAME, "header")?;
+        let (creation_time, modification_time, track_id, reserved, duration) = match header.version {
+            0 => (
+                buf.get_mp4_value::<u32>()
+                    .while_parsing_field(NAME, "creation_time")?
+                    .into(),
+                buf.get_mp4_value::<u32>()
+                    .while_parsing_field(NAME, "modification_time")?
+                    .into(),
+                buf.get_mp4_value().while_parsing_field(NAME, "track_id")?,
+                buf.get_mp4_value().while_parsing_field(NAME, "reserved")?,
+                buf.get_mp4_value::<u32>().while_parsing_field(NAME, "duration")?.into(),
+            ),
+            1 => (
+                buf.get_mp4_value().while_parsing_field(NAME, "creation_time")?,
+                buf.get_mp4_value().while_parsing_field(NAME, "modification_time")?,
+                buf.get_mp4_value().while_parsing_field(NAME, "track_id")?,
+                buf.get_mp4_value().while_parsing_field(NAME, "reserved")?,
+                buf.get_mp4_value().while_parsing_field(NAME, "duration")?,
+            ),
+            version => bail_attach!(
+                ParseError::InvalidInput,
+                format!("unsupported version {version}"),
+                WhileParsingField(NAME, "header"),
+            ),
+        };
+        ensure_attach!(
+            track_id != 0,
+            ParseError::InvalidInput,
+            "track id must not be zero",
+            WhileParsingField(NAME, "track_id"),
+        );
+        let rest = buf.get_mp4_value().while_parsing_field(NAME, "rest")?;
+        Ok(Self { header, creation_time, modification_time, track_id, reserved, duration, rest })
+    }
+
+    fn box_type() -> BoxType {
+        NAME
+    }
+}
+
+impl ParsedBox for TkhdBox {
+    fn encoded_len(&self) -> u64 {
+        let times_len = match self.header.version {
+            0 => 4 + 4 + 4,
+            _ => 8 + 8 + 8,
+        };
+        self.header.encoded_len() + times_len + self.track_id.encoded_len() + self.reserved.encoded_len() + 60
+    }
+
+    fn put_buf(&self, mut out: &mut dyn BufMut) {
+        self.header.put_buf(&mut out);
+        match self.header.version {
+            0 => {
+                (self.creation_time as u32).put_buf(&mut out);
+                (self.modification_time as u32).put_buf(&mut out);
+                self.track_id.put_buf(&mut out);
+                self.reserved.put_buf(&mut out);
+                (self.duration as u32).put_buf(&mut out);
+            }
+            _ => {
+                self.creation_time.put_buf(&mut out);
+                self.modification_time.put_buf(&mut out);
+                self.track_id.put_buf(&mut out);
+                self.reserved.put_buf(&mut out);
+                self.duration.put_buf(&mut out);
+            }
+        }
+        self.rest.put_buf(&mut out);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use assert_matches::assert_matches;
+    use bytes::BytesMut;
+
+    use crate::util::test::write_test_tkhd_data;
+
+    use super::*;
+
+    #[test]
+    fn roundtrip() {
+        let mut data = BytesMut::new();
+        write_test_tkhd_data(&mut data, 1, TkhdBox::TRACK_ENABLED | TkhdBox::TRACK_IN_MOVIE, 0);
+        let tkhd = TkhdBox::parse(&mut data.clone()).unwrap();
+        assert_eq!(tkhd.track_id(), 1);
+        assert!(tkhd.is_enabled());
+        assert!(tkhd.is_in_movie());
+        assert!(!tkhd.is_in_preview());
+
+        let mut encoded = BytesMut::new();
+        tkhd.put_buf(&mut encoded);
+        assert_eq!(tkhd.encoded_len(), encoded.len() as u64);
+        assert_eq!(encoded, data);
+    }
+
+    #[test]
+    fn unknown_duration() {
+        let mut data = BytesMut::new();
+        write_test_tkhd_data(&mut data, 1, TkhdBox::TRACK_ENABLED, u32::MAX.into());
+        assert!(TkhdBox::parse(&mut data).unwrap().is_duration_unknown());
+
+        let mut data = BytesMut::new();
+        write_test_tkhd_data(&mut data, 1, TkhdBox::TRACK_ENABLED, u64::MAX);
+        let tkhd = TkhdBox::parse(&mut data).unwrap();
+        assert!(tkhd.is_duration_unknown());
+        assert_eq!(tkhd.duration(), u64::MAX);
+
+        let mut data = BytesMut::new();
+        write_test_tkhd_data(&mut data, 1, TkhdBox::TRACK_ENABLED, u64::from(u32::MAX) + 1);
+        assert!(!TkhdBox::parse(&mut data).unwrap().is_duration_unknown());
+    }
+
+    #[test]
+    fn disabled() {
+        let mut data = BytesMut::new();
+        write_test_tkhd_data(&mut data, 1, TkhdBox::TRACK_IN_MOVIE, 0);
+        let tkhd = TkhdBox::parse(&mut data).unwrap();
+        assert!(!tkhd.is_enabled());
+    }
+
+    #[test]
+    fn zero_track_id() {
+        let mut data = BytesMut::new();
+        write_test_tkhd_data(&mut data, 0, TkhdBox::TRACK_ENABLED, 0);
+        let err = TkhdBox::parse(&mut data).unwrap_err();
+        assert_matches!(err.get_ref(), ParseError::InvalidInput, "{err:?}");
+    }
+}
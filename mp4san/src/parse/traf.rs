@@ -0,0 +1,44 @@
+#![allow(missing_docs)]
+
+use mediasan_common::ResultExt;
+
+use crate::error::Result;
+
+use super::error::{ParseResultExt, WhileParsingField};
+use super::{BoxType, Boxes, BoxesValidator, ParseBox, ParseError, ParsedBox, TfhdBox, TrunBox};
+
+/// The track fragment box, containing the metadata of a track's runs of samples within a movie fragment (`moof`).
+#[derive(Clone, Debug, ParseBox, ParsedBox)]
+#[box_type = "traf"]
+pub struct TrafBox {
+    children: Boxes<TrafChildrenValidator>,
+}
+
+pub(crate) struct TrafChildrenValidator;
+
+const NAME: BoxType = BoxType::TRAF;
+
+impl TrafBox {
+    #[cfg(test)]
+    pub(crate) fn with_children<C: Into<Boxes<TrafChildrenValidator>>>(children: C) -> Self {
+        Self { children: children.into() }
+    }
+
+    pub fn tfhd_mut(&mut self) -> Result<&mut TfhdBox, ParseError> {
+        self.children.get_one_mut().while_parsing_child(NAME, BoxType::TFHD)
+    }
+
+    pub fn truns(&mut self) -> impl Iterator<Item = Result<&mut TrunBox, ParseError>> + '_ {
+        self.children
+            .get_mut()
+            .map(|result| result.while_parsing_child(NAME, BoxType::TRUN))
+    }
+}
+
+impl BoxesValidator for TrafChildrenValidator {
+    fn validate<V>(children: &Boxes<V>) -> Result<(), ParseError> {
+        children
+            .ensure_one(BoxType::TFHD)
+            .attach_printable(WhileParsingField(NAME, "children"))
+    }
+}
@@ -1,10 +1,15 @@
 #![allow(missing_docs)]
 
+use alloc::vec;
+use alloc::vec::Vec;
+
 use crate::error::Result;
 
 use super::error::ParseResultExt;
 use super::mp4box::Boxes;
-use super::{BoxType, MdiaBox, ParseBox, ParseError, ParsedBox, StblCoMut};
+use super::{
+    BoxType, EdtsBox, FourCC, KindBox, MdiaBox, ParseBox, ParseError, ParsedBox, StblBox, StblCoMut, TkhdBox, UdtaBox,
+};
 
 #[derive(Clone, Debug, ParseBox, ParsedBox)]
 #[box_type = "trak"]
@@ -14,6 +19,9 @@ pub struct TrakBox {
 
 const NAME: BoxType = BoxType::TRAK;
 
+/// The child box types retained by [`TrakBox::minimize`].
+const MINIMAL_CHILDREN: &[BoxType] = &[BoxType::TKHD, BoxType::TREF, BoxType::EDTS, BoxType::MDIA];
+
 impl TrakBox {
     #[cfg(test)]
     pub(crate) fn with_children<C: Into<Boxes>>(children: C) -> Self {
@@ -21,10 +29,131 @@ impl TrakBox {
     }
 
     pub fn co_mut(&mut self) -> Result<StblCoMut<'_>, ParseError> {
-        self.mdia_mut()?.minf_mut()?.stbl_mut()?.co_mut()
+        self.stbl_mut()?.co_mut()
+    }
+
+    pub fn stbl_mut(&mut self) -> Result<&mut StblBox, ParseError> {
+        self.mdia_mut()?.minf_mut()?.stbl_mut()
+    }
+
+    /// The handler type of the track's media, e.g. `vide`, `soun`, or `hint`.
+    ///
+    /// See [`HdlrBox::handler_type`](super::HdlrBox::handler_type).
+    pub fn handler_type(&mut self) -> Result<FourCC, ParseError> {
+        Ok(self.mdia_mut()?.hdlr_mut()?.handler_type)
+    }
+
+    /// The format of the track's first sample entry, e.g. `avc1`, `hev1`, or `mp4a`, if any.
+    ///
+    /// See [`StsdBox::sample_format`](super::StsdBox::sample_format).
+    pub fn sample_format(&mut self) -> Result<Option<FourCC>, ParseError> {
+        Ok(self.stbl_mut()?.stsd_mut()?.sample_format())
+    }
+
+    /// Remove any boxes not required for playback from within this box and its descendants, returning the types of
+    /// the boxes removed, in order.
+    pub fn minimize(&mut self) -> Result<Vec<BoxType>, ParseError> {
+        let mut removed = self.children.retain_box_types(MINIMAL_CHILDREN);
+        removed.extend(self.mdia_mut()?.minimize()?);
+        Ok(removed)
+    }
+
+    /// Remove any user data (`udta`) and metadata (`meta`) boxes from within this box, returning the types of the boxes
+    /// removed.
+    ///
+    /// It is an error for a removed box to contain an item location box (`iloc`).
+    pub fn strip_metadata_boxes(&mut self) -> Result<Vec<BoxType>, ParseError> {
+        self.children
+            .strip_metadata_boxes()
+            .while_parsing_field(NAME, "children")
+    }
+
+    /// Remove any free space boxes (`free` or `skip`) from within this box and its descendants, returning the total
+    /// encoded length of the boxes removed.
+    pub fn strip_free_boxes(&mut self) -> Result<u64, ParseError> {
+        let stripped_len = self.children.strip_free_boxes();
+        Ok(stripped_len + self.mdia_mut()?.strip_free_boxes()?)
+    }
+
+    pub fn tkhd_mut(&mut self) -> Result<&mut TkhdBox, ParseError> {
+        self.children.get_one_mut().while_parsing_child(NAME, BoxType::TKHD)
+    }
+
+    pub fn edts_mut(&mut self) -> Result<Option<&mut EdtsBox>, ParseError> {
+        self.children
+            .get_optional_mut()
+            .while_parsing_child(NAME, BoxType::EDTS)
+    }
+
+    /// The track kind boxes (`kind`) declaring the roles of this track, found either directly within this box or within
+    /// its user data (`udta`).
+    pub fn kinds(&mut self) -> impl Iterator<Item = Result<&mut KindBox, ParseError>> + '_ {
+        self.children.iter_mut().flat_map(|child| -> Vec<_> {
+            match child.calculated_header().box_type() {
+                BoxType::KIND => child
+                    .parse_data_as()
+                    .while_parsing_child(NAME, BoxType::KIND)
+                    .transpose()
+                    .into_iter()
+                    .collect(),
+                BoxType::UDTA => match child
+                    .parse_data_as::<UdtaBox>()
+                    .while_parsing_child(NAME, BoxType::UDTA)
+                {
+                    Ok(Some(udta)) => udta
+                        .kinds()
+                        .map(|kind| kind.while_parsing_child(NAME, BoxType::UDTA))
+                        .collect(),
+                    Ok(None) => vec![],
+                    Err(err) => vec![Err(err)],
+                },
+                _ => vec![],
+            }
+        })
     }
 
     pub fn mdia_mut(&mut self) -> Result<&mut MdiaBox, ParseError> {
         self.children.get_one_mut().while_parsing_child(NAME, BoxType::MDIA)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use bytes::BytesMut;
+
+    use crate::parse::{MdiaBox, MinfBox, Mp4Box};
+    use crate::util::test::{test_hdlr, test_mdhd};
+
+    use super::*;
+
+    fn test_kind(value: &[u8]) -> Mp4Box<KindBox> {
+        Mp4Box::with_data(KindBox::new(KindBox::DASH_ROLE_SCHEME_URI, value).into()).unwrap()
+    }
+
+    #[test]
+    fn kinds() {
+        let udta = Mp4Box::with_data(UdtaBox::with_children(vec![test_kind(b"caption").into()]).into()).unwrap();
+        let mut data = BytesMut::new();
+        TrakBox::with_children(vec![test_kind(b"main").into(), udta.into()]).put_buf(&mut data);
+        let mut trak = TrakBox::parse(&mut data).unwrap();
+
+        let kinds = trak
+            .kinds()
+            .map(|kind| kind.unwrap().value().to_vec())
+            .collect::<Vec<_>>();
+        assert_eq!(kinds, [&b"main"[..], b"caption"]);
+    }
+
+    #[test]
+    fn handler_type() {
+        let soun = FourCC::from_str("soun");
+        let minf = Mp4Box::with_data(MinfBox::with_children(vec![]).into()).unwrap();
+        let mdia = MdiaBox::with_children(vec![test_mdhd(), test_hdlr(soun), minf.into()]);
+        let mdia = Mp4Box::with_data(mdia.into()).unwrap();
+        let mut data = BytesMut::new();
+        TrakBox::with_children(vec![mdia.into()]).put_buf(&mut data);
+        let mut trak = TrakBox::parse(&mut data).unwrap();
+
+        assert_eq!(trak.handler_type().unwrap(), soun);
+    }
+}
@@ -0,0 +1,231 @@
+#![allow(missing_docs)]
+
+use alloc::format;
+use alloc::vec::Vec;
+
+use bytes::{BufMut, BytesMut};
+
+use crate::error::Result;
+
+use super::error::{ParseResultExt, WhileParsingField};
+use super::{BoxType, FullBoxHeader, Mp4Value, Mp4ValueReaderExt, ParseBox, ParseError, ParsedBox};
+
+/// The track fragment run box, describing a contiguous run of samples of a track fragment (`traf`).
+#[derive(Clone, Debug)]
+pub struct TrunBox {
+    header: FullBoxHeader,
+    sample_count: u32,
+    data_offset: Option<i32>,
+    first_sample_flags: Option<u32>,
+    /// The per-sample fields, which are empty if no per-sample fields are present.
+    samples: Vec<TrunSample>,
+}
+
+/// The per-sample fields of a [`TrunBox`], each of which is present only if indicated by the box's flags.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TrunSample {
+    pub duration: Option<u32>,
+    pub size: Option<u32>,
+    pub flags: Option<u32>,
+    /// The composition time offset, which is signed in version 1 boxes.
+    pub composition_time_offset: Option<u32>,
+}
+
+const NAME: BoxType = BoxType::TRUN;
+
+impl TrunBox {
+    pub const DATA_OFFSET_PRESENT: u32 = 0x000001;
+    pub const FIRST_SAMPLE_FLAGS_PRESENT: u32 = 0x000004;
+    pub const SAMPLE_DURATION_PRESENT: u32 = 0x000100;
+    pub const SAMPLE_SIZE_PRESENT: u32 = 0x000200;
+    pub const SAMPLE_FLAGS_PRESENT: u32 = 0x000400;
+    pub const SAMPLE_COMPOSITION_TIME_OFFSETS_PRESENT: u32 = 0x000800;
+
+    /// Construct a [`TrunBox`] of samples with the given sizes.
+    pub fn new<I: IntoIterator<Item = u32>>(data_offset: Option<i32>, sample_sizes: I) -> Self {
+        let samples: Vec<_> = sample_sizes
+            .into_iter()
+            .map(|size| TrunSample { size: Some(size), ..Default::default() })
+            .collect();
+        let mut flags = Self::SAMPLE_SIZE_PRESENT;
+        if data_offset.is_some() {
+            flags |= Self::DATA_OFFSET_PRESENT;
+        }
+        Self {
+            header: FullBoxHeader { version: 0, flags },
+            sample_count: samples.len() as u32,
+            data_offset,
+            first_sample_flags: None,
+            samples,
+        }
+    }
+
+    pub fn flags(&self) -> u32 {
+        self.header.flags
+    }
+
+    pub fn sample_count(&self) -> u32 {
+        self.sample_count
+    }
+
+    /// The offset of the run's first sample, relative to the base data offset given by the track fragment header
+    /// (`tfhd`).
+    pub fn data_offset(&self) -> Option<i32> {
+        self.data_offset
+    }
+
+    pub fn first_sample_flags(&self) -> Option<u32> {
+        self.first_sample_flags
+    }
+
+    /// The per-sample fields of each sample, or none if no per-sample fields are present.
+    pub fn samples(&self) -> &[TrunSample] {
+        &self.samples
+    }
+
+    /// The flags of the per-sample fields present in the box.
+    fn sample_field_flags(&self) -> [u32; 4] {
+        [
+            Self::SAMPLE_DURATION_PRESENT,
+            Self::SAMPLE_SIZE_PRESENT,
+            Self::SAMPLE_FLAGS_PRESENT,
+            Self::SAMPLE_COMPOSITION_TIME_OFFSETS_PRESENT,
+        ]
+        .map(|flag| self.header.flags & flag)
+    }
+}
+
+impl TrunSample {
+    fn fields(&self) -> [Option<u32>; 4] {
+        [self.duration, self.size, self.flags, self.composition_time_offset]
+    }
+}
+
+impl ParseBox for TrunBox {
+    fn parse(buf: &mut BytesMut) -> Result<Self, ParseError> {
+        let header: FullBoxHeader = buf.get_mp4_value().while_parsing_field(NAME, "header")?;
+        ensure_attach!(
+            header.version <= 1,
+            ParseError::InvalidInput,
+            format!("unsupported version {}", header.version),
+            WhileParsingField(NAME, "header"),
+        );
+        let sample_count: u32 = buf.get_mp4_value().while_parsing_field(NAME, "sample_count")?;
+        let data_offset = (header.flags & Self::DATA_OFFSET_PRESENT != 0)
+            .then(|| buf.get_mp4_value())
+            .transpose()
+            .while_parsing_field(NAME, "data_offset")?;
+        let first_sample_flags = (header.flags & Self::FIRST_SAMPLE_FLAGS_PRESENT != 0)
+            .then(|| buf.get_mp4_value())
+            .transpose()
+            .while_parsing_field(NAME, "first_sample_flags")?;
+
+        let mut trun = Self { header, sample_count, data_offset, first_sample_flags, samples: Vec::new() };
+        let sample_field_flags = trun.sample_field_flags();
+        let sample_len = 4 * sample_field_flags.iter().filter(|flag| **flag != 0).count() as u64;
+        if sample_len != 0 {
+            ensure_attach!(
+                u64::from(sample_count) * sample_len == buf.len() as u64,
+                ParseError::InvalidInput,
+                format!("{sample_count} samples do not match box size"),
+                WhileParsingField(NAME, "samples"),
+            );
+            trun.samples.reserve_exact(sample_count as usize);
+            for _ in 0..sample_count {
+                let [duration, size, flags, composition_time_offset] = sample_field_flags.map(|flag| {
+                    (flag != 0)
+                        .then(|| buf.get_mp4_value())
+                        .transpose()
+                        .while_parsing_field(NAME, "samples")
+                });
+                trun.samples.push(TrunSample {
+                    duration: duration?,
+                    size: size?,
+                    flags: flags?,
+                    composition_time_offset: composition_time_offset?,
+                });
+            }
+        }
+        Ok(trun)
+    }
+
+    fn box_type() -> BoxType {
+        NAME
+    }
+}
+
+impl ParsedBox for TrunBox {
+    fn encoded_len(&self) -> u64 {
+        let data_offset_len = self.data_offset.as_ref().map_or(0, Mp4Value::encoded_len);
+        let first_sample_flags_len = self.first_sample_flags.as_ref().map_or(0, Mp4Value::encoded_len);
+        let samples_len: u64 = self
+            .samples
+            .iter()
+            .flat_map(TrunSample::fields)
+            .flatten()
+            .map(|field| field.encoded_len())
+            .sum();
+        self.header.encoded_len()
+            + self.sample_count.encoded_len()
+            + data_offset_len
+            + first_sample_flags_len
+            + samples_len
+    }
+
+    fn put_buf(&self, mut out: &mut dyn BufMut) {
+        self.header.put_buf(&mut out);
+        self.sample_count.put_buf(&mut out);
+        if let Some(data_offset) = self.data_offset {
+            data_offset.put_buf(&mut out);
+        }
+        if let Some(first_sample_flags) = self.first_sample_flags {
+            first_sample_flags.put_buf(&mut out);
+        }
+        for field in self.samples.iter().flat_map(TrunSample::fields).flatten() {
+            field.put_buf(&mut out);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use assert_matches::assert_matches;
+
+    use super::*;
+
+    #[test]
+    fn roundtrip() {
+        let mut data = BytesMut::new();
+        TrunBox::new(Some(-8), [3, 1, 2]).put_buf(&mut data);
+        let trun = TrunBox::parse(&mut data.clone()).unwrap();
+        assert_eq!(trun.sample_count(), 3);
+        assert_eq!(trun.data_offset(), Some(-8));
+        let sizes: Vec<_> = trun.samples().iter().map(|sample| sample.size).collect();
+        assert_eq!(sizes, [Some(3), Some(1), Some(2)]);
+
+        let mut encoded = BytesMut::new();
+        trun.put_buf(&mut encoded);
+        assert_eq!(trun.encoded_len(), encoded.len() as u64);
+        assert_eq!(encoded, data);
+    }
+
+    #[test]
+    fn no_sample_fields() {
+        let mut data = BytesMut::new();
+        FullBoxHeader { version: 0, flags: TrunBox::FIRST_SAMPLE_FLAGS_PRESENT }.put_buf(&mut data);
+        [u32::MAX, 0x02000000].put_buf(&mut data);
+        let trun = TrunBox::parse(&mut data).unwrap();
+        assert_eq!(trun.sample_count(), u32::MAX);
+        assert_eq!(trun.first_sample_flags(), Some(0x02000000));
+        assert!(trun.samples().is_empty());
+    }
+
+    #[test]
+    fn sample_count_too_large() {
+        let mut data = BytesMut::new();
+        TrunBox::new(None, [1, 2]).put_buf(&mut data);
+        data[7] = 3;
+        let err = TrunBox::parse(&mut data).unwrap_err();
+        assert_matches!(err.get_ref(), ParseError::InvalidInput, "{err:?}");
+    }
+}
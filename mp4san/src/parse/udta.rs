@@ -0,0 +1,40 @@
+#![allow(missing_docs)]
+
+use crate::error::Result;
+
+use super::error::ParseResultExt;
+use super::{BoxType, Boxes, CprtBox, KindBox, MetaBox, ParseBox, ParseError, ParsedBox};
+
+/// The user data box, containing informative metadata about its enclosing movie or track.
+#[derive(Clone, Debug, ParseBox, ParsedBox)]
+#[box_type = "udta"]
+pub struct UdtaBox {
+    children: Boxes,
+}
+
+const NAME: BoxType = BoxType::UDTA;
+
+impl UdtaBox {
+    #[cfg(test)]
+    pub(crate) fn with_children<C: Into<Boxes>>(children: C) -> Self {
+        Self { children: children.into() }
+    }
+
+    pub fn kinds(&mut self) -> impl Iterator<Item = Result<&mut KindBox, ParseError>> + '_ {
+        self.children
+            .get_mut()
+            .map(|result| result.while_parsing_child(NAME, BoxType::KIND))
+    }
+
+    pub fn cprts(&mut self) -> impl Iterator<Item = Result<&mut CprtBox, ParseError>> + '_ {
+        self.children
+            .get_mut()
+            .map(|result| result.while_parsing_child(NAME, BoxType::CPRT))
+    }
+
+    pub fn metas(&mut self) -> impl Iterator<Item = Result<&mut MetaBox, ParseError>> + '_ {
+        self.children
+            .get_mut()
+            .map(|result| result.while_parsing_child(NAME, BoxType::META))
+    }
+}
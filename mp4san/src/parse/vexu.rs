@@ -0,0 +1,263 @@
+#![allow(missing_docs)]
+
+use mediasan_common::ResultExt;
+
+use crate::error::Result;
+
+use super::error::{MultipleBoxes, ParseResultExt, WhileParsingField};
+use super::{BoxType, Boxes, BoxesValidator, ConstFullBoxHeader, FourCC, ParseBox, ParseError, ParsedBox};
+
+/// The video extended usage box, describing the stereo layout of Apple spatial video in a visual sample entry.
+#[derive(Clone, Debug, ParseBox, ParsedBox)]
+#[box_type = "vexu"]
+pub struct VexuBox {
+    children: Boxes<VexuChildrenValidator>,
+}
+
+/// The stereo view box, describing which eyes' views are present.
+#[derive(Clone, Debug, ParseBox, ParsedBox)]
+#[box_type = "eyes"]
+pub struct EyesBox {
+    children: Boxes<EyesChildrenValidator>,
+}
+
+/// The stereo view information box.
+#[derive(Clone, Debug, Default, ParseBox, ParsedBox)]
+#[box_type = "stri"]
+pub struct StriBox {
+    header: ConstFullBoxHeader,
+    flags: u8,
+}
+
+/// The hero stereo eye description box, indicating which eye's view is shown when displayed monoscopically.
+#[derive(Clone, Debug, Default, ParseBox, ParsedBox)]
+#[box_type = "hero"]
+pub struct HeroBox {
+    header: ConstFullBoxHeader,
+    hero_eye_indicator: u8,
+}
+
+/// The projection box, describing how the video is projected.
+#[derive(Clone, Debug, ParseBox, ParsedBox)]
+#[box_type = "proj"]
+pub struct ProjBox {
+    children: Boxes<ProjChildrenValidator>,
+}
+
+/// The projection information box.
+#[derive(Clone, Debug, ParseBox, ParsedBox)]
+#[box_type = "prji"]
+pub struct PrjiBox {
+    header: ConstFullBoxHeader,
+    projection_kind: FourCC,
+}
+
+pub(crate) struct VexuChildrenValidator;
+pub(crate) struct EyesChildrenValidator;
+pub(crate) struct ProjChildrenValidator;
+
+impl VexuBox {
+    #[cfg(test)]
+    pub(crate) fn with_children<C: Into<Boxes<VexuChildrenValidator>>>(children: C) -> Self {
+        Self { children: children.into() }
+    }
+
+    pub fn eyes_mut(&mut self) -> Result<Option<&mut EyesBox>, ParseError> {
+        self.children
+            .get_optional_mut()
+            .while_parsing_child(BoxType::VEXU, BoxType::EYES)
+    }
+
+    pub fn proj_mut(&mut self) -> Result<Option<&mut ProjBox>, ParseError> {
+        self.children
+            .get_optional_mut()
+            .while_parsing_child(BoxType::VEXU, BoxType::PROJ)
+    }
+}
+
+impl EyesBox {
+    #[cfg(test)]
+    pub(crate) fn with_children<C: Into<Boxes<EyesChildrenValidator>>>(children: C) -> Self {
+        Self { children: children.into() }
+    }
+
+    pub fn stri_mut(&mut self) -> Result<&mut StriBox, ParseError> {
+        self.children
+            .get_one_mut()
+            .while_parsing_child(BoxType::EYES, BoxType::STRI)
+    }
+
+    pub fn hero_mut(&mut self) -> Result<Option<&mut HeroBox>, ParseError> {
+        self.children
+            .get_optional_mut()
+            .while_parsing_child(BoxType::EYES, BoxType::HERO)
+    }
+}
+
+impl StriBox {
+    const HAS_LEFT_EYE_VIEW: u8 = 0x01;
+    const HAS_RIGHT_EYE_VIEW: u8 = 0x02;
+    const HAS_ADDITIONAL_VIEWS: u8 = 0x04;
+    const EYE_VIEWS_REVERSED: u8 = 0x08;
+
+    pub fn new(has_left_eye_view: bool, has_right_eye_view: bool) -> Self {
+        let mut flags = 0;
+        if has_left_eye_view {
+            flags |= Self::HAS_LEFT_EYE_VIEW;
+        }
+        if has_right_eye_view {
+            flags |= Self::HAS_RIGHT_EYE_VIEW;
+        }
+        Self { header: Default::default(), flags }
+    }
+
+    pub fn has_left_eye_view(&self) -> bool {
+        self.flags & Self::HAS_LEFT_EYE_VIEW != 0
+    }
+
+    pub fn has_right_eye_view(&self) -> bool {
+        self.flags & Self::HAS_RIGHT_EYE_VIEW != 0
+    }
+
+    pub fn has_additional_views(&self) -> bool {
+        self.flags & Self::HAS_ADDITIONAL_VIEWS != 0
+    }
+
+    pub fn eye_views_reversed(&self) -> bool {
+        self.flags & Self::EYE_VIEWS_REVERSED != 0
+    }
+}
+
+impl HeroBox {
+    pub fn new(hero_eye_indicator: u8) -> Self {
+        Self { header: Default::default(), hero_eye_indicator }
+    }
+
+    /// The hero eye: `0` for none, `1` for the left eye, or `2` for the right eye.
+    pub fn hero_eye_indicator(&self) -> u8 {
+        self.hero_eye_indicator
+    }
+}
+
+impl ProjBox {
+    #[cfg(test)]
+    pub(crate) fn with_children<C: Into<Boxes<ProjChildrenValidator>>>(children: C) -> Self {
+        Self { children: children.into() }
+    }
+
+    pub fn prji_mut(&mut self) -> Result<&mut PrjiBox, ParseError> {
+        self.children
+            .get_one_mut()
+            .while_parsing_child(BoxType::PROJ, BoxType::PRJI)
+    }
+}
+
+impl PrjiBox {
+    pub fn new(projection_kind: FourCC) -> Self {
+        Self { header: Default::default(), projection_kind }
+    }
+
+    /// The kind of projection, e.g. `rect` for rectilinear or `equi` for equirectangular.
+    pub fn projection_kind(&self) -> FourCC {
+        self.projection_kind
+    }
+}
+
+impl BoxesValidator for VexuChildrenValidator {
+    fn validate<V>(children: &Boxes<V>) -> Result<(), ParseError> {
+        for box_type in [BoxType::EYES, BoxType::PROJ] {
+            let count = children
+                .box_types()
+                .filter(|child_type| *child_type == box_type)
+                .count();
+            ensure_attach!(
+                count <= 1,
+                ParseError::InvalidBoxLayout,
+                MultipleBoxes(box_type),
+                WhileParsingField(BoxType::VEXU, "children"),
+            );
+        }
+        Ok(())
+    }
+}
+
+impl BoxesValidator for EyesChildrenValidator {
+    fn validate<V>(children: &Boxes<V>) -> Result<(), ParseError> {
+        children
+            .ensure_one(BoxType::STRI)
+            .attach_printable(WhileParsingField(BoxType::EYES, "children"))
+    }
+}
+
+impl BoxesValidator for ProjChildrenValidator {
+    fn validate<V>(children: &Boxes<V>) -> Result<(), ParseError> {
+        children
+            .ensure_one(BoxType::PRJI)
+            .attach_printable(WhileParsingField(BoxType::PROJ, "children"))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use assert_matches::assert_matches;
+    use bytes::BytesMut;
+
+    use crate::parse::Mp4Box;
+
+    use super::*;
+
+    const RECT: FourCC = FourCC { value: *b"rect" };
+
+    fn test_vexu() -> VexuBox {
+        let stri = Mp4Box::with_data(StriBox::new(true, true).into()).unwrap();
+        let hero = Mp4Box::with_data(HeroBox::new(1).into()).unwrap();
+        let eyes = Mp4Box::with_data(EyesBox::with_children(vec![stri.into(), hero.into()]).into()).unwrap();
+        let prji = Mp4Box::with_data(PrjiBox::new(RECT).into()).unwrap();
+        let proj = Mp4Box::with_data(ProjBox::with_children(vec![prji.into()]).into()).unwrap();
+        VexuBox::with_children(vec![eyes.into(), proj.into()])
+    }
+
+    #[test]
+    fn spatial_video() {
+        let mut data = BytesMut::new();
+        test_vexu().put_buf(&mut data);
+        let mut vexu = VexuBox::parse(&mut data).unwrap();
+
+        let eyes = vexu.eyes_mut().unwrap().unwrap();
+        let stri = eyes.stri_mut().unwrap();
+        assert!(stri.has_left_eye_view());
+        assert!(stri.has_right_eye_view());
+        assert!(!stri.has_additional_views());
+        assert!(!stri.eye_views_reversed());
+        assert_eq!(eyes.hero_mut().unwrap().unwrap().hero_eye_indicator(), 1);
+
+        let proj = vexu.proj_mut().unwrap().unwrap();
+        assert_eq!(proj.prji_mut().unwrap().projection_kind(), RECT);
+    }
+
+    #[test]
+    fn empty() {
+        let mut data = BytesMut::new();
+        VexuBox::with_children(vec![]).put_buf(&mut data);
+        let mut vexu = VexuBox::parse(&mut data).unwrap();
+        assert!(vexu.eyes_mut().unwrap().is_none());
+        assert!(vexu.proj_mut().unwrap().is_none());
+    }
+
+    #[test]
+    fn no_stri() {
+        let mut data = BytesMut::new();
+        EyesBox::with_children(vec![]).put_buf(&mut data);
+        let err = EyesBox::parse(&mut data).unwrap_err();
+        assert_matches!(err.get_ref(), ParseError::MissingRequiredBox(BoxType::STRI), "{err:?}");
+    }
+
+    #[test]
+    fn truncated() {
+        let mut data = BytesMut::new();
+        test_vexu().put_buf(&mut data);
+        data.truncate(data.len() - 1);
+        let err = VexuBox::parse(&mut data).unwrap_err();
+        assert_matches!(err.get_ref(), ParseError::TruncatedBox, "{err:?}");
+    }
+}
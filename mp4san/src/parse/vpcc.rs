@@ -0,0 +1,143 @@
+#![allow(missing_docs)]
+
+use alloc::format;
+
+use bytes::BytesMut;
+
+use crate::error::Result;
+
+use super::error::{ParseResultExt, WhileParsingField};
+use super::{BoxType, FullBoxHeader, Mp4ValueReaderExt, ParseBox, ParseError, ParsedBox, UnboundedArray};
+
+/// The VP codec configuration box, containing a VP8 or VP9 `VPCodecConfigurationRecord`.
+#[derive(Clone, Debug, ParsedBox)]
+pub struct VpcCBox {
+    header: FullBoxHeader,
+    profile: u8,
+    level: u8,
+    /// The bit depth, chroma subsampling, and video full range fields.
+    bit_depth_chroma_subsampling: u8,
+    colour_primaries: u8,
+    transfer_characteristics: u8,
+    matrix_coefficients: u8,
+    codec_initialization_data_size: u16,
+    codec_initialization_data: UnboundedArray<u8>,
+}
+
+const NAME: BoxType = BoxType::VPCC;
+
+impl VpcCBox {
+    pub fn profile(&self) -> u8 {
+        self.profile
+    }
+
+    pub fn level(&self) -> u8 {
+        self.level
+    }
+
+    pub fn bit_depth(&self) -> u8 {
+        self.bit_depth_chroma_subsampling >> 4
+    }
+}
+
+impl ParseBox for VpcCBox {
+    fn parse(buf: &mut BytesMut) -> Result<Self, ParseError> {
+        let header: FullBoxHeader = buf.get_mp4_value().while_parsing_field(NAME, "header")?;
+        ensure_attach!(
+            header.version == 1,
+            ParseError::UnsupportedBoxLayout,
+            format!("unsupported version {}", header.version),
+            WhileParsingField(NAME, "header"),
+        );
+        let profile = buf.get_mp4_value().while_parsing_field(NAME, "profile")?;
+        let level = buf.get_mp4_value().while_parsing_field(NAME, "level")?;
+        let bit_depth_chroma_subsampling = buf
+            .get_mp4_value()
+            .while_parsing_field(NAME, "bit_depth_chroma_subsampling")?;
+        let colour_primaries = buf.get_mp4_value().while_parsing_field(NAME, "colour_primaries")?;
+        let transfer_characteristics = buf
+            .get_mp4_value()
+            .while_parsing_field(NAME, "transfer_characteristics")?;
+        let matrix_coefficients = buf.get_mp4_value().while_parsing_field(NAME, "matrix_coefficients")?;
+        let codec_initialization_data_size: u16 = buf
+            .get_mp4_value()
+            .while_parsing_field(NAME, "codec_initialization_data_size")?;
+        ensure_attach!(
+            buf.len() >= codec_initialization_data_size.into(),
+            ParseError::TruncatedBox,
+            "codec initialization data size exceeds box size",
+            WhileParsingField(NAME, "codec_initialization_data"),
+        );
+        ensure_attach!(
+            buf.len() == codec_initialization_data_size.into(),
+            ParseError::InvalidInput,
+            "extra data after codec initialization data",
+            WhileParsingField(NAME, "codec_initialization_data"),
+        );
+        let codec_initialization_data = buf
+            .get_mp4_value()
+            .while_parsing_field(NAME, "codec_initialization_data")?;
+        Ok(Self {
+            header,
+            profile,
+            level,
+            bit_depth_chroma_subsampling,
+            colour_primaries,
+            transfer_characteristics,
+            matrix_coefficients,
+            codec_initialization_data_size,
+            codec_initialization_data,
+        })
+    }
+
+    fn box_type() -> BoxType {
+        NAME
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use assert_matches::assert_matches;
+    use bytes::BufMut;
+
+    use crate::parse::Mp4Value;
+
+    use super::*;
+
+    /// Write a `vpcC` box's data, for VP9 profile 0 level 3.1 with 8-bit 4:2:0 video.
+    fn write_test_vpcc_data(out: &mut BytesMut) {
+        FullBoxHeader { version: 1, flags: 0 }.put_buf(&mut *out);
+        out.put_u8(0); // profile
+        out.put_u8(31); // level
+        out.put_u8(0x82); // bit depth 8, chroma subsampling 4:2:0 colocated, limited range
+        out.put_u8(1); // colour primaries
+        out.put_u8(1); // transfer characteristics
+        out.put_u8(1); // matrix coefficients
+        out.put_u16(0); // codec initialization data size
+    }
+
+    #[test]
+    fn roundtrip() {
+        let mut data = BytesMut::new();
+        write_test_vpcc_data(&mut data);
+        let vpcc = VpcCBox::parse(&mut data.clone()).unwrap();
+        assert_eq!(vpcc.profile(), 0);
+        assert_eq!(vpcc.level(), 31);
+        assert_eq!(vpcc.bit_depth(), 8);
+
+        let mut encoded = BytesMut::new();
+        vpcc.put_buf(&mut encoded);
+        assert_eq!(vpcc.encoded_len(), encoded.len() as u64);
+        assert_eq!(encoded, data);
+    }
+
+    #[test]
+    fn codec_initialization_data_size_too_large() {
+        let mut data = BytesMut::new();
+        write_test_vpcc_data(&mut data);
+        let len = data.len();
+        data[len - 1] = 1;
+        let err = VpcCBox::parse(&mut data).unwrap_err();
+        assert_matches!(err.get_ref(), ParseError::TruncatedBox, "{err:?}");
+    }
+}
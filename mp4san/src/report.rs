@@ -0,0 +1,137 @@
+//! Reports of notable properties of sanitized inputs.
+
+use mediasan_common::InputSpan;
+
+use crate::parse::{BoxType, FourCC, FtypBox};
+
+/// A report of notable properties of an input, and of the changes made to its metadata, found while sanitizing it.
+///
+/// The report is purely informational; it doesn't affect how the input was sanitized.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct SanitizeReport {
+    /// The SystemIDs of the DRM protection systems found in the input's protection system specific headers (`pssh`),
+    /// in the order they were found.
+    pub protection_system_ids: Vec<[u8; 16]>,
+
+    /// The span of any trailing data found following the required top-level boxes, if
+    /// [`TrailingData::Report`](crate::TrailingData::Report) was configured.
+    pub trailing_data: Option<InputSpan>,
+
+    /// The software which likely produced the input, guessed from the order of its top-level boxes and its file type
+    /// header (`ftyp`).
+    ///
+    /// This is a heuristic, and is [`None`] if the input doesn't match any known producer.
+    pub likely_producer: Option<Producer>,
+
+    /// The amount by which chunk offsets were displaced to account for the media data moving within the sanitized
+    /// output, if they were displaced.
+    pub mdat_displacement: Option<i64>,
+
+    /// The size of the free space box (`free`) inserted before the media data to pad it to its offset in the sanitized
+    /// output, including its header, if one was inserted.
+    pub padding_size: Option<u64>,
+
+    /// The types of metadata boxes whose size extended to the end of the input, and which were given an explicit size
+    /// in the sanitized output.
+    pub explicitly_sized_boxes: Vec<BoxType>,
+
+    /// The types of the user data (`udta`) and metadata (`meta`) boxes stripped, if
+    /// [`Config::strip_all_metadata`](crate::Config::strip_all_metadata) was configured, in the order they were found.
+    pub stripped_metadata_boxes: Vec<BoxType>,
+
+    /// The types of the movie, track, and media header boxes (`mvhd`, `tkhd`, and `mdhd`) whose duration is all ones,
+    /// indicating it is unknown, as in live or streaming content, in the order they were found.
+    ///
+    /// Duration consistency checks are skipped for these boxes.
+    pub unknown_duration_boxes: Vec<BoxType>,
+
+    /// The types of the boxes dropped from the movie box (`moov`), such as disabled tracks (`trak`), if
+    /// [`Config::drop_disabled_tracks`](crate::Config::drop_disabled_tracks) was configured, and boxes not required for
+    /// playback, if [`Config::minimize_moov`](crate::Config::minimize_moov) was configured, in the order they were
+    /// dropped.
+    pub dropped_boxes: Vec<BoxType>,
+}
+
+/// Software which produces MP4 files, as guessed by [`SanitizeReport::likely_producer`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Producer {
+    /// FFmpeg's `mov`/`mp4` muxer (`libavformat`).
+    Ffmpeg,
+
+    /// Apple QuickTime or AVFoundation.
+    QuickTime,
+
+    /// Android's `MediaMuxer` (`MPEG4Writer`).
+    AndroidMediaMuxer,
+
+    /// GPAC's `MP4Box`.
+    Gpac,
+}
+
+/// The characteristic file type header and top-level box order of a [`Producer`].
+struct Fingerprint {
+    producer: Producer,
+    major_brand: FourCC,
+    minor_version: u32,
+    box_orders: &'static [&'static [BoxType]],
+}
+
+const FTYP: BoxType = BoxType::FTYP;
+const FREE: BoxType = BoxType::FREE;
+const MDAT: BoxType = BoxType::MDAT;
+const MOOV: BoxType = BoxType::MOOV;
+const WIDE: BoxType = BoxType::WIDE;
+
+const FINGERPRINTS: &[Fingerprint] = &[
+    // libavformat reserves a free box before the mdat, into which the moov is moved with `-movflags +faststart`.
+    Fingerprint {
+        producer: Producer::Ffmpeg,
+        major_brand: FourCC::from_str("isom"),
+        minor_version: 0x200,
+        box_orders: &[&[FTYP, FREE, MDAT, MOOV], &[FTYP, MOOV, FREE, MDAT]],
+    },
+    Fingerprint {
+        producer: Producer::QuickTime,
+        major_brand: FourCC::from_str("qt  "),
+        minor_version: 0,
+        box_orders: &[&[FTYP, WIDE, MDAT, MOOV], &[FTYP, MOOV, WIDE, MDAT]],
+    },
+    // MPEG4Writer reserves a free box for the moov, falling back to writing it at the end if it doesn't fit.
+    Fingerprint {
+        producer: Producer::AndroidMediaMuxer,
+        major_brand: FourCC::from_str("mp42"),
+        minor_version: 0,
+        box_orders: &[&[FTYP, MOOV, FREE, MDAT], &[FTYP, FREE, MDAT, MOOV]],
+    },
+    Fingerprint {
+        producer: Producer::Gpac,
+        major_brand: FourCC::from_str("isom"),
+        minor_version: 1,
+        box_orders: &[
+            &[FTYP, MOOV, MDAT],
+            &[FTYP, FREE, MOOV, MDAT],
+            &[FTYP, MOOV, FREE, MDAT],
+        ],
+    },
+];
+
+/// The maximum number of top-level box types recorded for [`Producer::guess`].
+pub(crate) const MAX_FINGERPRINT_BOX_COUNT: usize = 8;
+
+//
+// Producer impls
+//
+
+impl Producer {
+    /// Guess the producer of an input from its file type header and the types of its top-level boxes, in order.
+    pub(crate) fn guess(ftyp: &FtypBox, box_types: &[BoxType]) -> Option<Self> {
+        let fingerprint = FINGERPRINTS.iter().find(|fingerprint| {
+            fingerprint.major_brand == ftyp.major_brand
+                && fingerprint.minor_version == ftyp.minor_version
+                && fingerprint.box_orders.contains(&box_types)
+        })?;
+        Some(fingerprint.producer)
+    }
+}
@@ -0,0 +1,155 @@
+//! Sample tables assembled from a track's sample table box (`stbl`).
+
+use std::ops::Range;
+
+use mediasan_common::InputSpan;
+
+use crate::error::Result;
+use crate::parse::{ParseError, StblBox, StblCoMut, StscEntry};
+
+/// A track's sample tables, copied out of its `stbl` box.
+#[derive(Clone, Debug)]
+pub(crate) struct SampleTable {
+    /// Chunk offsets from `stco`/`co64`, relative to the start of the input.
+    chunk_offsets: Vec<u64>,
+
+    /// Entries from `stsc`.
+    sample_to_chunk: Vec<StscEntry>,
+
+    sample_sizes: SampleSizes,
+
+    /// Sync sample numbers from `stss`. If `None`, every sample is a sync sample.
+    sync_samples: Option<Vec<u32>>,
+}
+
+#[derive(Clone, Debug)]
+enum SampleSizes {
+    Constant { sample_size: u32, sample_count: u32 },
+    Variable(Vec<u32>),
+}
+
+impl SampleTable {
+    pub(crate) fn new(stbl: &mut StblBox) -> Result<Self, ParseError> {
+        let chunk_offsets = match stbl.co_mut()? {
+            StblCoMut::Stco(stco) => stco.values().map(|value| entry_value(value).into()).collect(),
+            StblCoMut::Co64(co64) => co64.values().map(entry_value).collect(),
+        };
+        let sample_to_chunk = stbl
+            .stsc_mut()?
+            .entries()
+            .map(|entry| entry_value(entry.get()))
+            .collect();
+
+        let stsz = stbl.stsz_mut()?;
+        let sample_sizes = match stsz.entry_sizes() {
+            Some(entry_sizes) => SampleSizes::Variable(entry_sizes.map(|entry| entry_value(entry.get())).collect()),
+            None => SampleSizes::Constant {
+                sample_size: stsz.sample_size().unwrap_or_default(),
+                sample_count: stsz.sample_count(),
+            },
+        };
+
+        let sync_samples = stbl
+            .stss_mut()?
+            .map(|stss| stss.entries().map(|entry| entry_value(entry.get())).collect());
+
+        Ok(Self { chunk_offsets, sample_to_chunk, sample_sizes, sync_samples })
+    }
+
+    /// Returns the chunk offsets, relative to the start of the input.
+    pub(crate) fn chunk_offsets(&self) -> &[u64] {
+        &self.chunk_offsets
+    }
+
+    pub(crate) fn sample_count(&self) -> u32 {
+        match &self.sample_sizes {
+            SampleSizes::Constant { sample_count, .. } => *sample_count,
+            SampleSizes::Variable(sample_sizes) => sample_sizes.len() as u32,
+        }
+    }
+
+    /// Returns the number of samples contained in the chunks described by `stsc`, up to the last chunk in `stco`/`co64`.
+    ///
+    /// Returns `None` if the `stsc` entries are not in increasing order of first chunk or the count overflows.
+    pub(crate) fn chunked_sample_count(&self) -> Option<u64> {
+        let chunk_count = self.chunk_offsets.len() as u64;
+        let mut sample_count = 0u64;
+        for (entry_idx, &StscEntry { first_chunk, samples_per_chunk, .. }) in self.sample_to_chunk.iter().enumerate() {
+            let next_first_chunk = match self.sample_to_chunk.get(entry_idx + 1) {
+                Some(next_entry) => next_entry.first_chunk.into(),
+                None => chunk_count.max(u64::from(first_chunk) - 1) + 1,
+            };
+            let run_chunk_count = next_first_chunk.checked_sub(first_chunk.into())?;
+            sample_count = sample_count.checked_add(run_chunk_count.checked_mul(samples_per_chunk.into())?)?;
+        }
+        Some(sample_count)
+    }
+
+    /// Returns the 1-based sample number of the first sync sample, if any.
+    pub(crate) fn first_sync_sample(&self) -> Option<u32> {
+        match &self.sync_samples {
+            Some(sync_samples) => sync_samples.first().copied(),
+            None if self.sample_count() != 0 => Some(1),
+            None => None,
+        }
+    }
+
+    /// Returns the offset within the input of the end of the track's last sample.
+    ///
+    /// Returns `None` if the track has no samples or the sample tables are inconsistent.
+    pub(crate) fn samples_end(&self) -> Option<u64> {
+        let last_sample = self.sample_span(self.sample_count())?;
+        last_sample.offset.checked_add(last_sample.len)
+    }
+
+    /// Returns the span within the input of the given 1-based sample number.
+    ///
+    /// Returns `None` if the sample doesn't exist or the sample tables are inconsistent.
+    pub(crate) fn sample_span(&self, sample_number: u32) -> Option<InputSpan> {
+        let sample_idx = sample_number.checked_sub(1)?;
+        if sample_idx >= self.sample_count() {
+            return None;
+        }
+
+        let chunk_count = self.chunk_offsets.len() as u64;
+        let mut run_first_sample_idx = 0u64;
+        for (entry_idx, &StscEntry { first_chunk, samples_per_chunk, .. }) in self.sample_to_chunk.iter().enumerate() {
+            let next_first_chunk = match self.sample_to_chunk.get(entry_idx + 1) {
+                Some(next_entry) => next_entry.first_chunk.into(),
+                None => chunk_count + 1,
+            };
+            let run_chunk_count = next_first_chunk.checked_sub(first_chunk.into())?;
+            let run_sample_count = run_chunk_count.checked_mul(samples_per_chunk.into())?;
+
+            let run_sample_idx = u64::from(sample_idx) - run_first_sample_idx;
+            if run_sample_idx < run_sample_count {
+                let chunk_idx = u64::from(first_chunk.checked_sub(1)?) + run_sample_idx / u64::from(samples_per_chunk);
+                let chunk_offset = *self.chunk_offsets.get(usize::try_from(chunk_idx).ok()?)?;
+                let chunk_first_sample_idx = sample_idx - (run_sample_idx % u64::from(samples_per_chunk)) as u32;
+                let offset = chunk_offset.checked_add(self.sample_sizes_sum(chunk_first_sample_idx..sample_idx)?)?;
+                let len = self.sample_sizes_sum(sample_idx..sample_idx + 1)?;
+                return Some(InputSpan { offset, len });
+            }
+            run_first_sample_idx = run_first_sample_idx.checked_add(run_sample_count)?;
+        }
+        None
+    }
+
+    /// Returns the sum of the sizes of the given 0-based sample indices.
+    fn sample_sizes_sum(&self, sample_idxs: Range<u32>) -> Option<u64> {
+        match &self.sample_sizes {
+            SampleSizes::Constant { sample_size, .. } => {
+                Some(u64::from(sample_idxs.end - sample_idxs.start) * u64::from(*sample_size))
+            }
+            SampleSizes::Variable(sample_sizes) => {
+                let sample_idxs = sample_idxs.start as usize..sample_idxs.end as usize;
+                Some(sample_sizes.get(sample_idxs)?.iter().map(|&size| u64::from(size)).sum())
+            }
+        }
+    }
+}
+
+/// Unwrap the value of an array entry, which has already been bounds checked by its array.
+fn entry_value<T>(value: Result<T, ParseError>) -> T {
+    value.unwrap_or_else(|_| unreachable!())
+}
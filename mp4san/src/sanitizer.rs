@@ -0,0 +1,4933 @@
+//! The sanitizer itself, reading an MP4 input and returning its sanitized metadata.
+
+use std::io::{self, Read, Write};
+use std::iter;
+use std::ops::{ControlFlow, Range};
+use std::vec;
+
+use bytes::{BufMut, Bytes, BytesMut};
+use derive_builder::Builder;
+use derive_more::Display;
+use futures_util::io::BufReader;
+use futures_util::{pin_mut, stream, AsyncBufReadExt, AsyncRead, AsyncSeek, Stream};
+use mediasan_common::sync;
+use mediasan_common::util::{checked_add_signed, IoResultExt};
+use mediasan_common::{AsyncSkip, AsyncSkipExt, InputSpan, ReadDiscardSkip, SeekSkipAdapter, Skip};
+
+use crate::error::{Error, Report};
+use crate::info::MediaInfo;
+use crate::log;
+use crate::parse::error::{AtOffset, MultipleBoxes, WhileParsingBox};
+use crate::parse::{
+    AnyMp4Box, BoxHeader, BoxType, Boxes, FourCC, FtypBox, MetaBox, MoofBox, MoovBox, Mp4Box, Mp4Value, ParseError,
+    PdinBox, SidxBox, StblCoMut, UdtaBox,
+};
+use crate::report::{Producer, SanitizeReport, MAX_FINGERPRINT_BOX_COUNT};
+use crate::sample_table::SampleTable;
+use crate::util::WriteBufMut;
+
+//
+// public types
+//
+
+#[derive(Builder, Clone)]
+#[builder(build_fn(name = "try_build"))]
+/// Configuration for the MP4 sanitizer.
+pub struct Config {
+    /// The maximum size of metadata to support.
+    ///
+    /// This is useful to set an upper bound on memory consumption in the parser.
+    ///
+    /// The default is 1 GiB.
+    #[builder(default = "1024 * 1024 * 1024")]
+    pub max_metadata_size: u64,
+
+    /// The maximum size of padding to add between the sanitized metadata and the media data.
+    ///
+    /// When the media data would otherwise move backward, free space boxes (`free`) are added following the metadata to
+    /// keep it in place, leaving chunk offsets unchanged. Padding too large to fit in a single free space box is split
+    /// across several. If more padding than this would be needed, the media data is displaced instead. As the padding is
+    /// returned as part of the [sanitized metadata](SanitizedMetadata::metadata), this bounds its memory consumption.
+    ///
+    /// The default is unlimited.
+    #[builder(default = "u64::MAX")]
+    pub max_pad_size: u64,
+    /// The cumulative MDAT box size
+    ///
+    /// The value is tightly associated with a specific
+    /// use case scenario in which the transcoder internally
+    /// generates a sequence of MDAT boxes, but delivers
+    /// them compounded as a single monolythic MDAT box.
+    ///
+    /// In order to avoid constantly updating the single
+    /// MDAT box size (which may be impossible when the
+    /// payload is continually encrypted and written to
+    /// the file), the transcoder instead does the following:
+    ///    a) writes the MDAT box size to be equal to the
+    ///       fixed zero value
+    ///    b) keeps accumulating the box size and passes
+    ///       it as config argument to mp4sanitizer
+    ///
+    /// IMPORTANT: given the special circumstances of
+    /// the use case scenario of transcoding on mobile
+    /// devices, the MDAT box size is expected to not
+    /// exceed the 32-bit max bytes limit. Hence the
+    /// cumulative_mdat_box_size is a 32-bit value
+    #[builder(default = None)]
+    pub cumulative_mdat_box_size: Option<u32>,
+
+    /// Whether to preserve free space boxes (`free` or `skip`) found within the movie box (`moov`).
+    ///
+    /// By default, such boxes are stripped from any metadata returned by the sanitizer, shrinking it. This has no effect
+    /// if the original metadata did not need to be modified.
+    #[builder(default)]
+    pub preserve_moov_free_boxes: bool,
+
+    /// Whether to drop tracks (`trak`) which are not marked as enabled in their track header (`tkhd`).
+    ///
+    /// It is an error for all tracks to be dropped. The default is `false`.
+    #[builder(default)]
+    pub drop_disabled_tracks: bool,
+
+    /// Whether to validate that string fields within the metadata are valid UTF-8.
+    ///
+    /// Invalid UTF-8 may indicate a corrupted or maliciously crafted input. Currently, this validates the handler name
+    /// (`hdlr`) of each track. The default is `false`.
+    #[builder(default)]
+    pub validate_strings: bool,
+
+    /// Whether to rewrite the movie box (`moov`) keeping only the boxes required for playback.
+    ///
+    /// Optional boxes such as user data (`udta`), metadata (`meta`), and free space boxes are removed from the movie box
+    /// and its tracks, shrinking it. Sample descriptions (`stsd`), timing, chunk offsets, track references, sample
+    /// groups, sample auxiliary information, and protection system headers are retained. Setting this always causes
+    /// rewritten metadata to be returned if any box was removed. The default is `false`.
+    #[builder(default)]
+    pub minimize_moov: bool,
+
+    /// How to handle unexpected content following the required top-level boxes.
+    ///
+    /// The default is [`TrailingData::Fail`].
+    #[builder(default)]
+    pub on_trailing_data: TrailingData,
+
+    /// The offset at which the media data (`mdat`) should start in the sanitized output, if any.
+    ///
+    /// When set, the sanitized metadata is followed by a free space box (`free`) padding it out to this offset, and the
+    /// chunk offsets are adjusted by the distance the media data was displaced from its position in the input. It is an
+    /// error for the target to be before the end of the sanitized metadata, or to leave too little room for padding or
+    /// require more than [`max_pad_size`](Self::max_pad_size). Setting this always causes rewritten metadata to be
+    /// returned unless the media data is already at the target offset. The default is [`None`], in which case the media
+    /// data is placed directly after the metadata.
+    #[builder(default)]
+    pub target_mdat_offset: Option<u64>,
+
+    /// The alignment, in bytes, of the media data (`mdat`) payload in the sanitized output, if any.
+    ///
+    /// When set, the sanitized metadata is followed by a free space box (`free`) sized so that the first byte of the
+    /// first media data box's payload, past its header, falls on a multiple of this alignment. Unlike
+    /// [`target_mdat_offset`](Self::target_mdat_offset), which places the start of the media data box itself, this
+    /// accounts for the size of the media data box's header. Setting this causes rewritten metadata to be returned unless
+    /// the payload is already aligned in the input. This is ignored if a target mdat offset is set. The default is
+    /// [`None`].
+    #[builder(default)]
+    pub align_mdat_data: Option<u64>,
+
+    /// Whether to strip all user data (`udta`) and metadata (`meta`) boxes, which commonly carry location, device, and
+    /// author information.
+    ///
+    /// Such boxes are removed from the top level of the input, and from within the movie box (`moov`) and its tracks.
+    /// Top-level boxes are read rather than skipped, up to [`max_metadata_size`](Self::max_metadata_size). It is an
+    /// error for a stripped box to contain an item location box (`iloc`) locating items by offset within the file, as its
+    /// items may be located within the media data. Setting this always causes rewritten metadata to be returned if any
+    /// box was stripped. The default is `false`.
+    #[builder(default)]
+    pub strip_all_metadata: bool,
+
+    /// The compatible brands accepted by the sanitizer.
+    ///
+    /// At least one of these must be present among the compatible brands of the input's file type header (`ftyp`), or
+    /// the input is rejected with [`ParseError::UnsupportedFormat`]. Brands such as `mp42`, `iso2`, `iso6`, or `dash`
+    /// may be added for inputs which are structurally compatible with `isom`. The default is just
+    /// [`COMPATIBLE_BRAND`].
+    #[builder(
+        default = "vec![COMPATIBLE_BRAND]",
+        setter(into, each(name = "add_compatible_brand"))
+    )]
+    pub compatible_brands: Vec<FourCC>,
+
+    /// The maximum number of top-level boxes to read from the input, if any.
+    ///
+    /// This bounds the time spent reading inputs containing huge numbers of tiny boxes, such as free space boxes, each of
+    /// which is cheap to read on its own. It is an error for the input to contain more boxes. The default is 1,000,000.
+    #[builder(default = "Some(1_000_000)")]
+    pub max_boxes: Option<u64>,
+
+    /// Whether to validate that every chunk offset in the chunk offset boxes (`stco` and `co64`) lies within the media
+    /// data (`mdat`).
+    ///
+    /// A chunk offset outside the media data may point into the metadata, which a decoder would then read as media. As
+    /// chunk offsets are displaced along with the media data, this is checked against the media data's position in the
+    /// input. The default is `true`.
+    #[builder(default = "true")]
+    pub validate_chunk_offsets: bool,
+
+    /// Whether to always return rewritten metadata, placing the movie box (`moov`) before the media data (`mdat`).
+    ///
+    /// The sanitized metadata always precedes the media data, so an input with the media data first is always rewritten
+    /// with its chunk offsets displaced by the distance the media data moved. By default, no metadata is returned if the
+    /// input already has the movie box first and otherwise needs no changes, leaving the input to be used as-is. When
+    /// this is set, metadata is returned even then, so that the sanitized output can always be formed by concatenating
+    /// the metadata and the media data, with chunk offsets rewritten to match. The default is `false`.
+    #[builder(default)]
+    pub faststart: bool,
+
+    /// Whether to accept media data boxes (`mdat`) separated by other boxes, such as a movie box (`moov`).
+    ///
+    /// By default, it is an error for the media data boxes not to be contiguous, other than for free space and metadata
+    /// boxes between them, which are kept along with the media data. When this is set, each contiguous run of media data
+    /// is returned as a separate span, the first in [`SanitizedMetadata::data`] and the rest in
+    /// [`SanitizedMetadata::additional_data`], and chunk offsets are adjusted for the spans being concatenated in order
+    /// following the sanitized metadata. Setting this always causes rewritten metadata to be returned if the media data
+    /// is discontiguous. The default is `false`.
+    #[builder(default)]
+    pub allow_discontiguous_mdat: bool,
+
+    /// Whether to preserve a file-level metadata box (`meta`) preceding the media data (`mdat`).
+    ///
+    /// By default, file-level metadata boxes are omitted from any rewritten metadata. When this is set, a metadata box
+    /// preceding the media data is read, up to [`max_metadata_size`](Self::max_metadata_size), and written between the
+    /// file type header (`ftyp`) and the movie box (`moov`) in the sanitized metadata. It is an error for it to contain
+    /// an item location box (`iloc`) locating items by offset within the file, as such offsets are not adjusted when the
+    /// media data is displaced. This has no effect if [`strip_all_metadata`](Self::strip_all_metadata) is set. The
+    /// default is `false`.
+    #[builder(default)]
+    pub preserve_meta: bool,
+
+    /// The media handler types (`hdlr`) of tracks (`trak`) to drop, such as `text` or `sbtl`.
+    ///
+    /// Tracks whose handler type is listed are removed from the sanitized metadata, along with their chunk offsets. It
+    /// is an error for all tracks to be dropped. The default is empty.
+    #[builder(default, setter(into, each(name = "add_drop_handler_type")))]
+    pub drop_handler_types: Vec<FourCC>,
+
+    /// The maximum number of bytes of unrecognized data, such as a prepended ID3 tag, to skip at the start of the input
+    /// while searching for the file type header (`ftyp`).
+    ///
+    /// When nonzero, only this many leading bytes are scanned for the signature of a file type header, or of a segment
+    /// type box (`styp`) if [`allow_media_segments`](Self::allow_media_segments) is set, and any data preceding it is
+    /// omitted from the sanitized metadata. It is an error for no file type header to be found within them. The default
+    /// is `0`, requiring the input to begin with a box.
+    #[builder(default)]
+    pub max_leading_data_len: u64,
+
+    /// Whether to tolerate a few stray bytes at the end of the input, following the required top-level boxes.
+    ///
+    /// Such bytes, fewer than [`BoxHeader::MAX_SIZE`], are treated as padding and dropped from the sanitized output,
+    /// unless they form exactly one complete box. Any longer trailing data is handled according to
+    /// [`on_trailing_data`](Self::on_trailing_data). The default is `false`.
+    #[builder(default)]
+    pub allow_trailing_padding: bool,
+
+    /// Whether to accept standalone media segments, such as DASH or CMAF segments, which begin with a segment type box
+    /// (`styp`) in place of the file type header (`ftyp`).
+    ///
+    /// The segment type box is parsed the same way as a file type header, and its compatible brands must likewise include
+    /// one of the [`compatible_brands`](Self::compatible_brands). A media segment has no movie box (`moov`); its segment
+    /// index (`sidx`) and movie fragment (`moof`) boxes are passed through verbatim along with the media data. Its
+    /// metadata is only rewritten to omit leading data or boxes stripped by
+    /// [`strip_all_metadata`](Self::strip_all_metadata), and [`target_mdat_offset`](Self::target_mdat_offset) and
+    /// [`align_mdat_data`](Self::align_mdat_data) are ignored. The default is `false`.
+    #[builder(default)]
+    pub allow_media_segments: bool,
+}
+
+/// How the sanitizer handles unexpected content following the required top-level boxes.
+///
+/// Once the file type (`ftyp`), movie (`moov`), and media data (`mdat`) boxes have been read, any following content
+/// which is not a box supported by the sanitizer, or is too short to be a box, is considered trailing data. Trailing data
+/// may be garbage, a second concatenated file, or an unsupported box such as a movie fragment random access box
+/// (`mfra`).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TrailingData {
+    /// Return an error, as if the trailing data were part of the input.
+    #[default]
+    Fail,
+
+    /// Stop reading the input at the start of the trailing data.
+    Ignore,
+
+    /// Stop reading the input at the start of the trailing data, recording its span in the
+    /// [report](SanitizeReport::trailing_data).
+    Report,
+}
+
+/// Sanitized metadata returned by the sanitizer.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SanitizedMetadata {
+    /// The sanitized metadata from the given input, as a self-contained contiguous byte array.
+    ///
+    /// "Self-contained and contiguous" means that the metadata can be concatenated with the [media data](Self::data) to
+    /// form a valid MP4 file.
+    ///
+    /// If the original metadata did not need to be modified, this will be [`None`].
+    pub metadata: Option<Vec<u8>>,
+
+    /// A pointer to the span in the input containing the (contiguous) media data.
+    pub data: InputSpan,
+
+    /// Pointers to the spans in the input containing any further media data, to be concatenated in order following the
+    /// [media data](Self::data).
+    ///
+    /// This is empty unless [`Config::allow_discontiguous_mdat`] is set and the input's media data is discontiguous.
+    pub additional_data: Vec<InputSpan>,
+
+    /// A report of notable properties of the input found while sanitizing it, and of the changes made to its metadata.
+    pub report: SanitizeReport,
+}
+
+/// An estimate of the cost of sanitizing an MP4 input, as returned by [`estimate_parse_cost`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ParseCost {
+    /// The number of top-level boxes in the input.
+    pub top_level_box_count: u64,
+
+    /// The total size of all movie (`moov`) boxes in the input, including their headers.
+    pub moov_size: u64,
+
+    /// The total size of all media data (`mdat`) boxes in the input, including their headers.
+    pub mdat_size: u64,
+}
+
+/// The parsed metadata of an MP4 input, as returned by [`parse_metadata`].
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct ParsedMp4 {
+    /// The file type header.
+    pub ftyp: FtypBox,
+
+    /// The movie box, containing the presentation metadata.
+    pub moov: MoovBox,
+
+    /// A pointer to the span in the input containing the (contiguous) media data.
+    pub data: InputSpan,
+}
+
+/// A source of bytes for a range of the sanitized output, as returned by [`SanitizedMetadata::map_output_range`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum OutputSource {
+    /// A range of bytes within the [sanitized metadata](SanitizedMetadata::metadata).
+    Metadata(Range<u64>),
+
+    /// A range of bytes within the original input.
+    Input(Range<u64>),
+}
+
+/// A complete map of the sources of every byte of the sanitized output, as returned by [`SanitizedMetadata::layout_map`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LayoutMap {
+    /// The sources of the sanitized output, in output order, with no gaps or overlaps between them.
+    pub sources: Vec<OutputSource>,
+
+    /// The total length of the sanitized output; equal to the sum of the lengths of the [`sources`](Self::sources).
+    pub len: u64,
+}
+
+/// The ISO Base Media File Format "compatble brand" recognized by the sanitizer.
+///
+/// By default, this compatible brand must be present in the input's file type header (`ftyp`) in order to be parsed by
+/// the sanitizer. See [`Config::compatible_brands`].
+pub const COMPATIBLE_BRAND: FourCC = FourCC { value: *b"isom" };
+
+//
+// private types
+//
+
+#[derive(Clone, Copy, Debug, Display)]
+#[display(fmt = "box data too large: {} > {}", _0, _1)]
+pub(crate) struct BoxDataTooLarge(pub(crate) u64, pub(crate) u64);
+
+/// The top-level boxes read from an MP4 input.
+struct InputBoxes {
+    ftyp: Mp4Box<FtypBox>,
+    /// The progressive download information box, if any.
+    pdin: Option<Mp4Box<PdinBox>>,
+    /// The file-level metadata box preserved by [`Config::preserve_meta`], if any.
+    meta: Option<Mp4Box<MetaBox>>,
+    /// The movie box, along with its offset, or [`None`] for a media segment, per [`Config::allow_media_segments`].
+    moov: Option<(Mp4Box<MoovBox>, u64)>,
+    /// The segment index box preceding the media data, if any, along with the offset of its anchor point at its end.
+    sidx: Option<(Mp4Box<SidxBox>, u64)>,
+    /// Unrecognized data skipped before the file type header, per [`Config::max_leading_data_len`].
+    ///
+    /// All other offsets are relative to the end of this data, i.e. the start of the file type header.
+    leading_data: Option<InputSpan>,
+    data: InputSpan,
+    /// The spans of any media data following [`data`](Self::data) discontiguously, in order.
+    additional_data: Vec<InputSpan>,
+    trailing_data: Option<InputSpan>,
+    /// The offset of the payload of the first media data box, past its header.
+    mdat_payload_offset: u64,
+    /// Whether any movie fragment (`moof`) has a track fragment with an explicit base data offset, which is an absolute
+    /// offset within the input.
+    fragment_base_data_offsets: bool,
+    /// The types of the first top-level boxes, in order, up to [`MAX_FINGERPRINT_BOX_COUNT`].
+    box_types: Vec<BoxType>,
+    /// The types of the top-level user data and metadata boxes stripped by [`Config::strip_all_metadata`], in order.
+    stripped_metadata_boxes: Vec<BoxType>,
+}
+
+/// Sanitized metadata, before it is serialized.
+struct SanitizedBoxes {
+    /// The sanitized metadata boxes, or [`None`] if the original metadata did not need to be modified.
+    metadata: Option<MetadataBoxes>,
+    data: InputSpan,
+    additional_data: Vec<InputSpan>,
+    report: SanitizeReport,
+}
+
+/// The boxes making up sanitized metadata, in output order.
+struct MetadataBoxes {
+    ftyp: Mp4Box<FtypBox>,
+    pdin: Option<Mp4Box<PdinBox>>,
+    meta: Option<Mp4Box<MetaBox>>,
+    /// The movie box, or [`None`] for a media segment.
+    moov: Option<Mp4Box<MoovBox>>,
+    sidx: Option<Mp4Box<SidxBox>>,
+    /// The size of the free space box padding the metadata, including its header, or `0` if there is none.
+    pad_size: u64,
+}
+
+const MAX_FTYP_SIZE: u64 = 1024;
+
+/// The maximum size of the chunks of media data yielded by [`sanitize_async_stream`].
+const STREAM_CHUNK_SIZE: u64 = 64 * 1024;
+
+/// The size of the header of a free space box used to pad the sanitized metadata.
+const PAD_HEADER_SIZE: u64 = BoxHeader::with_u32_data_size(BoxType::FREE, 0).encoded_len();
+
+/// The maximum size of a single free space box used to pad the sanitized metadata, including its header.
+const MAX_PAD_SIZE: u64 = u32::MAX as u64 - PAD_HEADER_SIZE;
+
+//
+// public functions
+//
+
+/// Sanitize an MP4 input, with the default [`Config`].
+///
+/// The `input` must implement [`Read`] + [`Skip`], where [`Skip`] represents a subset of the [`Seek`] trait; an input
+/// stream which can be skipped forward, but not necessarily seeked to arbitrary positions.
+///
+/// See the [crate-level documentation](crate) for usage examples.
+///
+/// # Errors
+///
+/// If the input cannot be parsed, or an IO error occurs, an [`Error`] is returned.
+///
+/// [`Seek`]: std::io::Seek
+pub fn sanitize<R: Read + Skip + Unpin>(input: R) -> Result<SanitizedMetadata, Error> {
+    sync::sanitize(input, sanitize_async)
+}
+
+/// Sanitize an MP4 input, with the given [`Config`].
+///
+/// The `input` must implement [`Read`] + [`Skip`], where [`Skip`] represents a subset of the [`Seek`] trait; an input
+/// stream which can be skipped forward, but not necessarily seeked to arbitrary positions.
+///
+/// ```
+/// # use mp4san_test::{example_ftyp, example_mdat, example_moov};
+/// #
+/// let example_input = [example_ftyp(), example_mdat(), example_moov()].concat();
+///
+/// let config = mp4san::Config::builder().max_metadata_size(example_moov().len() as u64).build();
+/// let sanitized = mp4san::sanitize_with_config(std::io::Cursor::new(example_input), config)?;
+///
+/// assert_eq!(sanitized.metadata, Some([example_ftyp(), example_moov()].concat()));
+/// assert_eq!(sanitized.data.offset, example_ftyp().len() as u64);
+/// assert_eq!(sanitized.data.len, example_mdat().len() as u64);
+/// #
+/// # Ok::<(), mp4san::Error>(())
+/// ```
+///
+/// # Errors
+///
+/// If the input cannot be parsed, or an IO error occurs, an [`Error`] is returned.
+///
+/// [`Seek`]: std::io::Seek
+pub fn sanitize_with_config<R: Read + Skip + Unpin>(input: R, config: Config) -> Result<SanitizedMetadata, Error> {
+    sync::sanitize(input, |input| sanitize_async_with_config(input, config))
+}
+
+/// Sanitize an MP4 input which can only be read forward, with the default [`Config`].
+///
+/// Unlike [`sanitize`], the `input` need only implement [`Read`]; it is wrapped in a [`ReadDiscardSkip`], so that any
+/// data the sanitizer would skip is instead read and discarded. This makes it possible to sanitize pipes or the output
+/// of decompressors, at the cost of reading the entire input, including all media data (`mdat`). Inputs containing a
+/// box which extends to the end of the input are not supported, as the input's length is unknown.
+///
+/// # Examples
+///
+/// ```
+/// # use mp4san_test::{example_ftyp, example_mdat, example_moov};
+/// #
+/// let example_input = [example_ftyp(), example_mdat(), example_moov()].concat();
+///
+/// let sanitized = mp4san::sanitize_read_only(&example_input[..])?;
+///
+/// assert_eq!(sanitized.metadata, Some([example_ftyp(), example_moov()].concat()));
+/// assert_eq!(sanitized.data.offset, example_ftyp().len() as u64);
+/// assert_eq!(sanitized.data.len, example_mdat().len() as u64);
+/// #
+/// # Ok::<(), mp4san::Error>(())
+/// ```
+///
+/// # Errors
+///
+/// If the input cannot be parsed, or an IO error occurs, an [`Error`] is returned.
+pub fn sanitize_read_only<R: Read + Unpin>(input: R) -> Result<SanitizedMetadata, Error> {
+    sanitize(ReadDiscardSkip::new(input))
+}
+
+/// Sanitize an MP4 input asynchronously, with the default [`Config`].
+///
+/// The `input` must implement [`AsyncRead`] + [`AsyncSkip`], where [`AsyncSkip`] represents a subset of the
+/// [`AsyncSeek`] trait; an input stream which can be skipped forward, but not necessarily seeked to arbitrary
+/// positions.
+///
+/// # Examples
+///
+/// ```
+/// # use mp4san::sanitize_async;
+/// # use mp4san_test::{example_ftyp, example_mdat, example_moov};
+/// #
+/// # fn main() -> Result<(), mp4san::Error> {
+/// #     futures_util::FutureExt::now_or_never(run()).unwrap()
+/// # }
+/// #
+/// # async fn run() -> Result<(), mp4san::Error> {
+/// let example_input = [example_ftyp(), example_mdat(), example_moov()].concat();
+///
+/// let sanitized = sanitize_async(futures_util::io::Cursor::new(example_input)).await?;
+///
+/// assert_eq!(sanitized.metadata, Some([example_ftyp(), example_moov()].concat()));
+/// assert_eq!(sanitized.data.offset, example_ftyp().len() as u64);
+/// assert_eq!(sanitized.data.len, example_mdat().len() as u64);
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// If the input cannot be parsed, or an IO error occurs, an [`Error`] is returned.
+///
+/// [`AsyncSeek`]: futures_util::io::AsyncSeek
+pub async fn sanitize_async<R: AsyncRead + AsyncSkip>(input: R) -> Result<SanitizedMetadata, Error> {
+    sanitize_async_with_config(input, Config::default()).await
+}
+
+/// Sanitize an MP4 input asynchronously, with the given [`Config`].
+///
+/// The `input` must implement [`AsyncRead`] + [`AsyncSkip`], where [`AsyncSkip`] represents a subset of the
+/// [`AsyncSeek`] trait; an input stream which can be skipped forward, but not necessarily seeked to arbitrary
+/// positions.
+///
+/// # Examples
+///
+/// ```
+/// # use mp4san::{sanitize_async_with_config, Config};
+/// # use mp4san_test::{example_ftyp, example_mdat, example_moov};
+/// #
+/// # fn main() -> Result<(), mp4san::Error> {
+/// #     futures_util::FutureExt::now_or_never(run()).unwrap()
+/// # }
+/// #
+/// # async fn run() -> Result<(), mp4san::Error> {
+/// let example_input = [example_ftyp(), example_mdat(), example_moov()].concat();
+///
+/// let config = Config::builder().max_metadata_size(example_moov().len() as u64).build();
+/// let sanitized = sanitize_async_with_config(futures_util::io::Cursor::new(example_input), config).await?;
+///
+/// assert_eq!(sanitized.metadata, Some([example_ftyp(), example_moov()].concat()));
+/// assert_eq!(sanitized.data.offset, example_ftyp().len() as u64);
+/// assert_eq!(sanitized.data.len, example_mdat().len() as u64);
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// If the input cannot be parsed, or an IO error occurs, an [`Error`] is returned.
+///
+/// [`AsyncSeek`]: futures_util::io::AsyncSeek
+pub async fn sanitize_async_with_config<R: AsyncRead + AsyncSkip>(
+    input: R,
+    config: Config,
+) -> Result<SanitizedMetadata, Error> {
+    let SanitizedBoxes { metadata, data, additional_data, report } = sanitize_boxes(input, &config, false).await?;
+    let metadata = metadata.map(|metadata| {
+        let mut buf = Vec::with_capacity(metadata.encoded_len() as usize);
+        metadata.put_buf(&mut buf);
+        buf
+    });
+    Ok(SanitizedMetadata { metadata, data, additional_data, report })
+}
+
+/// Sanitize an MP4 input asynchronously from a [`tokio`] reader, with the default [`Config`].
+///
+/// This is equivalent to [`sanitize_async`], adapting the `input` from [`tokio::io::AsyncRead`] +
+/// [`tokio::io::AsyncSeek`].
+///
+/// # Examples
+///
+/// ```
+/// # use mp4san::sanitize_tokio;
+/// # use mp4san_test::{example_ftyp, example_mdat, example_moov};
+/// #
+/// # fn main() -> Result<(), mp4san::Error> {
+/// #     futures_util::FutureExt::now_or_never(run()).unwrap()
+/// # }
+/// #
+/// # async fn run() -> Result<(), mp4san::Error> {
+/// let example_input = [example_ftyp(), example_mdat(), example_moov()].concat();
+///
+/// let sanitized = sanitize_tokio(std::io::Cursor::new(example_input)).await?;
+///
+/// assert_eq!(sanitized.metadata, Some([example_ftyp(), example_moov()].concat()));
+/// assert_eq!(sanitized.data.offset, example_ftyp().len() as u64);
+/// assert_eq!(sanitized.data.len, example_mdat().len() as u64);
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// If the input cannot be parsed, or an IO error occurs, an [`Error`] is returned.
+#[cfg(feature = "tokio")]
+pub async fn sanitize_tokio<R: tokio::io::AsyncRead + tokio::io::AsyncSeek + Unpin>(
+    input: R,
+) -> Result<SanitizedMetadata, Error> {
+    sanitize_tokio_with_config(input, Config::default()).await
+}
+
+/// Sanitize an MP4 input asynchronously from a [`tokio`] reader, with the given [`Config`].
+///
+/// This is equivalent to [`sanitize_async_with_config`], adapting the `input` from [`tokio::io::AsyncRead`] +
+/// [`tokio::io::AsyncSeek`].
+///
+/// # Errors
+///
+/// If the input cannot be parsed, or an IO error occurs, an [`Error`] is returned.
+#[cfg(feature = "tokio")]
+pub async fn sanitize_tokio_with_config<R: tokio::io::AsyncRead + tokio::io::AsyncSeek + Unpin>(
+    input: R,
+    config: Config,
+) -> Result<SanitizedMetadata, Error> {
+    use tokio_util::compat::TokioAsyncReadCompatExt;
+
+    sanitize_async_with_config(SeekSkipAdapter(input.compat()), config).await
+}
+
+/// Sanitize an MP4 input, with the default [`Config`], writing the sanitized metadata to `out`.
+///
+/// Unlike [`sanitize`], the sanitized metadata is written incrementally to `out` rather than buffered in memory, and
+/// is always written, even if the original metadata did not need to be modified. The returned [`InputSpan`] points
+/// to the media data in the input, which can be concatenated to the written metadata to form a valid MP4 file.
+///
+/// The `input` must implement [`Read`] + [`Skip`], where [`Skip`] represents a subset of the [`Seek`] trait; an input
+/// stream which can be skipped forward, but not necessarily seeked to arbitrary positions.
+///
+/// ```
+/// # use mp4san_test::{example_ftyp, example_mdat, example_moov};
+/// #
+/// let example_input = [example_ftyp(), example_mdat(), example_moov()].concat();
+///
+/// let mut metadata = Vec::new();
+/// let data = mp4san::sanitize_to(std::io::Cursor::new(example_input), &mut metadata)?;
+///
+/// assert_eq!(metadata, [example_ftyp(), example_moov()].concat());
+/// assert_eq!(data.offset, example_ftyp().len() as u64);
+/// assert_eq!(data.len, example_mdat().len() as u64);
+/// #
+/// # Ok::<(), mp4san::Error>(())
+/// ```
+///
+/// # Errors
+///
+/// If the input cannot be parsed, or an IO error occurs while reading the input or writing to `out`, an [`Error`] is
+/// returned. Some of the sanitized metadata may have already been written to `out` if writing to it fails.
+///
+/// [`Seek`]: std::io::Seek
+pub fn sanitize_to<R: Read + Skip + Unpin, W: Write>(input: R, out: W) -> Result<InputSpan, Error> {
+    sanitize_to_with_config(input, out, Config::default())
+}
+
+/// Sanitize an MP4 input, with the given [`Config`], writing the sanitized metadata to `out`.
+///
+/// See [`sanitize_to`] for details.
+///
+/// # Errors
+///
+/// If the input cannot be parsed, or an IO error occurs while reading the input or writing to `out`, an [`Error`] is
+/// returned. Some of the sanitized metadata may have already been written to `out` if writing to it fails.
+///
+/// As only a single span of media data can be returned, it is an error for the media data to be discontiguous, even if
+/// [`Config::allow_discontiguous_mdat`] is set.
+pub fn sanitize_to_with_config<R: Read + Skip + Unpin, W: Write>(
+    input: R,
+    out: W,
+    config: Config,
+) -> Result<InputSpan, Error> {
+    let SanitizedBoxes { metadata, data, additional_data, .. } =
+        sync::sanitize(input, |input| sanitize_boxes(input, &config, true))?;
+    ensure_attach!(
+        additional_data.is_empty(),
+        ParseError::UnsupportedBoxLayout,
+        "discontiguous mdat boxes",
+    );
+    if let Some(metadata) = metadata {
+        let mut writer = WriteBufMut::new(out);
+        metadata.put_buf(&mut writer);
+        writer.finish()?;
+    }
+    Ok(data)
+}
+
+/// Sanitize an MP4 input asynchronously, with the default [`Config`], returning a stream of the bytes of the entire
+/// sanitized output.
+///
+/// The stream yields the sanitized metadata, followed by the media data read from the input in chunks, so that the
+/// sanitized output can be piped to e.g. an HTTP response body without buffering it. If the original metadata did not
+/// need to be modified, the stream yields the prefix of the input ending with the media data.
+///
+/// Unlike [`sanitize_async`], the `input` must implement [`AsyncSeek`], as the media data must be read again after the
+/// metadata following it has been sanitized.
+///
+/// # Examples
+///
+/// ```
+/// # use futures_util::TryStreamExt;
+/// # use mp4san::sanitize_async_stream;
+/// # use mp4san_test::{example_ftyp, example_mdat, example_moov};
+/// #
+/// # fn main() -> Result<(), mp4san::Error> {
+/// #     futures_util::FutureExt::now_or_never(run()).unwrap()
+/// # }
+/// #
+/// # async fn run() -> Result<(), mp4san::Error> {
+/// let example_input = [example_ftyp(), example_mdat(), example_moov()].concat();
+///
+/// let stream = sanitize_async_stream(futures_util::io::Cursor::new(example_input));
+/// let output: Vec<u8> = stream.map_ok(Vec::from).try_concat().await?;
+///
+/// assert_eq!(output, [example_ftyp(), example_moov(), example_mdat()].concat());
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// If the input cannot be parsed, or an IO error occurs, an [`Error`] is yielded and the stream ends.
+///
+/// [`AsyncSeek`]: futures_util::io::AsyncSeek
+pub fn sanitize_async_stream<R: AsyncRead + AsyncSeek + Unpin>(input: R) -> impl Stream<Item = Result<Bytes, Error>> {
+    sanitize_async_stream_with_config(input, Config::default())
+}
+
+/// Sanitize an MP4 input asynchronously, with the given [`Config`], returning a stream of the bytes of the entire
+/// sanitized output.
+///
+/// See [`sanitize_async_stream`] for details.
+pub fn sanitize_async_stream_with_config<R: AsyncRead + AsyncSeek + Unpin>(
+    input: R,
+    config: Config,
+) -> impl Stream<Item = Result<Bytes, Error>> {
+    use futures_util::{AsyncReadExt, AsyncSeekExt};
+
+    enum State<R> {
+        Sanitize(R, Config),
+        Data(R, u64, vec::IntoIter<InputSpan>),
+    }
+
+    stream::try_unfold(State::Sanitize(input, config), |state| async move {
+        let (mut input, mut remaining, mut additional_data) = match state {
+            State::Sanitize(mut input, config) => {
+                let sanitized = sanitize_async_with_config(SeekSkipAdapter(&mut input), config).await?;
+                let additional_data = sanitized.additional_data.into_iter();
+                let (metadata, data) = match sanitized.metadata {
+                    Some(metadata) => (metadata, sanitized.data),
+                    None => {
+                        let data_end = sanitized.data.offset + sanitized.data.len;
+                        (vec![], InputSpan { offset: 0, len: data_end })
+                    }
+                };
+                input.seek(io::SeekFrom::Start(data.offset)).await?;
+                if !metadata.is_empty() {
+                    return Ok(Some((metadata.into(), State::Data(input, data.len, additional_data))));
+                }
+                (input, data.len, additional_data)
+            }
+            State::Data(input, remaining, additional_data) => (input, remaining, additional_data),
+        };
+        while remaining == 0 {
+            let Some(data) = additional_data.next() else {
+                return Ok(None);
+            };
+            input.seek(io::SeekFrom::Start(data.offset)).await?;
+            remaining = data.len;
+        }
+        let mut chunk = BytesMut::zeroed(remaining.min(STREAM_CHUNK_SIZE) as usize);
+        input
+            .read_exact(&mut chunk)
+            .await
+            .map_eof(|_| Error::Parse(report_attach!(ParseError::TruncatedBox, WhileParsingBox(BoxType::MDAT))))?;
+        let remaining = remaining - chunk.len() as u64;
+        Ok(Some((chunk.freeze(), State::Data(input, remaining, additional_data))))
+    })
+}
+
+/// Sanitize an MP4 input, returning the sanitized metadata boxes without serializing them.
+///
+/// If `always_rebuild` is `false`, no metadata boxes are returned if the original metadata did not need to be
+/// modified.
+async fn sanitize_boxes<R: AsyncRead + AsyncSkip>(
+    input: R,
+    config: &Config,
+    always_rebuild: bool,
+) -> Result<SanitizedBoxes, Error> {
+    let mut input = read_input(input, config, None).await?;
+    let report = input_report(&mut input, config)?;
+    sanitize_input_boxes(input, report, config, always_rebuild)
+}
+
+/// Sanitize the boxes read from an MP4 input, returning the sanitized metadata boxes without serializing them.
+///
+/// See [`sanitize_boxes`] for details.
+fn sanitize_input_boxes(
+    input: InputBoxes,
+    mut report: SanitizeReport,
+    config: &Config,
+    always_rebuild: bool,
+) -> Result<SanitizedBoxes, Error> {
+    let InputBoxes {
+        ftyp,
+        pdin,
+        meta,
+        moov,
+        sidx,
+        leading_data,
+        data,
+        additional_data,
+        mdat_payload_offset,
+        fragment_base_data_offsets,
+        ..
+    } = input;
+
+    // A media segment's movie fragments are passed through verbatim along with the media data, leaving only its
+    // segment type box (`styp`) and any other leading boxes to be carried through as read.
+    let Some((mut moov, moov_offset)) = moov else {
+        if !always_rebuild
+            && !config.faststart
+            && additional_data.is_empty()
+            && report.stripped_metadata_boxes.is_empty()
+            && leading_data.is_none()
+        {
+            log::info!("media segment: nothing to sanitize");
+            return Ok(SanitizedBoxes { metadata: None, data, additional_data, report });
+        }
+        let metadata = MetadataBoxes { ftyp, pdin, meta, moov: None, sidx: None, pad_size: 0 };
+        if metadata.encoded_len() != data.offset || !additional_data.is_empty() {
+            ensure_attach!(
+                !fragment_base_data_offsets,
+                ParseError::UnsupportedBoxLayout,
+                "movie fragment with explicit base data offset would be displaced",
+            );
+        }
+        let (data, additional_data) = rebase_past_leading_data(leading_data, data, additional_data);
+        return Ok(SanitizedBoxes { metadata: Some(metadata), data, additional_data, report });
+    };
+
+    if config.drop_disabled_tracks {
+        let dropped_trak_count = moov.data.parse()?.drop_disabled_traks()?;
+        if dropped_trak_count != 0 {
+            log::info!("moov: dropped {dropped_trak_count} disabled traks");
+        }
+        report
+            .dropped_boxes
+            .extend(iter::repeat(BoxType::TRAK).take(dropped_trak_count));
+    }
+
+    if !config.drop_handler_types.is_empty() {
+        let dropped_trak_count = moov
+            .data
+            .parse()?
+            .drop_traks_by_handler_type(&config.drop_handler_types)?;
+        if dropped_trak_count != 0 {
+            log::info!("moov: dropped {dropped_trak_count} traks by handler type");
+        }
+        report
+            .dropped_boxes
+            .extend(iter::repeat(BoxType::TRAK).take(dropped_trak_count));
+    }
+
+    if config.strip_all_metadata {
+        let stripped = moov.data.parse()?.strip_metadata_boxes()?;
+        if !stripped.is_empty() {
+            log::info!("moov: stripped {} user data and metadata boxes", stripped.len());
+        }
+        report.stripped_metadata_boxes.extend(stripped);
+    }
+
+    if config.minimize_moov {
+        let removed = moov.data.parse()?.minimize()?;
+        if !removed.is_empty() {
+            log::info!("moov: removed {} optional boxes", removed.len());
+        }
+        report.dropped_boxes.extend(removed);
+    }
+    for box_type in &report.dropped_boxes {
+        log::warn!("moov: dropped {box_type} box");
+    }
+
+    // Return early if there's nothing to sanitize. Other than dropping tracks and minimizing the moov, the only thing the
+    // sanitizer does currently is move the moov to before the mdat to make the mp4 streamable, so return if we don't
+    // need to do that.
+    let mdat_at_target = match (config.target_mdat_offset, config.align_mdat_data) {
+        (Some(target), _) => target == data.offset,
+        (None, Some(align)) => mdat_payload_offset.checked_rem(align).unwrap_or(0) == 0,
+        (None, None) => true,
+    };
+    if !always_rebuild
+        && !config.faststart
+        && moov_offset < data.offset
+        && report.dropped_boxes.is_empty()
+        && additional_data.is_empty()
+        && report.stripped_metadata_boxes.is_empty()
+        && leading_data.is_none()
+        && mdat_at_target
+    {
+        log::info!("metadata: nothing to sanitize");
+        return Ok(SanitizedBoxes { metadata: None, data, additional_data, report });
+    }
+
+    // Make sure none of the metadata boxes use BoxSize::UntilEof, as we want the caller to be able to concatenate movie
+    // data to the end of the metadata.
+    for header in [ftyp.calculated_header(), moov.calculated_header()] {
+        if header.box_size().is_none() {
+            report.explicitly_sized_boxes.push(header.box_type());
+        }
+    }
+    //
+    // The ftyp box contains no offsets, so it is otherwise carried through exactly as read, including its header, minor
+    // version, and the order of its compatible brands.
+    let ftyp = match ftyp.calculated_header().box_size() {
+        Some(_) => ftyp,
+        None => Mp4Box::with_data(ftyp.data)?,
+    };
+    // The pdin box contains no offsets either, so it is carried through verbatim, following the ftyp box.
+    let pdin = pdin.map(|pdin| Mp4Box::with_data(pdin.data)).transpose()?;
+    let meta = meta.map(|meta| Mp4Box::with_data(meta.data)).transpose()?;
+    let mut moov = Mp4Box::with_data(moov.data)?;
+    let sidx = match sidx {
+        Some((sidx, anchor)) => Some((Mp4Box::with_data(sidx.data)?, anchor)),
+        None => None,
+    };
+
+    if !config.preserve_moov_free_boxes {
+        let stripped_len = moov.data.parse()?.strip_free_boxes()?;
+        if stripped_len != 0 {
+            log::info!("moov: stripped 0x{stripped_len:08x} bytes of free boxes");
+        }
+    }
+
+    // Add free boxes to pad, if they will fit, if the mdat box would move backward. If they won't fit, or if the mdat
+    // box would move forward, adjust mdat offsets in stco/co64 the amount it was displaced. If a target mdat offset is
+    // given, pad up to it and displace the mdat to it. Padding which won't fit in a single free box is split across
+    // several, up to the maximum padding size.
+    //
+    // Chunk offsets which would be displaced past 4 GiB don't fit in a stco box, so it is promoted to a co64 box, which
+    // grows the moov box, and the metadata is laid out again.
+    let (mut metadata_len, mut pad_size, mut mdat_displacement);
+    loop {
+        metadata_len = ftyp.encoded_len()
+            + pdin.as_ref().map_or(0, Mp4Box::encoded_len)
+            + meta.as_ref().map_or(0, Mp4Box::encoded_len)
+            + moov.encoded_len()
+            + sidx.as_ref().map_or(0, |(sidx, _)| sidx.encoded_len());
+        (pad_size, mdat_displacement) = mdat_layout(config, data, mdat_payload_offset, metadata_len)?;
+        if !promote_overflowing_stcos(moov.data.parse()?, mdat_displacement)? {
+            break;
+        }
+    }
+    if pad_size != 0 {
+        log::info!("metadata: 0x{metadata_len:08x} bytes; adding padding of 0x{pad_size:08x} bytes");
+    } else if mdat_displacement == 0 {
+        log::info!("metadata: 0x{metadata_len:08x} bytes");
+    }
+
+    if mdat_displacement != 0 {
+        log::info!("metadata: 0x{metadata_len:08x} bytes; displacing chunk offsets by 0x{mdat_displacement:08x}");
+        report.mdat_displacement = Some(mdat_displacement);
+    }
+    if !additional_data.is_empty() {
+        log::info!(
+            "media data: concatenating {} discontiguous spans",
+            additional_data.len() + 1
+        );
+    }
+
+    if mdat_displacement != 0 || !additional_data.is_empty() {
+        // Movie fragments are passed through verbatim along with the media data, so absolute offsets within them
+        // can't be adjusted.
+        ensure_attach!(
+            !fragment_base_data_offsets,
+            ParseError::UnsupportedBoxLayout,
+            "movie fragment with explicit base data offset would be displaced",
+        );
+
+        for trak in &mut moov.data.parse()?.traks() {
+            let trak = trak?;
+            let track_id = trak.tkhd_mut()?.track_id();
+            let stbl = trak.stbl_mut()?;
+            for saio in stbl.saios() {
+                let saio = saio?;
+                ensure_attach!(
+                    additional_data.is_empty(),
+                    ParseError::UnsupportedBoxLayout,
+                    "sample auxiliary information with discontiguous mdat boxes",
+                    WhileParsingBox(BoxType::SAIO),
+                );
+                saio.displace_offsets(mdat_displacement)?;
+            }
+            let displace = |offset: u64| {
+                let displacement = data_displacement(data, &additional_data, mdat_displacement, offset)?;
+                checked_add_signed(offset, displacement)
+            };
+            let co = stbl.co_mut()?;
+            if let StblCoMut::Stco(stco) = co {
+                for (index, mut entry) in stco.entries_mut().enumerate() {
+                    let value = entry.get().unwrap_or_else(|_| unreachable!());
+                    let displaced = displace(value.into()).and_then(|displaced| u32::try_from(displaced).ok());
+                    entry.set(displaced.ok_or_else(|| {
+                        report_attach!(
+                            ParseError::InvalidInput,
+                            format!(
+                                "chunk offset 0x{value:08x} of track {track_id} entry {index} overflows when displaced"
+                            ),
+                            WhileParsingBox(BoxType::STCO),
+                        )
+                    })?);
+                }
+            } else if let StblCoMut::Co64(co64) = co {
+                for (index, mut entry) in co64.entries_mut().enumerate() {
+                    let value = entry.get().unwrap_or_else(|_| unreachable!());
+                    entry.set(displace(value).ok_or_else(|| {
+                        report_attach!(
+                            ParseError::InvalidInput,
+                            format!("chunk offset 0x{value:016x} of track {track_id} entry {index} overflows when displaced"),
+                            WhileParsingBox(BoxType::CO64),
+                        )
+                    })?);
+                }
+            }
+        }
+    }
+
+    if pad_size != 0 {
+        report.padding_size = Some(pad_size);
+    }
+
+    // The segment index box references the media data relative to its end, which in the sanitized metadata is followed
+    // only by any padding.
+    let sidx = match sidx {
+        Some((mut sidx, anchor)) => {
+            let sidx_data = sidx.data.parse()?;
+            let first_offset = sidx_data.first_offset();
+            let referenced_offset = (anchor.checked_add(first_offset))
+                .and_then(|referenced| referenced.checked_sub(data.offset))
+                .filter(|&referenced_offset| referenced_offset < data.len)
+                .ok_or_else(|| {
+                    report_attach!(
+                        ParseError::InvalidInput,
+                        format!("first offset 0x{first_offset:08x} does not reference the media data"),
+                        WhileParsingBox(BoxType::SIDX),
+                    )
+                })?;
+            let sanitized_first_offset = pad_size + referenced_offset;
+            if sanitized_first_offset != first_offset {
+                log::info!("sidx: adjusting first offset from 0x{first_offset:08x} to 0x{sanitized_first_offset:08x}");
+            }
+            sidx_data.set_first_offset(sanitized_first_offset)?;
+            Some(sidx)
+        }
+        None => None,
+    };
+
+    let (data, additional_data) = rebase_past_leading_data(leading_data, data, additional_data);
+
+    let metadata = MetadataBoxes { ftyp, pdin, meta, moov: Some(moov), sidx, pad_size };
+    Ok(SanitizedBoxes { metadata: Some(metadata), data, additional_data, report })
+}
+
+/// Probe an MP4 input for information about its tracks, without sanitizing it.
+///
+/// The returned [`MediaInfo`] can be used, for instance, to find the span of media data containing a track's first
+/// keyframe in order to generate a thumbnail. The input is parsed as far as is required to sanitize it, but no
+/// sanitized metadata is produced.
+///
+/// The `input` must implement [`Read`] + [`Skip`], where [`Skip`] represents a subset of the [`Seek`] trait; an input
+/// stream which can be skipped forward, but not necessarily seeked to arbitrary positions.
+///
+/// ```
+/// # use mp4san_test::{example_ftyp, example_mdat, example_moov};
+/// #
+/// let example_input = [example_ftyp(), example_mdat(), example_moov()].concat();
+///
+/// let info = mp4san::probe(std::io::Cursor::new(example_input))?;
+///
+/// assert_eq!(info.tracks().len(), 1);
+/// #
+/// # Ok::<(), mp4san::Error>(())
+/// ```
+///
+/// # Errors
+///
+/// If the input cannot be parsed, or an IO error occurs, an [`Error`] is returned.
+///
+/// [`Seek`]: std::io::Seek
+pub fn probe<R: Read + Skip + Unpin>(input: R) -> Result<MediaInfo, Error> {
+    sync::sanitize(input, probe_async)
+}
+
+/// Probe an MP4 input asynchronously for information about its tracks, without sanitizing it.
+///
+/// The `input` must implement [`AsyncRead`] + [`AsyncSkip`], where [`AsyncSkip`] represents a subset of the
+/// [`AsyncSeek`] trait; an input stream which can be skipped forward, but not necessarily seeked to arbitrary
+/// positions.
+///
+/// See [`probe`] for details.
+///
+/// # Errors
+///
+/// If the input cannot be parsed, or an IO error occurs, an [`Error`] is returned.
+///
+/// [`AsyncSeek`]: futures_util::io::AsyncSeek
+pub async fn probe_async<R: AsyncRead + AsyncSkip>(input: R) -> Result<MediaInfo, Error> {
+    let InputBoxes { moov, .. } = read_input(input, &Config::default(), None).await?;
+    let Some((mut moov, _)) = moov else {
+        bail_attach!(ParseError::MissingRequiredBox(BoxType::MOOV));
+    };
+    Ok(MediaInfo::new(moov.data.parse()?)?)
+}
+
+/// Parse the metadata of an MP4 input for read-only inspection, without sanitizing it.
+///
+/// The input is read and validated as far as is required to sanitize it, but no offsets are adjusted and no sanitized
+/// metadata is produced. The returned [`ParsedMp4`] gives access to the parsed file type header (`ftyp`) and movie box
+/// (`moov`), whose tracks, sample tables, and sample descriptions can be inspected through the [`parse`](crate::parse) API.
+///
+/// The `input` must implement [`Read`] + [`Skip`], where [`Skip`] represents a subset of the [`Seek`] trait; an input
+/// stream which can be skipped forward, but not necessarily seeked to arbitrary positions.
+///
+/// ```
+/// # use mp4san_test::{example_ftyp, example_mdat, example_moov};
+/// #
+/// let example_input = [example_ftyp(), example_mdat(), example_moov()].concat();
+///
+/// let mut parsed = mp4san::parse_metadata(std::io::Cursor::new(example_input))?;
+///
+/// assert_eq!(parsed.moov.traks().count(), 1);
+/// assert_eq!(parsed.data.len, example_mdat().len() as u64);
+/// #
+/// # Ok::<(), mp4san::Error>(())
+/// ```
+///
+/// # Errors
+///
+/// If the input cannot be parsed, or an IO error occurs, an [`Error`] is returned.
+///
+/// [`Seek`]: std::io::Seek
+pub fn parse_metadata<R: Read + Skip + Unpin>(input: R) -> Result<ParsedMp4, Error> {
+    sync::sanitize(input, parse_metadata_async)
+}
+
+/// Parse the metadata of an MP4 input asynchronously for read-only inspection, without sanitizing it.
+///
+/// The `input` must implement [`AsyncRead`] + [`AsyncSkip`], where [`AsyncSkip`] represents a subset of the
+/// [`AsyncSeek`] trait; an input stream which can be skipped forward, but not necessarily seeked to arbitrary
+/// positions.
+///
+/// See [`parse_metadata`] for details.
+///
+/// # Errors
+///
+/// If the input cannot be parsed, or an IO error occurs, an [`Error`] is returned.
+///
+/// [`AsyncSeek`]: futures_util::io::AsyncSeek
+pub async fn parse_metadata_async<R: AsyncRead + AsyncSkip>(input: R) -> Result<ParsedMp4, Error> {
+    let InputBoxes { ftyp, moov, data, .. } = read_input(input, &Config::default(), None).await?;
+    let Some((moov, _)) = moov else {
+        bail_attach!(ParseError::MissingRequiredBox(BoxType::MOOV));
+    };
+    Ok(ParsedMp4 { ftyp: *ftyp.data.into_parsed()?, moov: *moov.data.into_parsed()?, data })
+}
+
+/// Check whether an MP4 input is already sanitized, i.e. whether [`sanitize`] would leave its metadata unmodified.
+///
+/// Returns `true` iff the sanitized metadata would be byte-identical to the input's metadata and no chunk offsets would
+/// be displaced, in which case the input can be stored as-is rather than reconstructed. The input is parsed and
+/// validated exactly as by [`sanitize`].
+///
+/// The `input` must implement [`Read`] + [`Skip`], where [`Skip`] represents a subset of the [`Seek`] trait; an input
+/// stream which can be skipped forward, but not necessarily seeked to arbitrary positions.
+///
+/// ```
+/// # use mp4san_test::{example_ftyp, example_mdat, example_moov};
+/// #
+/// let faststart_input = [example_ftyp(), example_moov(), example_mdat()].concat();
+/// assert!(mp4san::is_already_sanitized(std::io::Cursor::new(faststart_input))?);
+///
+/// let example_input = [example_ftyp(), example_mdat(), example_moov()].concat();
+/// assert!(!mp4san::is_already_sanitized(std::io::Cursor::new(example_input))?);
+/// #
+/// # Ok::<(), mp4san::Error>(())
+/// ```
+///
+/// # Errors
+///
+/// If the input cannot be parsed, or an IO error occurs, an [`Error`] is returned.
+///
+/// [`Seek`]: std::io::Seek
+pub fn is_already_sanitized<R: Read + Skip + Unpin>(input: R) -> Result<bool, Error> {
+    sync::sanitize(input, is_already_sanitized_async)
+}
+
+/// Check asynchronously whether an MP4 input is already sanitized, i.e. whether [`sanitize_async`] would leave its
+/// metadata unmodified.
+///
+/// The `input` must implement [`AsyncRead`] + [`AsyncSkip`], where [`AsyncSkip`] represents a subset of the
+/// [`AsyncSeek`] trait; an input stream which can be skipped forward, but not necessarily seeked to arbitrary
+/// positions.
+///
+/// See [`is_already_sanitized`] for details.
+///
+/// # Errors
+///
+/// If the input cannot be parsed, or an IO error occurs, an [`Error`] is returned.
+///
+/// [`AsyncSeek`]: futures_util::io::AsyncSeek
+pub async fn is_already_sanitized_async<R: AsyncRead + AsyncSkip>(input: R) -> Result<bool, Error> {
+    let sanitized = sanitize_async(input).await?;
+    Ok(sanitized.metadata.is_none())
+}
+
+/// Compute the length of the sanitized metadata of an MP4 input, without serializing it.
+///
+/// This is the length of the [sanitized metadata](SanitizedMetadata::metadata) which [`sanitize_with_config`] would
+/// return with the same `config`, or, if the original metadata would not need to be modified, the offset of the media
+/// data within the input, as returned by [`SanitizedMetadata::metadata_len`]. This can be used to size buffers for the
+/// sanitized output before sanitizing it. The input is parsed and validated exactly as by [`sanitize_with_config`].
+///
+/// The `input` must implement [`Read`] + [`Skip`], where [`Skip`] represents a subset of the [`Seek`] trait; an input
+/// stream which can be skipped forward, but not necessarily seeked to arbitrary positions.
+///
+/// ```
+/// # use mp4san::Config;
+/// # use mp4san_test::{example_ftyp, example_mdat, example_moov};
+/// #
+/// let example_input = [example_ftyp(), example_mdat(), example_moov()].concat();
+///
+/// let metadata_len = mp4san::sanitized_metadata_len(std::io::Cursor::new(example_input), Config::default())?;
+///
+/// assert_eq!(metadata_len, (example_ftyp().len() + example_moov().len()) as u64);
+/// #
+/// # Ok::<(), mp4san::Error>(())
+/// ```
+///
+/// # Errors
+///
+/// If the input cannot be parsed, or an IO error occurs, an [`Error`] is returned.
+///
+/// [`Seek`]: std::io::Seek
+pub fn sanitized_metadata_len<R: Read + Skip + Unpin>(input: R, config: Config) -> Result<u64, Error> {
+    sync::sanitize(input, |input| sanitized_metadata_len_async(input, config))
+}
+
+/// Compute asynchronously the length of the sanitized metadata of an MP4 input, without serializing it.
+///
+/// The `input` must implement [`AsyncRead`] + [`AsyncSkip`], where [`AsyncSkip`] represents a subset of the
+/// [`AsyncSeek`] trait; an input stream which can be skipped forward, but not necessarily seeked to arbitrary
+/// positions.
+///
+/// See [`sanitized_metadata_len`] for details.
+///
+/// # Errors
+///
+/// If the input cannot be parsed, or an IO error occurs, an [`Error`] is returned.
+///
+/// [`AsyncSeek`]: futures_util::io::AsyncSeek
+pub async fn sanitized_metadata_len_async<R: AsyncRead + AsyncSkip>(input: R, config: Config) -> Result<u64, Error> {
+    let SanitizedBoxes { metadata, data, .. } = sanitize_boxes(input, &config, false).await?;
+    Ok(metadata.map_or(data.offset, |metadata| metadata.encoded_len()))
+}
+
+/// Estimate the cost of sanitizing an MP4 input, by reading only the headers of its top-level boxes.
+///
+/// This is much cheaper than sanitizing the input, as no box data is read, and can be used to decide whether to
+/// proceed with sanitizing the input, or to choose a [`Config`] proportional to the input.
+///
+/// The `input` must implement [`Read`] + [`Skip`], where [`Skip`] represents a subset of the [`Seek`] trait; an input
+/// stream which can be skipped forward, but not necessarily seeked to arbitrary positions.
+///
+/// ```
+/// # use mp4san_test::{example_ftyp, example_mdat, example_moov};
+/// #
+/// let example_input = [example_ftyp(), example_mdat(), example_moov()].concat();
+///
+/// let cost = mp4san::estimate_parse_cost(std::io::Cursor::new(example_input))?;
+///
+/// assert_eq!(cost.top_level_box_count, 3);
+/// assert_eq!(cost.moov_size, example_moov().len() as u64);
+/// assert_eq!(cost.mdat_size, example_mdat().len() as u64);
+/// #
+/// # Ok::<(), mp4san::Error>(())
+/// ```
+///
+/// # Errors
+///
+/// If a box header cannot be parsed, or an IO error occurs, an [`Error`] is returned.
+///
+/// [`Seek`]: std::io::Seek
+pub fn estimate_parse_cost<R: Read + Skip + Unpin>(input: R) -> Result<ParseCost, Error> {
+    sync::sanitize(input, estimate_parse_cost_async)
+}
+
+/// Estimate the cost of sanitizing an MP4 input asynchronously, by reading only the headers of its top-level boxes.
+///
+/// The `input` must implement [`AsyncRead`] + [`AsyncSkip`], where [`AsyncSkip`] represents a subset of the
+/// [`AsyncSeek`] trait; an input stream which can be skipped forward, but not necessarily seeked to arbitrary
+/// positions.
+///
+/// See [`estimate_parse_cost`] for details.
+///
+/// # Errors
+///
+/// If a box header cannot be parsed, or an IO error occurs, an [`Error`] is returned.
+///
+/// [`AsyncSeek`]: futures_util::io::AsyncSeek
+pub async fn estimate_parse_cost_async<R: AsyncRead + AsyncSkip>(input: R) -> Result<ParseCost, Error> {
+    let reader = BufReader::with_capacity(BoxHeader::MAX_SIZE as usize, input);
+    pin_mut!(reader);
+
+    let mut cost = ParseCost::default();
+    while !reader.as_mut().fill_buf().await?.is_empty() {
+        let header = BoxHeader::read(&mut reader)
+            .await
+            .map_eof(|_| Error::Parse(report_attach!(ParseError::TruncatedBox, "while parsing box header")))?;
+        let lazy_box = AnyMp4Box::read_header_only(reader.as_mut(), header).await?;
+        let box_size = lazy_box.encoded_len();
+        lazy_box.skip().await?;
+
+        cost.top_level_box_count += 1;
+        match header.box_type() {
+            BoxType::MOOV => cost.moov_size = cost.moov_size.saturating_add(box_size),
+            BoxType::MDAT => cost.mdat_size = cost.mdat_size.saturating_add(box_size),
+            _ => {}
+        }
+    }
+    Ok(cost)
+}
+
+/// Read the file type header (`ftyp`) of an MP4 input.
+///
+/// Only the input up to the end of the file type header is read. The header's brands are not checked; in particular,
+/// the input may not contain the [compatible brand](COMPATIBLE_BRAND) required by the sanitizer.
+///
+/// The `input` must implement [`Read`] + [`Skip`], where [`Skip`] represents a subset of the [`Seek`] trait; an input
+/// stream which can be skipped forward, but not necessarily seeked to arbitrary positions.
+///
+/// ```
+/// # use mp4san_test::{example_ftyp, example_mdat, example_moov};
+/// #
+/// let example_input = [example_ftyp(), example_mdat(), example_moov()].concat();
+///
+/// let ftyp = mp4san::read_ftyp(std::io::Cursor::new(example_input))?;
+///
+/// assert!(ftyp.compatible_brands().any(|brand| brand == mp4san::COMPATIBLE_BRAND));
+/// #
+/// # Ok::<(), mp4san::Error>(())
+/// ```
+///
+/// # Errors
+///
+/// If the file type header is not the first significant box in the input or cannot be parsed, or an IO error occurs,
+/// an [`Error`] is returned.
+///
+/// [`Seek`]: std::io::Seek
+pub fn read_ftyp<R: Read + Skip + Unpin>(input: R) -> Result<FtypBox, Error> {
+    sync::sanitize(input, read_ftyp_async)
+}
+
+/// Read the file type header (`ftyp`) of an MP4 input asynchronously.
+///
+/// The `input` must implement [`AsyncRead`] + [`AsyncSkip`], where [`AsyncSkip`] represents a subset of the
+/// [`AsyncSeek`] trait; an input stream which can be skipped forward, but not necessarily seeked to arbitrary
+/// positions.
+///
+/// See [`read_ftyp`] for details.
+///
+/// # Errors
+///
+/// If the file type header is not the first significant box in the input or cannot be parsed, or an IO error occurs,
+/// an [`Error`] is returned.
+///
+/// [`AsyncSeek`]: futures_util::io::AsyncSeek
+pub async fn read_ftyp_async<R: AsyncRead + AsyncSkip>(input: R) -> Result<FtypBox, Error> {
+    let reader = BufReader::with_capacity(BoxHeader::MAX_SIZE as usize, input);
+    pin_mut!(reader);
+
+    while !reader.as_mut().fill_buf().await?.is_empty() {
+        let header = BoxHeader::read(&mut reader)
+            .await
+            .map_eof(|_| Error::Parse(report_attach!(ParseError::TruncatedBox, "while parsing box header")))?;
+
+        match header.box_type() {
+            BoxType::FREE | BoxType::SKIP | BoxType::WIDE => {
+                AnyMp4Box::read_header_only(reader.as_mut(), header)
+                    .await?
+                    .skip()
+                    .await?;
+            }
+            BoxType::FTYP => {
+                let mut ftyp: Mp4Box<FtypBox> = AnyMp4Box::read_header_only(reader.as_mut(), header)
+                    .await?
+                    .read_data(MAX_FTYP_SIZE)
+                    .await?;
+                return Ok(ftyp.data.parse()?.clone());
+            }
+            _ => bail_attach!(ParseError::InvalidBoxLayout, "ftyp is not the first significant box"),
+        }
+    }
+    bail_attach!(ParseError::MissingRequiredBox(BoxType::FTYP));
+}
+
+/// Validate an MP4 input with the default [`Config`], without producing any sanitized output.
+///
+/// The input is parsed, validated, and its metadata sanitized exactly as by [`sanitize`], including any adjustment of
+/// chunk offsets, but the sanitized metadata is discarded rather than serialized. This is cheaper than [`sanitize`] for
+/// checking whether inputs would sanitize successfully, such as when scanning many files.
+///
+/// The `input` must implement [`Read`] + [`Skip`], where [`Skip`] represents a subset of the [`Seek`] trait; an input
+/// stream which can be skipped forward, but not necessarily seeked to arbitrary positions.
+///
+/// ```
+/// # use mp4san_test::{example_ftyp, example_mdat, example_moov};
+/// #
+/// let example_input = [example_ftyp(), example_mdat(), example_moov()].concat();
+///
+/// mp4san::validate(std::io::Cursor::new(example_input))?;
+/// #
+/// # Ok::<(), mp4san::Error>(())
+/// ```
+///
+/// # Errors
+///
+/// If the input cannot be parsed, or an IO error occurs, the same [`Error`] is returned as by [`sanitize`].
+///
+/// [`Seek`]: std::io::Seek
+pub fn validate<R: Read + Skip + Unpin>(input: R) -> Result<(), Error> {
+    sync::sanitize(input, validate_async)
+}
+
+/// Validate an MP4 input asynchronously, without producing any sanitized output.
+///
+/// The `input` must implement [`AsyncRead`] + [`AsyncSkip`], where [`AsyncSkip`] represents a subset of the
+/// [`AsyncSeek`] trait; an input stream which can be skipped forward, but not necessarily seeked to arbitrary
+/// positions.
+///
+/// See [`validate`] for details.
+///
+/// # Errors
+///
+/// If the input cannot be parsed, or an IO error occurs, the same [`Error`] is returned as by [`sanitize_async`].
+///
+/// [`AsyncSeek`]: futures_util::io::AsyncSeek
+pub async fn validate_async<R: AsyncRead + AsyncSkip>(input: R) -> Result<(), Error> {
+    sanitize_boxes(input, &Config::default(), false).await?;
+    Ok(())
+}
+
+/// Validate an MP4 input with the default [`Config`], collecting every validation error rather than only the first.
+///
+/// This is intended for fuzzing and diagnostics, to extract as much information as possible from a single run. Errors
+/// confined to a single track, such as a track missing a required box or whose samples lie outside the media data
+/// (`mdat`), are recoverable: they are collected, and validation continues with the remaining tracks. Any other error,
+/// such as a truncated box or an invalid box layout, is unrecoverable: validation stops, and the error is returned last.
+///
+/// As with [`validate`], once the input has been read through to the end, the checks made while laying out the sanitized
+/// metadata, such as whether displaced chunk offsets overflow, are also run. Any error from these is unrecoverable.
+///
+/// The returned [`SanitizeReport`] is empty unless every check which is run passes or is recoverable.
+///
+/// The `input` must implement [`Read`] + [`Skip`], where [`Skip`] represents a subset of the [`Seek`] trait; an input
+/// stream which can be skipped forward, but not necessarily seeked to arbitrary positions.
+///
+/// ```
+/// # use mp4san_test::{example_ftyp, example_mdat, example_moov};
+/// #
+/// let example_input = [example_ftyp(), example_mdat(), example_moov()].concat();
+///
+/// let (_report, errors) = mp4san::validate_collect_all(std::io::Cursor::new(example_input));
+///
+/// assert!(errors.is_empty());
+/// ```
+///
+/// [`Seek`]: std::io::Seek
+pub fn validate_collect_all<R: Read + Skip + Unpin>(input: R) -> (SanitizeReport, Vec<Error>) {
+    sync::sanitize(input, validate_collect_all_async)
+}
+
+/// Validate an MP4 input asynchronously, collecting every validation error rather than only the first.
+///
+/// The `input` must implement [`AsyncRead`] + [`AsyncSkip`], where [`AsyncSkip`] represents a subset of the
+/// [`AsyncSeek`] trait; an input stream which can be skipped forward, but not necessarily seeked to arbitrary
+/// positions.
+///
+/// See [`validate_collect_all`] for details.
+///
+/// [`AsyncSeek`]: futures_util::io::AsyncSeek
+pub async fn validate_collect_all_async<R: AsyncRead + AsyncSkip>(input: R) -> (SanitizeReport, Vec<Error>) {
+    let config = Config::default();
+    let mut errors = Vec::new();
+    let result = read_input(input, &config, Some(&mut errors)).await;
+    let mut errors: Vec<Error> = errors.into_iter().map(Error::Parse).collect();
+    let sanitized = result.and_then(|mut input| {
+        let report = input_report(&mut input, &config)?;
+        sanitize_input_boxes(input, report, &config, false)
+    });
+    match sanitized {
+        Ok(sanitized) => (sanitized.report, errors),
+        Err(err) => {
+            errors.push(err);
+            (SanitizeReport::default(), errors)
+        }
+    }
+}
+
+//
+// SanitizedMetadata impls
+//
+
+impl SanitizedMetadata {
+    /// Map a range of bytes of the sanitized output to the sources its bytes should be read from.
+    ///
+    /// The sanitized output consists of the [sanitized metadata](Self::metadata) followed by the [media
+    /// data](Self::data) and any [additional media data](Self::additional_data) read from the input. The returned sources
+    /// are in output order, and together cover the requested range, truncated to the length of the output. This is
+    /// useful, for example, when serving HTTP range requests for the sanitized output.
+    ///
+    /// If the original metadata did not need to be modified, the sanitized output is the prefix of the input ending with
+    /// the [media data](Self::data), and so the range is mapped to the input, truncated to the end of the media data.
+    ///
+    /// ```
+    /// # use mp4san::{InputSpan, OutputSource, SanitizedMetadata};
+    /// #
+    /// let sanitized = SanitizedMetadata {
+    ///     metadata: Some(vec![0; 100]),
+    ///     data: InputSpan { offset: 500, len: 1000 },
+    ///     additional_data: vec![],
+    ///     report: Default::default(),
+    /// };
+    ///
+    /// assert_eq!(
+    ///     sanitized.map_output_range(90..110),
+    ///     [OutputSource::Metadata(90..100), OutputSource::Input(500..510)],
+    /// );
+    /// ```
+    pub fn map_output_range(&self, range: Range<u64>) -> Vec<OutputSource> {
+        let Some(metadata) = &self.metadata else {
+            let output_len = self.data.offset + self.data.len;
+            let range = range.start.min(output_len)..range.end.min(output_len);
+            if range.is_empty() {
+                return vec![];
+            }
+            return vec![OutputSource::Input(range)];
+        };
+
+        let metadata_len = metadata.len() as u64;
+        let mut sources = Vec::with_capacity(2 + self.additional_data.len());
+
+        let metadata_range = range.start.min(metadata_len)..range.end.min(metadata_len);
+        if !metadata_range.is_empty() {
+            sources.push(OutputSource::Metadata(metadata_range));
+        }
+
+        let mut span_output_offset = metadata_len;
+        for span in iter::once(&self.data).chain(&self.additional_data) {
+            let data_start = range.start.saturating_sub(span_output_offset).min(span.len);
+            let data_end = range.end.saturating_sub(span_output_offset).min(span.len);
+            if data_start < data_end {
+                sources.push(OutputSource::Input(span.offset + data_start..span.offset + data_end));
+            }
+            span_output_offset += span.len;
+        }
+
+        sources
+    }
+
+    /// Return a [`LayoutMap`] describing the sources of every byte of the sanitized output.
+    ///
+    /// If the original metadata did not need to be modified, the sanitized output is the prefix of the input ending
+    /// with the [media data](Self::data).
+    ///
+    /// ```
+    /// # use mp4san::{InputSpan, LayoutMap, OutputSource, SanitizedMetadata};
+    /// #
+    /// let sanitized = SanitizedMetadata {
+    ///     metadata: Some(vec![0; 100]),
+    ///     data: InputSpan { offset: 500, len: 1000 },
+    ///     additional_data: vec![],
+    ///     report: Default::default(),
+    /// };
+    ///
+    /// assert_eq!(
+    ///     sanitized.layout_map(),
+    ///     LayoutMap {
+    ///         sources: vec![OutputSource::Metadata(0..100), OutputSource::Input(500..1500)],
+    ///         len: 1100,
+    ///     },
+    /// );
+    /// ```
+    pub fn layout_map(&self) -> LayoutMap {
+        let len = match &self.metadata {
+            Some(metadata) => {
+                let additional_data_len: u64 = self.additional_data.iter().map(|span| span.len).sum();
+                metadata.len() as u64 + self.data.len + additional_data_len
+            }
+            None => self.data.offset + self.data.len,
+        };
+        LayoutMap { sources: self.map_output_range(0..len), len }
+    }
+
+    /// The length of the sanitized output preceding the [media data](Self::data).
+    ///
+    /// This is the length of the [sanitized metadata](Self::metadata), or, if the original metadata did not need to be
+    /// modified, the offset of the media data within the input.
+    ///
+    /// ```
+    /// # use mp4san::{InputSpan, SanitizedMetadata};
+    /// #
+    /// let sanitized = SanitizedMetadata {
+    ///     metadata: Some(vec![0; 100]),
+    ///     data: InputSpan { offset: 500, len: 1000 },
+    ///     additional_data: vec![],
+    ///     report: Default::default(),
+    /// };
+    ///
+    /// assert_eq!(sanitized.metadata_len(), 100);
+    /// ```
+    pub fn metadata_len(&self) -> u64 {
+        self.metadata
+            .as_ref()
+            .map_or(self.data.offset, |metadata| metadata.len() as u64)
+    }
+
+    /// Reconstruct the entire sanitized output from an input held in memory.
+    ///
+    /// The sanitized output consists of the [sanitized metadata](Self::metadata) followed by the [media
+    /// data](Self::data) and any [additional media data](Self::additional_data) read from `input`. If the original
+    /// metadata did not need to be modified, the sanitized output is the prefix of `input` ending with the media data.
+    ///
+    /// ```
+    /// # use mp4san_test::{example_ftyp, example_mdat, example_moov};
+    /// #
+    /// let example_input = [example_ftyp(), example_mdat(), example_moov()].concat();
+    ///
+    /// let sanitized = mp4san::sanitize(std::io::Cursor::new(&example_input))?;
+    ///
+    /// assert_eq!(sanitized.reconstruct(&example_input), [example_ftyp(), example_moov(), example_mdat()].concat());
+    /// #
+    /// # Ok::<(), mp4san::Error>(())
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `input` is too short to contain the [media data](Self::data), e.g. if it is not the input which was
+    /// sanitized.
+    pub fn reconstruct(&self, input: &[u8]) -> Vec<u8> {
+        let span_end = |span: &InputSpan| {
+            (span.offset.checked_add(span.len))
+                .and_then(|span_end| usize::try_from(span_end).ok())
+                .filter(|&span_end| span_end <= input.len())
+                .unwrap_or_else(|| {
+                    panic!(
+                        "input of length 0x{:08x} does not contain media data at 0x{:08x} of length 0x{:08x}",
+                        input.len(),
+                        span.offset,
+                        span.len,
+                    )
+                })
+        };
+        let data_end = span_end(&self.data);
+        match &self.metadata {
+            Some(metadata) => {
+                let mut output = [&metadata[..], &input[self.data.offset as usize..data_end]].concat();
+                for span in &self.additional_data {
+                    output.extend_from_slice(&input[span.offset as usize..span_end(span)]);
+                }
+                output
+            }
+            None => input[..data_end].to_vec(),
+        }
+    }
+}
+
+//
+// MetadataBoxes impls
+//
+
+impl MetadataBoxes {
+    fn encoded_len(&self) -> u64 {
+        self.ftyp.encoded_len()
+            + self.pdin.as_ref().map_or(0, Mp4Box::encoded_len)
+            + self.meta.as_ref().map_or(0, Mp4Box::encoded_len)
+            + self.moov.as_ref().map_or(0, Mp4Box::encoded_len)
+            + self.sidx.as_ref().map_or(0, Mp4Box::encoded_len)
+            + self.pad_size
+    }
+
+    fn put_buf<B: BufMut>(&self, mut out: B) {
+        self.ftyp.put_buf(&mut out);
+        if let Some(pdin) = &self.pdin {
+            pdin.put_buf(&mut out);
+        }
+        if let Some(meta) = &self.meta {
+            meta.put_buf(&mut out);
+        }
+        if let Some(moov) = &self.moov {
+            moov.put_buf(&mut out);
+        }
+        if let Some(sidx) = &self.sidx {
+            sidx.put_buf(&mut out);
+        }
+        for pad_box_size in pad_box_sizes(self.pad_size) {
+            let pad_header = BoxHeader::with_u32_data_size(BoxType::FREE, (pad_box_size - PAD_HEADER_SIZE) as u32);
+            pad_header.put_buf(&mut out);
+            out.put_bytes(0, (pad_box_size - PAD_HEADER_SIZE) as usize);
+        }
+    }
+}
+
+/// Split padding of `pad_size` bytes into the sizes of consecutive free space boxes, including their headers.
+///
+/// `pad_size` must be either zero or at least [`PAD_HEADER_SIZE`].
+fn pad_box_sizes(mut pad_size: u64) -> impl Iterator<Item = u64> {
+    iter::from_fn(move || {
+        let pad_box_size = match pad_size {
+            0 => return None,
+            size if size <= MAX_PAD_SIZE => size,
+            // Leave room for at least the header of the next free box.
+            size => MAX_PAD_SIZE.min(size - PAD_HEADER_SIZE),
+        };
+        pad_size -= pad_box_size;
+        Some(pad_box_size)
+    })
+}
+
+//
+// Config impls
+//
+
+impl Config {
+    /// Construct a builder for `Config`.
+    ///
+    /// See the documentation for [`ConfigBuilder`].
+    pub fn builder() -> ConfigBuilder {
+        ConfigBuilder::default()
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self::builder().build()
+    }
+}
+
+//
+// ConfigBuilder impls
+//
+
+impl ConfigBuilder {
+    /// Build a new [`Config`].
+    pub fn build(&self) -> Config {
+        self.try_build().unwrap()
+    }
+}
+
+//
+// private functions
+//
+
+/// Rebase spans of media data, relative to the start of the file type header, onto the input, which still contains any
+/// leading data, as the sanitized media data is copied from it.
+fn rebase_past_leading_data(
+    leading_data: Option<InputSpan>,
+    data: InputSpan,
+    additional_data: Vec<InputSpan>,
+) -> (InputSpan, Vec<InputSpan>) {
+    let leading_len = leading_data.map_or(0, |leading_data| leading_data.len);
+    let data = InputSpan { offset: leading_len + data.offset, ..data };
+    let additional_data = additional_data
+        .into_iter()
+        .map(|span| InputSpan { offset: leading_len + span.offset, ..span })
+        .collect();
+    (data, additional_data)
+}
+
+/// Lay out the sanitized metadata of length `metadata_len`, returning the size of the padding following it and the
+/// displacement of the media data.
+fn mdat_layout(
+    config: &Config,
+    data: InputSpan,
+    mdat_payload_offset: u64,
+    metadata_len: u64,
+) -> Result<(u64, i64), Report<ParseError>> {
+    let mut pad_size = 0;
+    let mut mdat_displacement = 0;
+    let max_pad_size = config.max_pad_size;
+    let target_mdat_offset = match (config.target_mdat_offset, config.align_mdat_data) {
+        (Some(target_mdat_offset), _) => Some(target_mdat_offset),
+        (None, Some(align)) => {
+            // Pad the mdat payload out to the next aligned offset which leaves room for a free box.
+            let payload_offset = metadata_len + (mdat_payload_offset - data.offset);
+            let mut align_pad_size = payload_offset.checked_rem(align).map_or(0, |rem| (align - rem) % align);
+            if align_pad_size != 0 && align_pad_size < PAD_HEADER_SIZE {
+                let extra_aligns = (PAD_HEADER_SIZE - align_pad_size - 1) / align + 1;
+                align_pad_size = extra_aligns
+                    .checked_mul(align)
+                    .and_then(|extra| extra.checked_add(align_pad_size))
+                    .ok_or_else(|| report_attach!(ParseError::UnsupportedBoxLayout, "mdat alignment too large"))?;
+            }
+            Some(metadata_len + align_pad_size)
+        }
+        (None, None) => None,
+    };
+    if let Some(target_mdat_offset) = target_mdat_offset {
+        pad_size = target_mdat_offset.checked_sub(metadata_len).ok_or_else(|| {
+            report_attach!(
+                ParseError::UnsupportedBoxLayout,
+                format!("target mdat offset 0x{target_mdat_offset:08x} is before end of metadata 0x{metadata_len:08x}"),
+            )
+        })?;
+        ensure_attach!(
+            pad_size == 0 || (PAD_HEADER_SIZE..=max_pad_size).contains(&pad_size),
+            ParseError::UnsupportedBoxLayout,
+            format!("target mdat offset 0x{target_mdat_offset:08x} leaves no room for padding"),
+        );
+        mdat_displacement = displacement(data.offset, target_mdat_offset)?;
+    } else {
+        match data.offset.checked_sub(metadata_len) {
+            Some(0) => {}
+            Some(size) if (PAD_HEADER_SIZE..=max_pad_size).contains(&size) => {
+                pad_size = size;
+            }
+            _ => {
+                mdat_displacement = displacement(data.offset, metadata_len)?;
+            }
+        }
+    }
+    Ok((pad_size, mdat_displacement))
+}
+
+/// Returns the displacement of the media data at `offset` in the input, when the first span of media data, `data`, is
+/// displaced by `displacement` and the `additional_data` spans are concatenated in order following it.
+///
+/// Offsets outside of any span are displaced along with the last span starting before them, if any, or else the first
+/// span. Returns `None` on overflow.
+fn data_displacement(data: InputSpan, additional_data: &[InputSpan], displacement: i64, offset: u64) -> Option<i64> {
+    let mut span_displacement = displacement;
+    let mut preceding_end = data.offset + data.len;
+    for span in additional_data.iter().take_while(|span| span.offset <= offset) {
+        // Each span is moved back to follow the preceding span, closing the gap between them.
+        let gap_len = i64::try_from(span.offset.checked_sub(preceding_end)?).ok()?;
+        span_displacement = span_displacement.checked_sub(gap_len)?;
+        preceding_end = span.offset + span.len;
+    }
+    Some(span_displacement)
+}
+
+/// Promote the chunk offset boxes (`stco`) of any tracks whose chunk offsets would overflow 32 bits when displaced by
+/// `mdat_displacement` to `co64` boxes, returning whether any were promoted.
+fn promote_overflowing_stcos(moov: &mut MoovBox, mdat_displacement: i64) -> Result<bool, Report<ParseError>> {
+    let mut promoted = false;
+    for trak in &mut moov.traks() {
+        let stbl = trak?.stbl_mut()?;
+        let StblCoMut::Stco(stco) = stbl.co_mut()? else {
+            continue;
+        };
+        let overflows = stco.values().any(|value| {
+            let value = value.unwrap_or_else(|_| unreachable!());
+            checked_add_signed(u64::from(value), mdat_displacement)
+                .is_some_and(|displaced| u32::try_from(displaced).is_err())
+        });
+        if overflows {
+            stbl.promote_stco_to_co64()?;
+            promoted = true;
+        }
+    }
+    Ok(promoted)
+}
+
+/// Compute the displacement of the mdat when moving it from offset `from` to offset `to`.
+fn displacement(from: u64, to: u64) -> Result<i64, Report<ParseError>> {
+    let displacement = match to.checked_sub(from) {
+        Some(forward_displacement) => forward_displacement.try_into().ok(),
+        None => (from - to).try_into().ok().and_then(i64::checked_neg),
+    };
+    displacement.ok_or_else(|| report_attach!(ParseError::UnsupportedBoxLayout, "mdat displaced too far"))
+}
+
+/// Read the top-level boxes of an MP4 input, parsing the file type (`ftyp`) and movie (`moov`) boxes and skipping over
+/// the rest.
+///
+/// If `errors` is given, recoverable validation errors confined to a single track or check are collected into it,
+/// rather than returned, and reading continues.
+async fn read_input<R: AsyncRead + AsyncSkip>(
+    input: R,
+    config: &Config,
+    mut errors: Option<&mut Vec<Report<ParseError>>>,
+) -> Result<InputBoxes, Error> {
+    use futures_util::AsyncReadExt;
+
+    let reader = BufReader::with_capacity(BoxHeader::MAX_SIZE as usize, input);
+    pin_mut!(reader);
+
+    let mut ftyp: Option<Mp4Box<FtypBox>> = None;
+    let mut pdin: Option<Mp4Box<PdinBox>> = None;
+    let mut meta: Option<Mp4Box<MetaBox>> = None;
+    let mut moov: Option<(Mp4Box<MoovBox>, u64)> = None;
+    let mut data: Option<InputSpan> = None;
+    let mut additional_data: Vec<InputSpan> = Vec::new();
+    let mut segment = false;
+    let mut sidx = None;
+    let mut trailing_data_offset = None;
+    let mut box_types = Vec::new();
+    let mut stripped_metadata_boxes = Vec::new();
+    let mut mdat_payload_offset = None;
+    let mut fragment_base_data_offsets = false;
+    let mut pending_moof = false;
+    let mut wide: Option<InputSpan> = None;
+    let mut box_count = 0;
+
+    // Skip any unrecognized leading data, scanning only the allowed prefix for the signature of an ftyp header.
+    let mut leading_data = None;
+    let mut leading_header = None;
+    if config.max_leading_data_len != 0 {
+        let start_pos = reader.as_mut().stream_position().await?;
+        let mut window = [0; 8];
+        let mut skipped_len = 0;
+        reader
+            .as_mut()
+            .read_exact(&mut window)
+            .await
+            .map_eof(|_| Error::Parse(report_attach!(ParseError::TruncatedBox, "while parsing box header")))?;
+        // A media segment's segment type header is found the same way, if accepted.
+        let is_leading_box =
+            |window: &[u8; 8]| match BoxType::parse_fourcc(&[window[4], window[5], window[6], window[7]]) {
+                BoxType::FTYP => true,
+                BoxType::STYP => config.allow_media_segments,
+                _ => false,
+            };
+        let not_found = || {
+            let leading_box_types = if config.allow_media_segments {
+                "ftyp or styp"
+            } else {
+                "ftyp"
+            };
+            report_attach!(
+                ParseError::InvalidBoxLayout,
+                format!(
+                    "no {leading_box_types} within the first {} bytes",
+                    config.max_leading_data_len
+                ),
+            )
+        };
+        while !is_leading_box(&window) {
+            if skipped_len >= config.max_leading_data_len {
+                return Err(not_found().into());
+            }
+            window.copy_within(1.., 0);
+            reader
+                .as_mut()
+                .read_exact(&mut window[7..])
+                .await
+                .map_eof(|_| Error::Parse(not_found()))?;
+            skipped_len += 1;
+        }
+        if skipped_len != 0 {
+            log::info!("leading data @ 0x{start_pos:08x}: {skipped_len} bytes");
+            leading_data = Some(InputSpan { offset: start_pos, len: skipped_len });
+        }
+        let header = BoxHeader::read(AsyncReadExt::chain(&window[..], reader.as_mut()))
+            .await
+            .map_eof(|_| Error::Parse(report_attach!(ParseError::TruncatedBox, "while parsing box header")))?;
+        leading_header = Some(header);
+    }
+    // Offsets within the input are relative to the ftyp, as any chunk offsets are, and so exclude any leading data.
+    let leading_len = leading_data.map_or(0, |leading_data: InputSpan| leading_data.len);
+
+    while !reader.as_mut().fill_buf().await?.is_empty() {
+        // A header already read while skipping leading data begins before the current position.
+        let start_pos = reader.as_mut().stream_position().await?
+            - leading_len
+            - leading_header.map_or(0, |header| header.encoded_len());
+
+        #[cfg(feature = "tracing")]
+        let span = tracing::info_span!(
+            "box",
+            offset = leading_len + start_pos,
+            box_type = tracing::field::Empty
+        );
+        let read_box = async {
+            box_count += 1;
+            if let Some(max_boxes) = config.max_boxes {
+                ensure_attach!(
+                    box_count <= max_boxes,
+                    ParseError::InvalidBoxLayout,
+                    format!("more than {max_boxes} top-level boxes"),
+                );
+            }
+
+            // Once all the required boxes have been read, unexpected content may be treated as trailing data.
+            let required_boxes_read = (moov.is_some() || segment) && data.is_some() && ftyp.is_some();
+            let allow_trailing_data = config.on_trailing_data != TrailingData::Fail && required_boxes_read;
+
+            // Fewer remaining bytes than the largest box header may be padding. If the input's length is unknown, only
+            // bytes too short for any box header are.
+            let allow_trailing_padding = config.allow_trailing_padding && required_boxes_read;
+            let padding_len = match allow_trailing_padding && leading_header.is_none() {
+                true => match reader.as_mut().stream_len().await {
+                    Ok(input_len) => Some(input_len - leading_len - start_pos).filter(|&len| len < BoxHeader::MAX_SIZE),
+                    Err(err) if err.kind() == io::ErrorKind::Unsupported => None,
+                    Err(err) => return Err(err.into()),
+                },
+                false => None,
+            };
+
+            let header = match leading_header.take() {
+                Some(header) => Ok(header),
+                None => BoxHeader::read(&mut reader).await,
+            };
+            let mut header = match header {
+                Ok(header) if padding_len.is_some_and(|padding_len| header.box_size() != Some(padding_len)) => {
+                    log::info!(
+                        "trailing padding @ 0x{start_pos:08x}: {} bytes",
+                        padding_len.unwrap_or_default()
+                    );
+                    return Ok(ControlFlow::Break(()));
+                }
+                Err(err) if err.kind() == io::ErrorKind::UnexpectedEof && allow_trailing_padding => {
+                    log::info!("trailing padding @ 0x{start_pos:08x}: too short for a box header");
+                    return Ok(ControlFlow::Break(()));
+                }
+                Err(err) if err.kind() == io::ErrorKind::UnexpectedEof && allow_trailing_data => {
+                    trailing_data_offset = Some(start_pos);
+                    return Ok(ControlFlow::Break(()));
+                }
+                header => header
+                    .map_eof(|_| Error::Parse(report_attach!(ParseError::TruncatedBox, "while parsing box header")))?,
+            };
+            if box_types.len() < MAX_FINGERPRINT_BOX_COUNT {
+                box_types.push(header.box_type());
+            }
+            #[cfg(feature = "tracing")]
+            span.record("box_type", tracing::field::display(header.box_type()));
+
+            let preceding_wide = wide.take();
+            match header.box_type() {
+                name @ (BoxType::FREE | BoxType::SKIP | BoxType::WIDE) => {
+                    let lazy_box = AnyMp4Box::read_header_only(reader.as_mut(), header).await?;
+                    let box_size = lazy_box.encoded_len();
+                    lazy_box.skip().await?;
+                    log::info!("{name} @ 0x{start_pos:08x}: {box_size} bytes");
+
+                    // A QuickTime wide box is a placeholder for a 64-bit size of the following mdat box, so it's kept with
+                    // the media data.
+                    if name == BoxType::WIDE && data.is_none() {
+                        wide = Some(InputSpan { offset: start_pos, len: box_size });
+                    }
+
+                    // Try to extend any already accumulated data in case there's more mdat boxes to come.
+                    if let Some(data) = additional_data.last_mut().or(data.as_mut()) {
+                        if data.offset + data.len == start_pos {
+                            data.len += box_size;
+                        }
+                    }
+                }
+
+                name @ (BoxType::FTYP | BoxType::STYP)
+                    if name == BoxType::FTYP || (config.allow_media_segments && ftyp.is_none()) =>
+                {
+                    ensure_attach!(
+                        ftyp.is_none(),
+                        ParseError::InvalidBoxLayout,
+                        MultipleBoxes(BoxType::FTYP)
+                    );
+                    let mut read_ftyp = AnyMp4Box::read_header_only(reader.as_mut(), header)
+                        .await?
+                        .read_data(MAX_FTYP_SIZE)
+                        .await?;
+                    let ftyp_data: &mut FtypBox = read_ftyp.data.parse()?;
+                    let compatible_brand_count = ftyp_data.compatible_brands().len();
+                    let FtypBox { major_brand, minor_version, .. } = ftyp_data;
+                    log::info!("{name} @ 0x{start_pos:08x}: {major_brand} version {minor_version}, {compatible_brand_count} compatible brands");
+
+                    ensure_attach!(
+                        ftyp_data
+                            .compatible_brands()
+                            .any(|brand| config.compatible_brands.contains(&brand)),
+                        ParseError::UnsupportedFormat(ftyp_data.major_brand),
+                        format!(
+                            "compatible brands [{}] do not include any accepted brand",
+                            ftyp_data
+                                .compatible_brands()
+                                .map(|brand| brand.to_string())
+                                .collect::<Vec<_>>()
+                                .join(", "),
+                        ),
+                    );
+
+                    ftyp = Some(read_ftyp);
+                    segment = name == BoxType::STYP;
+                }
+
+                // NB: ISO 14496-12-2012 specifies a default ftyp, but we don't currently use it. The spec says that it
+                // contains a single compatible brand, "mp41", and notably not "isom" which is the ISO spec we follow for
+                // parsing now. This implies that there's additional stuff in "mp41" which is not in "isom". "mp41" is also
+                // very old at this point, so it'll require additional research/work to be able to parse/remux it.
+                _ if ftyp.is_none() => {
+                    bail_attach!(ParseError::InvalidBoxLayout, "ftyp is not the first significant box");
+                }
+
+                BoxType::PDIN => {
+                    ensure_attach!(
+                        pdin.is_none(),
+                        ParseError::InvalidBoxLayout,
+                        MultipleBoxes(BoxType::PDIN)
+                    );
+                    let read_pdin: Mp4Box<PdinBox> = AnyMp4Box::read_header_only(reader.as_mut(), header)
+                        .await?
+                        .read_data(config.max_metadata_size)
+                        .await?;
+                    log::info!("pdin @ 0x{start_pos:08x}: {} bytes", read_pdin.encoded_len());
+                    pdin = Some(read_pdin);
+                }
+
+                BoxType::MDAT => {
+                    if let Ok(None) = header.box_data_size() {
+                        if let Some(t) = config.cumulative_mdat_box_size {
+                            header.overwrite_size(t);
+                        }
+                    }
+
+                    let lazy_box = AnyMp4Box::read_header_only(reader.as_mut(), header).await?;
+                    let box_span = InputSpan { offset: lazy_box.offset() - leading_len, len: lazy_box.encoded_len() };
+                    lazy_box.skip().await?;
+                    log::info!("mdat @ 0x{:08x}: {} bytes", box_span.offset, box_span.len);
+                    mdat_payload_offset.get_or_insert(box_span.offset + header.encoded_len());
+
+                    if let Some(last_data) = additional_data.last_mut().or(data.as_mut()) {
+                        // Try to extend already accumulated data, or else start a new span of it.
+                        if last_data.offset + last_data.len == box_span.offset {
+                            last_data.len += box_span.len;
+                        } else {
+                            ensure_attach!(
+                                config.allow_discontiguous_mdat,
+                                ParseError::UnsupportedBoxLayout,
+                                "discontiguous mdat boxes",
+                            );
+                            additional_data.push(box_span);
+                        }
+                    } else {
+                        data = Some(match preceding_wide {
+                            Some(wide) if wide.offset + wide.len == box_span.offset => {
+                                InputSpan { offset: wide.offset, len: wide.len + box_span.len }
+                            }
+                            _ => box_span,
+                        });
+                    }
+                    pending_moof = false;
+                }
+
+                BoxType::MOOF => {
+                    ensure_attach!(
+                        moov.is_some() || segment,
+                        ParseError::InvalidBoxLayout,
+                        "moof before moov"
+                    );
+                    ensure_attach!(!pending_moof, ParseError::InvalidBoxLayout, "moof not followed by mdat");
+
+                    let lazy_box = AnyMp4Box::read_header_only(reader.as_mut(), header).await?;
+                    let box_span = InputSpan { offset: lazy_box.offset() - leading_len, len: lazy_box.encoded_len() };
+                    let mut read_moof: Mp4Box<MoofBox> = lazy_box.read_data(config.max_metadata_size).await?;
+
+                    let moof_data: &mut MoofBox = read_moof.data.parse()?;
+                    let sequence_number = moof_data.mfhd_mut()?.sequence_number();
+                    let mut traf_count = 0;
+                    for traf in moof_data.trafs() {
+                        let traf = traf?;
+                        fragment_base_data_offsets |= traf.tfhd_mut()?.base_data_offset().is_some();
+                        for trun in traf.truns() {
+                            trun?;
+                        }
+                        traf_count += 1;
+                    }
+                    log::info!("moof @ 0x{start_pos:08x}: sequence number {sequence_number}, {traf_count} trafs");
+
+                    // Movie fragments are passed through along with the media data, so they must be contiguous with it.
+                    if let Some(data) = additional_data.last_mut().or(data.as_mut()) {
+                        ensure_attach!(
+                            data.offset + data.len == box_span.offset,
+                            ParseError::UnsupportedBoxLayout,
+                            "discontiguous movie fragments",
+                        );
+                        data.len += box_span.len;
+                    } else {
+                        data = Some(box_span);
+                    }
+                    pending_moof = true;
+                }
+
+                BoxType::SIDX if data.is_none() && !segment => {
+                    ensure_attach!(
+                        sidx.is_none(),
+                        ParseError::InvalidBoxLayout,
+                        MultipleBoxes(BoxType::SIDX)
+                    );
+                    let mut read_sidx: Mp4Box<SidxBox> = AnyMp4Box::read_header_only(reader.as_mut(), header)
+                        .await?
+                        .read_data(config.max_metadata_size)
+                        .await?;
+                    let sidx_len = read_sidx.encoded_len();
+                    let sidx_data = read_sidx.data.parse()?;
+                    log::info!(
+                        "sidx @ 0x{start_pos:08x}: {} references, first offset 0x{:08x}",
+                        sidx_data.reference_count(),
+                        sidx_data.first_offset(),
+                    );
+                    sidx = Some((read_sidx, start_pos + sidx_len));
+                }
+
+                BoxType::SIDX => {
+                    let lazy_box = AnyMp4Box::read_header_only(reader.as_mut(), header).await?;
+                    let box_span = InputSpan { offset: lazy_box.offset() - leading_len, len: lazy_box.encoded_len() };
+                    lazy_box.skip().await?;
+                    log::info!("sidx @ 0x{start_pos:08x}: {} bytes", box_span.len);
+
+                    // Segment index boxes within the media data locate subsegments relative to themselves, so they're
+                    // passed through along with it. In a media segment, they begin the media data.
+                    if let Some(data) = additional_data.last_mut().or(data.as_mut()) {
+                        ensure_attach!(
+                            data.offset + data.len == box_span.offset,
+                            ParseError::UnsupportedBoxLayout,
+                            "discontiguous segment index",
+                        );
+                        data.len += box_span.len;
+                    } else {
+                        data = Some(box_span);
+                    }
+                }
+
+                BoxType::MOOV => {
+                    ensure_attach!(!segment, ParseError::InvalidBoxLayout, "moov in media segment");
+                    ensure_attach!(
+                        moov.is_none(),
+                        ParseError::InvalidBoxLayout,
+                        MultipleBoxes(BoxType::MOOV)
+                    );
+                    let mut read_moov = AnyMp4Box::read_header_only(reader.as_mut(), header)
+                        .await?
+                        .read_data(config.max_metadata_size)
+                        .await?;
+
+                    let moov_data: &mut MoovBox = read_moov.data.parse()?;
+                    let mut chunk_count = 0;
+                    for trak in moov_data.traks() {
+                        let trak_chunk_count = trak.and_then(|trak| {
+                            trak.tkhd_mut()?;
+                            trak.stbl_mut()?.stsd_mut()?.validate_sample_entries()?;
+                            if let Some(edts) = trak.edts_mut()? {
+                                edts.elst_mut()?;
+                            }
+                            Ok(trak.co_mut()?.entry_count())
+                        });
+                        chunk_count += recoverable(trak_chunk_count, errors.as_deref_mut())?.unwrap_or(0);
+                    }
+                    let trak_count = moov_data.traks().count();
+                    recoverable(moov_data.validate_durations(), errors.as_deref_mut())?;
+
+                    if config.validate_strings {
+                        recoverable(moov_data.validate_strings(), errors.as_deref_mut())?;
+                    }
+
+                    log::info!("moov @ 0x{start_pos:08x}: {trak_count} traks {chunk_count} chunks");
+                    moov = Some((read_moov, start_pos));
+                }
+
+                name @ (BoxType::META | BoxType::UDTA) if config.strip_all_metadata => {
+                    let lazy_box = AnyMp4Box::read_header_only(reader.as_mut(), header).await?;
+                    let box_size = lazy_box.encoded_len();
+                    let stripped_box: AnyMp4Box = match name {
+                        BoxType::META => lazy_box.read_data::<MetaBox>(config.max_metadata_size).await?.into(),
+                        _ => lazy_box.read_data::<UdtaBox>(config.max_metadata_size).await?.into(),
+                    };
+                    let mut stripped_boxes = Boxes::<()>::from(vec![stripped_box]);
+                    stripped_metadata_boxes.extend(stripped_boxes.strip_metadata_boxes()?);
+                    log::info!("{name} @ 0x{start_pos:08x}: stripped {box_size} bytes");
+                }
+
+                BoxType::META if config.preserve_meta && data.is_none() => {
+                    ensure_attach!(
+                        meta.is_none(),
+                        ParseError::InvalidBoxLayout,
+                        MultipleBoxes(BoxType::META)
+                    );
+                    let mut read_meta: Mp4Box<MetaBox> = AnyMp4Box::read_header_only(reader.as_mut(), header)
+                        .await?
+                        .read_data(config.max_metadata_size)
+                        .await?;
+                    if let Some(iloc) = read_meta.data.parse()?.iloc_mut()? {
+                        ensure_attach!(
+                            !iloc.has_file_offsets(),
+                            ParseError::UnsupportedBoxLayout,
+                            "file-level meta has items located by file offset",
+                            WhileParsingBox(BoxType::ILOC),
+                        );
+                    }
+                    log::info!("meta @ 0x{start_pos:08x}: {} bytes", read_meta.encoded_len());
+                    meta = Some(read_meta);
+                }
+
+                name @ (BoxType::META | BoxType::MECO | BoxType::UDTA) => {
+                    let lazy_box = AnyMp4Box::read_header_only(reader.as_mut(), header).await?;
+                    let box_size = lazy_box.encoded_len();
+                    lazy_box.skip().await?;
+                    log::info!("{name} @ 0x{start_pos:08x}: {box_size} bytes");
+
+                    // Try to extend any already accumulated data in case there's more mdat boxes to come.
+                    if let Some(data) = additional_data.last_mut().or(data.as_mut()) {
+                        if data.offset + data.len == start_pos {
+                            data.len += box_size;
+                        }
+                    }
+                }
+
+                _ if allow_trailing_data => {
+                    trailing_data_offset = Some(start_pos);
+                    return Ok(ControlFlow::Break(()));
+                }
+
+                name => {
+                    let lazy_box = AnyMp4Box::read_header_only(reader.as_mut(), header).await?;
+                    let box_size = lazy_box.encoded_len();
+                    lazy_box.skip().await?;
+                    log::info!("{name} @ 0x{start_pos:08x}: {box_size} bytes");
+                    bail_attach!(ParseError::UnsupportedBox(name));
+                }
+            }
+
+            // Guarantee forward progress, in case of a misbehaving input or a bug in the handling of some box.
+            let end_pos = reader.as_mut().stream_position().await? - leading_len;
+            ensure_attach!(
+                end_pos
+                    .checked_sub(start_pos)
+                    .is_some_and(|advanced| advanced >= header.encoded_len()),
+                ParseError::InvalidInput,
+                "box made no progress",
+                WhileParsingBox(header.box_type()),
+            );
+            Ok::<_, Error>(ControlFlow::Continue(()))
+        };
+        #[cfg(feature = "tracing")]
+        let read_box = tracing::Instrument::instrument(read_box, span.clone());
+        // Attach the offset of the box to errors about it, to help locate the problem within the input.
+        let read_box = read_box.await.map_err(|err| match err {
+            Error::Parse(err)
+                if matches!(
+                    err.get_ref(),
+                    ParseError::UnsupportedBox(_) | ParseError::TruncatedBox | ParseError::InvalidBoxLayout
+                ) =>
+            {
+                Error::Parse(err.attach_printable(AtOffset(leading_len + start_pos)))
+            }
+            err => err,
+        })?;
+        if read_box.is_break() {
+            break;
+        }
+    }
+
+    let Some(ftyp) = ftyp else {
+        bail_attach!(ParseError::MissingRequiredBox(BoxType::FTYP));
+    };
+    ensure_attach!(moov.is_some() || segment, ParseError::MissingRequiredBox(BoxType::MOOV));
+    let (Some(data), Some(mdat_payload_offset)) = (data, mdat_payload_offset) else {
+        bail_attach!(ParseError::MissingRequiredBox(BoxType::MDAT));
+    };
+    ensure_attach!(!pending_moof, ParseError::InvalidBoxLayout, "moof not followed by mdat");
+
+    let trailing_data = match trailing_data_offset {
+        Some(offset) => {
+            let len = reader.as_mut().stream_len().await? - leading_len - offset;
+            log::info!("trailing data @ 0x{offset:08x}: {len} bytes");
+            Some(InputSpan { offset, len })
+        }
+        None => None,
+    };
+
+    // The samples of each track must lie within the mdat, and in particular the last sample of the last chunk must end at
+    // or before the mdat's end. Any sample auxiliary information must also start within the mdat. The chunks described by
+    // the stsc must not contain more samples than the stsz declares. Unless disabled, every chunk must also start within
+    // the mdat. If the media data is discontiguous, each must lie within one of its spans.
+    let last_data = additional_data.last().unwrap_or(&data);
+    let mdat_end = last_data.offset.saturating_add(last_data.len);
+    let within_data = |offset: u64| {
+        iter::once(&data)
+            .chain(&additional_data)
+            .any(|span| (span.offset..span.offset.saturating_add(span.len)).contains(&offset))
+    };
+    if let Some((moov, _)) = &mut moov {
+        for trak in moov.data.parse()?.traks() {
+            let samples_within_mdat = trak.and_then(|trak| {
+                for saio in trak.stbl_mut()?.saios() {
+                    for offset in saio?.offsets() {
+                        let offset = offset?;
+                        ensure_attach!(
+                            within_data(offset),
+                            ParseError::InvalidInput,
+                            format!("sample auxiliary information at 0x{offset:08x} not within mdat"),
+                            WhileParsingBox(BoxType::SAIO),
+                        );
+                    }
+                }
+                let sample_table = SampleTable::new(trak.stbl_mut()?)?;
+                if config.validate_chunk_offsets {
+                    for (index, &offset) in sample_table.chunk_offsets().iter().enumerate() {
+                        ensure_attach!(
+                            within_data(offset),
+                            ParseError::InvalidInput,
+                            format!("chunk offset 0x{offset:08x} of entry {index} not within mdat"),
+                        );
+                    }
+                }
+                let sample_count = sample_table.sample_count();
+                match sample_table.chunked_sample_count() {
+                    Some(chunked_sample_count) => ensure_attach!(
+                        chunked_sample_count <= sample_count.into(),
+                        ParseError::InvalidInput,
+                        format!("{chunked_sample_count} samples in chunks exceeds sample count {sample_count}"),
+                        WhileParsingBox(BoxType::STSC),
+                    ),
+                    None => bail_attach!(
+                        ParseError::InvalidInput,
+                        "chunk runs out of order",
+                        WhileParsingBox(BoxType::STSC),
+                    ),
+                }
+                if let Some(samples_end) = sample_table.samples_end() {
+                    ensure_attach!(
+                        samples_end <= mdat_end,
+                        ParseError::InvalidInput,
+                        format!("samples end at 0x{samples_end:08x}, past end of mdat at 0x{mdat_end:08x}"),
+                    );
+                }
+                Ok(())
+            });
+            recoverable(samples_within_mdat, errors.as_deref_mut())?;
+        }
+    }
+
+    Ok(InputBoxes {
+        ftyp,
+        pdin,
+        meta,
+        moov,
+        sidx,
+        leading_data,
+        data,
+        additional_data,
+        trailing_data,
+        mdat_payload_offset,
+        fragment_base_data_offsets,
+        box_types,
+        stripped_metadata_boxes,
+    })
+}
+
+/// Handle the result of a recoverable validation, collecting its error into `errors` if given, or else returning it.
+fn recoverable<T>(
+    result: Result<T, Report<ParseError>>,
+    errors: Option<&mut Vec<Report<ParseError>>>,
+) -> Result<Option<T>, Report<ParseError>> {
+    match (result, errors) {
+        (Ok(value), _) => Ok(Some(value)),
+        (Err(err), Some(errors)) => {
+            errors.push(err);
+            Ok(None)
+        }
+        (Err(err), None) => Err(err),
+    }
+}
+
+/// Build the [`SanitizeReport`] describing the boxes read from an input.
+fn input_report(input: &mut InputBoxes, config: &Config) -> Result<SanitizeReport, Error> {
+    let mut report =
+        SanitizeReport { stripped_metadata_boxes: input.stripped_metadata_boxes.clone(), ..Default::default() };
+    if input.box_types.len() < MAX_FINGERPRINT_BOX_COUNT {
+        report.likely_producer = Producer::guess(input.ftyp.data.parse()?, &input.box_types);
+    }
+    if config.on_trailing_data == TrailingData::Report {
+        let leading_len = input.leading_data.map_or(0, |leading_data| leading_data.len);
+        report.trailing_data = input
+            .trailing_data
+            .map(|span| InputSpan { offset: leading_len + span.offset, ..span });
+    }
+    if let Some((moov, _)) = &mut input.moov {
+        for pssh in moov.data.parse()?.psshs() {
+            report.protection_system_ids.push(pssh?.system_id());
+        }
+        if !report.protection_system_ids.is_empty() {
+            log::info!("moov: {} protection system headers", report.protection_system_ids.len());
+        }
+        report.unknown_duration_boxes = moov.data.parse()?.unknown_duration_boxes()?;
+        for box_type in &report.unknown_duration_boxes {
+            log::warn!("{box_type}: unknown duration");
+        }
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod test {
+    use std::cell::Cell;
+    use std::future::Future;
+    use std::pin::Pin;
+    use std::task::{Context, Poll};
+    use std::{io, mem};
+
+    use assert_matches::assert_matches;
+    use bytes::BytesMut;
+    use futures_util::{FutureExt, TryStreamExt};
+    use mp4san_test::{ffmpeg_assert_eq, gpac_assert_eq, gpac_assert_remux_stable, gpac_assert_remux_unstable};
+
+    use crate::parse::box_type::{
+        CO64, FREE, FTYP, HVC1, MDAT, MDIA, MECO, META, MINF, MOOF, MOOV, PDIN, SDTP, SIDX, SKIP, STBL, STCO, STRI,
+        STYP, TKHD, TRAK, UDTA, WIDE,
+    };
+    use crate::util::test::{
+        init_logger, test_free, test_ftyp, test_hdlr, test_location_udta, test_meta, test_moof, test_moov, test_mp4,
+        test_pdin, test_sidx, test_visual_sample_entry, write_test_mdat, TestMp4, ISO2, ISOM, MP41, MP42, RECT, SBTL,
+        TEST_HVCC_DATA, TEST_UUID, XYZ,
+    };
+
+    use crate::parse::{
+        self, fourcc, ElstEntry, EyesBox, FullBoxHeader, HeroBox, ParsedBox, PrjiBox, ProjBox, StriBox, StszBox,
+        VexuBox,
+    };
+
+    use crate::{CodecConfigKind, SliceReader};
+
+    use super::*;
+
+    #[test]
+    fn until_eof_sized_moov() {
+        init_logger();
+
+        let mut data = vec![];
+        let mut metadata = vec![];
+        test_ftyp().build().put_buf(&mut data);
+        test_ftyp().build().put_buf(&mut metadata);
+        let mdat = write_test_mdat(&mut data, b"abcdefg");
+
+        let moov_pos = data.len();
+        test_moov().build().put_buf(&mut data);
+        test_moov().build().put_buf(&mut metadata);
+        BoxHeader::until_eof(MOOV).put_buf(&mut &mut data[moov_pos..]);
+
+        let sanitized = sanitize(io::Cursor::new(&data)).unwrap();
+        assert_eq!(sanitized.data, mdat);
+        assert_eq!(sanitized.metadata, Some(metadata));
+        assert_eq!(sanitized.report.explicitly_sized_boxes, [MOOV]);
+        sanitize(io::Cursor::new(sanitized.reconstruct(&data))).unwrap();
+    }
+
+    #[test]
+    fn ftyp_preserved() {
+        let ftyp = FtypBox::new(MP42, 0x200, [MP42, ISO2, ISOM, MP41]);
+        let mut data = vec![];
+        data.put_u32(1); // large size
+        data.put_slice(b"ftyp");
+        data.put_u64(16 + ftyp.encoded_len());
+        ftyp.put_buf(&mut data);
+        let ftyp_data = data.clone();
+        let mdat = write_test_mdat(&mut data, b"abcdefg");
+        test_moov().build().put_buf(&mut data);
+
+        let sanitized = sanitize(io::Cursor::new(&data)).unwrap();
+        assert_eq!(sanitized.data, mdat);
+        let metadata = sanitized.metadata.as_deref().unwrap();
+        assert_eq!(metadata[..ftyp_data.len()], ftyp_data[..]);
+        let resanitized = sanitize(io::Cursor::new(sanitized.reconstruct(&data))).unwrap();
+        assert_eq!(resanitized.metadata, None);
+    }
+
+    #[test]
+    fn max_boxes() {
+        let mut data = vec![];
+        test_ftyp().build().put_buf(&mut data);
+        for _ in 0..10_000 {
+            BoxHeader::with_u32_data_size(FREE, 0).put_buf(&mut data);
+        }
+        let mdat = write_test_mdat(&mut data, b"abcdefg");
+        test_moov().build().put_buf(&mut data);
+
+        let config = Config::builder().max_boxes(Some(1000)).build();
+        assert_matches!(sanitize_with_config(io::Cursor::new(&data), config).unwrap_err(), Error::Parse(err) => {
+            assert_matches!(err.into_inner(), ParseError::InvalidBoxLayout);
+        });
+
+        let config = Config::builder().max_boxes(None).build();
+        let sanitized = sanitize_with_config(io::Cursor::new(&data), config).unwrap();
+        assert_eq!(sanitized.data, mdat);
+    }
+
+    #[test]
+    fn largesize_mdat() {
+        let mdat_data_len = u64::from(u32::MAX) + 1;
+        let mut test = test_mp4()
+            .boxes(&[FTYP, MOOV, MDAT][..])
+            .mdat_data_len(mdat_data_len)
+            .build();
+        assert_eq!(
+            BoxHeader::parse(&test.data[test.mdat.offset as usize..])
+                .unwrap()
+                .encoded_len(),
+            16
+        );
+        assert_eq!(test.mdat.len, 16 + mdat_data_len);
+
+        let sanitized = sanitize(&mut test).unwrap();
+        assert_eq!(sanitized.data, test.mdat);
+        assert_eq!(sanitized.metadata, None);
+        assert_eq!(test.stream_position().unwrap(), test.mdat.offset + test.mdat.len);
+    }
+
+    #[test]
+    fn until_eof_sized_mdat() {
+        let test = test_mp4()
+            .boxes(&[FTYP, MOOV, MDAT][..])
+            .mdat_data(&b"abcdefg"[..])
+            .mdat_data_until_eof()
+            .build();
+        test.sanitize_ok_noop();
+    }
+
+    #[test]
+    fn skip() {
+        test_mp4().mdat_data(&b"abcdefg"[..]).build().sanitize_ok();
+    }
+
+    #[test]
+    fn max_input_length() {
+        let mut test = test_mp4().boxes(&[FTYP, MOOV, MDAT][..]).mdat_data(vec![]).clone();
+        let test_data_len = test.mdat_data_len(u64::MAX - 16).build().data.len() as u64;
+        let test = test.mdat_data_len(u64::MAX - test_data_len).build();
+        let sanitized = sanitize(test.clone()).unwrap();
+        assert_eq!(sanitized.data, test.mdat);
+        assert_eq!(sanitized.data.offset + sanitized.data.len, u64::MAX);
+        assert_eq!(sanitized.metadata, None);
+    }
+
+    #[test]
+    fn input_length_overflow() {
+        let mut test = test_mp4().mdat_data(vec![]).clone();
+        let test_data_len = test.mdat_data_len(u64::MAX - 16).build().data.len() as u64;
+        let test = test.mdat_data_len(u64::MAX - test_data_len + 1).build();
+        sanitize(test).unwrap_err();
+    }
+
+    #[test]
+    fn box_size_overflow() {
+        let test = test_mp4().mdat_data_len(u64::MAX - 16).build();
+        sanitize(test).unwrap_err();
+    }
+
+    #[test]
+    fn ftyp_too_large() {
+        let mut compatible_brands = vec![];
+        while compatible_brands.len() * COMPATIBLE_BRAND.value.len() < MAX_FTYP_SIZE as usize {
+            compatible_brands.push(COMPATIBLE_BRAND);
+        }
+
+        let test = test_mp4()
+            .ftyp(test_ftyp().compatible_brands(compatible_brands).clone())
+            .build();
+        assert_matches!(sanitize(test).unwrap_err(), Error::Parse(err) => {
+            assert_matches!(err.into_inner(), ParseError::InvalidInput);
+        });
+    }
+
+    #[test]
+    fn max_moov_size() {
+        let test_spec = test_mp4().build_spec().unwrap();
+        let config = Config::builder()
+            .max_metadata_size(test_spec.moov().build().encoded_len())
+            .build();
+        test_spec.build().sanitize_ok_with_config(config);
+    }
+
+    #[test]
+    fn mdat_larger_than_max_metadata_size() {
+        let test_spec = test_mp4()
+            .boxes(&[FTYP, MOOV, MDAT][..])
+            .mdat_data_len(1 << 32)
+            .build_spec()
+            .unwrap();
+        let config = Config::builder()
+            .max_metadata_size(test_spec.moov().build().encoded_len())
+            .build();
+        let test = test_spec.build();
+        let sanitized = sanitize_with_config(test.clone(), config).unwrap();
+        assert_eq!(sanitized.data, test.mdat);
+        assert_eq!(sanitized.metadata, None);
+    }
+
+    #[test]
+    fn moov_too_large() {
+        let test_spec = test_mp4().build_spec().unwrap();
+        let config = Config::builder()
+            .max_metadata_size(test_spec.moov().build().data.encoded_len() - 1)
+            .build();
+        let test = test_spec.build();
+        test.sanitize_ok();
+        assert_matches!(sanitize_with_config(test, config).unwrap_err(), Error::Parse(err) => {
+            assert_matches!(err.into_inner(), ParseError::InvalidInput);
+        });
+    }
+
+    #[test]
+    fn no_progress() {
+        /// An input whose reported stream position never advances.
+        struct StuckPositionInput(TestMp4);
+
+        impl io::Read for StuckPositionInput {
+            fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+                self.0.read(buf)
+            }
+        }
+
+        impl Skip for StuckPositionInput {
+            fn skip(&mut self, amount: u64) -> io::Result<()> {
+                self.0.skip(amount)
+            }
+
+            fn stream_position(&mut self) -> io::Result<u64> {
+                Ok(0)
+            }
+
+            fn stream_len(&mut self) -> io::Result<u64> {
+                self.0.stream_len()
+            }
+        }
+
+        let test = test_mp4().build();
+        assert_matches!(sanitize(StuckPositionInput(test)).unwrap_err(), Error::Parse(err) => {
+            assert_matches!(err.into_inner(), ParseError::InvalidInput);
+        });
+    }
+
+    #[test]
+    fn lazy_box_reads_data_on_demand() {
+        /// An input which counts the number of bytes read from it.
+        struct CountingInput<'a>(TestMp4, &'a Cell<u64>);
+
+        impl io::Read for CountingInput<'_> {
+            fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+                let read_len = self.0.read(buf)?;
+                self.1.set(self.1.get() + read_len as u64);
+                Ok(read_len)
+            }
+        }
+
+        impl Skip for CountingInput<'_> {
+            fn skip(&mut self, amount: u64) -> io::Result<()> {
+                self.0.skip(amount)
+            }
+
+            fn stream_position(&mut self) -> io::Result<u64> {
+                self.0.stream_position()
+            }
+
+            fn stream_len(&mut self) -> io::Result<u64> {
+                self.0.stream_len()
+            }
+        }
+
+        let test = test_mp4().mdat_data(vec![0; 4096]).build();
+        let read_len = Cell::new(0);
+        let box_spans = sync::sanitize(CountingInput(test.clone(), &read_len), |input| async move {
+            let reader = BufReader::with_capacity(BoxHeader::MAX_SIZE as usize, input);
+            pin_mut!(reader);
+
+            let mut box_spans = Vec::new();
+            while !reader.as_mut().fill_buf().await?.is_empty() {
+                let header = BoxHeader::read(&mut reader).await?;
+                let lazy_box = AnyMp4Box::read_header_only(reader.as_mut(), header).await?;
+                box_spans.push((header.box_type(), lazy_box.offset(), lazy_box.encoded_len()));
+                lazy_box.skip().await?;
+            }
+            Ok::<_, Error>(box_spans)
+        })
+        .unwrap();
+
+        assert_eq!(
+            box_spans,
+            [
+                (FTYP, 0, test.mdat.offset),
+                (MDAT, test.mdat.offset, test.mdat.len),
+                (
+                    MOOV,
+                    test.mdat.offset + test.mdat.len,
+                    test.data_len - test.mdat.offset - test.mdat.len
+                ),
+            ]
+        );
+        assert!(
+            read_len.get() <= box_spans.len() as u64 * BoxHeader::MAX_SIZE,
+            "{}",
+            read_len.get()
+        );
+
+        read_len.set(0);
+        let moov_len = sync::sanitize(CountingInput(test.clone(), &read_len), |input| async move {
+            let reader = BufReader::with_capacity(BoxHeader::MAX_SIZE as usize, input);
+            pin_mut!(reader);
+
+            loop {
+                let header = BoxHeader::read(&mut reader).await?;
+                let lazy_box = AnyMp4Box::read_header_only(reader.as_mut(), header).await?;
+                if header.box_type() == MOOV {
+                    let moov: Mp4Box<MoovBox> = lazy_box.read_data(u64::MAX).await?;
+                    return Ok::<_, Error>(moov.encoded_len());
+                }
+                lazy_box.skip().await?;
+            }
+        })
+        .unwrap();
+        assert!(read_len.get() >= moov_len, "{}", read_len.get());
+    }
+
+    #[test]
+    fn invalid_utf8_hdlr_name() {
+        let test = test_mp4().moov(test_moov().hdlr_name(&b"\xc0\0"[..]).clone()).build();
+        test.sanitize_ok();
+
+        let config = Config::builder().validate_strings(true).build();
+        assert_matches!(sanitize_with_config(test, config).unwrap_err(), Error::Parse(err) => {
+            assert_matches!(err.into_inner(), ParseError::InvalidInput);
+        });
+    }
+
+    #[test]
+    fn valid_utf8_hdlr_name() {
+        let test = test_mp4()
+            .moov(test_moov().hdlr_name(&b"Metadata \xe2\x9c\x93\0"[..]).clone())
+            .build();
+        let config = Config::builder().validate_strings(true).build();
+        test.sanitize_ok_with_config(config);
+    }
+
+    #[test]
+    fn mdat_after_moov() {
+        test_mp4().boxes(&[FTYP, MOOV, MDAT][..]).build().sanitize_ok_noop();
+    }
+
+    #[test]
+    fn no_ftyp() {
+        let test = test_mp4().boxes(&[MOOV, MDAT][..]).build();
+        assert_matches!(sanitize(test).unwrap_err(), Error::Parse(err) => {
+            assert_matches!(err.into_inner(), ParseError::InvalidBoxLayout);
+        });
+    }
+
+    #[test]
+    fn multiple_ftyp() {
+        let test = test_mp4().boxes(&[FTYP, FTYP, MOOV, MDAT][..]).build();
+        assert_matches!(sanitize(test).unwrap_err(), Error::Parse(err) => {
+            assert_matches!(err.into_inner(), ParseError::InvalidBoxLayout);
+        });
+    }
+
+    #[test]
+    fn ftyp_not_first_box() {
+        let test = test_mp4().boxes(&[FREE, FREE, FTYP, MDAT, MOOV][..]).build();
+        test.sanitize_ok();
+    }
+
+    #[test]
+    fn ftyp_not_first_significant_box() {
+        let test = test_mp4().boxes(&[MOOV, FTYP, MDAT][..]).build();
+        assert_matches!(sanitize(test).unwrap_err(), Error::Parse(err) => {
+            assert_matches!(err.into_inner(), ParseError::InvalidBoxLayout);
+        });
+    }
+
+    #[test]
+    fn no_moov() {
+        let test = test_mp4().boxes(&[FTYP, MDAT][..]).build();
+        assert_matches!(sanitize(test).unwrap_err(), Error::Parse(err) => {
+            assert_matches!(err.into_inner(), ParseError::MissingRequiredBox(MOOV));
+        });
+    }
+
+    #[test]
+    fn no_mdat() {
+        let test = test_mp4().boxes(&[FTYP, MOOV][..]).build();
+        assert_matches!(sanitize(test).unwrap_err(), Error::Parse(err) => {
+            assert_matches!(err.into_inner(), ParseError::MissingRequiredBox(MDAT));
+        });
+    }
+
+    #[test]
+    fn empty_mdat() {
+        let test = test_mp4().mdat_data(vec![]).build();
+        let sanitized = test.sanitize_ok();
+        assert_eq!(sanitized.data.len, 8);
+
+        let test = test_mp4().boxes(&[FTYP, MOOV, MDAT][..]).mdat_data(vec![]).build();
+        let sanitized = test.sanitize_ok_noop();
+        assert_eq!(sanitized.data, InputSpan { offset: test.data_len - 8, len: 8 });
+    }
+
+    #[test]
+    fn empty_mdat_with_fragments() {
+        let test = test_mp4()
+            .boxes(&[FTYP, MOOV, MDAT, MOOF, MDAT][..])
+            .mdat_data(vec![])
+            .build();
+        let sanitized = test.sanitize_ok_noop();
+        assert_eq!(sanitized.data.len, test.data_len - sanitized.data.offset);
+    }
+
+    #[test]
+    fn pdin() {
+        let pdin_len = test_pdin().encoded_len() as usize;
+        let test = test_mp4().boxes(&[FTYP, PDIN, MDAT, MOOV][..]).build();
+        let sanitized = test.sanitize_ok();
+        let metadata = sanitized.metadata.unwrap();
+        let ftyp_len = test_ftyp().build().encoded_len() as usize;
+        assert_eq!(&metadata[ftyp_len..][4..8], b"pdin");
+        assert_eq!(metadata[ftyp_len..][..pdin_len], test.data[ftyp_len..][..pdin_len]);
+
+        let test = test_mp4().boxes(&[FTYP, PDIN, MOOV, MDAT][..]).build();
+        test.sanitize_ok_noop();
+        let config = Config::builder().faststart(true).build();
+        test.sanitize_ok_with_config(config);
+    }
+
+    #[test]
+    fn multiple_pdin() {
+        let test = test_mp4().boxes(&[FTYP, PDIN, PDIN, MDAT, MOOV][..]).build();
+        assert_matches!(sanitize(test).unwrap_err(), Error::Parse(err) => {
+            assert_matches!(err.into_inner(), ParseError::InvalidBoxLayout);
+        });
+    }
+
+    #[test]
+    fn leading_data() {
+        let test = test_mp4().boxes(&[FTYP, MOOV, MDAT][..]).build();
+        let input = [&[0xA5; 64][..], &test.data[..]].concat();
+        assert_matches!(sanitize(io::Cursor::new(&input)).unwrap_err(), Error::Parse(_));
+
+        let config = Config::builder().max_leading_data_len(64).build();
+        let sanitized = super::sanitize_with_config(io::Cursor::new(&input), config).unwrap();
+        assert_eq!(sanitized.data, InputSpan { offset: test.mdat.offset + 64, ..test.mdat });
+        assert_eq!(
+            sanitized.metadata.as_deref(),
+            Some(&test.data[..test.mdat.offset as usize])
+        );
+        let sanitized_data = sanitized.reconstruct(&input);
+        assert_eq!(sanitized_data, test.data);
+        ffmpeg_assert_eq(&sanitized_data, &test.mdat_data);
+
+        let config = Config::builder().max_leading_data_len(63).build();
+        assert_matches!(super::sanitize_with_config(io::Cursor::new(&input), config).unwrap_err(), Error::Parse(err) => {
+            assert!(format!("{err:?}").contains("no ftyp within the first 63 bytes"), "{err:?}");
+            assert_matches!(err.into_inner(), ParseError::InvalidBoxLayout);
+        });
+
+        // The end of the input is reached before the limit.
+        let config = Config::builder().max_leading_data_len(1024).build();
+        let err = super::sanitize_with_config(io::Cursor::new(&input[..64]), config).unwrap_err();
+        assert_matches!(err, Error::Parse(err) => {
+            assert!(format!("{err:?}").contains("no ftyp within the first 1024 bytes"), "{err:?}");
+            assert_matches!(err.into_inner(), ParseError::InvalidBoxLayout);
+        });
+    }
+
+    #[test]
+    fn multiple_moov() {
+        let test = test_mp4().boxes(&[FTYP, MOOV, MDAT, MOOV][..]).build();
+        assert_matches!(sanitize(test).unwrap_err(), Error::Parse(err) => {
+            assert_matches!(err.into_inner(), ParseError::InvalidBoxLayout);
+        });
+    }
+
+    #[test]
+    fn sidx() {
+        let test = test_mp4().boxes(&[FTYP, MOOV, SIDX, MOOF, MDAT][..]).build();
+        test.sanitize_ok_noop();
+
+        let test = test_mp4().boxes(&[FTYP, SIDX, MDAT, MOOV][..]).build();
+        test.sanitize_ok();
+    }
+
+    #[test]
+    fn sidx_first_offset_adjusted() {
+        let test = test_mp4().boxes(&[FTYP, MOOV, SIDX, MOOF, MDAT][..]).build();
+        let config = Config::builder().align_mdat_data(Some(4096)).build();
+        let sanitized = super::sanitize_with_config(test.clone(), config).unwrap();
+        let pad_size = sanitized.report.padding_size.unwrap();
+        let metadata = sanitized.metadata.as_ref().unwrap();
+
+        let sidx_len = test_sidx(0, 0).encoded_len() as usize;
+        let sidx_offset = metadata.len() - pad_size as usize - sidx_len;
+        assert_eq!(&metadata[sidx_offset + 4..][..4], b"sidx");
+        let mut sidx_data = BytesMut::from(&metadata[sidx_offset..][..sidx_len]);
+        let mut sidx: Mp4Box<SidxBox> = Mp4Value::parse(&mut sidx_data).unwrap();
+        assert_eq!(sidx.data.parse().unwrap().first_offset(), pad_size);
+
+        let output = sanitized.reconstruct(&test.data);
+        let referenced_offset = sidx_offset + sidx_len + pad_size as usize;
+        assert_eq!(&output[referenced_offset + 4..][..4], b"moof");
+    }
+
+    #[test]
+    fn sidx_within_fragments() {
+        let test = test_mp4()
+            .boxes(&[FTYP, MOOV, MOOF, MDAT, SIDX, MOOF, MDAT][..])
+            .build();
+        test.sanitize_ok_noop();
+    }
+
+    #[test]
+    fn multiple_sidx() {
+        let test = test_mp4().boxes(&[FTYP, MOOV, SIDX, SIDX, MOOF, MDAT][..]).build();
+        assert_matches!(sanitize(test).unwrap_err(), Error::Parse(err) => {
+            assert_matches!(err.into_inner(), ParseError::InvalidBoxLayout);
+        });
+    }
+
+    #[test]
+    fn validate() {
+        let test = test_mp4().build();
+        super::validate(test.clone()).unwrap();
+        super::validate(test_mp4().boxes(&[FTYP, MOOV, MDAT][..]).build()).unwrap();
+
+        let invalid_tests = [
+            test_mp4().boxes(&[FTYP, MOOV][..]).build(),
+            test_mp4().boxes(&[MOOV, FTYP, MDAT][..]).build(),
+            test_mp4().boxes(&[FTYP, MOOV, MDAT, MOOV][..]).build(),
+            test_mp4().boxes(&[FTYP, MDAT, MOOV, TEST_UUID][..]).build(),
+        ];
+        for test in invalid_tests {
+            let validate_err = super::validate(test.clone()).unwrap_err();
+            let sanitize_err = sanitize(test).unwrap_err();
+            assert_eq!(format!("{validate_err:?}"), format!("{sanitize_err:?}"));
+        }
+    }
+
+    #[test]
+    fn trailing_padding() {
+        let test = test_mp4().build();
+        let input = [&test.data[..], &[0; 3][..]].concat();
+        assert_matches!(sanitize(io::Cursor::new(&input)).unwrap_err(), Error::Parse(err) => {
+            assert_matches!(err.into_inner(), ParseError::TruncatedBox);
+        });
+
+        let config = Config::builder().allow_trailing_padding(true).build();
+        let sanitized = super::sanitize_with_config(io::Cursor::new(&input), config.clone()).unwrap();
+        assert_eq!(sanitized.data, test.mdat);
+        assert_eq!(sanitized.report.trailing_data, None);
+        assert_eq!(
+            sanitized.reconstruct(&input),
+            test.sanitize_ok().reconstruct(&test.data)
+        );
+
+        for padding_len in 8..BoxHeader::MAX_SIZE as usize {
+            let input = [&test.data[..], &vec![0; padding_len][..]].concat();
+            let sanitized = super::sanitize_with_config(io::Cursor::new(&input), config.clone()).unwrap();
+            assert_eq!(sanitized.data, test.mdat, "{padding_len} bytes");
+        }
+
+        // A complete box is read as such, even if it's shorter than the largest box header.
+        let mut input = test.data.to_vec();
+        test_free(XYZ, 16).put_buf(&mut input);
+        assert_matches!(super::sanitize_with_config(io::Cursor::new(&input), config.clone()).unwrap_err(), Error::Parse(err) => {
+            assert_matches!(err.into_inner(), ParseError::UnsupportedBox(XYZ));
+        });
+
+        let input = [&test.data[..], &[0; BoxHeader::MAX_SIZE as usize][..]].concat();
+        assert_matches!(super::sanitize_with_config(io::Cursor::new(&input), config).unwrap_err(), Error::Parse(err) => {
+            assert_matches!(err.into_inner(), ParseError::InvalidBoxLayout | ParseError::UnsupportedBox(_));
+        });
+    }
+
+    #[test]
+    fn free_boxes_in_metadata() {
+        let test = test_mp4().boxes(&[FTYP, FREE, SKIP, MDAT, MOOV, FREE][..]).build();
+        test.sanitize_ok();
+    }
+
+    #[test]
+    fn report_mdat_displacement() {
+        let test = test_mp4().build();
+        let sanitized = test.sanitize_ok();
+        let moov_len = test.expected_metadata.len() - test.mdat.offset as usize;
+        assert_eq!(sanitized.report.mdat_displacement, Some(moov_len as i64));
+        assert_eq!(sanitized.report.padding_size, None);
+        assert_eq!(sanitized.report.explicitly_sized_boxes, []);
+    }
+
+    #[test]
+    fn wide_before_mdat() {
+        let test = test_mp4().boxes(&[FTYP, WIDE, MDAT, MOOV][..]).build();
+        let sanitized = test.sanitize_ok();
+        assert_eq!(&test.data[sanitized.data.offset as usize..][..8], b"\0\0\0\x08wide");
+        assert_eq!(sanitized.data.len, 8 + 8 + test.mdat_data.len() as u64);
+    }
+
+    #[test]
+    fn wide_after_mdat() {
+        let test = test_mp4().boxes(&[FTYP, MDAT, WIDE, MOOV][..]).build();
+        let sanitized = test.sanitize_ok();
+        assert_eq!(sanitized.data.len, 8 + test.mdat_data.len() as u64 + 8);
+    }
+
+    #[test]
+    fn free_boxes_after_mdat() {
+        let test = test_mp4().boxes(&[FTYP, MDAT, SKIP, FREE, MOOV][..]).build();
+        test.sanitize_ok();
+    }
+
+    #[test]
+    fn meta_boxes_in_metadata() {
+        let test = test_mp4().boxes(&[FTYP, MDAT, MOOV, META, MECO][..]).build();
+        test.sanitize_ok();
+    }
+
+    #[test]
+    fn meta_boxes_after_mdat() {
+        let test = test_mp4().boxes(&[FTYP, MDAT, META, MDAT, MECO, MOOV][..]).build();
+        test.sanitize_ok();
+    }
+
+    #[test]
+    fn preserve_meta() {
+        let test = test_mp4().boxes(&[FTYP, META, MDAT, MOOV][..]).build();
+        let sanitized = sanitize(test.clone()).unwrap();
+        assert_eq!(sanitized.metadata.as_deref(), Some(&test.expected_metadata[..]));
+
+        let config = || Config::builder().preserve_meta(true).build();
+        let sanitized = sanitize_with_config(test.clone(), config()).unwrap();
+        let metadata = sanitized.metadata.as_deref().unwrap();
+        let mut meta = vec![];
+        test_meta().put_buf(&mut meta);
+        let ftyp_len = test_ftyp().build().encoded_len() as usize;
+        assert_eq!(&metadata[ftyp_len..][..meta.len()], meta);
+        assert_eq!(metadata.len(), test.expected_metadata.len() + meta.len());
+
+        let output = sanitized.reconstruct(&test.data);
+        ffmpeg_assert_eq(&output, &test.mdat_data);
+        let resanitized = sanitize_with_config(io::Cursor::new(&output), config()).unwrap();
+        assert_eq!(resanitized.metadata, None);
+    }
+
+    #[test]
+    fn preserve_meta_item_file_offsets() {
+        let mut iloc_data = BytesMut::new();
+        FullBoxHeader::default().put_buf(&mut iloc_data);
+        iloc_data.put_u8(0x44); // offset size and length size
+        iloc_data.put_u8(0); // base offset size and index size
+        iloc_data.put_u16(1); // item count
+        iloc_data.put_u16(1); // item ID
+        iloc_data.put_u16(0); // data reference index
+        iloc_data.put_u16(1); // extent count
+        iloc_data.put_u32(0); // extent offset
+        iloc_data.put_u32(1); // extent length
+        let iloc = Mp4Box::with_bytes(BoxType::ILOC, iloc_data);
+        let meta = MetaBox::with_children(vec![test_hdlr(parse::fourcc::META), iloc]);
+
+        let mut data = vec![];
+        test_ftyp().build().put_buf(&mut data);
+        Mp4Box::with_data(meta.into()).unwrap().put_buf(&mut data);
+        write_test_mdat(&mut data, b"abcdefg");
+
+        let config = Config::builder().preserve_meta(true).build();
+        assert_matches!(sanitize_with_config(io::Cursor::new(data), config).unwrap_err(), Error::Parse(err) => {
+            assert_matches!(err.into_inner(), ParseError::UnsupportedBoxLayout);
+        });
+    }
+
+    #[test]
+    fn multiple_mdat() {
+        test_mp4()
+            .boxes(&[FTYP, MDAT, FREE, MDAT, MDAT, FREE, MOOV][..])
+            .build()
+            .sanitize_ok();
+    }
+
+    #[test]
+    fn multiple_mdat_skip() {
+        let test = test_mp4()
+            .boxes(&[FTYP, MDAT, SKIP, MDAT, MDAT, SKIP, MOOV][..])
+            .build();
+        let sanitized = test.sanitize_ok();
+        let data = &test.data[sanitized.data.offset as usize..][..sanitized.data.len as usize];
+        assert!(data.windows(4).any(|window| window == b"skip"));
+    }
+
+    #[test]
+    fn discontiguous_mdat() {
+        let mdat_data = b"abcdefg";
+        let (first_mdat_data, second_mdat_data) = mdat_data.split_at(3);
+        let moov = |co_entries| test_moov().co_entries(co_entries).build();
+
+        // Write ftyp, mdat, moov, mdat, with the first chunks in the first mdat and the rest in the second.
+        let mut input = vec![];
+        test_ftyp().build().put_buf(&mut input);
+        let first_mdat = write_test_mdat(&mut input, first_mdat_data);
+        let moov_offset = input.len();
+        let moov_len = moov(vec![0; mdat_data.len()]).encoded_len();
+        let second_mdat_offset = moov_offset as u64 + moov_len;
+        let co_entries: Vec<u64> = (0..mdat_data.len() as u64)
+            .map(|idx| match idx.checked_sub(first_mdat_data.len() as u64) {
+                None => first_mdat.offset + 8 + idx,
+                Some(second_idx) => second_mdat_offset + 8 + second_idx,
+            })
+            .collect();
+        moov(co_entries).put_buf(&mut input);
+        let second_mdat = write_test_mdat(&mut input, second_mdat_data);
+        assert_eq!(second_mdat.offset, second_mdat_offset);
+
+        assert_matches!(sanitize(io::Cursor::new(&input)).unwrap_err(), Error::Parse(err) => {
+            assert_matches!(err.into_inner(), ParseError::UnsupportedBoxLayout);
+        });
+
+        let config = Config::builder().allow_discontiguous_mdat(true).build();
+        let sanitized = sanitize_with_config(io::Cursor::new(&input), config).unwrap();
+        assert_eq!(sanitized.data, first_mdat);
+        assert_eq!(sanitized.additional_data, [second_mdat]);
+
+        let output = sanitized.reconstruct(&input);
+        let metadata_len = sanitized.metadata.as_ref().unwrap().len() as u64;
+        assert_eq!(output.len() as u64, metadata_len + first_mdat.len + second_mdat.len);
+        assert_eq!(sanitized.layout_map().len, output.len() as u64);
+
+        let mut parsed = super::parse_metadata(io::Cursor::new(&output)).unwrap();
+        let trak = parsed.moov.traks().next().unwrap().unwrap();
+        let StblCoMut::Stco(stco) = trak.co_mut().unwrap() else {
+            unreachable!();
+        };
+        let output_samples: Vec<u8> = stco
+            .entries()
+            .map(|entry| output[entry.get().unwrap() as usize])
+            .collect();
+        assert_eq!(output_samples, mdat_data);
+        ffmpeg_assert_eq(&output, mdat_data);
+
+        let config = Config::builder().allow_discontiguous_mdat(true).build();
+        let chunks = collect_sanitize_async_stream(&input, config).unwrap();
+        assert_eq!(chunks.concat(), output);
+
+        let config = Config::builder().allow_discontiguous_mdat(true).build();
+        assert_matches!(super::sanitize_to_with_config(io::Cursor::new(&input), vec![], config).unwrap_err(), Error::Parse(err) => {
+            assert_matches!(err.into_inner(), ParseError::UnsupportedBoxLayout);
+        });
+    }
+
+    #[test]
+    fn uuid() {
+        let test = test_mp4().boxes(&[FTYP, MOOV, TEST_UUID, MDAT][..]).build();
+        assert_matches!(sanitize(test).unwrap_err(), Error::Parse(err) => {
+            assert_matches!(err.into_inner(), ParseError::UnsupportedBox(TEST_UUID));
+        });
+    }
+
+    #[test]
+    fn uuid_offset() {
+        let test = test_mp4().boxes(&[FTYP, MOOV, TEST_UUID, MDAT][..]).build();
+        let offset = test.mdat.offset - BoxHeader::with_u32_data_size(TEST_UUID, 0).encoded_len();
+        assert_matches!(sanitize(test).unwrap_err(), Error::Parse(err) => {
+            assert!(format!("{err:?}").contains(&format!("at offset 0x{offset:08x}")), "{err:?}");
+        });
+    }
+
+    #[test]
+    fn truncated_box_header_offset() {
+        let test = test_mp4().boxes(&[FTYP, MOOV, MDAT][..]).build();
+        let data = [&test.data[..], &[0, 0, 0, 8]].concat();
+        let offset = test.data.len();
+        assert_matches!(sanitize(io::Cursor::new(data)).unwrap_err(), Error::Parse(err) => {
+            assert_matches!(err.get_ref(), ParseError::TruncatedBox);
+            assert!(format!("{err:?}").contains(&format!("at offset 0x{offset:08x}")), "{err:?}");
+        });
+    }
+
+    #[test]
+    fn mp41() {
+        let test = test_mp4()
+            .ftyp(test_ftyp().major_brand(MP41).add_compatible_brand(MP41).clone())
+            .build();
+        assert_matches!(sanitize(test).unwrap_err(), Error::Parse(err) => {
+            assert_matches!(err.into_inner(), ParseError::UnsupportedFormat(MP41));
+        });
+    }
+
+    #[test]
+    fn mp42() {
+        let ftyp = test_ftyp()
+            .major_brand(MP42)
+            .compatible_brands(vec![MP42, ISOM])
+            .clone();
+        let test = test_mp4().ftyp(ftyp).build();
+        test.sanitize_ok();
+    }
+
+    #[test]
+    fn compatible_brands_config() {
+        let ftyp = test_ftyp()
+            .major_brand(MP42)
+            .compatible_brands(vec![MP42, ISO2])
+            .clone();
+        let test = test_mp4().ftyp(ftyp).build();
+        assert_matches!(sanitize(test.clone()).unwrap_err(), Error::Parse(err) => {
+            assert!(format!("{err:?}").contains("compatible brands [mp42, iso2]"), "{err:?}");
+            assert_matches!(err.into_inner(), ParseError::UnsupportedFormat(MP42));
+        });
+
+        let config = Config::builder()
+            .add_compatible_brand(COMPATIBLE_BRAND)
+            .add_compatible_brand(ISO2)
+            .build();
+        let sanitized = sanitize_with_config(test.clone(), config).unwrap();
+        assert_eq!(sanitized.metadata.unwrap()[..], test.expected_metadata[..]);
+        assert_eq!(sanitized.data, test.mdat);
+    }
+
+    #[test]
+    fn no_compatible_brands() {
+        let test = test_mp4()
+            .ftyp(test_ftyp().major_brand(ISOM).compatible_brands(vec![]).clone())
+            .build();
+        assert_matches!(sanitize(test).unwrap_err(), Error::Parse(err) => {
+            assert_matches!(err.into_inner(), ParseError::UnsupportedFormat(ISOM));
+        });
+    }
+
+    #[test]
+    fn no_trak() {
+        let test = test_mp4().moov(test_moov().trak(false).clone()).build();
+        assert_matches!(sanitize(test).unwrap_err(), Error::Parse(err) => {
+            assert_matches!(err.into_inner(), ParseError::MissingRequiredBox(TRAK));
+        });
+    }
+
+    #[test]
+    fn no_mdia() {
+        let test = test_mp4()
+            .boxes(&[FTYP, MDAT, MOOV][..])
+            .moov(test_moov().mdia(false).clone())
+            .build();
+        assert_matches!(sanitize(test).unwrap_err(), Error::Parse(err) => {
+            assert_matches!(err.into_inner(), ParseError::MissingRequiredBox(MDIA));
+        });
+    }
+
+    #[test]
+    fn no_minf() {
+        let test = test_mp4()
+            .boxes(&[FTYP, MDAT, MOOV][..])
+            .moov(test_moov().minf(false).clone())
+            .build();
+        assert_matches!(sanitize(test).unwrap_err(), Error::Parse(err) => {
+            assert_matches!(err.into_inner(), ParseError::MissingRequiredBox(MINF));
+        });
+    }
+
+    #[test]
+    fn no_stbl() {
+        let test = test_mp4()
+            .boxes(&[FTYP, MDAT, MOOV][..])
+            .moov(test_moov().stbl(false).clone())
+            .build();
+        assert_matches!(sanitize(test).unwrap_err(), Error::Parse(err) => {
+            assert_matches!(err.into_inner(), ParseError::MissingRequiredBox(STBL));
+        });
+    }
+
+    #[test]
+    fn no_stco() {
+        let test = test_mp4()
+            .boxes(&[FTYP, MDAT, MOOV][..])
+            .moov(test_moov().stco(false).clone())
+            .build();
+        assert_matches!(sanitize(test).unwrap_err(), Error::Parse(err) => {
+            assert_matches!(err.into_inner(), ParseError::MissingRequiredBox(STCO | CO64));
+        });
+    }
+
+    #[test]
+    fn co64() {
+        test_mp4()
+            .boxes(&[FTYP, MDAT, MOOV][..])
+            .moov(test_moov().stco(false).co64(true).clone())
+            .build()
+            .sanitize_ok();
+    }
+
+    #[test]
+    fn stco_and_co64() {
+        let test = test_mp4()
+            .boxes(&[FTYP, MDAT, MOOV][..])
+            .moov(test_moov().co64(true).clone())
+            .build();
+        assert_matches!(sanitize(test).unwrap_err(), Error::Parse(err) => {
+            assert_matches!(err.into_inner(), ParseError::InvalidBoxLayout);
+        });
+    }
+
+    #[test]
+    fn moov_free_boxes_stripped() {
+        let free_test = test_mp4().moov(test_moov().free_len(1024).clone()).build();
+        let test = test_mp4().build();
+
+        let sanitized = sanitize(free_test.clone()).unwrap();
+        let metadata = sanitized.metadata.clone().unwrap();
+        assert_eq!(metadata[..], test.expected_metadata[..]);
+
+        let sanitized_data = sanitized.reconstruct(&free_test.data);
+        let info = probe(io::Cursor::new(&sanitized_data)).unwrap();
+        let keyframe = info.first_keyframe(0).unwrap();
+        assert_eq!(
+            keyframe.offset,
+            metadata.len() as u64 + free_test.mdat.len - free_test.mdat_data.len() as u64
+        );
+        assert_eq!(sanitized_data[keyframe.offset as usize], free_test.mdat_data[0]);
+    }
+
+    #[test]
+    fn moov_free_boxes_preserved() {
+        let test = test_mp4().moov(test_moov().free_len(1024).clone()).build();
+        let config = Config::builder().preserve_moov_free_boxes(true).build();
+        test.sanitize_ok_with_config(config);
+    }
+
+    #[test]
+    fn moov_udta() {
+        let test = test_mp4().moov(test_moov().optional_boxes(true).clone()).build();
+        test.sanitize_ok();
+    }
+
+    #[test]
+    fn top_level_udta() {
+        let test = test_mp4().boxes(&[FTYP, UDTA, MDAT, MOOV, UDTA][..]).build();
+        test.sanitize_ok();
+    }
+
+    #[test]
+    fn strip_all_metadata() {
+        let test = test_mp4()
+            .boxes(&[FTYP, UDTA, MOOV, MDAT][..])
+            .moov(test_moov().optional_boxes(true).clone())
+            .build();
+        test.sanitize_ok_noop();
+
+        let config = Config::builder().strip_all_metadata(true).build();
+        let sanitized = sanitize_with_config(test.clone(), config).unwrap();
+        assert_eq!(sanitized.data, test.mdat);
+        assert_eq!(sanitized.report.stripped_metadata_boxes, [UDTA, UDTA, UDTA]);
+        let metadata = sanitized.metadata.clone().unwrap();
+        for stripped in [&b"udta"[..], b"\xa9xyz", b"+37.3349-122.0090/"] {
+            assert!(!metadata.windows(stripped.len()).any(|window| window == stripped));
+        }
+
+        let sanitized_data = sanitized.reconstruct(&test.data);
+        ffmpeg_assert_eq(&sanitized_data, &test.mdat_data);
+        let config = Config::builder().strip_all_metadata(true).build();
+        let resanitized = sanitize_with_config(io::Cursor::new(&sanitized_data), config).unwrap();
+        assert_eq!(resanitized.metadata, None);
+        assert_eq!(resanitized.report.stripped_metadata_boxes, []);
+    }
+
+    #[test]
+    fn deeply_nested_boxes() {
+        // Container boxes are only parsed along the fixed paths the sanitizer needs, so arbitrarily deep nesting of
+        // other boxes is passed through or removed without being descended into.
+        const DEPTH: u32 = 100_000;
+        let test = test_mp4().build();
+        let moov_offset = (test.mdat.offset + test.mdat.len) as usize;
+        let mut data = test.data.to_vec();
+        for level in 0..DEPTH {
+            BoxHeader::with_u32_data_size(UDTA, 8 * (DEPTH - level - 1)).put_buf(&mut data);
+        }
+        let moov_len = (data.len() - moov_offset) as u32;
+        data[moov_offset..][..4].copy_from_slice(&moov_len.to_be_bytes());
+
+        let sanitized = sanitize(io::Cursor::new(&data)).unwrap();
+        assert_eq!(
+            sanitized.metadata.unwrap().len(),
+            test.expected_metadata.len() + 8 * DEPTH as usize
+        );
+
+        for config in [
+            Config::builder().strip_all_metadata(true).build(),
+            Config::builder().minimize_moov(true).build(),
+        ] {
+            let sanitized = sanitize_with_config(io::Cursor::new(&data), config).unwrap();
+            assert_eq!(sanitized.metadata.unwrap()[..], test.expected_metadata[..]);
+        }
+    }
+
+    #[test]
+    fn minimize_moov() {
+        let optional_test = test_mp4()
+            .moov(test_moov().optional_boxes(true).free_len(1024).clone())
+            .build();
+        let test = test_mp4().build();
+
+        let config = Config::builder().minimize_moov(true).build();
+        let sanitized = sanitize_with_config(optional_test.clone(), config).unwrap();
+        let metadata = sanitized.metadata.clone().unwrap();
+        assert_eq!(metadata[..], test.expected_metadata[..]);
+
+        let sanitized_data = sanitized.reconstruct(&optional_test.data);
+        ffmpeg_assert_eq(&sanitized_data, &optional_test.mdat_data);
+        let info = probe(io::Cursor::new(&sanitized_data)).unwrap();
+        let keyframe = info.first_keyframe(0).unwrap();
+        assert_eq!(sanitized_data[keyframe.offset as usize], optional_test.mdat_data[0]);
+    }
+
+    #[test]
+    fn minimize_moov_dropped_boxes() {
+        let test = test_mp4()
+            .moov(test_moov().optional_boxes(true).free_len(1024).clone())
+            .build();
+
+        let config = Config::builder().minimize_moov(true).build();
+        let sanitized = sanitize_with_config(test.clone(), config).unwrap();
+        assert_eq!(sanitized.report.dropped_boxes, [FREE, UDTA, FREE, UDTA, SDTP]);
+
+        let sanitized = sanitize(test).unwrap();
+        assert_eq!(sanitized.report.dropped_boxes, []);
+    }
+
+    #[test]
+    fn minimize_moov_retains_boxes() {
+        let moov = test_moov()
+            .track_reference(true)
+            .aux_info(true)
+            .pssh_system_ids(vec![[1; 16]])
+            .clone();
+        let optional_test = test_mp4().moov(moov.clone().optional_boxes(true).clone()).build();
+        let test = test_mp4().moov(moov).build();
+
+        let config = Config::builder().minimize_moov(true).build();
+        let sanitized = sanitize_with_config(optional_test.clone(), config).unwrap();
+        assert_eq!(sanitized.report.dropped_boxes, [UDTA, UDTA, SDTP]);
+        let metadata = sanitized.metadata.clone().unwrap();
+        assert_eq!(metadata[..], test.expected_metadata[..]);
+    }
+
+    #[test]
+    fn minimize_moov_before_mdat() {
+        let test = test_mp4()
+            .boxes(&[FTYP, MOOV, MDAT][..])
+            .moov(test_moov().optional_boxes(true).clone())
+            .build();
+        test.sanitize_ok_noop();
+
+        let config = Config::builder().minimize_moov(true).build();
+        let sanitized = sanitize_with_config(test.clone(), config).unwrap();
+        assert_eq!(sanitized.data, test.mdat);
+        assert!(sanitized.metadata.is_some());
+
+        let sanitized_data = sanitized.reconstruct(&test.data);
+        ffmpeg_assert_eq(&sanitized_data, &test.mdat_data);
+        let info = probe(io::Cursor::new(&sanitized_data)).unwrap();
+        let keyframe = info.first_keyframe(0).unwrap();
+        assert_eq!(sanitized_data[keyframe.offset as usize], test.mdat_data[0]);
+    }
+
+    #[test]
+    fn target_mdat_offset() {
+        let test = test_mp4().build();
+        let target_mdat_offset = test.expected_metadata.len() as u64 + 1024;
+
+        let config = Config::builder().target_mdat_offset(Some(target_mdat_offset)).build();
+        let sanitized = sanitize_with_config(test.clone(), config).unwrap();
+        let metadata = sanitized.metadata.clone().unwrap();
+        assert_eq!(metadata.len() as u64, target_mdat_offset);
+        assert_eq!(metadata[test.expected_metadata.len() + 4..][..4], *b"free");
+        assert_eq!(sanitized.report.padding_size, Some(1024));
+
+        let sanitized_data = sanitized.reconstruct(&test.data);
+        ffmpeg_assert_eq(&sanitized_data, &test.mdat_data);
+        let info = probe(io::Cursor::new(&sanitized_data)).unwrap();
+        let keyframe = info.first_keyframe(0).unwrap();
+        assert_eq!(sanitized_data[keyframe.offset as usize], test.mdat_data[0]);
+    }
+
+    #[test]
+    fn edit_list() {
+        let edit_list = vec![
+            ElstEntry { segment_duration: 1, media_time: -1, media_rate: 0x10000 },
+            ElstEntry { segment_duration: 6, media_time: 0, media_rate: 0x10000 },
+        ];
+        let test = test_mp4().moov(test_moov().edit_list(edit_list).clone()).build();
+        test.sanitize_ok();
+    }
+
+    #[test]
+    fn sample_aux_info() {
+        let test = test_mp4().moov(test_moov().aux_info(true).clone()).build();
+        test.sanitize_ok();
+    }
+
+    #[test]
+    fn sample_aux_info_minimized() {
+        let test = test_mp4().moov(test_moov().aux_info(true).clone()).build();
+
+        let config = Config::builder().minimize_moov(true).build();
+        let sanitized = sanitize_with_config(test.clone(), config).unwrap();
+        assert_eq!(sanitized.report.dropped_boxes, []);
+        assert_eq!(sanitized.metadata.unwrap()[..], test.expected_metadata[..]);
+    }
+
+    #[test]
+    fn sample_aux_info_not_within_mdat() {
+        let test = test_mp4().moov(test_moov().aux_info(true).clone()).build();
+        let moov_offset = (test.mdat.offset + test.mdat.len) as usize;
+        let mut moov: Mp4Box<MoovBox> = Mp4Box::parse(&mut BytesMut::from(&test.data[moov_offset..])).unwrap();
+        for trak in moov.data.parse().unwrap().traks() {
+            for saio in trak.unwrap().stbl_mut().unwrap().saios() {
+                saio.unwrap().displace_offsets(test.mdat.len as i64).unwrap();
+            }
+        }
+        let mut data = test.data[..moov_offset].to_vec();
+        moov.put_buf(&mut data);
+
+        assert_matches!(sanitize(io::Cursor::new(data)).unwrap_err(), Error::Parse(err) => {
+            assert_matches!(err.into_inner(), ParseError::InvalidInput);
+        });
+    }
+
+    #[test]
+    fn fragmented() {
+        let test = test_mp4().boxes(&[FTYP, MOOV, MOOF, MDAT, MOOF, MDAT][..]).build();
+        let sanitized = sanitize(test.clone()).unwrap();
+        assert_eq!(sanitized.data, test.mdat);
+        assert_eq!(sanitized.data.offset + sanitized.data.len, test.data.len() as u64);
+        assert_eq!(sanitized.metadata, None);
+    }
+
+    #[test]
+    fn fragmented_displaced() {
+        let test = test_mp4().boxes(&[FTYP, MOOV, MOOF, MDAT, MOOF, MDAT][..]).build();
+        let target_mdat_offset = test.expected_metadata.len() as u64 + 1024;
+
+        let config = Config::builder().target_mdat_offset(Some(target_mdat_offset)).build();
+        let sanitized = sanitize_with_config(test.clone(), config).unwrap();
+        assert_eq!(sanitized.data, test.mdat);
+        assert_eq!(sanitized.metadata.as_ref().unwrap().len() as u64, target_mdat_offset);
+        sanitize(io::Cursor::new(sanitized.reconstruct(&test.data))).unwrap();
+    }
+
+    #[test]
+    fn fragmented_base_data_offset_displaced() {
+        let test = test_mp4()
+            .boxes(&[FTYP, MOOV, MOOF, MDAT, MOOF, MDAT][..])
+            .fragment_base_data_offsets(true)
+            .build();
+        sanitize(test.clone()).unwrap();
+
+        let config = Config::builder()
+            .target_mdat_offset(Some(test.expected_metadata.len() as u64 + 1024))
+            .build();
+        assert_matches!(sanitize_with_config(test, config).unwrap_err(), Error::Parse(err) => {
+            assert_matches!(err.into_inner(), ParseError::UnsupportedBoxLayout);
+        });
+    }
+
+    #[test]
+    fn moof_before_moov() {
+        let test = test_mp4().boxes(&[FTYP, MOOF, MDAT, MOOV][..]).build();
+        assert_matches!(sanitize(test).unwrap_err(), Error::Parse(err) => {
+            assert_matches!(err.into_inner(), ParseError::InvalidBoxLayout);
+        });
+    }
+
+    #[test]
+    fn moof_without_mdat() {
+        let test = test_mp4().boxes(&[FTYP, MOOV, MDAT, MOOF][..]).build();
+        assert_matches!(sanitize(test).unwrap_err(), Error::Parse(err) => {
+            assert_matches!(err.into_inner(), ParseError::InvalidBoxLayout);
+        });
+    }
+
+    #[test]
+    fn media_segment() {
+        let mut styp_data = BytesMut::new();
+        FtypBox::new(ISOM, 0, [ISOM]).put_buf(&mut styp_data);
+        let mut data = vec![];
+        AnyMp4Box::with_bytes(STYP, styp_data).put_buf(&mut data);
+        let styp_len = data.len() as u64;
+        test_sidx(0, 0).put_buf(&mut data);
+        test_moof(data.len() as u64, 1, 2, false).put_buf(&mut data);
+        write_test_mdat(&mut data, &[0; 2]);
+
+        assert_matches!(super::sanitize(io::Cursor::new(&data)).unwrap_err(), Error::Parse(err) => {
+            assert_matches!(err.into_inner(), ParseError::InvalidBoxLayout);
+        });
+
+        let config = Config::builder().allow_media_segments(true).build();
+        let sanitized = super::sanitize_with_config(io::Cursor::new(&data), config.clone()).unwrap();
+        assert_eq!(sanitized.metadata, None);
+        assert_eq!(
+            sanitized.data,
+            InputSpan { offset: styp_len, len: data.len() as u64 - styp_len }
+        );
+
+        let mut output = vec![];
+        let span = super::sanitize_to_with_config(io::Cursor::new(&data), &mut output, config).unwrap();
+        output.extend_from_slice(&data[span.offset as usize..][..span.len as usize]);
+        assert_eq!(output, data);
+    }
+
+    #[test]
+    fn media_segment_strip_all_metadata() {
+        let mut styp_data = BytesMut::new();
+        FtypBox::new(ISOM, 0, [ISOM]).put_buf(&mut styp_data);
+        let mut styp = vec![];
+        AnyMp4Box::with_bytes(STYP, styp_data).put_buf(&mut styp);
+        let mut fragments = vec![];
+        test_moof(0, 1, 2, false).put_buf(&mut fragments);
+        write_test_mdat(&mut fragments, &[0; 2]);
+        let mut udta = vec![];
+        test_location_udta().put_buf(&mut udta);
+        let data = [&styp[..], &udta, &fragments].concat();
+
+        let config = Config::builder()
+            .allow_media_segments(true)
+            .strip_all_metadata(true)
+            .build();
+        let sanitized = super::sanitize_with_config(io::Cursor::new(&data), config).unwrap();
+        assert_eq!(sanitized.metadata.as_deref(), Some(&styp[..]));
+        assert_eq!(sanitized.report.stripped_metadata_boxes, [UDTA]);
+        assert_eq!(sanitized.reconstruct(&data), [&styp[..], &fragments].concat());
+    }
+
+    #[test]
+    fn media_segment_leading_data() {
+        let mut styp_data = BytesMut::new();
+        FtypBox::new(ISOM, 0, [ISOM]).put_buf(&mut styp_data);
+        let mut segment = vec![];
+        AnyMp4Box::with_bytes(STYP, styp_data).put_buf(&mut segment);
+        let styp_len = segment.len();
+        test_moof(styp_len as u64, 1, 2, false).put_buf(&mut segment);
+        write_test_mdat(&mut segment, &[0; 2]);
+        let leading_data = [0xff; 10];
+        let data = [&leading_data[..], &segment].concat();
+
+        let config = Config::builder()
+            .allow_media_segments(true)
+            .max_leading_data_len(leading_data.len() as u64)
+            .build();
+        let sanitized = super::sanitize_with_config(io::Cursor::new(&data), config.clone()).unwrap();
+        assert_eq!(sanitized.metadata.as_deref(), Some(&segment[..styp_len]));
+        assert_eq!(sanitized.reconstruct(&data), segment);
+
+        let err = super::sanitize_with_config(io::Cursor::new(&leading_data), config).unwrap_err();
+        assert_matches!(err, Error::Parse(err) => {
+            assert!(format!("{err:?}").contains("no ftyp or styp within the first 10 bytes"), "{err:?}");
+        });
+    }
+
+    #[test]
+    fn media_segment_with_moov() {
+        let mut styp_data = BytesMut::new();
+        FtypBox::new(ISOM, 0, [ISOM]).put_buf(&mut styp_data);
+        let mut data = vec![];
+        AnyMp4Box::with_bytes(STYP, styp_data).put_buf(&mut data);
+        test_moov().build().put_buf(&mut data);
+        write_test_mdat(&mut data, &[0; 2]);
+
+        let config = Config::builder().allow_media_segments(true).build();
+        assert_matches!(super::sanitize_with_config(io::Cursor::new(&data), config).unwrap_err(), Error::Parse(err) => {
+            assert_matches!(err.into_inner(), ParseError::InvalidBoxLayout);
+        });
+    }
+
+    #[test]
+    fn align_mdat_data() {
+        for align in [16, 4096] {
+            let test = test_mp4().build();
+            let config = Config::builder().align_mdat_data(Some(align)).build();
+            let sanitized = sanitize_with_config(test.clone(), config).unwrap();
+            let metadata_len = sanitized.metadata.as_ref().unwrap().len() as u64;
+            let mdat_header_len = test.mdat.len - test.mdat_data.len() as u64;
+            assert_eq!((metadata_len + mdat_header_len) % align, 0);
+
+            let sanitized_data = sanitized.reconstruct(&test.data);
+            ffmpeg_assert_eq(&sanitized_data, &test.mdat_data);
+            let info = probe(io::Cursor::new(&sanitized_data)).unwrap();
+            let keyframe = info.first_keyframe(0).unwrap();
+            assert_eq!(keyframe.offset % align, 0);
+            assert_eq!(sanitized_data[keyframe.offset as usize], test.mdat_data[0]);
+        }
+    }
+
+    #[test]
+    fn align_mdat_data_already_aligned() {
+        let test = test_mp4().boxes(&[FTYP, MOOV, MDAT][..]).build();
+        let config = Config::builder().align_mdat_data(Some(1)).build();
+        let sanitized = sanitize_with_config(test, config).unwrap();
+        assert_eq!(sanitized.metadata, None);
+    }
+
+    /// An input consisting of `prefix`, followed by `gap_len` zero bytes, followed by `suffix`.
+    #[derive(Clone)]
+    struct GapInput {
+        prefix: Vec<u8>,
+        gap_len: u64,
+        suffix: Vec<u8>,
+        position: u64,
+    }
+
+    impl io::Read for GapInput {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let prefix_len = self.prefix.len() as u64;
+            let suffix_offset = prefix_len + self.gap_len;
+            let read_len = if self.position < prefix_len {
+                (&self.prefix[self.position as usize..]).read(buf)?
+            } else if self.position < suffix_offset {
+                let read_len = (buf.len() as u64).min(suffix_offset - self.position) as usize;
+                buf[..read_len].fill(0);
+                read_len
+            } else {
+                let suffix = self.suffix.get((self.position - suffix_offset) as usize..);
+                suffix.unwrap_or_default().read(buf)?
+            };
+            self.position += read_len as u64;
+            Ok(read_len)
+        }
+    }
+
+    impl Skip for GapInput {
+        fn skip(&mut self, amount: u64) -> io::Result<()> {
+            self.position += amount;
+            Ok(())
+        }
+
+        fn stream_position(&mut self) -> io::Result<u64> {
+            Ok(self.position)
+        }
+
+        fn stream_len(&mut self) -> io::Result<u64> {
+            Ok(self.prefix.len() as u64 + self.gap_len + self.suffix.len() as u64)
+        }
+    }
+
+    #[test]
+    fn pad_larger_than_free_box() {
+        let mdat_data = b"abcdefg";
+        let gap_len = u64::from(u32::MAX) + 1024;
+        let gap_header = BoxHeader::with_data_size(FREE, gap_len).unwrap();
+        let moov = |co_entries| {
+            test_moov()
+                .optional_boxes(true)
+                .stco(false)
+                .co64(true)
+                .co_entries(co_entries)
+                .build()
+        };
+
+        let mut prefix = vec![];
+        test_ftyp().build().put_buf(&mut prefix);
+        let moov_len = moov(vec![0; mdat_data.len()]).encoded_len();
+        let mdat_offset = prefix.len() as u64 + moov_len + gap_header.encoded_len() + gap_len;
+        let co_entries: Vec<u64> = (0..mdat_data.len() as u64).map(|idx| mdat_offset + 8 + idx).collect();
+        moov(co_entries.clone()).put_buf(&mut prefix);
+        gap_header.put_buf(&mut prefix);
+        let mut suffix = vec![];
+        write_test_mdat(&mut suffix, mdat_data);
+        let input = GapInput { prefix, gap_len, suffix, position: 0 };
+
+        let config = Config::default();
+        let SanitizedBoxes { metadata, data, report, .. } =
+            sync::sanitize(input.clone(), |input| sanitize_boxes(input, &config, true)).unwrap();
+        let mut metadata = metadata.unwrap();
+        assert_eq!(data.offset, mdat_offset);
+        assert_eq!(metadata.encoded_len(), mdat_offset);
+        assert_eq!(report.mdat_displacement, None);
+        assert_eq!(report.padding_size, Some(metadata.pad_size));
+
+        let pad_box_sizes: Vec<u64> = pad_box_sizes(metadata.pad_size).collect();
+        assert_eq!(pad_box_sizes.len(), 2);
+        assert_eq!(pad_box_sizes.iter().sum::<u64>(), metadata.pad_size);
+        assert!(pad_box_sizes
+            .iter()
+            .all(|size| (PAD_HEADER_SIZE..=MAX_PAD_SIZE).contains(size)));
+
+        let mut traks = metadata.moov.as_mut().unwrap().data.parse().unwrap().traks();
+        let trak = traks.next().unwrap().unwrap();
+        assert_matches!(trak.co_mut().unwrap(), StblCoMut::Co64(co64) => {
+            let entries: Vec<u64> = co64.entries_mut().map(|entry| entry.get().unwrap()).collect();
+            assert_eq!(entries, co_entries);
+        });
+
+        let config = Config::builder().max_pad_size(MAX_PAD_SIZE).build();
+        let SanitizedBoxes { metadata, report, .. } =
+            sync::sanitize(input, |input| sanitize_boxes(input, &config, true)).unwrap();
+        let metadata = metadata.unwrap();
+        assert_eq!(metadata.pad_size, 0);
+        assert_eq!(
+            report.mdat_displacement,
+            Some(metadata.encoded_len() as i64 - mdat_offset as i64)
+        );
+    }
+
+    #[test]
+    fn displacement_beyond_i32() {
+        let mdat_data = b"abcdefg";
+        let gap_len = 2 * u64::from(u32::MAX);
+        let gap_header = BoxHeader::with_data_size(FREE, gap_len).unwrap();
+        let moov = |co_entries| test_moov().stco(false).co64(true).co_entries(co_entries).build();
+
+        let mut prefix = vec![];
+        test_ftyp().build().put_buf(&mut prefix);
+        let moov_len = moov(vec![0; mdat_data.len()]).encoded_len();
+        let metadata_len = prefix.len() as u64 + moov_len;
+        let mdat_offset = metadata_len + gap_header.encoded_len() + gap_len;
+        let co_entries: Vec<u64> = (0..mdat_data.len() as u64).map(|idx| mdat_offset + 8 + idx).collect();
+        moov(co_entries).put_buf(&mut prefix);
+        gap_header.put_buf(&mut prefix);
+        let mut suffix = vec![];
+        write_test_mdat(&mut suffix, mdat_data);
+        let input = GapInput { prefix, gap_len, suffix, position: 0 };
+
+        let config = Config::builder().faststart(true).max_pad_size(0).build();
+        let SanitizedBoxes { metadata, data, report, .. } =
+            sync::sanitize(input, |input| sanitize_boxes(input, &config, false)).unwrap();
+        let mut metadata = metadata.unwrap();
+        assert_eq!(data.offset, mdat_offset);
+        assert_eq!(metadata.encoded_len(), metadata_len);
+        let mdat_displacement = -i64::try_from(mdat_offset - metadata_len).unwrap();
+        assert!(mdat_displacement < i32::MIN.into());
+        assert_eq!(report.mdat_displacement, Some(mdat_displacement));
+
+        let mut traks = metadata.moov.as_mut().unwrap().data.parse().unwrap().traks();
+        let trak = traks.next().unwrap().unwrap();
+        assert_matches!(trak.co_mut().unwrap(), StblCoMut::Co64(co64) => {
+            let entries: Vec<u64> = co64.entries_mut().map(|entry| entry.get().unwrap()).collect();
+            let expected_entries: Vec<u64> = (0..mdat_data.len() as u64).map(|idx| metadata_len + 8 + idx).collect();
+            assert_eq!(entries, expected_entries);
+        });
+    }
+
+    #[test]
+    fn pad_box_sizes_split() {
+        assert_eq!(pad_box_sizes(0).collect::<Vec<_>>(), []);
+        assert_eq!(pad_box_sizes(MAX_PAD_SIZE).collect::<Vec<_>>(), [MAX_PAD_SIZE]);
+        assert_eq!(
+            pad_box_sizes(MAX_PAD_SIZE + 1).collect::<Vec<_>>(),
+            [MAX_PAD_SIZE + 1 - PAD_HEADER_SIZE, PAD_HEADER_SIZE],
+        );
+        assert_eq!(
+            pad_box_sizes(2 * MAX_PAD_SIZE + 16).collect::<Vec<_>>(),
+            [MAX_PAD_SIZE, MAX_PAD_SIZE, 16],
+        );
+    }
+
+    #[test]
+    fn target_mdat_offset_before_metadata_end() {
+        let test = test_mp4().build();
+        let target_mdat_offset = test.expected_metadata.len() as u64 - 1;
+
+        let config = Config::builder().target_mdat_offset(Some(target_mdat_offset)).build();
+        assert_matches!(sanitize_with_config(test, config).unwrap_err(), Error::Parse(err) => {
+            assert_matches!(err.into_inner(), ParseError::UnsupportedBoxLayout);
+        });
+    }
+
+    #[test]
+    fn samples_past_mdat_end() {
+        let test = test_mp4().build();
+        let moov_offset = (test.mdat.offset + test.mdat.len) as usize;
+        let mut moov: Mp4Box<MoovBox> = Mp4Box::parse(&mut BytesMut::from(&test.data[moov_offset..])).unwrap();
+        for trak in moov.data.parse().unwrap().traks() {
+            let StblCoMut::Stco(stco) = trak.unwrap().co_mut().unwrap() else {
+                unreachable!();
+            };
+            let mut entry = stco.entries_mut().last().unwrap();
+            entry.set(entry.get().unwrap() + 1);
+        }
+        let mut data = test.data[..moov_offset].to_vec();
+        moov.put_buf(&mut data);
+
+        assert_matches!(sanitize(io::Cursor::new(data)).unwrap_err(), Error::Parse(err) => {
+            assert_matches!(err.into_inner(), ParseError::InvalidInput);
+        });
+    }
+
+    #[test]
+    fn faststart() {
+        let config = || Config::builder().faststart(true).build();
+
+        let test = test_mp4().boxes(&[FTYP, MDAT, MOOV][..]).build();
+        let sanitized = test.sanitize_ok_with_config(config());
+        let sanitized_data = sanitized.reconstruct(&test.data);
+        let moov_offset = test_ftyp().build().encoded_len() as usize;
+        assert_eq!(&sanitized_data[moov_offset + 4..][..4], b"moov");
+        let resanitized = sanitize_with_config(io::Cursor::new(&sanitized_data), config()).unwrap();
+        assert_eq!(resanitized.metadata.as_deref(), sanitized.metadata.as_deref());
+
+        let test = test_mp4().boxes(&[FTYP, MOOV, MDAT][..]).build();
+        assert_eq!(sanitize(test.clone()).unwrap().metadata, None);
+        test.sanitize_ok_with_config(config());
+    }
+
+    #[test]
+    fn chunk_offset_not_within_mdat() {
+        let test = test_mp4().build();
+        let moov_offset = (test.mdat.offset + test.mdat.len) as usize;
+        let mut moov: Mp4Box<MoovBox> = Mp4Box::parse(&mut BytesMut::from(&test.data[moov_offset..])).unwrap();
+        for trak in moov.data.parse().unwrap().traks() {
+            let StblCoMut::Stco(stco) = trak.unwrap().co_mut().unwrap() else {
+                unreachable!();
+            };
+            // Point the first chunk into the ftyp, leaving the last sample within the mdat.
+            stco.entries_mut().next().unwrap().set(0);
+        }
+        let mut data = test.data[..moov_offset].to_vec();
+        moov.put_buf(&mut data);
+
+        assert_matches!(sanitize(io::Cursor::new(&data)).unwrap_err(), Error::Parse(err) => {
+            assert!(format!("{err:?}").contains("chunk offset 0x00000000 of entry 0 not within mdat"), "{err:?}");
+            assert_matches!(err.into_inner(), ParseError::InvalidInput);
+        });
+
+        let config = Config::builder().validate_chunk_offsets(false).build();
+        sanitize_with_config(io::Cursor::new(&data), config).unwrap();
+    }
+
+    #[test]
+    fn stco_displacement_overflow_mixed_co64() {
+        let mdat_data_len = 1 << 32;
+        let test = test_mp4()
+            .boxes(&[FTYP, MOOV, MDAT][..])
+            .mdat_data_len(mdat_data_len)
+            .moov(test_moov().disabled_trak(true).disabled_trak_co64(true).clone())
+            .build();
+
+        // Move the last chunk of the stco track to the end of 32-bit offsets, so that it overflows when displaced.
+        let moov_offset = test_ftyp().build().encoded_len() as usize;
+        let mut moov: Mp4Box<MoovBox> = Mp4Box::parse(&mut BytesMut::from(&test.data[moov_offset..])).unwrap();
+        for trak in moov.data.parse().unwrap().traks() {
+            if let StblCoMut::Stco(stco) = trak.unwrap().co_mut().unwrap() {
+                stco.entries_mut().last().unwrap().set(u32::MAX - 1);
+            }
+        }
+        let mut data = test.data.to_vec();
+        moov.put_buf(&mut data[moov_offset..]);
+        let test = TestMp4 { data: data.into(), ..test };
+
+        let config = Config::builder()
+            .target_mdat_offset(Some(test.mdat.offset + 64))
+            .build();
+        let sanitized = sanitize_with_config(test.clone(), config).unwrap();
+        assert_eq!(sanitized.data, test.mdat);
+        let metadata = sanitized.metadata.unwrap();
+        assert_eq!(metadata.len() as u64, test.mdat.offset + 64);
+
+        let mut metadata = BytesMut::from(&metadata[moov_offset..]);
+        let mut moov: Mp4Box<MoovBox> = Mp4Box::parse(&mut metadata).unwrap();
+        let mut traks = moov.data.parse().unwrap().traks();
+        assert_matches!(traks.next().unwrap().unwrap().co_mut().unwrap(), StblCoMut::Co64(co64) => {
+            let last_entry = co64.entries().last().unwrap().get().unwrap();
+            assert_eq!(last_entry, u64::from(u32::MAX) - 1 + 64);
+        });
+        assert_matches!(traks.next().unwrap().unwrap().co_mut().unwrap(), StblCoMut::Co64(co64) => {
+            assert!(co64.entries().all(|entry| entry.get().unwrap() < u64::from(u32::MAX)));
+        });
+    }
+
+    #[test]
+    fn chunked_samples_exceed_sample_count() {
+        let test = test_mp4().build();
+        let moov_offset = (test.mdat.offset + test.mdat.len) as usize;
+        let mut moov: Mp4Box<MoovBox> = Mp4Box::parse(&mut BytesMut::from(&test.data[moov_offset..])).unwrap();
+        for trak in moov.data.parse().unwrap().traks() {
+            let stsz = trak.unwrap().stbl_mut().unwrap().stsz_mut().unwrap();
+            *stsz = StszBox::with_sample_size(1, stsz.sample_count() - 1);
+        }
+        let mut data = test.data[..moov_offset].to_vec();
+        moov.put_buf(&mut data);
+
+        assert_matches!(sanitize(io::Cursor::new(data)).unwrap_err(), Error::Parse(err) => {
+            assert!(format!("{err:?}").contains("while parsing `stsc` box"), "{err:?}");
+            assert_matches!(err.into_inner(), ParseError::InvalidInput);
+        });
+    }
+
+    #[test]
+    fn validate_collect_all_two_broken_traks() {
+        let test = test_mp4().moov(test_moov().disabled_trak(true).clone()).build();
+        let moov_offset = (test.mdat.offset + test.mdat.len) as usize;
+        let mut moov: Mp4Box<MoovBox> = Mp4Box::parse(&mut BytesMut::from(&test.data[moov_offset..])).unwrap();
+        for trak in moov.data.parse().unwrap().traks() {
+            let StblCoMut::Stco(stco) = trak.unwrap().co_mut().unwrap() else {
+                unreachable!();
+            };
+            let mut entry = stco.entries_mut().last().unwrap();
+            entry.set(entry.get().unwrap() + 1);
+        }
+        let mut data = test.data[..moov_offset].to_vec();
+        moov.put_buf(&mut data);
+
+        let (_, errors) = validate_collect_all(io::Cursor::new(data));
+        assert_eq!(errors.len(), 2, "{errors:?}");
+        for err in errors {
+            assert_matches!(err, Error::Parse(err) => {
+                assert_matches!(err.into_inner(), ParseError::InvalidInput);
+            });
+        }
+    }
+
+    #[test]
+    fn validate_collect_all_unrecoverable() {
+        let test = test_mp4().build();
+        let (report, errors) = validate_collect_all(io::Cursor::new(&test.data[..test.data.len() - 1]));
+        assert_eq!(report, SanitizeReport::default());
+        assert_matches!(&errors[..], [Error::Parse(err)] => {
+            assert_matches!(err.get_ref(), ParseError::TruncatedBox);
+        });
+    }
+
+    #[test]
+    fn validate_collect_all_sanitize_phase() {
+        let test = test_mp4().boxes(&[FTYP, SIDX, MDAT, MOOV][..]).build();
+        let first_offset_offset = test_ftyp().build().encoded_len() as usize + 24;
+        let mut data = test.data.to_vec();
+        data[first_offset_offset..][..4].copy_from_slice(&u32::MAX.to_be_bytes());
+
+        assert_matches!(super::validate(io::Cursor::new(&data)).unwrap_err(), Error::Parse(err) => {
+            assert!(format!("{err:?}").contains("does not reference the media data"), "{err:?}");
+        });
+        let (report, errors) = validate_collect_all(io::Cursor::new(&data));
+        assert_eq!(report, SanitizeReport::default());
+        assert_matches!(&errors[..], [Error::Parse(err)] => {
+            assert!(format!("{err:?}").contains("does not reference the media data"), "{err:?}");
+            assert_matches!(err.get_ref(), ParseError::InvalidInput);
+        });
+    }
+
+    #[test]
+    fn slice_reader_bytes() {
+        let test = test_mp4().build();
+        let sanitized = sanitize(SliceReader::new(test.data.clone())).unwrap();
+        assert_eq!(sanitized.metadata.unwrap()[..], test.expected_metadata[..]);
+        assert_eq!(sanitized.data, test.mdat);
+
+        let sanitized = sanitize_async(SliceReader::new(&test.data[..]))
+            .now_or_never()
+            .unwrap()
+            .unwrap();
+        assert_eq!(sanitized.metadata.unwrap()[..], test.expected_metadata[..]);
+        assert_eq!(sanitized.data, test.mdat);
+    }
+
+    #[test]
+    fn sanitize_read_only_bare_read() {
+        let test = test_mp4().build();
+        let sanitized = sanitize_read_only(&test.data[..]).unwrap();
+        assert_eq!(sanitized.metadata.unwrap()[..], test.expected_metadata[..]);
+        assert_eq!(sanitized.data, test.mdat);
+    }
+
+    /// An input which can only be read forward, like a network socket, implementing [`AsyncSkip`] without seeking.
+    struct ForwardOnlyInput {
+        data: Vec<u8>,
+        position: usize,
+    }
+
+    impl ForwardOnlyInput {
+        fn new(data: Vec<u8>) -> Self {
+            Self { data, position: 0 }
+        }
+    }
+
+    impl AsyncRead for ForwardOnlyInput {
+        fn poll_read(mut self: Pin<&mut Self>, _cx: &mut Context<'_>, buf: &mut [u8]) -> Poll<io::Result<usize>> {
+            let remaining = self.data.get(self.position..).unwrap_or_default();
+            let read_len = remaining.len().min(buf.len());
+            buf[..read_len].copy_from_slice(&remaining[..read_len]);
+            self.position += read_len;
+            Poll::Ready(Ok(read_len))
+        }
+    }
+
+    impl AsyncSkip for ForwardOnlyInput {
+        fn poll_skip(mut self: Pin<&mut Self>, _cx: &mut Context<'_>, amount: u64) -> Poll<io::Result<()>> {
+            let amount = usize::try_from(amount).map_err(|_| io::Error::from(io::ErrorKind::InvalidInput))?;
+            self.position = self.position.saturating_add(amount);
+            Poll::Ready(Ok(()))
+        }
+
+        fn poll_stream_position(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<u64>> {
+            Poll::Ready(Ok(self.position as u64))
+        }
+
+        fn poll_stream_len(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<u64>> {
+            Poll::Ready(Err(io::Error::new(io::ErrorKind::Unsupported, "unknown stream length")))
+        }
+    }
+
+    #[cfg(feature = "tokio")]
+    #[test]
+    fn tokio_input() {
+        let test = test_mp4().mdat_data(&b"abcdefg"[..]).build();
+        let sanitized = sanitize_tokio(io::Cursor::new(&test.data))
+            .now_or_never()
+            .unwrap()
+            .unwrap();
+        assert_eq!(sanitized.metadata.unwrap()[..], test.expected_metadata[..]);
+        assert_eq!(sanitized.data, test.mdat);
+    }
+
+    #[test]
+    fn forward_only_async_input() {
+        let test = test_mp4().mdat_data(&b"abcdefg"[..]).build();
+        let input = ForwardOnlyInput::new(test.data.to_vec());
+        let sanitized = sanitize_async(input).now_or_never().unwrap().unwrap();
+        assert_eq!(sanitized.metadata.unwrap()[..], test.expected_metadata[..]);
+        assert_eq!(sanitized.data, test.mdat);
+    }
+
+    #[test]
+    fn forward_only_async_input_until_eof_mdat() {
+        let test = test_mp4()
+            .boxes(&[FTYP, MOOV, MDAT][..])
+            .mdat_data(&b"abcdefg"[..])
+            .mdat_data_until_eof()
+            .build();
+        let input = ForwardOnlyInput::new(test.data.to_vec());
+        assert_matches!(sanitize_async(input).now_or_never().unwrap().unwrap_err(), Error::Parse(err) => {
+            assert_matches!(err.into_inner(), ParseError::UnsupportedBoxLayout);
+        });
+    }
+
+    /// An input which delivers its data in chunks of at most the given sizes, in turn, and which isn't ready before each
+    /// chunk, to exercise partial reads across box boundaries.
+    struct ChunkedInput {
+        inner: SliceReader<Vec<u8>>,
+        chunk_sizes: Vec<usize>,
+        chunk_idx: usize,
+        ready: bool,
+    }
+
+    impl ChunkedInput {
+        fn new(data: Vec<u8>, chunk_sizes: Vec<usize>) -> Self {
+            Self { inner: SliceReader::new(data), chunk_sizes, chunk_idx: 0, ready: false }
+        }
+    }
+
+    impl AsyncRead for ChunkedInput {
+        fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut [u8]) -> Poll<io::Result<usize>> {
+            if !mem::replace(&mut self.ready, false) {
+                self.ready = true;
+                cx.waker().wake_by_ref();
+                return Poll::Pending;
+            }
+            let chunk_size = self.chunk_sizes[self.chunk_idx % self.chunk_sizes.len()];
+            self.chunk_idx += 1;
+            let read_len = buf.len().min(chunk_size);
+            Pin::new(&mut self.inner).poll_read(cx, &mut buf[..read_len])
+        }
+    }
+
+    impl AsyncSkip for ChunkedInput {
+        fn poll_skip(mut self: Pin<&mut Self>, cx: &mut Context<'_>, amount: u64) -> Poll<io::Result<()>> {
+            Pin::new(&mut self.inner).poll_skip(cx, amount)
+        }
+
+        fn poll_stream_position(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<u64>> {
+            Pin::new(&mut self.inner).poll_stream_position(cx)
+        }
+
+        fn poll_stream_len(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<u64>> {
+            Pin::new(&mut self.inner).poll_stream_len(cx)
+        }
+    }
+
+    /// Poll `future` to completion, without waiting in between polls.
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let mut future = Box::pin(future);
+        let mut cx = Context::from_waker(futures_util::task::noop_waker_ref());
+        loop {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+                return output;
+            }
+        }
+    }
+
+    #[test]
+    fn chunked_async_input() {
+        let tests = [
+            test_mp4().mdat_data(&b"abcdefg"[..]).build(),
+            test_mp4()
+                .boxes(&[FTYP, MDAT, MOOV][..])
+                .mdat_data(&b"abcdefg"[..])
+                .build(),
+            test_mp4()
+                .boxes(&[FTYP, MOOV, MDAT][..])
+                .mdat_data(&b"abcdefg"[..])
+                .mdat_data_until_eof()
+                .build(),
+        ];
+        for test in tests {
+            let expected = sanitize(io::Cursor::new(&test.data)).unwrap();
+            for chunk_sizes in [vec![1], vec![3, 1, 7], vec![4096]] {
+                let input = ChunkedInput::new(test.data.to_vec(), chunk_sizes);
+                assert_eq!(block_on(sanitize_async(input)).unwrap(), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn chunked_async_input_truncated() {
+        let test = test_mp4().mdat_data(&b"abcdefg"[..]).build();
+        let moov_end = test.data.len() - test.mdat.len as usize;
+        for len in [3, 9, moov_end - 1] {
+            let input = ChunkedInput::new(test.data[..len].to_vec(), vec![2, 5]);
+            assert_matches!(block_on(sanitize_async(input)), Err(_));
+        }
+    }
+
+    fn collect_sanitize_async_stream(data: &[u8], config: Config) -> Result<Vec<Bytes>, Error> {
+        let stream = sanitize_async_stream_with_config(futures_util::io::Cursor::new(data), config);
+        stream.try_collect().now_or_never().unwrap()
+    }
+
+    #[test]
+    fn async_stream() {
+        let test = test_mp4().mdat_data(&b"abcdefg"[..]).build();
+        let sanitized = sanitize(test.clone()).unwrap();
+        let chunks = collect_sanitize_async_stream(&test.data, Config::default()).unwrap();
+        assert_eq!(chunks[0][..], test.expected_metadata[..]);
+        assert_eq!(chunks.concat(), sanitized.reconstruct(&test.data));
+    }
+
+    #[test]
+    fn async_stream_metadata_unchanged() {
+        let test = test_mp4().boxes(&[FTYP, MOOV, MDAT][..]).build();
+        let sanitized = sanitize(test.clone()).unwrap();
+        assert_eq!(sanitized.metadata, None);
+        let chunks = collect_sanitize_async_stream(&test.data, Config::default()).unwrap();
+        assert_eq!(chunks.concat(), sanitized.reconstruct(&test.data));
+    }
+
+    #[test]
+    fn async_stream_chunked_mdat() {
+        let mdat_data = (0..3 * STREAM_CHUNK_SIZE / 2).map(|i| i as u8).collect::<Vec<_>>();
+        let test = test_mp4().mdat_data(mdat_data).build();
+        let sanitized = sanitize(test.clone()).unwrap();
+        let chunks = collect_sanitize_async_stream(&test.data, Config::default()).unwrap();
+        assert!(chunks[1..].iter().all(|chunk| chunk.len() as u64 <= STREAM_CHUNK_SIZE));
+        assert_eq!(chunks.concat(), sanitized.reconstruct(&test.data));
+    }
+
+    #[test]
+    fn async_stream_invalid() {
+        let test = test_mp4().boxes(&[FTYP, MDAT][..]).build();
+        assert_matches!(collect_sanitize_async_stream(&test.data, Config::default()), Err(Error::Parse(err)) => {
+            assert_matches!(err.into_inner(), ParseError::MissingRequiredBox(MOOV));
+        });
+    }
+
+    #[test]
+    fn track_duration_overflow() {
+        let test = test_mp4()
+            .moov(
+                test_moov()
+                    .movie_timescale(90000)
+                    .media_timescale(48000)
+                    .media_duration(u64::MAX / 48000)
+                    .clone(),
+            )
+            .build();
+        assert_matches!(sanitize(test).unwrap_err(), Error::Parse(err) => {
+            assert_matches!(err.into_inner(), ParseError::InvalidInput);
+        });
+    }
+
+    #[test]
+    fn track_duration_large() {
+        test_mp4()
+            .moov(
+                test_moov()
+                    .movie_timescale(1)
+                    .media_timescale(48000)
+                    .media_duration(u64::MAX / 2)
+                    .clone(),
+            )
+            .build()
+            .sanitize_ok();
+    }
+
+    #[test]
+    fn track_duration_unknown() {
+        test_mp4()
+            .moov(
+                test_moov()
+                    .movie_timescale(90000)
+                    .media_timescale(48000)
+                    .media_duration(u64::MAX)
+                    .clone(),
+            )
+            .build()
+            .sanitize_ok();
+    }
+
+    #[test]
+    fn reconstruct() {
+        let test = test_mp4().build();
+        let sanitized = test.sanitize_ok();
+        let output = sanitized.reconstruct(&test.data);
+        let mdat = &test.data[test.mdat.offset as usize..][..test.mdat.len as usize];
+        assert_eq!(output, [&test.expected_metadata[..], mdat].concat());
+
+        let resanitized = sanitize(io::Cursor::new(&output)).unwrap();
+        assert_eq!(resanitized.metadata, None);
+        assert_eq!(resanitized.reconstruct(&output), output);
+    }
+
+    #[test]
+    #[should_panic]
+    fn reconstruct_short_input() {
+        let test = test_mp4().build();
+        let sanitized = test.sanitize_ok();
+        sanitized.reconstruct(&test.data[..(test.mdat.offset + test.mdat.len - 1) as usize]);
+    }
+
+    #[test]
+    fn track_header_duration_unknown() {
+        let test = test_mp4()
+            .moov(test_moov().movie_timescale(90000).track_duration(u64::MAX).clone())
+            .build();
+        let sanitized = test.sanitize_ok();
+        assert_eq!(sanitized.report.unknown_duration_boxes, [TKHD]);
+    }
+
+    #[test]
+    fn remux_stable() {
+        let test = test_mp4().build();
+        let sanitized = test.sanitize_ok();
+        gpac_assert_remux_stable(&sanitized.reconstruct(&test.data), &test.mdat_data);
+    }
+
+    #[test]
+    fn remux_corrupt_sample_timing() {
+        let test = test_mp4().build();
+        let mut data = test.data.to_vec();
+
+        // Give every sample the same decoding time. The samples still demux to the expected media data, but GPAC
+        // refuses to add a sample with the same decoding time as the previous one when remuxing.
+        let stts_offset = data.windows(4).position(|window| window == b"stts").unwrap();
+        let sample_delta_offset = stts_offset + 16;
+        data[sample_delta_offset..sample_delta_offset + 4].copy_from_slice(&0u32.to_be_bytes());
+
+        gpac_assert_eq(&data, &test.mdat_data);
+        gpac_assert_remux_unstable(&data, &test.mdat_data);
+    }
+
+    fn test_trailing_data(trailing_data: &[u8]) -> (TestMp4, Vec<u8>) {
+        let test = test_mp4().build();
+        let data = [&test.data[..], trailing_data].concat();
+        (test, data)
+    }
+
+    #[test]
+    fn trailing_data_fail() {
+        let (_, data) = test_trailing_data(&[0xff; 500]);
+        sanitize(io::Cursor::new(data)).unwrap_err();
+    }
+
+    #[test]
+    fn trailing_data_ignore() {
+        let (test, data) = test_trailing_data(&[0xff; 500]);
+        let config = Config::builder().on_trailing_data(TrailingData::Ignore).build();
+        let sanitized = sanitize_with_config(io::Cursor::new(&data), config).unwrap();
+        assert_eq!(sanitized.data, test.mdat);
+        assert_eq!(sanitized.metadata.as_deref(), Some(&test.expected_metadata[..]));
+        assert_eq!(sanitized.report.trailing_data, None);
+    }
+
+    #[test]
+    fn trailing_data_report() {
+        let (test, data) = test_trailing_data(&[0xff; 500]);
+        let config = Config::builder().on_trailing_data(TrailingData::Report).build();
+        let sanitized = sanitize_with_config(io::Cursor::new(&data), config).unwrap();
+        assert_eq!(sanitized.data, test.mdat);
+        assert_eq!(
+            sanitized.report.trailing_data,
+            Some(InputSpan { offset: test.data.len() as u64, len: 500 })
+        );
+    }
+
+    #[test]
+    fn trailing_data_short() {
+        let (test, data) = test_trailing_data(&[0xff; 3]);
+        sanitize(io::Cursor::new(&data)).unwrap_err();
+
+        let config = Config::builder().on_trailing_data(TrailingData::Report).build();
+        let sanitized = sanitize_with_config(io::Cursor::new(&data), config).unwrap();
+        assert_eq!(
+            sanitized.report.trailing_data,
+            Some(InputSpan { offset: test.data.len() as u64, len: 3 })
+        );
+    }
+
+    #[test]
+    fn trailing_data_before_required_boxes() {
+        let test = test_mp4().boxes(&[FTYP, MDAT][..]).build();
+        let data = [&test.data[..], &[0xff; 500]].concat();
+        let config = Config::builder().on_trailing_data(TrailingData::Ignore).build();
+        sanitize_with_config(io::Cursor::new(data), config).unwrap_err();
+    }
+
+    #[test]
+    fn disabled_trak() {
+        test_mp4()
+            .moov(test_moov().disabled_trak(true).clone())
+            .build()
+            .sanitize_ok();
+    }
+
+    #[test]
+    fn disabled_trak_dropped() {
+        let test = test_mp4()
+            .boxes(&[FTYP, MOOV, MDAT][..])
+            .moov(test_moov().disabled_trak(true).clone())
+            .build();
+        assert_eq!(probe(test.clone()).unwrap().tracks().len(), 2);
+
+        let config = Config::builder().drop_disabled_tracks(true).build();
+        let sanitized = sanitize_with_config(test.clone(), config).unwrap();
+        assert_eq!(sanitized.data, test.mdat);
+        assert_eq!(sanitized.report.dropped_boxes, [TRAK]);
+
+        let sanitized_data = sanitized.reconstruct(&test.data);
+        let info = probe(io::Cursor::new(&sanitized_data)).unwrap();
+        assert_eq!(info.tracks().len(), 1);
+        let keyframe = info.first_keyframe(0).unwrap();
+        assert_eq!(
+            keyframe.offset,
+            test.mdat.offset + test.mdat.len - test.mdat_data.len() as u64
+        );
+        assert_eq!(sanitized_data[keyframe.offset as usize], test.mdat_data[0]);
+    }
+
+    #[test]
+    fn subtitle_trak_dropped() {
+        let test = test_mp4()
+            .boxes(&[FTYP, MOOV, MDAT][..])
+            .moov(test_moov().subtitle_trak(true).clone())
+            .build();
+        assert_eq!(probe(test.clone()).unwrap().tracks().len(), 2);
+
+        let config = Config::builder().add_drop_handler_type(SBTL).build();
+        let sanitized = sanitize_with_config(test.clone(), config).unwrap();
+        assert_eq!(sanitized.data, test.mdat);
+        assert_eq!(sanitized.report.dropped_boxes, [TRAK]);
+
+        let sanitized_data = sanitized.reconstruct(&test.data);
+        let mut metadata = super::parse_metadata(io::Cursor::new(&sanitized_data)).unwrap();
+        let handler_types = metadata
+            .moov
+            .traks()
+            .map(|trak| trak.unwrap().handler_type().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(handler_types, [fourcc::META]);
+        assert_eq!(probe(io::Cursor::new(&sanitized_data)).unwrap().tracks().len(), 1);
+    }
+
+    #[test]
+    fn all_traks_dropped_by_handler_type() {
+        let test = test_mp4().moov(test_moov().subtitle_trak(true).clone()).build();
+        let config = Config::builder().drop_handler_types(vec![fourcc::META, SBTL]).build();
+        assert_matches!(sanitize_with_config(test, config).unwrap_err(), Error::Parse(err) => {
+            assert_matches!(err.into_inner(), ParseError::InvalidInput);
+        });
+    }
+
+    #[test]
+    fn map_output_range() {
+        let test = test_mp4().build();
+        let sanitized = test.sanitize_ok();
+        let output = sanitized.reconstruct(&test.data);
+        let metadata_len = sanitized.metadata.as_ref().unwrap().len() as u64;
+
+        let sources = sanitized.map_output_range(metadata_len - 4..metadata_len + 4);
+        assert_eq!(
+            sources,
+            [
+                OutputSource::Metadata(metadata_len - 4..metadata_len),
+                OutputSource::Input(test.mdat.offset..test.mdat.offset + 4),
+            ]
+        );
+
+        let mut mapped = vec![];
+        for source in sources {
+            match source {
+                OutputSource::Metadata(range) => {
+                    let metadata = sanitized.metadata.as_ref().unwrap();
+                    mapped.extend_from_slice(&metadata[range.start as usize..range.end as usize]);
+                }
+                OutputSource::Input(range) => {
+                    mapped.extend_from_slice(&test.data[range.start as usize..range.end as usize])
+                }
+            }
+        }
+        assert_eq!(mapped, output[metadata_len as usize - 4..][..8]);
+    }
+
+    #[test]
+    fn map_output_range_truncated() {
+        let sanitized = SanitizedMetadata {
+            metadata: Some(vec![0; 10]),
+            data: InputSpan { offset: 20, len: 5 },
+            additional_data: vec![],
+            report: Default::default(),
+        };
+        assert_eq!(sanitized.map_output_range(0..5), [OutputSource::Metadata(0..5)]);
+        assert_eq!(sanitized.map_output_range(12..100), [OutputSource::Input(22..25)]);
+        assert_eq!(sanitized.map_output_range(15..100), []);
+        assert_eq!(sanitized.map_output_range(5..5), []);
+
+        let sanitized = SanitizedMetadata { metadata: None, ..sanitized };
+        assert_eq!(sanitized.map_output_range(5..50), [OutputSource::Input(5..25)]);
+        assert_eq!(sanitized.map_output_range(25..50), []);
+    }
+
+    #[test]
+    fn map_output_range_trailing_data() {
+        let test = test_mp4().boxes(&[FTYP, MOOV, MDAT][..]).build();
+        let data = [&test.data[..], &[0xff; 500]].concat();
+        let config = Config::builder().on_trailing_data(TrailingData::Ignore).build();
+        let sanitized = sanitize_with_config(io::Cursor::new(&data), config).unwrap();
+        assert_eq!(sanitized.metadata, None);
+
+        let mdat_end = test.mdat.offset + test.mdat.len;
+        let sources = sanitized.map_output_range(0..data.len() as u64);
+        assert_eq!(sources, [OutputSource::Input(0..mdat_end)]);
+        assert_eq!(sanitized.map_output_range(mdat_end..data.len() as u64), []);
+    }
+
+    #[test]
+    fn sanitize_to() {
+        for test in [test_mp4().build(), test_mp4().boxes(&[FTYP, MOOV, MDAT][..]).build()] {
+            let mut metadata = Vec::new();
+            let data = super::sanitize_to(test.clone(), &mut metadata).unwrap();
+            assert_eq!(data, test.mdat);
+            let sanitized = sanitize(test.clone()).unwrap();
+            if let Some(expected_metadata) = sanitized.metadata {
+                assert_eq!(metadata, expected_metadata);
+            }
+
+            let output = [&metadata[..], &test.data[data.offset as usize..][..data.len as usize]].concat();
+            sanitize(io::Cursor::new(&output)).unwrap();
+            ffmpeg_assert_eq(&output, &test.mdat_data);
+        }
+    }
+
+    #[test]
+    fn sanitize_to_write_error() {
+        let test = test_mp4().build();
+        let mut out = [0; 16];
+        let err = super::sanitize_to(test, &mut out[..]).unwrap_err();
+        assert_matches!(err, Error::Io(err) => assert_eq!(err.kind(), io::ErrorKind::WriteZero));
+    }
+
+    #[test]
+    fn metadata_len() {
+        let test = test_mp4().build();
+        let noop_test = test_mp4().boxes(&[FTYP, MOOV, MDAT][..]).build();
+        let config = Config::builder().align_mdat_data(Some(4096)).build();
+        for (test, config) in [
+            (&test, Config::default()),
+            (&noop_test, Config::default()),
+            (&test, config),
+        ] {
+            let sanitized = super::sanitize_with_config(test.clone(), config.clone()).unwrap();
+            let metadata_len = sanitized_metadata_len(test.clone(), config).unwrap();
+            assert_eq!(metadata_len, sanitized.metadata_len());
+            let expected_len = sanitized
+                .metadata
+                .as_ref()
+                .map_or(test.mdat.offset, |metadata| metadata.len() as u64);
+            assert_eq!(metadata_len, expected_len);
+        }
+    }
+
+    #[test]
+    fn layout_map() {
+        let test = test_mp4().build();
+        let noop_test = test_mp4().boxes(&[FTYP, MOOV, MDAT][..]).build();
+        for (test, sanitized) in [(&test, test.sanitize_ok()), (&noop_test, noop_test.sanitize_ok_noop())] {
+            let layout = sanitized.layout_map();
+            let metadata_len =
+                (sanitized.metadata.as_ref()).map_or(sanitized.data.offset, |metadata| metadata.len() as u64);
+            assert_eq!(layout.len, metadata_len + sanitized.data.len);
+
+            let mut output = vec![];
+            for source in &layout.sources {
+                match source {
+                    OutputSource::Metadata(range) => {
+                        let metadata = sanitized.metadata.as_ref().unwrap();
+                        output.extend_from_slice(&metadata[range.start as usize..range.end as usize]);
+                    }
+                    OutputSource::Input(range) => {
+                        output.extend_from_slice(&test.data[range.start as usize..range.end as usize])
+                    }
+                }
+            }
+            assert_eq!(output.len() as u64, layout.len);
+            assert_eq!(output, sanitized.reconstruct(&test.data));
+            sanitize(io::Cursor::new(output)).unwrap();
+        }
+    }
+
+    #[test]
+    fn parse_metadata() {
+        let test = test_mp4().build();
+        let mut parsed = super::parse_metadata(test.clone()).unwrap();
+        assert_eq!(parsed.ftyp.major_brand, ISOM);
+        assert_eq!(parsed.data, test.mdat);
+
+        let mut traks = parsed.moov.traks().collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(traks.len(), 1);
+        let stsd = traks[0].stbl_mut().unwrap().stsd_mut().unwrap();
+        assert_eq!(stsd.entry_types().collect::<Vec<_>>(), [BoxType::METT]);
+        assert_eq!(traks[0].sample_format().unwrap(), Some(crate::parse::fourcc::METT));
+    }
+
+    #[test]
+    fn is_already_sanitized_faststart() {
+        let test = test_mp4().boxes(&[FTYP, MOOV, MDAT][..]).build();
+        assert!(super::is_already_sanitized(test).unwrap());
+    }
+
+    #[test]
+    fn is_already_sanitized_mdat_before_moov() {
+        let test = test_mp4().build();
+        assert!(!super::is_already_sanitized(test).unwrap());
+    }
+
+    #[test]
+    fn estimate_parse_cost() {
+        let test_spec = test_mp4().boxes(&[FTYP, FREE, MDAT, MOOV][..]).build_spec().unwrap();
+        let test = test_spec.build();
+        let cost = super::estimate_parse_cost(test.clone()).unwrap();
+        assert_eq!(cost.top_level_box_count, 4);
+        assert_eq!(cost.moov_size, test_spec.moov().build().encoded_len());
+        assert_eq!(cost.mdat_size, test.mdat.len);
+    }
+
+    #[test]
+    fn estimate_parse_cost_truncated_header() {
+        let test = test_mp4().build();
+        let data = [&test.data[..], &[0, 0, 0, 8]].concat();
+        assert_matches!(super::estimate_parse_cost(io::Cursor::new(data)).unwrap_err(), Error::Parse(err) => {
+            assert_matches!(err.into_inner(), ParseError::TruncatedBox);
+        });
+    }
+
+    #[test]
+    fn pssh_system_ids() {
+        let system_ids = [[1; 16], [2; 16]];
+        let test = test_mp4().moov(test_moov().pssh_system_ids(system_ids).clone()).build();
+        let sanitized = test.sanitize_ok();
+        assert_eq!(sanitized.report.protection_system_ids, system_ids);
+    }
+
+    #[test]
+    fn read_ftyp_async() {
+        let ftyp = test_ftyp()
+            .major_brand(MP42)
+            .compatible_brands(vec![MP42, ISOM])
+            .clone();
+        let test = test_mp4().boxes(&[FREE, FTYP, MDAT, MOOV][..]).ftyp(ftyp).build();
+
+        let sync_ftyp = read_ftyp(test.clone()).unwrap();
+        let async_ftyp = super::read_ftyp_async(futures_util::io::Cursor::new(test.data.to_vec()))
+            .now_or_never()
+            .unwrap()
+            .unwrap();
+        assert_eq!(async_ftyp.major_brand, MP42);
+        assert_eq!(async_ftyp.major_brand, sync_ftyp.major_brand);
+        assert_eq!(async_ftyp.compatible_brands().collect::<Vec<_>>(), [MP42, ISOM]);
+        assert!(async_ftyp.compatible_brands().eq(sync_ftyp.compatible_brands()));
+    }
+
+    #[test]
+    fn read_ftyp_not_first() {
+        let test = test_mp4().boxes(&[MOOV, FTYP, MDAT][..]).build();
+        assert_matches!(read_ftyp(test).unwrap_err(), Error::Parse(err) => {
+            assert_matches!(err.into_inner(), ParseError::InvalidBoxLayout);
+        });
+    }
+
+    #[test]
+    fn probe_async_first_keyframe() {
+        let test = test_mp4().build();
+        let sync_info = probe(test.clone()).unwrap();
+        let async_info = probe_async(futures_util::io::Cursor::new(test.data.to_vec()))
+            .now_or_never()
+            .unwrap()
+            .unwrap();
+        assert_eq!(async_info.first_keyframe(0), sync_info.first_keyframe(0));
+    }
+
+    #[test]
+    fn probe_first_keyframe() {
+        let test = test_mp4().build();
+        let info = probe(test.clone()).unwrap();
+        assert_eq!(info.tracks().len(), 1);
+        assert_eq!(info.tracks()[0].sample_count(), test.mdat_data.len() as u32);
+
+        let mdat_data_offset = test.mdat.offset + test.mdat.len - test.mdat_data.len() as u64;
+        assert_eq!(
+            info.first_keyframe(0),
+            Some(InputSpan { offset: mdat_data_offset, len: 1 })
+        );
+        assert_eq!(info.first_keyframe(1), None);
+    }
+
+    #[test]
+    fn probe_first_keyframe_ffmpeg() {
+        let data = include_bytes!("../fuzz/input/ffmpeg-smptebars-30f.mp4");
+        let info = probe(io::Cursor::new(data)).unwrap();
+        let keyframe = info.first_keyframe(0).unwrap();
+        assert_eq!(keyframe, InputSpan { offset: 48, len: 1390 });
+
+        // The keyframe should consist entirely of length-prefixed NAL units, including an IDR slice.
+        let mut keyframe = &data[keyframe.offset as usize..][..keyframe.len as usize];
+        let mut nal_unit_types = vec![];
+        while !keyframe.is_empty() {
+            let nal_unit_len = u32::from_be_bytes(keyframe[..4].try_into().unwrap()) as usize;
+            nal_unit_types.push(keyframe[4] & 0x1f);
+            keyframe = &keyframe[4 + nal_unit_len..];
+        }
+        assert!(nal_unit_types.contains(&5), "{nal_unit_types:?}");
+    }
+
+    #[test]
+    fn likely_producer_ffmpeg() {
+        let data = include_bytes!("../fuzz/input/ffmpeg-smptebars-30f.mp4");
+        let sanitized = sanitize(io::Cursor::new(data)).unwrap();
+        assert_eq!(sanitized.report.likely_producer, Some(Producer::Ffmpeg));
+    }
+
+    #[test]
+    fn likely_producer_unknown() {
+        let test = test_mp4().build();
+        let sanitized = sanitize(test).unwrap();
+        assert_eq!(sanitized.report.likely_producer, None);
+    }
+
+    #[test]
+    fn vexu_sample_entry() {
+        let hvcc = Mp4Box::with_bytes(BoxType::HVCC, BytesMut::from(TEST_HVCC_DATA));
+        let stri = Mp4Box::with_data(StriBox::new(true, true).into()).unwrap();
+        let hero = Mp4Box::with_data(HeroBox::new(1).into()).unwrap();
+        let eyes = Mp4Box::with_data(EyesBox::with_children(vec![stri.into(), hero.into()]).into()).unwrap();
+        let prji = Mp4Box::with_data(PrjiBox::new(RECT).into()).unwrap();
+        let proj = Mp4Box::with_data(ProjBox::with_children(vec![prji.into()]).into()).unwrap();
+        let vexu = Mp4Box::with_data(VexuBox::with_children(vec![eyes.into(), proj.into()]).into()).unwrap();
+        let hvc1 = test_visual_sample_entry(HVC1, &[hvcc.clone(), vexu.into()]);
+        test_mp4()
+            .moov(test_moov().sample_entry(hvc1).clone())
+            .build()
+            .sanitize_ok();
+
+        // The eyes box is missing its required stri box.
+        let eyes = Mp4Box::with_data(EyesBox::with_children(vec![]).into()).unwrap();
+        let vexu = Mp4Box::with_data(VexuBox::with_children(vec![eyes.into()]).into()).unwrap();
+        let hvc1 = test_visual_sample_entry(HVC1, &[hvcc, vexu.into()]);
+        let test = test_mp4().moov(test_moov().sample_entry(hvc1).clone()).build();
+        assert_matches!(sanitize(test).unwrap_err(), Error::Parse(err) => {
+            assert_matches!(err.into_inner(), ParseError::MissingRequiredBox(STRI));
+        });
+    }
+
+    #[test]
+    fn probe_codec_config_ffmpeg() {
+        let data = include_bytes!("../fuzz/input/ffmpeg-smptebars-30f.mp4");
+        let info = probe(io::Cursor::new(data)).unwrap();
+        let codec_config = info.tracks()[0].codec_config().unwrap();
+        assert_eq!(codec_config.kind, CodecConfigKind::Avc);
+
+        let avcc_type_offset = data.windows(4).position(|window| window == b"avcC").unwrap();
+        let avcc_size = u32::from_be_bytes(data[avcc_type_offset - 4..avcc_type_offset].try_into().unwrap());
+        let avcc_data = &data[avcc_type_offset + 4..avcc_type_offset - 4 + avcc_size as usize];
+        assert_eq!(codec_config.data, avcc_data);
+    }
+
+    #[test]
+    fn invalid_codec_config() {
+        let mut hvcc_data = BytesMut::from(TEST_HVCC_DATA);
+        let valid_hvcc = Mp4Box::with_bytes(BoxType::HVCC, hvcc_data.clone());
+        let valid_hvc1 = test_visual_sample_entry(HVC1, &[valid_hvcc]);
+        test_mp4()
+            .moov(test_moov().sample_entry(valid_hvc1).clone())
+            .build()
+            .sanitize_ok();
+
+        // A single NAL unit array is declared, but none is present.
+        *hvcc_data.last_mut().unwrap() = 1;
+        let hvcc = Mp4Box::with_bytes(BoxType::HVCC, hvcc_data);
+        let hvc1 = test_visual_sample_entry(HVC1, &[hvcc]);
+        let test = test_mp4().moov(test_moov().sample_entry(hvc1).clone()).build();
+        assert_matches!(sanitize(test).unwrap_err(), Error::Parse(err) => {
+            assert_matches!(err.into_inner(), ParseError::TruncatedBox);
+        });
+    }
+
+    #[test]
+    fn cumulative_mdat_box_size() {
+        let test_spec = test_mp4().mdat_data_until_eof().build_spec().unwrap();
+        let test_1 = test_spec.build();
+        let mdat_box_length = test_1.mdat.len as u32;
+
+        let config_bad = Config::builder().build();
+        assert_matches!(sanitize_with_config(test_1, config_bad).unwrap_err(), Error::Parse(err) => {
+            assert_matches!(err.into_inner(), ParseError::MissingRequiredBox(_));
+        });
+
+        let test_2 = test_spec.build();
+        let config_good = Config::builder()
+            .cumulative_mdat_box_size(Some(mdat_box_length))
+            .build();
+        test_2.sanitize_ok_with_config(config_good);
+    }
+}
@@ -1,4 +1,66 @@
 #[cfg(test)]
 pub mod test;
 
+use std::io::{self, Write};
+
+use bytes::buf::UninitSlice;
+use bytes::BufMut;
+
 pub use mediasan_common::util::IoResultExt;
+
+/// A [`BufMut`] which writes to an [`io::Write`] through a fixed-size buffer.
+///
+/// As [`BufMut`] methods can't fail, the first IO error encountered is saved and returned by [`finish`](Self::finish),
+/// and any data written after it is discarded.
+pub struct WriteBufMut<W> {
+    writer: W,
+    buf: Box<[u8]>,
+    len: usize,
+    error: Option<io::Error>,
+}
+
+const WRITE_BUF_SIZE: usize = 8192;
+
+impl<W: Write> WriteBufMut<W> {
+    pub fn new(writer: W) -> Self {
+        Self { writer, buf: vec![0; WRITE_BUF_SIZE].into(), len: 0, error: None }
+    }
+
+    /// Write any buffered data and flush the underlying writer, returning the first IO error encountered, if any.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.write_buf();
+        if let Some(err) = self.error {
+            return Err(err);
+        }
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+
+    fn write_buf(&mut self) {
+        if self.error.is_none() {
+            self.error = self.writer.write_all(&self.buf[..self.len]).err();
+        }
+        self.len = 0;
+    }
+}
+
+// SAFETY: `chunk_mut` always returns a non-empty slice of `buf`, and `advance_mut` only advances within it.
+unsafe impl<W: Write> BufMut for WriteBufMut<W> {
+    fn remaining_mut(&self) -> usize {
+        usize::MAX
+    }
+
+    unsafe fn advance_mut(&mut self, cnt: usize) {
+        assert!(cnt <= self.buf.len() - self.len, "advance_mut past end of chunk");
+        self.len += cnt;
+    }
+
+    fn chunk_mut(&mut self) -> &mut UninitSlice {
+        if self.len == self.buf.len() {
+            self.write_buf();
+        }
+        let chunk = &mut self.buf[self.len..];
+        // SAFETY: `chunk` is a valid, initialized slice, borrowed mutably for the lifetime of the returned slice.
+        unsafe { UninitSlice::from_raw_parts_mut(chunk.as_mut_ptr(), chunk.len()) }
+    }
+}
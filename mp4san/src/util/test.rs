@@ -6,31 +6,35 @@ use std::iter;
 
 use bytes::{BufMut, BytesMut};
 
-use crate::parse::box_type::{DINF, DREF, HDLR, MDAT, MDHD, MECO, META, METT, MVHD, STSC, STSD, STSZ, STTS, TKHD, URL};
-use crate::parse::{fourcc, AnyMp4Box, BoxHeader, BoxType, BoxUuid, FourCC, FullBoxHeader, Mp4Box, Mp4Value};
-use crate::{InputSpan, SanitizedMetadata};
+use crate::parse::box_type::{
+    DINF, DREF, HDLR, ILOC, MDAT, MDHD, MECO, META, METT, MVHD, PDIN, PSSH, SAIZ, SIDX, STSC, STSD, STSZ, STTS, TKHD,
+    TREF, URL,
+};
+use crate::parse::{
+    fourcc, AnyMp4Box, BoxHeader, BoxType, BoxUuid, FourCC, FullBoxHeader, MfhdBox, MoofBox, Mp4Box, Mp4Value,
+    SidxReference, TfhdBox, TrafBox, TrunBox, UdtaBox,
+};
+use crate::InputSpan;
 
 pub const TEST_UUID: BoxType = BoxType::Uuid(BoxUuid { value: *b"thisisatestuuid!" });
 pub const MP42: FourCC = FourCC { value: *b"mp42" };
 pub const MP41: FourCC = FourCC { value: *b"mp41" };
+pub const ISO2: FourCC = FourCC { value: *b"iso2" };
 pub const ISOM: FourCC = FourCC { value: *b"isom" };
+pub const SBTL: FourCC = FourCC { value: *b"sbtl" };
+pub const STAI: FourCC = FourCC { value: *b"stai" };
+pub const XYZ: BoxType = BoxType::FourCC(FourCC { value: *b"\xa9xyz" });
+pub const RECT: FourCC = FourCC { value: *b"rect" };
+
+/// The data of an `hvcC` box for Main profile level 4.1 with no NAL unit arrays.
+pub const TEST_HVCC_DATA: &[u8] = b"\x01\x01\x60\0\0\0\0\0\0\0\0\0\x7b\0\0\0\0\0\0\0\0\0\0";
 
 pub use ftyp::TestFtypBuilder;
 pub use moov::TestMoovBuilder;
-pub use mp4::TestMp4Builder;
+pub use mp4::{TestMp4, TestMp4Builder};
 
 pub use mediasan_common_test::init_logger;
 
-pub fn sanitized_data(sanitized: SanitizedMetadata, data: &[u8]) -> Vec<u8> {
-    match sanitized.metadata {
-        Some(metadata) => {
-            let mdat = &data[sanitized.data.offset as usize..][..sanitized.data.len as usize];
-            [&metadata[..], mdat].concat()
-        }
-        None => data.to_vec(),
-    }
-}
-
 pub fn test_dinf() -> AnyMp4Box {
     let mut data = BytesMut::new();
     write_test_dinf_data(&mut data);
@@ -49,16 +53,36 @@ pub fn test_ftyp() -> TestFtypBuilder {
 
 pub fn test_hdlr(handler_type: FourCC) -> AnyMp4Box {
     let mut data = BytesMut::new();
-    write_hdlr_data(&mut data, handler_type);
+    write_hdlr_data(&mut data, handler_type, b"\0");
     Mp4Box::with_bytes(HDLR, data)
 }
 
+pub fn test_hdlr_with_name(handler_type: FourCC, name: &[u8]) -> AnyMp4Box {
+    let mut data = BytesMut::new();
+    write_hdlr_data(&mut data, handler_type, name);
+    Mp4Box::with_bytes(HDLR, data)
+}
+
+/// An `iloc` box with a single item whose single extent is located according to `construction_method`: by offset within
+/// the file if `0`, or within the `idat` box if `1`.
+pub fn test_iloc(construction_method: u16) -> AnyMp4Box {
+    let mut data = BytesMut::new();
+    write_test_iloc_data(&mut data, construction_method);
+    Mp4Box::with_bytes(ILOC, data)
+}
+
 pub fn test_mdhd() -> AnyMp4Box {
     let mut data = BytesMut::new();
     write_test_mdhd_data(&mut data);
     Mp4Box::with_bytes(MDHD, data)
 }
 
+pub fn test_mdhd_with(timescale: u32, duration: u64) -> AnyMp4Box {
+    let mut data = BytesMut::new();
+    write_mdhd_data(&mut data, timescale, duration);
+    Mp4Box::with_bytes(MDHD, data)
+}
+
 pub fn test_meco() -> AnyMp4Box {
     let mut data = BytesMut::new();
     write_test_meco_data(&mut data);
@@ -71,6 +95,30 @@ pub fn test_meta() -> AnyMp4Box {
     Mp4Box::with_bytes(META, data)
 }
 
+/// A movie fragment at `offset` of `sample_count` samples of size 1, to be followed by an mdat with a 32-bit size.
+///
+/// If `explicit_base_data_offset` is set, the track fragment has an absolute base data offset; otherwise, its data offset
+/// is relative to the movie fragment.
+pub fn test_moof(offset: u64, sequence_number: u32, sample_count: usize, explicit_base_data_offset: bool) -> AnyMp4Box {
+    let build = |base_data_offset, data_offset| {
+        let mfhd = Mp4Box::with_data(MfhdBox::new(sequence_number).into()).unwrap();
+        let tfhd = Mp4Box::with_data(TfhdBox::new(1, base_data_offset).into()).unwrap();
+        let trun =
+            Mp4Box::with_data(TrunBox::new(Some(data_offset), iter::repeat(1).take(sample_count)).into()).unwrap();
+        let traf = Mp4Box::with_data(TrafBox::with_children(vec![tfhd.into(), trun.into()]).into()).unwrap();
+        Mp4Box::with_data(MoofBox::with_children(vec![mfhd.into(), traf.into()]).into()).unwrap()
+    };
+
+    // The length of the moof doesn't depend on the values of its offsets.
+    let moof_len = build(explicit_base_data_offset.then_some(0), 0).encoded_len();
+    let data_offset = moof_len + BoxHeader::with_u32_data_size(MDAT, 0).encoded_len();
+    let moof: Mp4Box<MoofBox> = match explicit_base_data_offset {
+        true => build(Some(offset + data_offset), 0),
+        false => build(None, data_offset as i32),
+    };
+    moof.into()
+}
+
 pub fn test_moov() -> TestMoovBuilder {
     Default::default()
 }
@@ -79,12 +127,37 @@ pub fn test_mp4() -> TestMp4Builder {
     Default::default()
 }
 
-pub fn test_mvhd() -> AnyMp4Box {
+pub fn test_mvhd_with(timescale: u32) -> AnyMp4Box {
     let mut data = BytesMut::new();
-    write_test_mvhd_data(&mut data);
+    write_mvhd_data(&mut data, timescale);
     Mp4Box::with_bytes(MVHD, data)
 }
 
+pub fn test_pdin() -> AnyMp4Box {
+    let mut data = BytesMut::new();
+    write_test_pdin_data(&mut data);
+    Mp4Box::with_bytes(PDIN, data)
+}
+
+pub fn test_sidx(first_offset: u32, referenced_size: u32) -> AnyMp4Box {
+    let reference = SidxReference { referenced_size, starts_with_sap: true, sap_type: 1, ..Default::default() };
+    let mut data = BytesMut::new();
+    write_test_sidx_data(&mut data, 0, first_offset, &[reference]);
+    Mp4Box::with_bytes(SIDX, data)
+}
+
+pub fn test_pssh(system_id: [u8; 16]) -> AnyMp4Box {
+    let mut data = BytesMut::new();
+    write_test_pssh_data(&mut data, 1, system_id, &[[0; 16]], &[]);
+    Mp4Box::with_bytes(PSSH, data)
+}
+
+pub fn test_saiz(aux_info_type: FourCC, sample_count: u32) -> AnyMp4Box {
+    let mut data = BytesMut::new();
+    write_test_saiz_data(&mut data, aux_info_type, sample_count);
+    Mp4Box::with_bytes(SAIZ, data)
+}
+
 pub fn test_stsc() -> AnyMp4Box {
     let mut data = BytesMut::new();
     write_test_stsc_data(&mut data);
@@ -97,6 +170,24 @@ pub fn test_stsd() -> AnyMp4Box {
     Mp4Box::with_bytes(STSD, data)
 }
 
+pub fn test_stsd_with_entry(sample_entry: &AnyMp4Box) -> AnyMp4Box {
+    let mut data = BytesMut::new();
+    FullBoxHeader::default().put_buf(&mut data);
+    data.put_u32(1); // entry count
+    sample_entry.put_buf(&mut data);
+    Mp4Box::with_bytes(STSD, data)
+}
+
+/// A visual sample entry of type `entry_type` with zeroed fields, followed by `children`.
+pub fn test_visual_sample_entry(entry_type: BoxType, children: &[AnyMp4Box]) -> AnyMp4Box {
+    let mut data = BytesMut::new();
+    data.put_bytes(0, 78);
+    for child in children {
+        child.put_buf(&mut data);
+    }
+    Mp4Box::with_bytes(entry_type, data)
+}
+
 pub fn test_stsz(chunk_count: u32) -> AnyMp4Box {
     let mut data = BytesMut::new();
     write_test_stsz_data(&mut data, chunk_count);
@@ -109,20 +200,49 @@ pub fn test_stts(chunk_count: u32) -> AnyMp4Box {
     Mp4Box::with_bytes(STTS, data)
 }
 
-pub fn test_tkhd(track_id: u32) -> AnyMp4Box {
+pub fn test_tkhd(track_id: u32, flags: u32, duration: u64) -> AnyMp4Box {
     let mut data = BytesMut::new();
-    write_test_tkhd_data(&mut data, track_id);
+    write_test_tkhd_data(&mut data, track_id, flags, duration);
     Mp4Box::with_bytes(TKHD, data)
 }
 
-pub fn write_hdlr_data<B: BufMut>(mut out: B, handler_type: FourCC) {
+/// A track reference box containing a `cdsc` reference to the track with ID 1.
+pub fn test_tref() -> AnyMp4Box {
+    let mut data = BytesMut::new();
+    data.put_u32(12);
+    data.put_slice(b"cdsc");
+    data.put_u32(1);
+    Mp4Box::with_bytes(TREF, data)
+}
+
+/// A user data box containing a metadata box, as written by phone cameras.
+pub fn test_udta() -> AnyMp4Box {
+    Mp4Box::with_data(UdtaBox::with_children(vec![test_meta()]).into())
+        .unwrap()
+        .into()
+}
+
+/// A user data box containing a QuickTime location (`©xyz`) and a metadata box.
+pub fn test_location_udta() -> AnyMp4Box {
+    let mut location = BytesMut::new();
+    let iso6709 = b"+37.3349-122.0090/";
+    location.put_u16(iso6709.len() as u16);
+    location.put_u16(0x15c7); // language
+    location.put_slice(iso6709);
+    let children = vec![Mp4Box::with_bytes(XYZ, location), test_meta()];
+    Mp4Box::with_data(UdtaBox::with_children(children).into())
+        .unwrap()
+        .into()
+}
+
+pub fn write_hdlr_data<B: BufMut>(mut out: B, handler_type: FourCC, name: &[u8]) {
     FullBoxHeader::default().put_buf(&mut out);
     out.put_u32(0); // pre-defined
     handler_type.put_buf(&mut out);
     for _ in 0..3 {
         out.put_u32(0); // reserved
     }
-    out.put_u8(0); // name
+    out.put_slice(name); // name
 }
 
 pub fn write_mdat_header(out: &mut Vec<u8>, data_len: Option<u64>) -> InputSpan {
@@ -143,6 +263,19 @@ pub fn write_test_dinf_data<B: BufMut>(mut out: B) {
     FullBoxHeader { version: 0, flags: 1 }.put_buf(&mut out);
 }
 
+pub fn write_test_iloc_data<B: BufMut>(mut out: B, construction_method: u16) {
+    FullBoxHeader { version: 1, flags: 0 }.put_buf(&mut out);
+    out.put_u8(0x44); // offset size and length size
+    out.put_u8(0); // base offset size and index size
+    out.put_u16(1); // item count
+    out.put_u16(1); // item ID
+    out.put_u16(construction_method);
+    out.put_u16(0); // data reference index
+    out.put_u16(1); // extent count
+    out.put_u32(0); // extent offset
+    out.put_u32(1); // extent length
+}
+
 pub fn write_test_mdat(out: &mut Vec<u8>, data: &[u8]) -> InputSpan {
     let mut span = write_mdat_header(out, Some(data.len() as u64));
     out.extend_from_slice(data);
@@ -159,21 +292,36 @@ pub fn write_test_meta_data<B: BufMut>(mut out: B) {
     test_hdlr(fourcc::META).put_buf(&mut out);
 }
 
-pub fn write_test_mdhd_data<B: BufMut>(mut out: B) {
-    FullBoxHeader::default().put_buf(&mut out);
-    out.put_u32(0); // creation time
-    out.put_u32(0); // modification time
-    out.put_u32(1); // timescale
-    out.put_u32(0); // duration
+pub fn write_test_mdhd_data<B: BufMut>(out: B) {
+    write_mdhd_data(out, 1, 0);
+}
+
+pub fn write_mdhd_data<B: BufMut>(mut out: B, timescale: u32, duration: u64) {
+    match u32::try_from(duration) {
+        Ok(duration) => {
+            FullBoxHeader::default().put_buf(&mut out);
+            out.put_u32(0); // creation time
+            out.put_u32(0); // modification time
+            out.put_u32(timescale); // timescale
+            out.put_u32(duration); // duration
+        }
+        Err(_) => {
+            FullBoxHeader { version: 1, flags: 0 }.put_buf(&mut out);
+            out.put_u64(0); // creation time
+            out.put_u64(0); // modification time
+            out.put_u32(timescale); // timescale
+            out.put_u64(duration); // duration
+        }
+    }
     out.put_u16(u16::from_be_bytes(*b"US")); // language
     out.put_u16(0); // pre-defined
 }
 
-pub fn write_test_mvhd_data<B: BufMut>(mut out: B) {
+pub fn write_mvhd_data<B: BufMut>(mut out: B, timescale: u32) {
     FullBoxHeader::default().put_buf(&mut out);
     out.put_u32(0); // creation time
     out.put_u32(0); // modification time
-    out.put_u32(1); // timescale
+    out.put_u32(timescale); // timescale
     out.put_u32(0); // duration
     out.put_u32(0x00010000); // rate
     out.put_u16(0x0100); // volume
@@ -190,6 +338,60 @@ pub fn write_test_mvhd_data<B: BufMut>(mut out: B) {
     out.put_u32(u32::MAX); // next track id
 }
 
+pub fn write_test_pdin_data<B: BufMut>(mut out: B) {
+    FullBoxHeader::default().put_buf(&mut out);
+    out.put_u32(128_000); // rate
+    out.put_u32(1_000); // initial delay
+}
+
+pub fn write_test_sidx_data<B: BufMut>(mut out: B, version: u8, first_offset: u32, references: &[SidxReference]) {
+    FullBoxHeader { version, flags: 0 }.put_buf(&mut out);
+    out.put_u32(1); // reference id
+    out.put_u32(1000); // timescale
+    match version {
+        0 => {
+            out.put_u32(0); // earliest presentation time
+            out.put_u32(first_offset); // first offset
+        }
+        _ => {
+            out.put_u64(0); // earliest presentation time
+            out.put_u64(first_offset.into()); // first offset
+        }
+    }
+    out.put_u16(0); // reserved
+    out.put_u16(references.len() as u16); // reference count
+    for reference in references {
+        reference.put_buf(&mut out);
+    }
+}
+
+pub fn write_test_pssh_data<B: BufMut>(
+    mut out: B,
+    version: u8,
+    system_id: [u8; 16],
+    key_ids: &[[u8; 16]],
+    data: &[u8],
+) {
+    FullBoxHeader { version, flags: 0 }.put_buf(&mut out);
+    out.put_slice(&system_id); // system id
+    if version > 0 {
+        out.put_u32(key_ids.len() as u32); // key id count
+        for key_id in key_ids {
+            out.put_slice(key_id); // key id
+        }
+    }
+    out.put_u32(data.len() as u32); // data size
+    out.put_slice(data); // data
+}
+
+pub fn write_test_saiz_data<B: BufMut>(mut out: B, aux_info_type: FourCC, sample_count: u32) {
+    FullBoxHeader { version: 0, flags: 1 }.put_buf(&mut out);
+    aux_info_type.put_buf(&mut out);
+    out.put_u32(0); // aux info type parameter
+    out.put_u8(1); // default sample info size
+    out.put_u32(sample_count);
+}
+
 pub fn write_test_stsc_data<B: BufMut>(mut out: B) {
     FullBoxHeader::default().put_buf(&mut out);
     out.put_u32(1); // entry count
@@ -222,13 +424,25 @@ pub fn write_test_stts_data<B: BufMut>(mut out: B, chunk_count: u32) {
     out.put_u32(1); // sample delta
 }
 
-pub fn write_test_tkhd_data<B: BufMut>(mut out: B, track_id: u32) {
-    FullBoxHeader::default().put_buf(&mut out);
-    out.put_u32(0); // creation time
-    out.put_u32(0); // modification time
-    out.put_u32(track_id); // track id
-    out.put_u32(0); // reserved
-    out.put_u32(0); // duration
+pub fn write_test_tkhd_data<B: BufMut>(mut out: B, track_id: u32, flags: u32, duration: u64) {
+    match u32::try_from(duration) {
+        Ok(duration) => {
+            FullBoxHeader { version: 0, flags }.put_buf(&mut out);
+            out.put_u32(0); // creation time
+            out.put_u32(0); // modification time
+            out.put_u32(track_id); // track id
+            out.put_u32(0); // reserved
+            out.put_u32(duration); // duration
+        }
+        Err(_) => {
+            FullBoxHeader { version: 1, flags }.put_buf(&mut out);
+            out.put_u64(0); // creation time
+            out.put_u64(0); // modification time
+            out.put_u32(track_id); // track id
+            out.put_u32(0); // reserved
+            out.put_u64(duration); // duration
+        }
+    }
     for _ in 0..2 {
         out.put_u32(0); // reserved
     }
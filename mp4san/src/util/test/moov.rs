@@ -1,8 +1,15 @@
 use derive_builder::Builder;
 
-use crate::parse::{fourcc, Co64Box, MdiaBox, MinfBox, MoovBox, Mp4Box, StblBox, StcoBox, TrakBox};
+use crate::parse::box_type::{FREE, SDTP, UDTA};
+use crate::parse::{
+    fourcc, AnyMp4Box, Co64Box, EdtsBox, ElstBox, ElstEntry, FourCC, MdiaBox, MinfBox, MoovBox, Mp4Box, SaioBox,
+    StblBox, StcoBox, TkhdBox, TrakBox,
+};
 
-use super::{test_dinf, test_hdlr, test_mdhd, test_mvhd, test_stsc, test_stsd, test_stsz, test_stts, test_tkhd};
+use super::{
+    test_dinf, test_free, test_hdlr_with_name, test_mdhd_with, test_mvhd_with, test_pssh, test_saiz, test_stsc,
+    test_stsd, test_stsd_with_entry, test_stsz, test_stts, test_tkhd, test_tref, test_udta, SBTL, STAI,
+};
 
 #[derive(Builder)]
 #[builder(name = "TestMoovBuilder", build_fn(name = "build_spec"))]
@@ -28,42 +35,184 @@ pub struct TestMoovSpec {
 
     #[builder(default = "true")]
     pub trak: bool,
+
+    /// The length of a free box to add within the moov and trak boxes, if any.
+    #[builder(default, setter(strip_option))]
+    pub free_len: Option<u32>,
+
+    /// The handler name to write in the hdlr box, including its null terminator.
+    #[builder(default = "b\"\\0\".to_vec()", setter(into))]
+    pub hdlr_name: Vec<u8>,
+
+    /// The SystemIDs of version 1 pssh boxes to add within the moov box.
+    #[builder(default, setter(into))]
+    pub pssh_system_ids: Vec<[u8; 16]>,
+
+    /// Whether to add optional boxes not required for playback (`udta` within the moov and trak boxes, and `sdtp` within
+    /// the stbl box).
+    #[builder(default)]
+    pub optional_boxes: bool,
+
+    /// The timescale to write in the mvhd box.
+    #[builder(default = "1")]
+    pub movie_timescale: u32,
+
+    /// The timescale to write in the mdhd box.
+    #[builder(default = "1")]
+    pub media_timescale: u32,
+
+    /// The duration to write in the tkhd boxes, using a version 1 box if it doesn't fit in 32 bits.
+    #[builder(default)]
+    pub track_duration: u64,
+
+    /// The duration to write in the mdhd box, using a version 1 box if it doesn't fit in 32 bits.
+    #[builder(default)]
+    pub media_duration: u64,
+
+    /// The entries of an edit list (`elst`) to add within an `edts` box in the trak box, if any.
+    #[builder(default, setter(into, strip_option))]
+    pub edit_list: Option<Vec<ElstEntry>>,
+
+    /// Whether to add sample auxiliary information boxes (`saiz` and `saio`) within the stbl box, locating a TAI
+    /// timestamp at the start of each chunk.
+    #[builder(default)]
+    pub aux_info: bool,
+
+    /// Whether to add a track reference box (`tref`) within the trak box, referencing itself.
+    #[builder(default)]
+    pub track_reference: bool,
+
+    /// Whether to add a second, disabled trak box sharing the first's media.
+    #[builder(default)]
+    pub disabled_trak: bool,
+
+    /// Whether the disabled trak box locates its chunks using only a `co64` box, regardless of [`stco`](Self::stco) and
+    /// [`co64`](Self::co64).
+    #[builder(default)]
+    pub disabled_trak_co64: bool,
+
+    /// Whether to add a second, enabled trak box with a subtitle (`sbtl`) handler, sharing the first's media.
+    #[builder(default)]
+    pub subtitle_trak: bool,
+
+    /// A sample entry to write in the stsd box, in place of the default `mett` entry.
+    #[builder(default, setter(strip_option))]
+    pub sample_entry: Option<AnyMp4Box>,
 }
 
 impl TestMoovBuilder {
     pub fn build(&self) -> Mp4Box<MoovBox> {
         let spec = self.build_spec().unwrap();
-        let chunk_count = spec.co_entries.len() as u32;
+        let mdia = spec.build_mdia(fourcc::META, spec.stco, spec.co64);
+
+        let mut trak = vec![test_tkhd(
+            1,
+            TkhdBox::TRACK_ENABLED | TkhdBox::TRACK_IN_MOVIE,
+            spec.track_duration,
+        )];
+        if spec.track_reference {
+            trak.push(test_tref());
+        }
+        if let Some(edit_list) = &spec.edit_list {
+            let elst = Mp4Box::with_data(ElstBox::new(edit_list.iter().copied()).into()).unwrap();
+            trak.push(
+                Mp4Box::with_data(EdtsBox::with_children(vec![elst.into()]).into())
+                    .unwrap()
+                    .into(),
+            );
+        }
+        if let Some(free_len) = spec.free_len {
+            trak.push(test_free(FREE, free_len));
+        }
+        if spec.optional_boxes {
+            trak.push(test_free(UDTA, 32));
+        }
+        trak.extend(mdia.clone());
+
+        let mut disabled_trak = vec![test_tkhd(2, TkhdBox::TRACK_IN_MOVIE, spec.track_duration)];
+        match spec.disabled_trak_co64 {
+            true => disabled_trak.extend(spec.build_mdia(fourcc::META, false, true)),
+            false => disabled_trak.extend(mdia),
+        }
+
+        let mut subtitle_trak = vec![test_tkhd(
+            3,
+            TkhdBox::TRACK_ENABLED | TkhdBox::TRACK_IN_MOVIE,
+            spec.track_duration,
+        )];
+        subtitle_trak.extend(spec.build_mdia(SBTL, spec.stco, spec.co64));
+
+        let mut moov = vec![test_mvhd_with(spec.movie_timescale)];
+        if let Some(free_len) = spec.free_len {
+            moov.push(test_free(FREE, free_len));
+        }
+        if spec.optional_boxes {
+            moov.push(test_udta());
+        }
+        for system_id in &spec.pssh_system_ids {
+            moov.push(test_pssh(*system_id));
+        }
+        if spec.trak {
+            moov.push(Mp4Box::with_data(TrakBox::with_children(trak).into()).unwrap().into());
+        }
+        if spec.disabled_trak {
+            moov.push(
+                Mp4Box::with_data(TrakBox::with_children(disabled_trak).into())
+                    .unwrap()
+                    .into(),
+            );
+        }
+        if spec.subtitle_trak {
+            moov.push(
+                Mp4Box::with_data(TrakBox::with_children(subtitle_trak).into())
+                    .unwrap()
+                    .into(),
+            );
+        }
+        Mp4Box::with_data(MoovBox::with_children(moov).into()).unwrap()
+    }
+}
 
-        let mut stbl = vec![test_stsd(), test_stts(chunk_count), test_stsc(), test_stsz(chunk_count)];
-        if spec.co64 {
-            let entries = spec.co_entries.iter().cloned();
+impl TestMoovSpec {
+    fn build_mdia(&self, handler_type: FourCC, stco: bool, co64: bool) -> Option<AnyMp4Box> {
+        let chunk_count = self.co_entries.len() as u32;
+
+        let stsd = match &self.sample_entry {
+            Some(sample_entry) => test_stsd_with_entry(sample_entry),
+            None => test_stsd(),
+        };
+        let mut stbl = vec![stsd, test_stts(chunk_count), test_stsc(), test_stsz(chunk_count)];
+        if co64 {
+            let entries = self.co_entries.iter().cloned();
             stbl.push(Mp4Box::with_data(Co64Box::from_iter(entries).into()).unwrap().into());
         }
-        if spec.stco {
-            let entries = spec.co_entries.into_iter().map(|entry| entry as u32);
+        if stco {
+            let entries = self.co_entries.iter().map(|entry| *entry as u32);
             stbl.push(Mp4Box::with_data(StcoBox::from_iter(entries).into()).unwrap().into());
         }
+        if self.optional_boxes {
+            stbl.push(test_free(SDTP, 16));
+        }
+        if self.aux_info {
+            stbl.push(test_saiz(STAI, chunk_count));
+            let saio = SaioBox::new(Some((STAI, 0)), self.co_entries.iter().cloned());
+            stbl.push(Mp4Box::with_data(saio.into()).unwrap().into());
+        }
 
         let mut minf = vec![test_dinf()];
-        if spec.stbl {
+        if self.stbl {
             minf.push(Mp4Box::with_data(StblBox::with_children(stbl).into()).unwrap().into());
         }
 
-        let mut mdia = vec![test_mdhd(), test_hdlr(fourcc::META)];
-        if spec.minf {
+        let mut mdia = vec![
+            test_mdhd_with(self.media_timescale, self.media_duration),
+            test_hdlr_with_name(handler_type, &self.hdlr_name),
+        ];
+        if self.minf {
             mdia.push(Mp4Box::with_data(MinfBox::with_children(minf).into()).unwrap().into());
         }
 
-        let mut trak = vec![test_tkhd(1)];
-        if spec.mdia {
-            trak.push(Mp4Box::with_data(MdiaBox::with_children(mdia).into()).unwrap().into());
-        }
-
-        let mut moov = vec![test_mvhd()];
-        if spec.trak {
-            moov.push(Mp4Box::with_data(TrakBox::with_children(trak).into()).unwrap().into());
-        }
-        Mp4Box::with_data(MoovBox::with_children(moov).into()).unwrap()
+        self.mdia
+            .then(|| Mp4Box::with_data(MdiaBox::with_children(mdia).into()).unwrap().into())
     }
 }
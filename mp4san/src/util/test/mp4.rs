@@ -6,13 +6,13 @@ use bytes::{Buf, Bytes};
 use derive_builder::Builder;
 use mp4san_test::{ffmpeg_assert_eq, gpac_assert_eq};
 
-use crate::parse::box_type::{FREE, FTYP, MDAT, MECO, META, MOOV, SKIP};
+use crate::parse::box_type::{FREE, FTYP, MDAT, MECO, META, MOOF, MOOV, PDIN, SIDX, SKIP, UDTA, WIDE};
 use crate::parse::{BoxType, Mp4Value};
 use crate::{sanitize, sanitize_with_config, Config, InputSpan, SanitizedMetadata, Skip};
 
 use super::{
-    init_logger, sanitized_data, test_free, test_meco, test_meta, write_mdat_header, write_test_uuid, TestFtypBuilder,
-    TestMoovBuilder, TEST_UUID,
+    init_logger, test_free, test_location_udta, test_meco, test_meta, test_moof, test_pdin, test_sidx,
+    write_mdat_header, write_test_uuid, TestFtypBuilder, TestMoovBuilder, TEST_UUID,
 };
 
 #[derive(Builder)]
@@ -37,6 +37,10 @@ pub struct TestMp4Spec {
     #[builder(default = "vec![FTYP, MDAT, MOOV]")]
     #[builder(setter(into, each(name = "add_box")))]
     boxes: Vec<BoxType>,
+
+    /// Whether movie fragments (`moof`) have absolute base data offsets, rather than offsets relative to themselves.
+    #[builder(default)]
+    fragment_base_data_offsets: bool,
 }
 
 #[derive(Clone)]
@@ -72,32 +76,77 @@ impl TestMp4Spec {
         let mut mdat: Option<InputSpan> = None;
         let mut mdat_header_len = None;
         let mut moov_offsets = Vec::new();
+        let mut moof_count = 0;
+        let mut wide_offset = None;
         for box_type in &self.boxes {
+            let preceding_wide_offset = wide_offset.take();
             match *box_type {
                 FTYP => {
                     self.ftyp.build().put_buf(&mut data);
                 }
+                PDIN => {
+                    test_pdin().put_buf(&mut data);
+                }
+                SIDX => {
+                    // The segment index box references the box following it.
+                    let sidx = test_sidx(0, 0);
+                    if let Some(mdat) = &mut mdat {
+                        mdat.len += sidx.encoded_len();
+                    }
+                    sidx.put_buf(&mut data);
+                }
                 MOOV => {
                     moov_offsets.push(data.len());
                     moov.build().put_buf(&mut data);
                 }
                 MDAT => {
                     let written_mdat = write_mdat_header(&mut data, self.mdat_data_len);
-                    mdat_header_len = Some(data.len() as u64 - written_mdat.offset);
+                    let mdat_data_offset = data.len() as u64;
                     data.extend_from_slice(&self.mdat_data);
 
                     let mdat_data_len = self.mdat_data_len.unwrap_or(self.mdat_data.len() as u64);
                     let mdat_len = written_mdat.len.saturating_add(mdat_data_len);
+                    let mdat = match &mut mdat {
+                        Some(mdat) => {
+                            mdat.len += mdat_len;
+                            mdat
+                        }
+                        // A wide box immediately preceding the first mdat is included in the media data.
+                        None => match preceding_wide_offset {
+                            Some(offset) => {
+                                mdat.insert(InputSpan { offset, len: written_mdat.offset - offset + mdat_len })
+                            }
+                            None => mdat.insert(InputSpan { len: mdat_len, ..written_mdat }),
+                        },
+                    };
+                    // NB: Chunk offsets point into the first mdat, which may be preceded by a movie fragment.
+                    mdat_header_len.get_or_insert(mdat_data_offset - mdat.offset);
+                }
+                MOOF => {
+                    moof_count += 1;
+                    let moof_offset = data.len() as u64;
+                    let moof = test_moof(
+                        moof_offset,
+                        moof_count,
+                        self.mdat_data.len(),
+                        self.fragment_base_data_offsets,
+                    );
                     match &mut mdat {
-                        Some(mdat) => mdat.len += mdat_len,
-                        None => mdat = Some(InputSpan { len: mdat_len, ..written_mdat }),
+                        Some(mdat) => mdat.len += moof.encoded_len(),
+                        None => mdat = Some(InputSpan { offset: moof_offset, len: moof.encoded_len() }),
                     }
+                    moof.put_buf(&mut data);
                 }
-                name @ (FREE | META | MECO | SKIP) => {
+                name @ (FREE | META | MECO | SKIP | UDTA | WIDE) => {
+                    if name == WIDE && mdat.is_none() {
+                        wide_offset = Some(data.len() as u64);
+                    }
                     let mp4_box = match name {
                         FREE | SKIP => test_free(name, 13),
+                        WIDE => test_free(name, 8),
                         META => test_meta(),
                         MECO => test_meco(),
+                        UDTA => test_location_udta(),
                         _ => unreachable!(),
                     };
                     if let Some(mdat) = &mut mdat {
@@ -131,12 +180,22 @@ impl TestMp4Spec {
         // metadata verbatim is overly-strict and could be weakened.
         let mut expected_metadata = vec![];
         self.ftyp.build().put_buf(&mut expected_metadata);
+        if self.boxes.contains(&PDIN) {
+            test_pdin().put_buf(&mut expected_metadata);
+        }
         let mut expected_metadata_moov_offsets = Vec::new();
         for _ in moov_offsets {
             expected_metadata_moov_offsets.push(expected_metadata.len());
             let moov = moov.co_entries(co_entries.clone()).build();
             moov.put_buf(&mut expected_metadata);
         }
+        let first_data_index = self
+            .boxes
+            .iter()
+            .position(|&box_type| box_type == MDAT || box_type == MOOF);
+        if self.boxes[..first_data_index.unwrap_or(self.boxes.len())].contains(&SIDX) {
+            test_sidx(0, 0).put_buf(&mut expected_metadata);
+        }
 
         // Calculate and write correct expected output chunk offsets
         for co_entry in &mut co_entries {
@@ -178,7 +237,7 @@ impl TestMp4 {
         assert_matches!(sanitized.metadata.as_deref(), Some(metadata) => {
             assert_eq!(metadata, self.expected_metadata(metadata.len()));
         });
-        let sanitized_data = sanitized_data(sanitized.clone(), &self.data);
+        let sanitized_data = sanitized.reconstruct(&self.data);
         sanitize(io::Cursor::new(&sanitized_data)).unwrap();
         ffmpeg_assert_eq(&sanitized_data, &self.mdat_data);
         gpac_assert_eq(&sanitized_data, &self.mdat_data);
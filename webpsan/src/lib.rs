@@ -58,7 +58,7 @@ pub struct Config {
     pub allow_unknown_chunks: bool,
 }
 
-pub use mediasan_common::{Report, SeekSkipAdapter, Skip};
+pub use mediasan_common::{ReadDiscardSkip, Report, SeekSkipAdapter, Skip, SliceReader};
 
 /// Maximum file length as permitted by WebP.
 pub const MAX_FILE_LEN: u32 = u32::MAX - 2;
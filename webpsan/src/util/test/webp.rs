@@ -155,7 +155,7 @@ impl TestWebpSpec {
             let len = file_header.len.unwrap_or(0xDEADBEEF);
 
             data.extend_from_slice(&file_header.chunk_type.value);
-            data.put_u32_le(len as u32);
+            data.put_u32_le(len);
             data.extend_from_slice(&file_header.name.value);
         }
 
@@ -197,7 +197,11 @@ impl TestWebpSpec {
                 }
                 ANIM => write_test_anim(&mut data),
                 ANMF => {
-                    let anmf = (!anmfs.is_empty()).then(|| anmfs.remove(0)).unwrap_or_default();
+                    let anmf = if !anmfs.is_empty() {
+                        anmfs.remove(0)
+                    } else {
+                        Default::default()
+                    };
                     let TestAnmfSpec { x, y, width, height, alph, vp8l_data, vp8_data, chunks } = anmf.build().unwrap();
                     let alph = alph.build().unwrap();
 